@@ -84,8 +84,33 @@ def benchmark_case(name, builder, expect_sat):
     else:
         if result.sat:
             raise AssertionError(f"{name} expected UNSAT but was SAT")
+    stats = solver.stats()
     print(
-        f"{name}: {status} in {duration:.4f}s with {solver.last_conflicts} conflicts"
+        f"{name}: {status} in {duration:.4f}s with {stats.conflicts} conflicts, "
+        f"{stats.decisions} decisions, {stats.propagations} propagations"
+    )
+    return duration
+
+
+def arena_benchmark(n_vars, n_clauses, seed):
+    """Compare the clause arena with list-of-int clauses and time the solve."""
+    cnf, _ = planted_3sat(n_vars, n_clauses, seed)
+    arena_bytes = sum(a.itemsize * len(a) for a in (cnf.lits, cnf.starts, cnf.lengths))
+    as_lists = sum(
+        sys.getsizeof(clause) + sum(sys.getsizeof(lit) for lit in clause) for clause in cnf.clauses
+    )
+    solver = CDCLSolver(cnf)
+    start = time.perf_counter()
+    result = solver.solve([])
+    duration = time.perf_counter() - start
+    if not result.sat:
+        raise AssertionError("planted instance expected SAT")
+    verify_solution(cnf.clauses, result.assign)
+    print(
+        f"arena-{n_vars}v-{n_clauses}c: {arena_bytes / 1e6:.1f} MB of clauses "
+        f"(~{as_lists / 1e6:.1f} MB as lists of ints), "
+        f"{len(cnf.lits) / 1e6:.2f}M literals, solved in {duration:.3f}s "
+        f"({n_clauses / duration:,.0f} clauses/s)"
     )
     return duration
 
@@ -113,6 +138,8 @@ def run():
             expect_sat=True,
         )
 
+    arena_benchmark(20000, 80000, 3)
+
 
 if __name__ == "__main__":
     run()
@@ -6,7 +6,7 @@ import urllib.request
 from email.header import decode_header, make_header
 from email.message import Message
 from pathlib import Path
-from typing import Any, Optional, Tuple
+from typing import Any, Callable, Optional, Tuple
 
 from tqdm import tqdm
 
@@ -42,7 +42,12 @@ def _content_disposition_filename(header: str | None) -> Optional[str]:
     return Path(filename).name
 
 
-def urlread(url: str, timeout: float | None = 10) -> Tuple[bytes, Optional[str]]:
+def urlread(
+    url: str,
+    timeout: float | None = 10,
+    on_progress: Optional[Callable[[int], None]] = None,
+) -> Tuple[bytes, Optional[str]]:
+    # When *on_progress* is given it receives each chunk size and replaces the built-in bar.
     max_download = 1 << 30  # 1 GiB hard safety cap
     try:
         with urllib.request.urlopen(url, timeout=timeout) as r:
@@ -55,10 +60,16 @@ def urlread(url: str, timeout: float | None = 10) -> Tuple[bytes, Optional[str]]
                 data = r.read()
                 if len(data) > max_download:
                     raise RuntimeError(f"Refusing to download content larger than {max_download} bytes")
+                if on_progress is not None:
+                    on_progress(len(data))
                 return data, meta_filename or final_url
             chunk_size = 1 << 14
             data = bytearray()
-            with tqdm(total=total, desc="Downloading", unit="B", unit_scale=True, ncols=80, colour="cyan") as bar:
+            bar = None
+            if on_progress is None:
+                bar = tqdm(total=total, desc="Downloading", unit="B", unit_scale=True, ncols=80, colour="cyan")
+                on_progress = bar.update
+            try:
                 while True:
                     chunk = r.read(chunk_size)
                     if not chunk:
@@ -66,7 +77,10 @@ def urlread(url: str, timeout: float | None = 10) -> Tuple[bytes, Optional[str]]
                     data.extend(chunk)
                     if len(data) > max_download:
                         raise RuntimeError(f"Refusing to download content larger than {max_download} bytes")
-                    bar.update(len(chunk))
+                    on_progress(len(chunk))
+            finally:
+                if bar is not None:
+                    bar.close()
             return bytes(data), meta_filename or final_url
     except urllib.error.URLError as e:
         raise RuntimeError(f"Failed to read URL {url}") from e
@@ -14,7 +14,22 @@ if __package__ in {None, ""}:
         sys.path.insert(0, str(src_root))
     __package__ = "lpm"
 
-from .resolver import CDCLSolver, CNF, Implication, SATResult
+from .resolver import (
+    CDCLSolver,
+    CNF,
+    ConflictGraph,
+    DecisionHeuristic,
+    DecisionState,
+    DimacsError,
+    Implication,
+    Random,
+    SATResult,
+    SolverError,
+    SolverEvent,
+    SolverStats,
+    StaticOrder,
+    Vsids,
+)
 from .hooks import Hook, HookAction, HookError, HookTransactionManager, HookTrigger, load_hooks
 
 __all__ = [
@@ -28,6 +43,16 @@ __all__ = [
     "SATResult",
     "Implication",
     "CDCLSolver",
+    "ConflictGraph",
+    "DecisionHeuristic",
+    "DecisionState",
+    "Vsids",
+    "StaticOrder",
+    "Random",
+    "SolverError",
+    "DimacsError",
+    "SolverEvent",
+    "SolverStats",
     "Hook",
     "HookAction",
     "HookTransactionManager",
@@ -16,14 +16,15 @@ License: MIT
 """
 
 from __future__ import annotations
-import argparse, contextlib, dataclasses, errno, fnmatch, hashlib, io, json, os, re, shlex, shutil, sqlite3, stat, subprocess, sys, tarfile, tempfile, time, urllib.parse
+import argparse, contextlib, dataclasses, errno, fnmatch, hashlib, inspect, io, json, os, posixpath, re, shlex, shutil, sqlite3, stat, subprocess, sys, tarfile, tempfile, threading, time, urllib.parse
 import importlib.util
 from datetime import datetime, timezone
 from email.parser import Parser
 from concurrent.futures import ThreadPoolExecutor, as_completed
 from dataclasses import dataclass, field
+from enum import Enum
 from pathlib import Path
-from typing import Any, Dict, List, Optional, Set, Tuple, Iterable, Callable, BinaryIO, Mapping
+from typing import Any, Dict, List, Optional, Set, Tuple, Iterable, Iterator, Callable, BinaryIO, Mapping, Sequence, Protocol, Union
 from collections import deque
 
 if __package__ in {None, ""}:
@@ -230,13 +231,46 @@ from installgen import generate_install_script
 from first_run_ui import FirstRunSetupError, run_first_run_wizard
 import maintainer_mode
 from . import config as _config
-from .atomic_io import atomic_replace, safe_write
-from .fs_ops import operation_phase, prepare_directory
-from .privileges import privilege_info, privileged_section, privileges_enabled, require_root
-from .resolver import CNF, CDCLSolver
+from .atomic_io import atomic_replace, read_bytes, safe_write
+from .fs_ops import (
+    ChecksumMismatch,
+    FsError,
+    RootContext,
+    Transaction,
+    operation_phase,
+    prepare_directory,
+    read_link_hash,
+    sha256_file,
+    write_verified,
+)
+from .privileges import (
+    PrivilegeGate,
+    gate_for,
+    privilege_info,
+    privileged_section,
+    privileges_enabled,
+    require_root,
+    use_configured_gate,
+)
+from .resolver import CNF, CDCLSolver, SATResult, SolverEvent
+from .version import Constraint, Version, compare as compare_versions
 from .hooks import HookExecutionError, HookFailureMode, HookTransactionManager, _ensure_executable, load_hooks
+from .lpmbuild import HookContext, HookResult, run_hook as run_install_script, wrap_install_body
 from .delta import apply_delta, find_cached_by_sha, file_sha256, zstd_version, version_at_least
 from . import bootstrap
+from . import cache as _cache
+from .httpfetch import FetchError, HttpFetcher
+from .verify import Keyring, VerifyError, sign_command
+from . import completions as _completions
+from .retry import ChecksumError, RetryPolicy
+from . import cancel as _cancel
+from .cancel import UserAborted
+from . import deadline as _deadline
+from . import maxsat as _maxsat
+from . import output as _output
+from . import progress as _progress
+from .deadline import Deadline, DeadlineExceeded
+from .resolvecache import ResolutionCache
 from . import chroot_helpers
 
 # =========================== Protected packages ===============================
@@ -260,11 +294,7 @@ def load_protected() -> List[str]:
 PROTECTED = load_protected()
 
 # =========================== Logging/IO utils =================================
-CYAN   = "\033[1;36m"
-PURPLE = "\033[1;35m"
-GREEN  = "\033[1;32m"
-RED    = "\033[1;31m"
-RESET  = "\033[0m"
+from .output import CYAN, GREEN, PURPLE, RED, RESET
 
 def _resolve_lpm_attr(name: str, default):
     for module_name in ("lpm", "src.lpm"):
@@ -293,26 +323,29 @@ def log(msg: str):
     override = _resolve_lpm_attr("log", None)
     if override is not None and override is not log:
         return override(msg)
-    print(f"{PURPLE}{msg}{RESET}", file=sys.stderr)
+    print(_output.purple(msg, sys.stderr), file=sys.stderr)
+
+# Exit status of a command that ran past its --timeout, as timeout(1) uses.
+EXIT_DEADLINE = 124
 
 def die(msg: str, code: int = 2):
     override = _resolve_lpm_attr("die", None)
     if override is not None and override is not die:
         return override(msg, code)
-    print(f"{RED}[ERROR]{RESET} {msg}", file=sys.stderr)
+    print(f"{_output.red('[ERROR]', sys.stderr)} {msg}", file=sys.stderr)
     sys.exit(code)
 
 def ok(msg: str):
     override = _resolve_lpm_attr("ok", None)
     if override is not None and override is not ok:
         return override(msg)
-    print(f"{GREEN}[OK]{RESET} {msg}", file=sys.stderr)
+    print(f"{_output.green('[OK]', sys.stderr)} {msg}", file=sys.stderr)
 
 def warn(msg: str):
     override = _resolve_lpm_attr("warn", None)
     if override is not None and override is not warn:
         return override(msg)
-    print(f"{CYAN}[WARN]{RESET} {msg}", file=sys.stderr)
+    print(f"{_output.cyan('[WARN]', sys.stderr)} {msg}", file=sys.stderr)
 
 
 _DELTA_MODE = _config.USE_DELTAS
@@ -329,6 +362,26 @@ def _delta_mode(mode: str):
         _DELTA_MODE = previous
 
 
+_OFFLINE = False
+
+
+@contextlib.contextmanager
+def offline_mode(enabled: bool = True):
+    """Forbid network access: only cached or local resources may be read."""
+
+    global _OFFLINE
+    previous = _OFFLINE
+    _OFFLINE = bool(enabled)
+    try:
+        yield
+    finally:
+        _OFFLINE = previous
+
+
+def is_offline() -> bool:
+    return _OFFLINE
+
+
 def _current_delta_mode() -> str:
     mode = (_DELTA_MODE or "auto").lower()
     if mode not in {"auto", "always", "never"}:
@@ -638,38 +691,31 @@ def sandboxed_run(
 EXT = ".zst"
 
 # =========================== Version / Semver ops =============================
-SEMVER_RE = re.compile(r"^(\d+)(?:\.(\d+))?(?:\.(\d+))?(?:[-+~].*)?$")
-def parse_semver(v: str) -> Tuple[int,int,int]:
-    m = SEMVER_RE.match(str(v).strip())
-    if not m: return (0,0,0)
-    return tuple(int(x) if x is not None else 0 for x in m.groups())
+def parse_semver(v: str) -> Version:
+    """Return a sortable :class:`~lpm.version.Version` for *v*."""
+    return Version.parse(v)
 def cmp_semver(a: str, b: str) -> int:
-    ta, tb = parse_semver(a), parse_semver(b)
-    return (ta > tb) - (ta < tb)
+    return compare_versions(a, b)
 def satisfies(ver: str, cons: str) -> bool:
-    """Supports =, ==, >=, <=, >, <, ~=, and 'X.*' (e.g., 3.3.* == ~=3). Comma-separated parts are ANDed."""
-    if not cons or cons.strip() in ("", "*"): return True
-    for part in [p.strip() for p in cons.split(",") if p.strip()]:
-        if part.endswith(".*"): op, val = "~=", part[:-2]
-        elif part.startswith("=="): op, val = "==", part[2:].strip()
-        elif part.startswith("="):  op, val = "==", part[1:].strip()
-        elif part.startswith(">="): op, val = ">=", part[2:].strip()
-        elif part.startswith("<="): op, val = "<=", part[2:].strip()
-        elif part.startswith(">"):  op, val = ">",  part[1:].strip()
-        elif part.startswith("<"):  op, val = "<",  part[1:].strip()
-        elif part.startswith("~="): op, val = "~=", part[2:].strip()
-        else:                       op, val = "==", part
-        cmpv = cmp_semver(ver, val)
-        okk = (op=="==" and cmpv==0) or (op==">=" and cmpv>=0) or (op=="<=" and cmpv<=0) or (op==">" and cmpv>0) or (op=="<" and cmpv<0) or (op=="~=" and (parse_semver(ver)[0]==parse_semver(val)[0] and cmpv>=0))
-        if not okk: return False
-    return True
-    
+    """Supports =, ==, !=, >=, <=, >, <, ~=, and 'X.*' (e.g., 3.3.* == ~=3). Comma-separated parts are ANDed."""
+    return Constraint.parse(cons).matches(ver)
+
+_ARCH_ALIASES = {"amd64": "x86_64", "x86-64": "x86_64", "arm64": "aarch64"}
+
+
+def normalize_arch(arch: str) -> str:
+    """Lower-case *arch* and map distribution aliases (``amd64``) to uname names."""
+
+    norm = (arch or "").strip().lower()
+    return _ARCH_ALIASES.get(norm, norm)
+
+
 def arch_compatible(pkg_arch: str, want_arch: str) -> bool:
-    pkg_norm = (pkg_arch or "").strip().lower()
-    want_norm = (want_arch or "").strip().lower()
+    pkg_norm = normalize_arch(pkg_arch)
+    want_norm = normalize_arch(want_arch)
 
     host_arch = (os.uname().machine if hasattr(os, "uname") else "")
-    host_norm = (host_arch or "").strip().lower()
+    host_norm = normalize_arch(host_arch)
 
     universal_arches = {"noarch", "any", "none"}
 
@@ -681,6 +727,124 @@ def arch_compatible(pkg_arch: str, want_arch: str) -> bool:
 
     return pkg_norm == want_norm
 
+
+_UNIVERSAL_ARCHES = {"noarch", "any", "none"}
+
+
+def filter_arch(pkgs: Iterable[PkgMeta], arch: str) -> List[PkgMeta]:
+    """Keep the packages installable on *arch*.
+
+    A ``noarch`` target only accepts architecture-independent packages, which
+    is what an arch-neutral install root (shared data, docs) should contain.
+    """
+
+    if (arch or "").strip().lower() in _UNIVERSAL_ARCHES:
+        return [p for p in pkgs if (p.arch or "").strip().lower() in _UNIVERSAL_ARCHES]
+    return [p for p in pkgs if arch_compatible(p.arch, arch)]
+
+
+@dataclass
+class DeferredHook:
+    """A hook that was recorded instead of run because the root is cross-arch."""
+
+    hook: str
+    command: List[str]
+    env: Dict[str, str]
+    package: Optional[str] = None
+
+
+class CrossArchSession:
+    """Install into a root whose architecture is not the host's.
+
+    Hooks cannot run on the host, so they are collected here and written to
+    the ``deferred_hooks`` table once the command succeeds, for the target
+    system to run on first boot.
+    """
+
+    def __init__(self, root: Path, arch: str) -> None:
+        self.root = Path(root)
+        self.arch = arch
+        self.pending: List[DeferredHook] = []
+        self._lock = threading.Lock()
+
+    def defer(self, hook: str, command: Sequence[str], env: Mapping[str, str], package: Optional[str] = None) -> None:
+        with self._lock:
+            self.pending.append(DeferredHook(hook, [str(c) for c in command], dict(env), package))
+        log(f"[cross-arch] deferred hook {hook}{f' for {package}' if package else ''} until first boot")
+
+    def persist(self) -> int:
+        if not self.pending:
+            return 0
+        conn = db()
+        try:
+            with conn:
+                record_deferred_hooks(conn, self.root, self.arch, self.pending)
+        finally:
+            conn.close()
+        return len(self.pending)
+
+
+_TARGET_ARCH: Optional[str] = None
+_CROSS_ARCH: Optional[CrossArchSession] = None
+
+
+def host_arch() -> str:
+    return (os.uname().machine if hasattr(os, "uname") else "") or _resolve_lpm_attr("ARCH", ARCH)
+
+
+def effective_arch() -> str:
+    """Architecture packages are selected for: ``--target-arch`` or ``ARCH``."""
+
+    return _TARGET_ARCH or _resolve_lpm_attr("ARCH", ARCH)
+
+
+def is_cross_arch(target: Optional[str]) -> bool:
+    if not normalize_arch(target or ""):
+        return False
+    return not arch_compatible(target or "", host_arch())
+
+
+@contextlib.contextmanager
+def target_arch(arch: Optional[str], root: Path):
+    """Select packages for *arch* and, when it is foreign, defer hooks on *root*."""
+
+    global _TARGET_ARCH, _CROSS_ARCH
+    if not arch:
+        yield None
+        return
+    previous = (_TARGET_ARCH, _CROSS_ARCH)
+    session = CrossArchSession(root, arch) if is_cross_arch(arch) else None
+    _TARGET_ARCH, _CROSS_ARCH = arch, session
+    try:
+        yield session
+        if session is not None and session.persist():
+            ok(f"[cross-arch] recorded {len(session.pending)} hook(s) for first boot of {session.root}")
+    finally:
+        _TARGET_ARCH, _CROSS_ARCH = previous
+
+
+def _hook_deferrer() -> Optional[Callable[..., None]]:
+    session = _CROSS_ARCH
+    return session.defer if session is not None else None
+
+
+def _run_install_script(script: Path, context: HookContext, *, durable: Optional[Path] = None) -> Optional[HookResult]:
+    """Run an embedded ``.lpm-install.sh`` function, or defer it on a cross-arch root.
+
+    A deferred call is recorded against *durable* (default *script*), a copy
+    that is still there when the target boots, by its path inside the root.
+    Returns ``None`` when deferred.
+    """
+
+    defer = _hook_deferrer()
+    if defer is None:
+        return run_install_script(script, context)
+    kept = Path(durable or script)
+    with contextlib.suppress(ValueError):
+        kept = Path("/") / kept.relative_to(context.root)
+    defer(context.function, context.argv(kept), context.environ(), context.name or None)
+    return None
+
 # =========================== Dep grammar (AND/OR + atoms) =====================
 TOK_RE = re.compile(r"\s*(\(|\)|\|\||\||,|>=|<=|==|=|>|<|~=?|\w[\w\-\._+]*)")
 
@@ -837,6 +1001,95 @@ class PkgMeta:
             repo=repo_name, prio=prio, bias=bias, decay=decay, kernel=d.get("kernel", False),
             mkinitcpio_preset=d.get("mkinitcpio_preset"), deltas=d.get("deltas", []))
 
+_LIST_FIELDS = (
+    "requires", "conflicts", "build_requires", "obsoletes", "provides", "symbols", "recommends", "suggests",
+)
+
+
+class IndexFormatError(ValueError):
+    """Raised when a package index file is not valid JSON or holds a malformed record."""
+
+    def __init__(self, path: Union[str, Path], message: str, entry: Optional[int] = None) -> None:
+        where = f"{path}" if entry is None else f"{path}: entry {entry}"
+        super().__init__(f"{where}: {message}")
+        self.path = str(path)
+        self.entry = entry
+
+
+class Index(Protocol):
+    """Read-only view of the packages a repository offers."""
+
+    def all(self) -> List[PkgMeta]:
+        """Return every package, grouped by name with the newest version first."""
+
+    def get(self, name: str) -> Optional[PkgMeta]:
+        """Return the newest version of *name*, or ``None``."""
+
+    def query(self, text: str) -> List[PkgMeta]:
+        """Return the newest version of each package whose name or summary contains *text*."""
+
+
+class JsonIndex:
+    """:class:`Index` over package records from JSON repository files.
+
+    Versions of a name are ordered like :func:`load_universe` orders them,
+    by repository priority and then version, so "newest" agrees with it.
+    """
+
+    def __init__(self, packages: Iterable[PkgMeta]) -> None:
+        self._by_name: Dict[str, List[PkgMeta]] = {}
+        for pkg in packages:
+            self._by_name.setdefault(pkg.name, []).append(pkg)
+        for lst in self._by_name.values():
+            lst.sort(key=lambda p: (p.prio, parse_semver(p.version)), reverse=True)
+
+    @classmethod
+    def load(cls, path: Union[str, Path], repo_name: str = "(local)", priority: int = 0) -> "JsonIndex":
+        """Load *path*, either an array of records or an ``index.json`` object.
+
+        Unreadable files raise :class:`~lpm.fs_ops.FsError`; invalid JSON and
+        malformed records raise :class:`IndexFormatError`.
+        """
+
+        try:
+            raw = read_bytes(path)
+        except OSError as exc:
+            raise FsError(exc.errno, f"cannot read {path}: {exc.strerror}") from exc
+        try:
+            doc = json.loads(raw.decode("utf-8"))
+        except (UnicodeDecodeError, json.JSONDecodeError) as exc:
+            raise IndexFormatError(path, f"invalid JSON: {exc}") from exc
+        records = doc.get("packages") if isinstance(doc, dict) else doc
+        if not isinstance(records, list):
+            raise IndexFormatError(path, "expected an array of package records")
+        packages = []
+        for i, record in enumerate(records):
+            if not isinstance(record, dict):
+                raise IndexFormatError(path, "record is not an object", i)
+            for key in ("name", "version"):
+                if not isinstance(record.get(key), str) or not record[key]:
+                    raise IndexFormatError(path, f"missing or invalid {key!r}", i)
+            for key in _LIST_FIELDS:
+                if not isinstance(record.get(key, []), list):
+                    raise IndexFormatError(path, f"{key!r} must be a list", i)
+            packages.append(PkgMeta.from_dict(record, repo_name, priority))
+        return cls(packages)
+
+    def all(self) -> List[PkgMeta]:
+        return [p for name in sorted(self._by_name) for p in self._by_name[name]]
+
+    def get(self, name: str) -> Optional[PkgMeta]:
+        lst = self._by_name.get(name)
+        return lst[0] if lst else None
+
+    def query(self, text: str) -> List[PkgMeta]:
+        needle = text.lower()
+        return [
+            lst[0]
+            for name, lst in sorted(self._by_name.items())
+            if needle in name.lower() or needle in lst[0].summary.lower()
+        ]
+
 # =========================== Repos ============================================
 @dataclass
 class Repo:
@@ -849,6 +1102,21 @@ class Repo:
 def list_repos() -> List[Repo]:
     return [Repo(**r) for r in read_json(REPO_LIST)]
 
+# Repository URLs from the ``repos`` key of config.toml; set by main().
+CONFIG_REPOS: Tuple[str, ...] = ()
+
+def configured_repos() -> List[Repo]:
+    """Return the saved repositories plus the ``config.toml`` ones not already saved."""
+    rs = list_repos()
+    if not CONFIG_REPOS:
+        return rs
+    known = {r.url for r in rs}
+    for i, url in enumerate(CONFIG_REPOS, 1):
+        if url not in known:
+            rs.append(Repo(f"config{i}", url))
+            known.add(url)
+    return rs
+
 def save_repos(rs: List[Repo]):
     with operation_phase(privileged=True):
         write_json(REPO_LIST, [dataclasses.asdict(r) for r in rs])
@@ -861,26 +1129,188 @@ def add_repo(name,url,priority=10,bias=1.0,decay=0.95):
 def del_repo(name):
     save_repos([r for r in list_repos() if r.name!=name]); ok(f"Removed repo {name}")
 
-def fetch_repo_index(repo: Repo) -> List[PkgMeta]:
+def _accepts_keyword(func: Callable, name: str) -> bool:
+    try:
+        params = inspect.signature(func).parameters
+    except (TypeError, ValueError):
+        return False
+    return name in params or any(p.kind is inspect.Parameter.VAR_KEYWORD for p in params.values())
+
+
+class OfflineError(RuntimeError):
+    """Raised in offline mode when a resource is not available locally."""
+
+
+def _is_local_location(url: str) -> bool:
+    return url.startswith("file://") or url.startswith("/")
+
+
+def repo_urlread(
+    url: str,
+    *,
+    validate: Optional[Callable[[bytes], None]] = None,
+    policy: Optional[RetryPolicy] = None,
+    sleep: Optional[Callable[[float], None]] = None,
+    on_progress: Optional[Callable[[int], None]] = None,
+    offline: Optional[bool] = None,
+) -> Tuple[bytes, Optional[str]]:
+    """``urlread`` under the configured :class:`RetryPolicy`.
+
+    *validate* runs on every downloaded payload and may raise
+    :class:`ChecksumError`, which is never retried.  Bytes reported to
+    *on_progress* by a failed attempt are rewound before the next one.  In
+    offline mode (*offline*, default: :func:`is_offline`) remote URLs raise
+    :class:`OfflineError` without any I/O.
+    """
+
+    if (is_offline() if offline is None else offline) and not _is_local_location(url):
+        raise OfflineError(f"{url} is not cached and --offline forbids downloading it")
+    policy = policy or RetryPolicy.from_config(_config)
+    reader = _resolve_lpm_attr("urlread", urlread)
+    deadline = _deadline.current()
+    streams = on_progress is not None and _accepts_keyword(reader, "on_progress")
+
+    def _attempt() -> Tuple[bytes, Optional[str]]:
+        kwargs: Dict[str, object] = {}
+        received = [0]
+        if deadline is not None:
+            deadline.check(f"download of {url}")
+            kwargs["timeout"] = deadline.timeout(10)
+        if streams:
+            def _report(n: int) -> None:
+                received[0] += n
+                on_progress(n)
+
+            kwargs["on_progress"] = _report
+        try:
+            data, meta = reader(url, **kwargs)
+            if validate is not None:
+                validate(data)
+        except Exception:
+            if received[0]:
+                on_progress(-received[0])
+            raise
+        if on_progress is not None and not streams:
+            on_progress(len(data))
+        return data, meta
+
+    def _retrying(attempt: int, delay: float, exc: BaseException) -> None:
+        warn(f"{url}: {exc}; retrying in {delay:.1f}s (attempt {attempt + 1}/{policy.max_attempts})")
+
+    def _sleep(delay: float) -> None:
+        if deadline is not None:
+            delay = deadline.timeout(delay)
+        (sleep or time.sleep)(delay)
+
+    return policy.call(_attempt, sleep=_sleep, on_retry=_retrying)
+
+
+class RepoIndexError(RuntimeError):
+    """Raised when a repository index is unsigned or fails signature checks."""
+
+
+def verify_index_signature(repo: Repo, raw: bytes, sig: Optional[bytes]) -> Path:
+    """Check *raw* against the detached ``index.json.sig`` using the trust store.
+
+    Returns the trusted key that produced the signature.
+    """
+
+    if not sig:
+        raise RepoIndexError(f"repo {repo.name}: index.json is not signed")
+    trust_dir = Path(_resolve_lpm_attr("TRUST_DIR", TRUST_DIR))
+    try:
+        keyring = Keyring.load(trust_dir)
+        if not keyring:
+            raise RepoIndexError(f"repo {repo.name}: no trusted public keys in {trust_dir}")
+        return keyring.verify(raw, sig).path
+    except VerifyError as exc:
+        raise RepoIndexError(f"repo {repo.name}: index.json signature verification failed ({exc})") from exc
+
+
+def _index_cache_path(repo: Repo) -> Path:
+    return Path(_resolve_lpm_attr("CACHE_DIR", CACHE_DIR)) / "indexes" / f"{repo.name}.json"
+
+
+def _read_cached_index(repo: Repo) -> Tuple[bytes, Optional[bytes]]:
+    path = _index_cache_path(repo)
+    try:
+        raw = path.read_bytes()
+    except OSError:
+        raise OfflineError(f"repo {repo.name}: index is not cached and --offline forbids downloading it") from None
+    sig_path = path.with_name(path.name + ".sig")
+    return raw, sig_path.read_bytes() if sig_path.exists() else None
+
+
+def _store_cached_index(repo: Repo, raw: bytes, sig: Optional[bytes]) -> None:
+    path = _index_cache_path(repo)
+    sig_path = path.with_name(path.name + ".sig")
+    try:
+        safe_write(path, raw)
+        if sig is not None:
+            safe_write(sig_path, sig)
+        elif sig_path.exists():
+            sig_path.unlink()
+    except OSError:
+        pass
+
+
+def fetch_repo_index(
+    repo: Repo,
+    *,
+    allow_unsigned: Optional[bool] = None,
+    index_versions: Optional[Dict[str, str]] = None,
+) -> List[PkgMeta]:
+    """Fetch and verify *repo*'s index, keeping a copy for offline use.
+
+    In offline mode indexes of remote repositories come from that copy, and
+    :class:`OfflineError` is raised when there is none.
+    """
+
     idx_url = repo.url.rstrip("/") + "/index.json"
-    raw, _ = _resolve_lpm_attr("urlread", urlread)(idx_url)
+    if allow_unsigned is None:
+        allow_unsigned = _config.ALLOW_UNSIGNED_REPO
+    if _OFFLINE and not _is_local_location(idx_url):
+        raw, sig = _read_cached_index(repo)
+    else:
+        raw, _ = repo_urlread(idx_url)
+        sig = None
+        if not allow_unsigned:
+            try:
+                sig, _ = repo_urlread(idx_url + ".sig")
+            except Exception:
+                sig = None
+    if allow_unsigned:
+        warn(f"repo {repo.name}: index signature not checked (--allow-unsigned-repo)")
+    else:
+        verify_index_signature(repo, raw, sig)
+    if not (_OFFLINE or _is_local_location(idx_url)):
+        _store_cached_index(repo, raw, sig)
     j = json.loads(raw.decode("utf-8"))
+    if index_versions is not None:
+        index_versions[repo.name] = hashlib.sha256(raw).hexdigest()
     return [PkgMeta.from_dict(p, repo.name, repo.priority, repo.bias, repo.decay) for p in j.get("packages",[])]
 
-def load_universe() -> Dict[str, List[PkgMeta]]:
+def load_universe(index_versions: Optional[Dict[str, str]] = None) -> Dict[str, List[PkgMeta]]:
     out: Dict[str,List[PkgMeta]] = {}
-    for repo in sorted(list_repos(), key=lambda r: r.priority):
+    for repo in sorted(configured_repos(), key=lambda r: r.priority):
         try:
-            pkgs = fetch_repo_index(repo)
+            pkgs = fetch_repo_index(repo, index_versions=index_versions)
+        except OfflineError:
+            raise
         except Exception as e:
             warn(f"repo {repo.name}: {e}"); continue
-        for p in pkgs:
-            if not arch_compatible(p.arch, ARCH): continue
+        for p in filter_arch(pkgs, effective_arch()):
             out.setdefault(p.name, []).append(p)
     for name, lst in out.items(): 
         lst.sort(key=lambda p: (p.prio, parse_semver(p.version)), reverse=True)
     return out
 
+
+def repo_index() -> JsonIndex:
+    """Return the packages of the configured repositories as an :class:`Index`."""
+
+    return JsonIndex(p for lst in load_universe().values() for p in lst)
+
 # =========================== SQLite state =====================================
 SCHEMA = """
 CREATE TABLE IF NOT EXISTS installed(
@@ -893,7 +1323,8 @@ CREATE TABLE IF NOT EXISTS installed(
   requires TEXT NOT NULL,
   manifest TEXT NOT NULL,
   explicit INTEGER NOT NULL,
-  install_time INTEGER NOT NULL
+  install_time INTEGER NOT NULL,
+  broken INTEGER NOT NULL DEFAULT 0
 );
 CREATE TABLE IF NOT EXISTS history(
   id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -904,14 +1335,35 @@ CREATE TABLE IF NOT EXISTS history(
   to_ver TEXT,
   details TEXT
 );
+CREATE TABLE IF NOT EXISTS transactions(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  ts INTEGER NOT NULL,
+  packages TEXT NOT NULL,
+  previous TEXT NOT NULL,
+  backup TEXT,
+  state TEXT NOT NULL DEFAULT 'committed'
+);
 CREATE TABLE IF NOT EXISTS snapshots(
   id INTEGER PRIMARY KEY,
   ts INTEGER NOT NULL,
   tag TEXT NOT NULL,
   archive TEXT NOT NULL
 );
+CREATE TABLE IF NOT EXISTS deferred_hooks(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  ts INTEGER NOT NULL,
+  root TEXT NOT NULL,
+  arch TEXT NOT NULL,
+  hook TEXT NOT NULL,
+  package TEXT,
+  command TEXT NOT NULL,
+  env TEXT NOT NULL
+);
 """
 _DB_PATH_OVERRIDE: Optional[Path] = None
+_ROOT_STATE = threading.local()
+# Set by command_root_state() for every thread of a single-root command.
+_COMMAND_ROOT_PATHS: Optional[Tuple[Path, Path]] = None
 
 
 def _state_owner_group() -> tuple[Optional[int], Optional[int]]:
@@ -955,21 +1407,92 @@ def _open_state_db(path: Path) -> sqlite3.Connection:
         c.execute("ALTER TABLE installed ADD COLUMN requires TEXT NOT NULL DEFAULT '[]'")
     if "explicit" not in cols:
         c.execute("ALTER TABLE installed ADD COLUMN explicit INTEGER NOT NULL DEFAULT 0")
+    if "broken" not in cols:
+        c.execute("ALTER TABLE installed ADD COLUMN broken INTEGER NOT NULL DEFAULT 0")
     c.commit()
     _apply_state_permissions(path, directory=False)
     return c
 
 
+def _root_state_paths(root: Path) -> Tuple[Path, Path]:
+    """The state database and transaction lock kept inside *root*, mirroring ``DB_PATH``."""
+
+    root = Path(root)
+    return (
+        root / str(DB_PATH).lstrip("/"),
+        root / str(_config.LOCK_PATH).lstrip("/"),
+    )
+
+
+@contextlib.contextmanager
+def root_state(root: Path):
+    """Keep the state database and transaction lock inside *root* for this thread.
+
+    Used when one invocation provisions several roots: each one then records
+    its installed packages in ``<root>/var/lib/lpm/state.db`` (mirroring
+    ``DB_PATH``) and locks independently of the others.
+    """
+
+    previous = getattr(_ROOT_STATE, "paths", None)
+    _ROOT_STATE.paths = _root_state_paths(root)
+    try:
+        yield
+    finally:
+        _ROOT_STATE.paths = previous
+
+
+@contextlib.contextmanager
+def command_root_state(root) -> Iterator[None]:
+    """Switch to the state kept inside a command's ``--root``, for every thread.
+
+    Single-root commands go through this and :func:`install_to_roots` through
+    :func:`root_state`, so any root other than ``/`` keeps its packages in its
+    own ``<root>/var/lib/lpm/state.db`` whichever way it was installed.
+    """
+
+    global _COMMAND_ROOT_PATHS
+    if isinstance(root, (list, tuple)):
+        root = root[0] if len(root) == 1 else None
+    if not root or Path(root).resolve() == Path("/"):
+        yield
+        return
+    previous = _COMMAND_ROOT_PATHS
+    _COMMAND_ROOT_PATHS = _root_state_paths(Path(root))
+    try:
+        yield
+    finally:
+        _COMMAND_ROOT_PATHS = previous
+
+
+def _current_root_paths() -> Optional[Tuple[Path, Path]]:
+    return getattr(_ROOT_STATE, "paths", None) or _COMMAND_ROOT_PATHS
+
+
+def _root_lock_path() -> Optional[Path]:
+    paths = _current_root_paths()
+    return paths[1] if paths else None
+
+
 def db() -> sqlite3.Connection:
     override = _resolve_lpm_attr("db", None)
     if override is not None and override is not db:
         return override()
+    return _open_state_db(_state_db_path())
+
+def _state_db_path() -> Path:
+    paths = _current_root_paths()
     state_dir_override = os.environ.get("LPM_STATE_DIR")
+    if paths:
+        return Path(paths[0])
     if state_dir_override:
-        path = Path(state_dir_override) / "state.db"
-    else:
-        path = _DB_PATH_OVERRIDE or DB_PATH
-    return _open_state_db(path)
+        return Path(state_dir_override) / "state.db"
+    return Path(_DB_PATH_OVERRIDE or DB_PATH)
+
+def _transaction_backup_dir() -> Path:
+    """Create a directory next to the state database for a transaction's backups."""
+    base = _state_db_path().parent / "transactions"
+    base.mkdir(parents=True, exist_ok=True)
+    return Path(tempfile.mkdtemp(prefix="txn-", dir=base))
 
 def db_installed(conn) -> Dict[str,dict]:
     override = _resolve_lpm_attr("db_installed", None)
@@ -992,6 +1515,91 @@ def db_installed(conn) -> Dict[str,dict]:
         }
     return res
 
+@dataclass
+class RepairReport:
+    """Differences between the installed database and the files under a root."""
+
+    missing: Dict[str, List[str]] = field(default_factory=dict)
+    orphans: List[str] = field(default_factory=list)
+    marked: List[str] = field(default_factory=list)
+    cleared: List[str] = field(default_factory=list)
+
+    @property
+    def clean(self) -> bool:
+        return not self.missing and not self.orphans and not self.cleared
+
+
+def repair_db(root: Path, *, fix: bool = False, conn: Optional[sqlite3.Connection] = None) -> RepairReport:
+    """Cross-check the installed manifests against the files under *root*.
+
+    Packages with manifest entries missing on disk end up in ``missing`` and
+    files sharing a directory with packaged files but owned by no package in
+    ``orphans``.  With *fix* the ``broken`` flag of each package is brought in
+    line with what was found; orphans are only reported.
+    """
+
+    root = Path(root)
+    own = conn is None
+    conn = conn or db()
+    report = RepairReport()
+    owned: Set[str] = set()
+    flagged: Dict[str, bool] = {}
+    try:
+        for name, mani, broken in conn.execute("SELECT name,manifest,broken FROM installed ORDER BY name"):
+            flagged[name] = bool(broken)
+            for entry in json.loads(mani) if mani else []:
+                path = entry["path"] if isinstance(entry, dict) else entry
+                owned.add("/" + path.lstrip("/"))
+                if not os.path.lexists(root / path.lstrip("/")):
+                    report.missing.setdefault(name, []).append(path)
+
+        dirs = {posixpath.dirname(path) for path in owned}
+        for directory in sorted(dirs):
+            base = root / directory.lstrip("/")
+            try:
+                children = sorted(os.listdir(base))
+            except OSError:
+                continue
+            for child in children:
+                path = posixpath.join(directory, child)
+                if path not in owned and path not in dirs and not (base / child).is_dir():
+                    report.orphans.append(path)
+
+        for name, broken in flagged.items():
+            if name in report.missing and not broken:
+                report.marked.append(name)
+            elif name not in report.missing and broken:
+                report.cleared.append(name)
+        if fix:
+            conn.executemany("UPDATE installed SET broken=1 WHERE name=?", [(n,) for n in report.marked])
+            conn.executemany("UPDATE installed SET broken=0 WHERE name=?", [(n,) for n in report.cleared])
+            conn.commit()
+    finally:
+        if own:
+            conn.close()
+    return report
+
+
+def record_deferred_hooks(conn, root: Path, arch: str, hooks: Iterable[DeferredHook]) -> None:
+    ts = int(time.time())
+    conn.executemany(
+        "INSERT INTO deferred_hooks(ts,root,arch,hook,package,command,env) VALUES(?,?,?,?,?,?,?)",
+        [
+            (ts, str(root), arch, h.hook, h.package, json.dumps(h.command), json.dumps(h.env, sort_keys=True))
+            for h in hooks
+        ],
+    )
+
+
+def load_deferred_hooks(conn, root: Optional[Path] = None) -> List[DeferredHook]:
+    sql = "SELECT hook,command,env,package FROM deferred_hooks"
+    params: Tuple = ()
+    if root is not None:
+        sql += " WHERE root=?"
+        params = (str(root),)
+    rows = conn.execute(sql + " ORDER BY id", params)
+    return [DeferredHook(r[0], json.loads(r[1]), json.loads(r[2]), r[3]) for r in rows]
+
 # =========================== Snapshots =====================================
 def create_snapshot(tag: str, files: Iterable[Path]) -> str:
     ts = int(time.time())
@@ -1058,6 +1666,8 @@ class Universe:
     installed: Dict[str, dict]
     pins: Dict[str,str]
     holds: Set[str]
+    # repo name -> digest of the index it was loaded from (see ResolutionCache)
+    index_versions: Dict[str, str] = field(default_factory=dict)
 
 
 def build_universe() -> Universe:
@@ -1065,7 +1675,8 @@ def build_universe() -> Universe:
     pins = read_json(PIN_FILE)
     holds=set(pins.get("hold",[]))
     prefer: Dict[str,str] = pins.get("prefer",{})
-    allpkgs = load_universe()
+    index_versions: Dict[str, str] = {}
+    allpkgs = load_universe(index_versions)
     providers: Dict[str,List[PkgMeta]] = {}
     def add_prov(tok: str, p: PkgMeta): providers.setdefault(tok, []).append(p)
     for name, lst in allpkgs.items():
@@ -1079,7 +1690,7 @@ def build_universe() -> Universe:
                     if op and ver: add_prov(f"{nm}{'==' if op=='=' else op}{ver}", p)
     for tok, lst in providers.items():
         lst.sort(key=lambda p: (p.prio, parse_semver(p.version)), reverse=True)
-    return Universe(allpkgs, providers, installed, prefer, holds)
+    return Universe(allpkgs, providers, installed, prefer, holds, index_versions)
 
 
 def register_universe_candidate(u: Universe, meta: PkgMeta) -> None:
@@ -1241,6 +1852,34 @@ def providers_for(u: Universe, atom: Atom) -> List[PkgMeta]:
         cands = [p for p in cands if satisfies(p.version, f"{atom.op}{atom.ver}")]
     return cands
 
+def canonicalize_universe(u: Universe) -> Universe:
+    """Return a copy of *u* whose iteration order no longer depends on load order.
+
+    Package names, provider tokens, installed packages and pins are sorted,
+    and candidates that tie on priority and version are ordered by repository,
+    release and architecture.  Seeded resolutions run on a canonical universe,
+    so their plan depends only on what the universe contains and the seed.
+    *u* itself is left as it is.
+    """
+
+    def _candidates(lists: Dict[str, List[PkgMeta]]) -> Dict[str, List[PkgMeta]]:
+        ordered: Dict[str, List[PkgMeta]] = {}
+        for name, pkgs in sorted(lists.items()):
+            pkgs = sorted(pkgs, key=lambda p: (p.repo or "", p.release, p.arch, p.name))
+            pkgs.sort(key=lambda p: (p.prio, parse_semver(p.version)), reverse=True)
+            ordered[name] = pkgs
+        return ordered
+
+    return Universe(
+        candidates_by_name=_candidates(u.candidates_by_name),
+        providers=_candidates(u.providers),
+        installed=dict(sorted(u.installed.items())),
+        pins=dict(sorted(u.pins.items())),
+        holds=set(u.holds),
+        index_versions=dict(u.index_versions),
+    )
+
+
 # =========================== Resolver encoding =================================
 def expr_to_cnf_disj(u: Universe, e: DepExpr, cnf: CNF, var_of: Dict[Tuple[str,str],int]) -> List[int]:
     if e.kind=="atom":
@@ -1251,12 +1890,29 @@ def expr_to_cnf_disj(u: Universe, e: DepExpr, cnf: CNF, var_of: Dict[Tuple[str,s
     else:
         die("expr_to_cnf_disj called on AND unexpectedly")
 
+@dataclass(frozen=True)
+class ClauseOrigin:
+    """The requirement a clause of :func:`encode_resolution` was built from.
+
+    ``requester`` is the name of the package whose ``requires`` entry it is,
+    or ``None`` for one of the resolution goals; ``goal`` then holds the
+    goal as it was requested.
+    """
+
+    requester: Optional[str]
+    expr: DepExpr
+    goal: Optional[str] = None
+
+
 def encode_resolution(
     u: Universe,
     goals: List[DepExpr],
     goal_texts: Optional[List[str]] = None,
     *,
     include_build_requires: bool = False,
+    recommends: bool = True,
+    origins: Optional[Dict[int, ClauseOrigin]] = None,
+    dependencies: bool = True,
 ) -> Tuple[
     CNF,
     Dict[Tuple[str, str], int],
@@ -1266,13 +1922,31 @@ def encode_resolution(
     Dict[int, float],
     Dict[Tuple[str, str], str],
     Dict[str, List[str]],
+    List[int],
 ]:
-    disqualified, terminal = prune_universe_missing_providers(
-        u, include_build_requires
-    )
+    # Without *dependencies* no requires (or recommends) clause is generated,
+    # so a candidate missing a dependency is not pruned either; conflicts and
+    # obsoletes are still encoded.
+    if dependencies:
+        disqualified, terminal = prune_universe_missing_providers(
+            u, include_build_requires
+        )
+    else:
+        disqualified, terminal = {}, {}
     installed_providers = _installed_provider_map(u.installed)
 
     cnf = CNF()
+
+    def add_required(
+        clause: List[int], requester: Optional[str], expr: DepExpr, goal: Optional[str] = None
+    ) -> None:
+        # Remember which requirement produced each stored clause, so an
+        # unsatisfiable core can be explained in terms of requirements.
+        idx = len(cnf.lengths)
+        cnf.add(clause)
+        if origins is not None and len(cnf.lengths) > idx:
+            origins[idx] = ClauseOrigin(requester, expr, goal)
+
     var_of: Dict[Tuple[str,str],int] = {}
     bias_map: Dict[int,float] = {}
     decay_map: Dict[int,float] = {}
@@ -1299,6 +1973,7 @@ def encode_resolution(
         cnf.add([-vars_for_name[-1], -aux[-1]])
 
     prefer_true: Set[int]=set(); prefer_false: Set[int]=set()
+    soft: List[int] = []
     # Bias: installed, newest
     for name,lst in u.candidates_by_name.items():
         inst=u.installed.get(name)
@@ -1322,7 +1997,7 @@ def encode_resolution(
     def add_pkg_constraints(p: PkgMeta):
         vp = var_of[(p.name,p.version)]
         # requires
-        for s in _iter_requires(p, include_build_requires):
+        for s in (_iter_requires(p, include_build_requires) if dependencies else ()):
             if not s:
                 continue
             e = parse_dep_expr(s)
@@ -1336,7 +2011,7 @@ def encode_resolution(
                         raise ResolutionError(
                             f"No provider for dependency '{part_label}' required by {p.name}-{p.version}"
                         )
-                    cnf.add([-vp] + disj)
+                    add_required([-vp] + disj, p.name, part)
             else:
                 disj = expr_to_cnf_disj(u, e, cnf, var_of)
                 if not disj:
@@ -1346,7 +2021,7 @@ def encode_resolution(
                     raise ResolutionError(
                         f"No provider for dependency '{req_label}' required by {p.name}-{p.version}"
                     )
-                cnf.add([-vp] + disj)
+                add_required([-vp] + disj, p.name, e)
         # conflicts / obsoletes
         for lst in (p.conflicts, p.obsoletes):
             for s in lst:
@@ -1356,20 +2031,75 @@ def encode_resolution(
                 for part in parts:
                     disj = expr_to_cnf_disj(u, part, cnf, var_of)
                     for q in disj: cnf.add([-vp, -q])
-        # soft deps bias
-        for s in p.recommends + p.suggests:
+                    if lst is p.obsoletes:
+                        continue
+                    # An installed package that stays as it is would still be
+                    # on disk next to p, so p needs it replaced by a version
+                    # outside the conflict.
+                    for rname in sorted(_match_dep_expr_against_installed(part, u.installed, installed_providers)):
+                        if rname == p.name:
+                            continue
+                        conflicting = set(disj)
+                        rvars = [
+                            var_of[(q.name, q.version)]
+                            for q in u.candidates_by_name.get(rname, [])
+                            if var_of[(q.name, q.version)] not in conflicting
+                        ]
+                        cnf.add([-vp] + rvars)
+                        if not rvars:
+                            disqualified.setdefault(
+                                (p.name, p.version),
+                                f"{p.name}-{p.version} conflicts with installed "
+                                f"{rname}-{u.installed[rname].get('version')}",
+                            )
+        # soft deps: a recommends clause is relaxed by a fresh variable whose
+        # negation is a soft literal for the optimizer; suggests only bias the phase
+        for s in (p.recommends if recommends and dependencies else []) + p.suggests:
             try: e=parse_dep_expr(s)
             except Exception: continue
-            lits=[]
-            if e.kind=="and":
-                for part in flatten_and(e): lits += expr_to_cnf_disj(u, part, cnf, var_of)
-            else:
-                lits = expr_to_cnf_disj(u, e, cnf, var_of)
-            for lit in lits: prefer_true.add(lit)
+            parts = flatten_and(e) if e.kind=="and" else [e]
+            for part in parts:
+                lits = expr_to_cnf_disj(u, part, cnf, var_of)
+                if not lits: continue
+                for lit in lits: prefer_true.add(lit)
+                if s in p.recommends and recommends:
+                    relax = cnf.new_var(f"recommends_{p.name}_{p.version}_{len(soft)}")
+                    cnf.add([-vp] + lits + [relax])
+                    prefer_false.add(relax)
+                    soft.append(-relax)
 
     for name,lst in u.candidates_by_name.items():
         for p in lst: add_pkg_constraints(p)
 
+    # Installed reverse dependencies: replacing an installed package with a
+    # version that an installed dependant does not accept (e.g. a downgrade
+    # below a ``>=`` bound) is only allowed if that dependant is replaced too.
+    for rname, rmeta in u.installed.items():
+        rvars = [
+            var_of[(q.name, q.version)]
+            for q in u.candidates_by_name.get(rname, [])
+            if q.version != rmeta.get("version")
+        ]
+        for s in rmeta.get("requires") or []:
+            try:
+                e = parse_dep_expr(s)
+            except Exception:
+                continue
+            parts = flatten_and(e) if e.kind == "and" else [e]
+            for part in parts:
+                if part.kind != "atom" or not part.atom.op or not part.atom.ver:
+                    continue
+                for q in u.candidates_by_name.get(part.atom.name, []):
+                    if q.name == rname or satisfies(q.version, f"{part.atom.op}{part.atom.ver}"):
+                        continue
+                    cnf.add([-var_of[(q.name, q.version)]] + rvars)
+                    if not rvars:
+                        disqualified.setdefault(
+                            (q.name, q.version),
+                            f"{q.name}-{q.version} would break installed "
+                            f"{rname}-{rmeta.get('version')} (requires {dep_expr_to_str(part)})",
+                        )
+
     # goals
     def _format_reasons(name: Optional[str]) -> Optional[str]:
         if not name:
@@ -1399,7 +2129,7 @@ def encode_resolution(
                     raise ResolutionError(
                         f"No provider for goal part '{part_label}' (from '{goal_label}')"
                     )
-                cnf.add(disj)
+                add_required(disj, None, part, goal_label)
         else:
             disj = expr_to_cnf_disj(u, g, cnf, var_of)
             if not disj:
@@ -1411,7 +2141,7 @@ def encode_resolution(
                 if reason:
                     raise ResolutionError(reason)
                 raise ResolutionError(f"No provider for goal '{goal_label}'")
-            cnf.add(disj)
+            add_required(disj, None, g, goal_label)
 
     return (
         cnf,
@@ -1422,26 +2152,438 @@ def encode_resolution(
         decay_map,
         disqualified,
         terminal,
+        soft,
     )
 
-def solve(
-    goals: List[str], universe: Universe, *, include_build_requires: bool = False
-) -> List[PkgMeta]:
-    def _summarize_unsat_packages(packages: List[str]) -> str:
-        pkg_set = set(packages)
-        conflicts: Set[Tuple[str, str]] = set()
-        dep_edges: Dict[str, Set[str]] = {name: set() for name in pkg_set}
+@dataclass
+class InstallSelection:
+    """Packages picked by a solver model, keyed by package name."""
 
-        for name in pkg_set:
-            for candidate in universe.candidates_by_name.get(name, []):
-                # detect conflicts/obsoletes among the unsat core packages
-                for expr_text in list(candidate.conflicts) + list(candidate.obsoletes):
-                    if not expr_text:
-                        continue
-                    try:
-                        expr = parse_dep_expr(expr_text)
-                    except Exception:
-                        continue
+    packages: Dict[str, PkgMeta] = field(default_factory=dict)
+
+    def ids(self) -> Set[Tuple[str, str]]:
+        return {(p.name, p.version) for p in self.packages.values()}
+
+    def __contains__(self, name: object) -> bool:
+        return name in self.packages
+
+    def __len__(self) -> int:
+        return len(self.packages)
+
+
+def decode_model(
+    model: Mapping[int, bool],
+    universe: Universe,
+    var_of: Mapping[Tuple[str, str], int],
+) -> InstallSelection:
+    """Turn a solver assignment into the selected package versions.
+
+    Only variables in *var_of* denote packages; auxiliary variables such as the
+    at-most-one ladder are ignored.
+    """
+
+    inv = {v: key for key, v in var_of.items()}
+    selection = InstallSelection()
+    for vid, val in model.items():
+        if not val:
+            continue
+        key = inv.get(vid)
+        if not key:
+            continue
+        name, ver = key
+        for p in universe.candidates_by_name.get(name, []):
+            if p.version == ver:
+                selection.packages[name] = p
+                break
+    return selection
+
+
+def minimize_model(
+    cnf: CNF,
+    model: Mapping[int, bool],
+    var_of: Mapping[Tuple[str, str], int],
+) -> Dict[int, bool]:
+    """Drop packages the goals do not need from a satisfying *model*.
+
+    Starting from no packages, a package variable is kept only when a clause
+    would otherwise be violated; every kept package is one the model already
+    selected, so the result still satisfies the CNF.  A recommends clause the
+    model honoured (its relaxation variable is false) keeps its provider the
+    same way.
+    """
+
+    pkg_vars = set(var_of.values())
+    kept: Set[int] = set()
+    by_negated: Dict[int, List[List[int]]] = {}
+    pending: List[List[int]] = []
+
+    for idx, clause in enumerate(cnf.clauses):
+        if not clause or idx in cnf.learnts:
+            continue
+        pending.append(clause)
+        for lit in clause:
+            if lit < 0 and -lit in pkg_vars:
+                by_negated.setdefault(-lit, []).append(clause)
+
+    def holds(lit: int) -> bool:
+        v = abs(lit)
+        val = (v in kept) if v in pkg_vars else bool(model.get(v))
+        return val if lit > 0 else not val
+
+    while pending:
+        clause = pending.pop()
+        if any(holds(lit) for lit in clause):
+            continue
+        for lit in clause:
+            if lit > 0 and lit in pkg_vars and model.get(lit):
+                kept.add(lit)
+                pending.extend(by_negated.get(lit, ()))
+                break
+
+    return {v: (v in kept) if v in pkg_vars else val for v, val in model.items()}
+
+
+def package_id(p: PkgMeta) -> str:
+    return f"{p.name}-{p.version}-{p.release}.{p.arch}"
+
+
+def _expr_atoms(e: DepExpr) -> List[Atom]:
+    if e.kind == "atom":
+        return [e.atom] if e.atom else []
+    return _expr_atoms(e.left) + _expr_atoms(e.right)
+
+
+def _requirement_atoms(text: str) -> List[Atom]:
+    try:
+        return _expr_atoms(parse_dep_expr(text))
+    except Exception:
+        return []
+
+
+def resolution_trace(
+    goals: List[str],
+    universe: Universe,
+    plan: List[PkgMeta],
+    *,
+    disqualified: Optional[Mapping[Tuple[str, str], str]] = None,
+    include_build_requires: bool = False,
+) -> Dict[str, object]:
+    """Explain *plan*: why each package was selected and its rivals were not.
+
+    Every selected package, keyed by :func:`package_id`, lists the goals and
+    dependency edges (``requires`` or ``recommends`` of another selected
+    package) it satisfies, and the other candidates for its name with the
+    reason they lost: ``conflict``, ``version`` (excluded by a requirement or
+    pin), ``arch``, ``missing-dependency`` or plain ``preference``.
+    """
+
+    arch = effective_arch()
+
+    def provides(atom: Atom, pkg: PkgMeta) -> bool:
+        return pkg in providers_for(universe, atom)
+
+    def names(atom: Atom) -> Set[str]:
+        return {q.name for q in universe.providers.get(atom.name, [])} | {atom.name}
+
+    edges: List[Tuple[str, Optional[PkgMeta], str]] = [("goal", None, g) for g in goals]
+    for q in plan:
+        edges += [("dependency", q, r) for r in _iter_requires(q, include_build_requires) if r]
+        edges += [("recommends", q, r) for r in q.recommends if r]
+
+    def conflict_with(cand: PkgMeta) -> Optional[str]:
+        for other in plan:
+            if other.name == cand.name:
+                continue
+            for text in list(cand.conflicts) + list(cand.obsoletes):
+                if any(provides(a, other) for a in _requirement_atoms(text)):
+                    return f"conflicts with {package_id(other)} ({text})"
+            for text in list(other.conflicts) + list(other.obsoletes):
+                if any(provides(a, cand) for a in _requirement_atoms(text)):
+                    return f"{package_id(other)} conflicts with it ({text})"
+        return None
+
+    packages: Dict[str, object] = {}
+    for p in plan:
+        reasons: List[Dict[str, str]] = []
+        constraints: List[Tuple[str, Atom]] = []
+        for kind, source, text in edges:
+            if source is not None and source.name == p.name:
+                continue
+            atoms = [a for a in _requirement_atoms(text) if p.name in names(a)]
+            if not atoms:
+                continue
+            if any(provides(a, p) for a in atoms):
+                reason = {"kind": kind, "requirement": text}
+                if source is not None:
+                    reason["package"] = package_id(source)
+                reasons.append(reason)
+            if kind != "recommends":
+                origin = package_id(source) if source is not None else "goal"
+                constraints += [(origin, a) for a in atoms if a.op and a.ver]
+
+        rejected: Dict[str, Dict[str, str]] = {}
+        for cand in universe.candidates_by_name.get(p.name, []):
+            if cand is p:
+                continue
+            kind, detail = "conflict", conflict_with(cand)
+            if detail is None and not arch_compatible(cand.arch, arch):
+                kind, detail = "arch", f"{cand.arch} does not run on {arch}"
+            if detail is None:
+                unmet = [
+                    f"{origin} requires {atom_to_str(a)}"
+                    for origin, a in constraints
+                    if not provides(a, cand)
+                ]
+                pin = universe.pins.get(p.name)
+                if pin and not satisfies(cand.version, pin):
+                    unmet.append(f"pinned to {pin}")
+                if unmet:
+                    kind, detail = "version", "; ".join(unmet)
+            if detail is None and (cand.name, cand.version) in (disqualified or {}):
+                kind, detail = "missing-dependency", disqualified[(cand.name, cand.version)]
+            if detail is None:
+                kind, detail = "preference", f"{p.version} preferred over {cand.version}"
+            rejected[package_id(cand)] = {"reason": kind, "detail": detail}
+
+        packages[package_id(p)] = {
+            "name": p.name,
+            "version": p.version,
+            "selected_because": reasons,
+            "rejected": rejected,
+        }
+    return {"goals": list(goals), "packages": packages}
+
+
+class ResolutionPolicy(str, Enum):
+    """Which version wins when several satisfy every constraint."""
+
+    PREFER_NEWEST = "newest"
+    PREFER_INSTALLED = "installed"
+    MINIMAL = "minimal"
+
+
+def _older_candidates(pkgs: Sequence[PkgMeta]) -> List[PkgMeta]:
+    """Return the candidates of *pkgs* older than the newest version among them."""
+
+    if not pkgs:
+        return []
+    newest = max(pkgs, key=lambda p: parse_semver(p.version)).version
+    return [p for p in pkgs if cmp_semver(p.version, newest) < 0]
+
+
+def policy_soft_levels(
+    policy: ResolutionPolicy,
+    universe: Universe,
+    var_of: Mapping[Tuple[str, str], int],
+) -> List[List[int]]:
+    """Return the soft literals, highest priority first, that express *policy*.
+
+    Each literal should hold; violating an earlier level costs more than any
+    number of violations in later ones (see :func:`maxsat.minimize_lexicographic`).
+    Candidates are ordered by priority first, so the newest and oldest ones
+    are found by comparing versions.
+    """
+
+    def avoid(pkgs: Iterable[PkgMeta]) -> List[int]:
+        return [-var_of[(p.name, p.version)] for p in pkgs]
+
+    lists = universe.candidates_by_name
+    older = [lit for lst in lists.values() for lit in avoid(_older_candidates(lst))]
+    if policy is ResolutionPolicy.MINIMAL:
+        oldest = {name: min(lst, key=lambda p: parse_semver(p.version)).version for name, lst in lists.items() if lst}
+        return [[
+            lit
+            for name, lst in lists.items()
+            for lit in avoid(p for p in lst if cmp_semver(p.version, oldest[name]) > 0)
+        ]]
+    if policy is ResolutionPolicy.PREFER_INSTALLED:
+        moved = [
+            lit
+            for name, meta in universe.installed.items()
+            for lit in avoid(p for p in lists.get(name, []) if p.version != meta.get("version"))
+        ]
+        return [moved, older]
+    return [older]
+
+
+class PlanError(ResolutionError):
+    """Raised when a resolved package set cannot be put in install order."""
+
+
+class PlanCycleError(PlanError):
+    """Raised by :func:`order_plan` when packages depend on each other in a loop.
+
+    ``cycle`` lists the package names along the loop, in dependency order.
+    """
+
+    def __init__(self, cycle: List[str]) -> None:
+        super().__init__("dependency cycle: " + " -> ".join(cycle + cycle[:1]))
+        self.cycle = cycle
+
+
+class VersionConflictError(ResolutionError):
+    """Raised when the requirements on one package admit no common version.
+
+    ``package`` is the contested name and ``requested_by`` pairs each
+    requester (``None`` for the resolution goals) with its constraint.
+    """
+
+    def __init__(self, package: str, requested_by: List[Tuple[Optional[str], Constraint]]) -> None:
+        wants = ", ".join(f"{who or 'the request'} requires {package}{want}" for who, want in requested_by)
+        super().__init__(f"version conflict on {package}: {wants}")
+        self.package = package
+        self.requested_by = requested_by
+
+
+class GoalConflictError(ResolutionError):
+    """Raised when several requested packages cannot be installed together.
+
+    ``goals`` lists, in request order, the requests that take part in a
+    minimal unsatisfiable core of the combined resolution.
+    """
+
+    def __init__(self, goals: List[str], message: str) -> None:
+        super().__init__(f"{message}; requested packages cannot be installed together: {', '.join(goals)}")
+        self.goals = goals
+
+
+def _version_conflict(
+    core: Iterable[int], origins: Mapping[int, ClauseOrigin], universe: Universe
+) -> Optional[VersionConflictError]:
+    """Explain an UNSAT result as a :class:`VersionConflictError`, if it is one.
+
+    The minimal unsatisfiable *core* is mapped back to the requirements that
+    produced its clauses; a package constrained by several requesters whose
+    versioned requirements share no provider is the conflict.
+    """
+
+    wants: Dict[str, Dict[Optional[str], Atom]] = {}
+    for idx in core:
+        origin = origins.get(idx)
+        if origin is None or origin.expr.kind != "atom":
+            continue
+        atom = origin.expr.atom
+        if atom and atom.op and atom.ver:
+            wants.setdefault(atom.name, {}).setdefault(origin.requester, atom)
+    for name, by in wants.items():
+        if len(by) < 2:
+            continue
+        common: Optional[Set[Tuple[str, str]]] = None
+        for atom in by.values():
+            ids = {(p.name, p.version) for p in providers_for(universe, atom)}
+            common = ids if common is None else common & ids
+        if not common:
+            return VersionConflictError(
+                name, [(who, Constraint.parse(f"{atom.op}{atom.ver}")) for who, atom in by.items()]
+            )
+    return None
+
+
+def plan_edges(packages: Iterable[PkgMeta], universe: Universe) -> Dict[str, List[str]]:
+    """Map each package name to the names in *packages* it requires."""
+
+    chosen = {p.name: p for p in packages}
+    edges: Dict[str, List[str]] = {}
+    for p in chosen.values():
+        deps: Dict[str, None] = {}
+        for s in p.requires:
+            for part in flatten_and(parse_dep_expr(s)):
+                for alt in flatten_or(part):
+                    if alt.kind != "atom":
+                        continue
+                    for q in providers_for(universe, alt.atom):
+                        if q.name in chosen and q.name != p.name:
+                            deps[q.name] = None
+        edges[p.name] = list(deps)
+    return edges
+
+
+def order_plan(packages: Iterable[PkgMeta], universe: Universe) -> List[PkgMeta]:
+    """Return *packages* with every dependency before the packages requiring it.
+
+    Packages are emitted in waves: first those requiring nothing else in the
+    set, then those whose requirements are all emitted, and so on.  Within a
+    wave the input order is kept, so the result is deterministic.  Raises
+    :class:`PlanCycleError` when the remaining packages only depend on each
+    other.
+    """
+
+    pending = list(packages)
+    edges = plan_edges(pending, universe)
+    done: Set[str] = set()
+    ordered: List[PkgMeta] = []
+    while pending:
+        wave = [p for p in pending if all(d in done for d in edges[p.name])]
+        if not wave:
+            raise PlanCycleError(_find_cycle({p.name for p in pending}, edges))
+        ordered.extend(wave)
+        done.update(p.name for p in wave)
+        pending = [p for p in pending if p.name not in done]
+    return ordered
+
+
+def _find_cycle(names: Set[str], edges: Mapping[str, List[str]]) -> List[str]:
+    # Every package in *names* has a dependency in *names*, so walking any
+    # dependency chain must eventually revisit a package.
+    path: List[str] = []
+    seen: Dict[str, int] = {}
+    node = min(names)
+    while node not in seen:
+        seen[node] = len(path)
+        path.append(node)
+        node = next(d for d in edges[node] if d in names)
+    return path[seen[node]:]
+
+
+def solve(
+    goals: List[str],
+    universe: Universe,
+    *,
+    include_build_requires: bool = False,
+    recommends: bool = True,
+    policy: Optional[ResolutionPolicy] = None,
+    deadline: Optional[Deadline] = None,
+    cache: Optional[ResolutionCache] = None,
+    on_event: Optional[Callable[[SolverEvent], None]] = None,
+    trace: Optional[Dict[str, object]] = None,
+    seed: Optional[int] = None,
+    dependencies: bool = True,
+) -> List[PkgMeta]:
+    """Resolve *goals* against *universe* and return the plan in install order.
+
+    With a *cache* (or ``RESOLVE_CACHE`` enabled in ``lpm.conf``) a plan for
+    the same fingerprint is returned without running the solver.  *on_event*
+    is forwarded to :meth:`CDCLSolver.solve` for progress reporting.  With
+    ``SOLVER_OPTIMIZE`` set, the plan is then optimised to select as few
+    non-newest versions as possible.  An explicit *policy* instead optimises
+    the plan for that :class:`ResolutionPolicy`.  A *trace* dict is filled
+    with the :func:`resolution_trace` of the returned plan.
+
+    A *seed* (default: ``SOLVER_SEED`` from ``lpm.conf``) makes resolution
+    reproducible: the universe is put in :func:`canonicalize_universe` order
+    and the solver breaks ties with :meth:`CDCLSolver.with_seed`, so the same
+    goals, universe contents and seed always give the same plan.
+
+    With *dependencies* false the requirements of the candidates are ignored
+    and the plan holds just the goals; their conflicts with each other and
+    with installed packages are still enforced.
+    """
+
+    def _summarize_unsat_packages(packages: List[str]) -> str:
+        pkg_set = set(packages)
+        conflicts: Set[Tuple[str, str]] = set()
+        dep_edges: Dict[str, Set[str]] = {name: set() for name in pkg_set}
+
+        for name in pkg_set:
+            for candidate in universe.candidates_by_name.get(name, []):
+                # detect conflicts/obsoletes among the unsat core packages
+                for expr_text in list(candidate.conflicts) + list(candidate.obsoletes):
+                    if not expr_text:
+                        continue
+                    try:
+                        expr = parse_dep_expr(expr_text)
+                    except Exception:
+                        continue
                     parts = flatten_and(expr) if expr.kind == "and" else [expr]
                     for part in parts:
                         if part.kind != "atom" or not part.atom:
@@ -1502,7 +2644,42 @@ def solve(
             return ""
         return " (" + "; ".join(details) + ")"
 
+    deadline = deadline or _deadline.current()
+    if deadline is not None:
+        deadline.check("dependency resolution")
+    var_decay = float(CONF.get("VSIDS_VAR_DECAY", "0.95"))
+    cla_decay = float(CONF.get("VSIDS_CLAUSE_DECAY", "0.999"))
+    seed = _config.SOLVER_SEED if seed is None else seed
+    if seed is not None:
+        universe = canonicalize_universe(universe)
+    if cache is None and _config.RESOLVE_CACHE:
+        cache = ResolutionCache(CACHE_DIR / "resolve")
+    cache_key = None
+    if cache is not None:
+        cache_key = cache.fingerprint(
+            goals,
+            universe,
+            {
+                "include_build_requires": include_build_requires,
+                "recommends": recommends,
+                "var_decay": var_decay,
+                "cla_decay": cla_decay,
+                "max_learnts": MAX_LEARNT_CLAUSES,
+                "optimize": _config.SOLVER_OPTIMIZE,
+                "policy": ResolutionPolicy(policy).value if policy else None,
+                "seed": seed,
+                "dependencies": dependencies,
+            },
+        )
+        cached = cache.load(cache_key, universe)
+        if cached is not None:
+            if trace is not None:
+                trace.update(
+                    resolution_trace(goals, universe, cached, include_build_requires=include_build_requires)
+                )
+            return cached
     goal_exprs = [parse_dep_expr(s) for s in goals]
+    origins: Dict[int, ClauseOrigin] = {}
     (
         cnf,
         var_of,
@@ -1510,10 +2687,17 @@ def solve(
         pfalse,
         bias_map,
         decay_map,
-        _disqualified,
+        disqualified,
         terminal_errors,
+        soft,
     ) = encode_resolution(
-        universe, goal_exprs, goals, include_build_requires=include_build_requires
+        universe,
+        goal_exprs,
+        goals,
+        include_build_requires=include_build_requires,
+        recommends=recommends,
+        origins=origins,
+        dependencies=dependencies,
     )
 
     for expr in goal_exprs:
@@ -1522,8 +2706,12 @@ def solve(
         reasons = terminal_errors.get(expr.atom.name)
         if reasons:
             raise ResolutionError("; ".join(dict.fromkeys(reasons)))
-    var_decay = float(CONF.get("VSIDS_VAR_DECAY", "0.95"))
-    cla_decay = float(CONF.get("VSIDS_CLAUSE_DECAY", "0.999"))
+        blocked = [
+            disqualified.get((p.name, p.version))
+            for p in providers_for(universe, expr.atom)
+        ]
+        if blocked and all(blocked):
+            raise ResolutionError("; ".join(dict.fromkeys(blocked)))
     solver = CDCLSolver(
         cnf,
         ptrue,
@@ -1533,37 +2721,70 @@ def solve(
         var_decay=var_decay,
         cla_decay=cla_decay,
         max_learnts=MAX_LEARNT_CLAUSES,
+        seed=seed,
     )
-    res = solver.solve([])
+    res = solver.solve([], deadline=deadline, on_event=on_event)
     inv: Dict[int,Tuple[str,str]] = {v:k for k,v in var_of.items()}
     if not res.sat:
         names = sorted({inv.get(abs(l))[0] for l in (res.unsat_core or []) if abs(l) in inv})
         details = _summarize_unsat_packages(names)
-        raise ResolutionError(
-            "Unsatisfiable dependency set involving: " + ", ".join(names) + details
+        message = "Unsatisfiable dependency set involving: " + ", ".join(names) + details
+        # Explaining the conflict is best effort: once the deadline or the
+        # core budget runs out, report the cheap core of the failed solve.
+        try:
+            core = solver.minimal_unsat_core(origins, deadline=deadline, budget=_config.UNSAT_CORE_BUDGET)
+        except DeadlineExceeded:
+            core = None
+        if core is None:
+            raise ResolutionError(message)
+        conflict = _version_conflict(core, origins, universe)
+        if conflict is not None:
+            raise conflict
+        in_core = {origins[idx].goal for idx in core if idx in origins and origins[idx].requester is None}
+        clashing = [g for g in dict.fromkeys(goals) if g in in_core]
+        if len(clashing) > 1:
+            raise GoalConflictError(clashing, message)
+        raise ResolutionError(message)
+    if policy is not None:
+        # Declining a recommendation costs more than any policy violation.
+        levels = [soft] if soft else []
+        levels += policy_soft_levels(ResolutionPolicy(policy), universe, var_of)
+        best = _maxsat.minimize_lexicographic(cnf, levels, solver, deadline=deadline, on_event=on_event)
+        if best.sat:
+            res = SATResult(True, best.assign)
+    elif soft or _config.SOLVER_OPTIMIZE != "off":
+        # Recommends are optimized even with SOLVER_OPTIMIZE=off, and
+        # declining one costs more than any number of older versions.
+        levels = [soft] if soft else []
+        if _config.SOLVER_OPTIMIZE != "off":
+            levels.append([
+                -var_of[(p.name, p.version)]
+                for lst in universe.candidates_by_name.values()
+                for p in _older_candidates(lst)
+            ])
+        if len(levels) > 1:
+            best = _maxsat.minimize_lexicographic(cnf, levels, solver, deadline=deadline, on_event=on_event)
+        else:
+            minimize = _maxsat.minimize_core_guided if _config.SOLVER_OPTIMIZE == "core" else _maxsat.minimize_linear
+            best = minimize(cnf, levels[0], solver, deadline=deadline, on_event=on_event)
+        if best.sat:
+            res = SATResult(True, best.assign)
+    model = minimize_model(cnf, res.assign, var_of)
+    chosen = decode_model(model, universe, var_of).packages
+    plan = order_plan(chosen.values(), universe)
+    if cache is not None:
+        cache.store(cache_key, plan)
+    if trace is not None:
+        trace.update(
+            resolution_trace(
+                goals,
+                universe,
+                plan,
+                disqualified=disqualified,
+                include_build_requires=include_build_requires,
+            )
         )
-    chosen: Dict[str,PkgMeta] = {}
-    for vid,val in res.assign.items():
-        if not val: continue
-        key = inv.get(vid); 
-        if not key: continue
-        name,ver = key
-        for p in universe.candidates_by_name.get(name, []):
-            if p.version==ver: chosen[name]=p; break
-    # topo-ish order by requires depth
-    chosen_names=set(chosen.keys()); dep_depth: Dict[str,int]={}
-    def depth_of(p: PkgMeta)->int:
-        if p.name in dep_depth: return dep_depth[p.name]
-        d=0
-        for s in p.requires:
-            e=parse_dep_expr(s); parts=flatten_and(e) if e.kind=="and" else [e]
-            for part in parts:
-                if part.kind=="atom":
-                    for q in providers_for(universe, part.atom):
-                        if q.name in chosen_names:
-                            d=max(d, 1+depth_of(chosen[q.name]))
-        dep_depth[p.name]=d; return d
-    return sorted(chosen.values(), key=lambda p: depth_of(p))
+    return plan
 
 # =========================== Hooks =============================================
 def _detect_python_interpreter() -> Optional[str]:
@@ -1615,11 +2836,15 @@ def _shebang_command(script: Path) -> Optional[List[str]]:
 
 
 def _run_hook_script(script: Path, env: Dict[str, str], *, hook_name: str, package_context: str):
+    defer = _hook_deferrer()
+    if defer is not None:
+        defer(hook_name, [str(script)], env, package_context)
+        return
     merged_env = {**os.environ, **env}
     if os.access(script, os.X_OK):
         try:
             with privileged_section():
-                subprocess.run([str(script)], env=merged_env, check=True)
+                subprocess.run([str(script)], env=merged_env, check=True, **_deadline.subprocess_kwargs())
             return
         except OSError as exc:
             if exc.errno not in {errno.ENOEXEC, errno.EACCES}:
@@ -1629,13 +2854,13 @@ def _run_hook_script(script: Path, env: Dict[str, str], *, hook_name: str, packa
         interpreter = _detect_python_for_hooks()
         if interpreter:
             with privileged_section():
-                subprocess.run([interpreter, str(script)], env=merged_env, check=True)
+                subprocess.run([interpreter, str(script)], env=merged_env, check=True, **_deadline.subprocess_kwargs())
             return
 
         shebang_cmd = _shebang_command(script)
         if shebang_cmd:
             with privileged_section():
-                subprocess.run([*shebang_cmd, str(script)], env=merged_env, check=True)
+                subprocess.run([*shebang_cmd, str(script)], env=merged_env, check=True, **_deadline.subprocess_kwargs())
             return
 
         raise RuntimeError(f"Unable to locate Python interpreter for hook {script}")
@@ -1643,10 +2868,14 @@ def _run_hook_script(script: Path, env: Dict[str, str], *, hook_name: str, packa
     shebang_cmd = _shebang_command(script)
     if shebang_cmd:
         with privileged_section():
-            subprocess.run([*shebang_cmd, str(script)], env=merged_env, check=True)
+            subprocess.run([*shebang_cmd, str(script)], env=merged_env, check=True, **_deadline.subprocess_kwargs())
 
 
 def _run_hook_config(config_path: Path, env: Dict[str, str], *, hook_name: str, package_context: str) -> None:
+    defer = _hook_deferrer()
+    if defer is not None:
+        defer(hook_name, [str(config_path)], env, package_context)
+        return
     merged_env = {**os.environ, **env}
     hooks = load_hooks([config_path.parent])
     hook = hooks.get(config_path.stem)
@@ -1667,7 +2896,7 @@ def _run_hook_config(config_path: Path, env: Dict[str, str], *, hook_name: str,
         if exec_path.is_absolute():
             _ensure_executable(exec_path)
     with privileged_section():
-        subprocess.run(exec_cmd, env=merged_env, check=True)
+        subprocess.run(exec_cmd, env=merged_env, check=True, **_deadline.subprocess_kwargs())
 
 
 def run_hook(hook: str, env: Dict[str,str], *, failure_mode: str = HookFailureMode.STRICT, package_context: Optional[str] = None):
@@ -2078,6 +3307,8 @@ def collect_manifest(stagedir: Path) -> List[Dict[str, object]]:
                 continue
 
             entry["size"] = st.st_size
+            if rel.startswith("etc/"):
+                entry["config"] = True
             try:
                 entry["sha256"] = sha256sum(f)
             except OSError:
@@ -2638,19 +3869,7 @@ def build_package(stagedir: Path, meta: PkgMeta, out: Path, sign=True):
         else:
             sig = out.with_suffix(out.suffix + ".sig")
             try:
-                subprocess.run(
-                    [
-                        "openssl",
-                        "dgst",
-                        "-sha256",
-                        "-sign",
-                        str(SIGN_KEY),
-                        "-out",
-                        str(sig),
-                        str(out),
-                    ],
-                    check=True,
-                )
+                subprocess.run(sign_command(SIGN_KEY, out, sig), check=True)
             except subprocess.CalledProcessError as exc:
                 warn(
                     "openssl failed to sign package; package will remain unsigned. "
@@ -2686,37 +3905,44 @@ def read_package_meta(blob: Path) -> Tuple[PkgMeta, List[dict]]:
 
   
 # =========================== Signature verification ===========================
-def _verify_with_key(pubkey: Path, blob: Path, sig: Path) -> bool:
-    try:
-        subprocess.run(
-            ["openssl","dgst","-sha256","-verify",str(pubkey),"-signature",str(sig),str(blob)],
-            check=True, stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL
-        )
-        return True
-    except subprocess.CalledProcessError:
-        return False
+def trusted_keyring() -> Keyring:
+    return Keyring.load(Path(_resolve_lpm_attr("TRUST_DIR", TRUST_DIR)))
 
-def verify_signature(blob: Path, sig: Optional[Path]) -> None:
+def verify_signature(blob: Path, sig: Optional[Path], keyring: Optional[Keyring] = None) -> None:
+    """Check *blob* against its detached *sig*, raising :class:`VerifyError` on failure."""
     if not sig or not sig.exists():
-        raise RuntimeError(f"Missing signature for {blob.name}")
-    keys = sorted(TRUST_DIR.glob("*.pem")) if TRUST_DIR.exists() else []
-    if not keys:
-        raise RuntimeError(f"No trusted public keys in {TRUST_DIR}")
-    for k in keys:
-        if _verify_with_key(k, blob, sig):
-            ok(f"Signature OK ({k.name}) for {blob.name}")
-            return
-    raise RuntimeError(f"Signature verification failed for {blob.name}")
+        raise VerifyError(f"Missing signature for {blob.name}")
+    keyring = keyring if keyring is not None else trusted_keyring()
+    if not keyring:
+        raise VerifyError(f"No trusted public keys in {_resolve_lpm_attr('TRUST_DIR', TRUST_DIR)}")
+    try:
+        key = keyring.verify(blob.read_bytes(), sig.read_bytes())
+    except VerifyError as exc:
+        raise VerifyError(f"Signature verification failed for {blob.name}: {exc}") from exc
+    ok(f"Signature OK ({key.path.name}) for {blob.name}")
 
 # =========================== Install/Remove/Upgrade ===========================
-def extract_tar(blob: Path, root: Path) -> List[str]:
+def extract_tar(
+    blob: Path,
+    root: Path,
+    *,
+    total_bytes: Optional[int] = None,
+    on_progress: Optional[Callable[[int], None]] = None,
+) -> List[str]:
     """
     Extract a .zst package into root using streaming mode.
     Returns the list of installed file paths.
+
+    The size of each extracted regular file is reported to *on_progress*;
+    without one a progress display over *total_bytes* is shown.
     """
     manifest = []
-    with open_package_tar(blob, stream=True) as tf:
-        for m in progress_bar(tf, desc=f"Extracting {blob.name}", unit="file"):
+    if on_progress is None:
+        reporter = _progress.track(f"Extracting {blob.name}", total_bytes)
+    else:
+        reporter = contextlib.nullcontext(on_progress)
+    with open_package_tar(blob, stream=True) as tf, reporter as report:
+        for m in tf:
             if Path(m.name).name in (".lpm-meta.json", ".lpm-manifest.json"):
                 continue
             rel = Path(m.name).as_posix().lstrip("/")
@@ -2739,6 +3965,8 @@ def extract_tar(blob: Path, root: Path) -> List[str]:
                     else:
                         raise
             manifest.append("/" + rel)
+            if m.isfile():
+                report(m.size)
 
     return manifest
 
@@ -2781,11 +4009,18 @@ def _download_to(location: str, dest: Path) -> None:
             raise FileNotFoundError(src)
         shutil.copy2(src, dest)
         return
-    data, _ = _resolve_lpm_attr("urlread", urlread)(location)
+    data, _ = repo_urlread(location)
     dest.write_bytes(data)
 
 
-def _ensure_signature(url: str, sig_dst: Path) -> Optional[Path]:
+def _ensure_signature(
+    url: str,
+    sig_dst: Path,
+    *,
+    offline: Optional[bool] = None,
+    policy: Optional[RetryPolicy] = None,
+    sleep: Optional[Callable[[float], None]] = None,
+) -> Optional[Path]:
     try:
         if url.startswith("file://"):
             src = Path(url[7:]).with_suffix(Path(url[7:]).suffix + ".sig")
@@ -2799,7 +4034,7 @@ def _ensure_signature(url: str, sig_dst: Path) -> Optional[Path]:
                 return sig_dst
         else:
             sig_url = url + ".sig"
-            data, _ = _resolve_lpm_attr("urlread", urlread)(sig_url)
+            data, _ = repo_urlread(sig_url, policy=policy, sleep=sleep, offline=offline)
             sig_dst.write_bytes(data)
             return sig_dst
     except Exception:
@@ -2870,61 +4105,198 @@ def _attempt_delta(pkg: PkgMeta, dst: Path) -> bool:
         raise RuntimeError(f"delta required for {pkg.name} but no candidate succeeded")
     return False
 
-def fetch_blob(p: PkgMeta) -> Tuple[Path, Optional[Path]]:
+def fetch_blob(
+    p: PkgMeta,
+    on_progress: Optional[Callable[[int], None]] = None,
+    *,
+    offline: Optional[bool] = None,
+    policy: Optional[RetryPolicy] = None,
+    sleep: Optional[Callable[[float], None]] = None,
+) -> Tuple[Path, Optional[Path]]:
+    """Return the cached archive and signature for *p*, downloading as needed.
+
+    *on_progress* receives byte deltas; archives already in the cache, or
+    rebuilt from a delta, are reported in one step.  Downloads are retried
+    under *policy* (default: the configured :class:`RetryPolicy`), waiting
+    with *sleep*.
+
+    With *offline* (default: :func:`is_offline`) nothing is downloaded and a
+    cached archive is used as is.  One without a sha256 in the index must
+    then pass :func:`verify_signature` instead.
+    """
     if not p.blob: die(f"{p.name}-{p.version} missing blob")
+    offline = is_offline() if offline is None else offline
     url = p.blob
     dst = _cache_path_for(url)
     sig_dst = dst.with_suffix(dst.suffix + ".sig")
 
-    if dst.exists() and p.sha256:
+    if dst.exists() and (p.sha256 or offline):
         try:
-            if file_sha256(dst) == p.sha256:
-                sig_path = _ensure_signature(url, sig_dst)
+            if not p.sha256 or file_sha256(dst) == p.sha256:
+                sig_path = _ensure_signature(url, sig_dst, offline=offline, policy=policy, sleep=sleep)
+                if not p.sha256:
+                    # Without a checksum only the signature vouches for the cached copy.
+                    verify_signature(dst, sig_path)
+                _cache.touch(dst)
+                if on_progress is not None:
+                    on_progress(dst.stat().st_size)
                 return dst, sig_path
         except Exception:
             pass
 
     used_delta = False
     try:
-        used_delta = _attempt_delta(p, dst)
+        used_delta = not offline and _attempt_delta(p, dst)
     except Exception:
         if _current_delta_mode() == "always":
             raise
 
-    if not used_delta:
+    if used_delta:
+        if on_progress is not None:
+            on_progress(dst.stat().st_size)
+    else:
         if url.startswith("file://"):
             src = Path(url[7:])
             if not src.exists():
                 die(f"blob not found {src}")
             if not dst.exists() or src.stat().st_mtime > dst.stat().st_mtime:
                 shutil.copy2(src, dst)
+            if on_progress is not None:
+                on_progress(dst.stat().st_size)
         elif url.startswith("/") and Path(url).exists():
             src = Path(url)
             if not dst.exists() or src.stat().st_mtime > dst.stat().st_mtime:
                 shutil.copy2(src, dst)
+            if on_progress is not None:
+                on_progress(dst.stat().st_size)
         else:
-            for _ in progress_bar(range(1), desc=f"Downloading {p.name}"):
-                data, _ = _resolve_lpm_attr("urlread", urlread)(url)
-                dst.write_bytes(data)
+            def _verify(data: bytes) -> None:
+                if p.sha256 and hashlib.sha256(data).hexdigest() != p.sha256:
+                    raise ChecksumError(f"{p.name}-{p.version}: checksum mismatch for {url}")
+
+            if on_progress is not None:
+                data, _ = repo_urlread(
+                    url, validate=_verify, policy=policy, sleep=sleep, on_progress=on_progress, offline=offline
+                )
+            else:
+                with _progress.track(f"Downloading {p.name}", p.size) as bar:
+                    data, _ = repo_urlread(
+                        url, validate=_verify, policy=policy, sleep=sleep, on_progress=bar, offline=offline
+                    )
+            dst.write_bytes(data)
 
-    sig_path = _ensure_signature(url, sig_dst)
+    sig_path = _ensure_signature(url, sig_dst, offline=offline, policy=policy, sleep=sleep)
+    _cache.touch(dst)
     return dst, sig_path
 
 
+class Fetcher(Protocol):
+    """Source of package archives for :func:`download_packages`."""
+
+    def fetch(self, pkg: PkgMeta) -> Tuple[bytes, Optional[bytes]]:
+        """Return the archive bytes of *pkg* and its detached signature, if any."""
+
+
+class RepoFetcher:
+    """:class:`Fetcher` that downloads through the package cache.
+
+    Transient failures are retried under *policy*, by default the configured
+    :class:`RetryPolicy`; *sleep* replaces :func:`time.sleep` between attempts.
+    """
+
+    def __init__(
+        self, policy: Optional[RetryPolicy] = None, sleep: Optional[Callable[[float], None]] = None
+    ) -> None:
+        self.policy = policy
+        self.sleep = sleep
+
+    def fetch(self, pkg: PkgMeta) -> Tuple[bytes, Optional[bytes]]:
+        blob, sig = fetch_blob(pkg, policy=self.policy, sleep=self.sleep)
+        return blob.read_bytes(), sig.read_bytes() if sig is not None and sig.exists() else None
+
+
+def download_packages(
+    plan: Iterable[PkgMeta],
+    dest: Path,
+    fetcher: Fetcher,
+    *,
+    keyring: Optional[Keyring] = None,
+    progress: Optional[_progress.Progress] = None,
+) -> List[Path]:
+    """Write the archive (and signature) of every package in *plan* into *dest*.
+
+    Archives with a repository checksum are verified as they are written; a
+    mismatch removes the file and raises :class:`~lpm.fs_ops.ChecksumMismatch`.
+    With a *keyring*, an archive whose detached signature is missing or not
+    made by a trusted key raises :class:`~lpm.verify.VerifyError` before
+    anything is written for it.  Each finished package advances *progress*
+    by one.
+    """
+
+    plan = list(plan)
+    if progress is None:
+        progress = _progress.track("Downloading", unit="pkg")
+    dest.mkdir(parents=True, exist_ok=True)
+    progress.start(len(plan))
+    try:
+        return _download_each(plan, dest, fetcher, keyring, progress)
+    finally:
+        progress.finish()
+
+
+def _download_each(
+    plan: List[PkgMeta], dest: Path, fetcher: Fetcher, keyring: Optional[Keyring], progress: _progress.Progress
+) -> List[Path]:
+    written: List[Path] = []
+    for pkg in plan:
+        if not pkg.blob:
+            die(f"{pkg.name}-{pkg.version} missing blob")
+        target = dest / _cache_path_for(pkg.blob).name
+        data, sig = fetcher.fetch(pkg)
+        if keyring is not None:
+            if sig is None:
+                raise VerifyError(f"Missing signature for {target.name}")
+            try:
+                keyring.verify(data, sig)
+            except VerifyError as exc:
+                raise VerifyError(f"Signature verification failed for {target.name}: {exc}") from exc
+        if pkg.sha256:
+            write_verified(target, data, mode=0o644, expected_sha256=pkg.sha256)
+        else:
+            safe_write(target, data, mode=0o644)
+        if sig is not None:
+            safe_write(target.with_name(target.name + ".sig"), sig, mode=0o644)
+        written.append(target)
+        progress.inc(1)
+    return written
+
+
+def enforce_cache_limit(protect: Iterable[Path] = ()) -> List[Path]:
+    """Trim the package cache to ``CACHE_MAX_SIZE`` bytes, keeping *protect*."""
+
+    limit = _config.CACHE_MAX_SIZE
+    if limit <= 0:
+        return []
+    with _cache.in_use(protect):
+        evicted = _cache.enforce_limit(limit, CACHE_DIR)
+    for path in evicted:
+        log(f"[cache] evicted {path.name}")
+    return evicted
+
+
 def fetch_all(pkgs: List[PkgMeta]) -> Dict[str, object]:
     """Fetch all package blobs concurrently."""
     results: Dict[str, object] = {}
     if not pkgs:
         return results
     max_workers = min(max(1, FETCH_MAX_WORKERS), len(pkgs))
-    with ThreadPoolExecutor(max_workers=max_workers) as ex:
-        future_map = {ex.submit(fetch_blob, p): p.name for p in pkgs}
-        for fut in progress_bar(
-            as_completed(future_map),
-            total=len(future_map),
-            desc="Fetching",
-            unit="pkg",
-        ):
+    total = sum(p.size for p in pkgs) if all(p.size for p in pkgs) else None
+    with _progress.track(f"Fetching {len(pkgs)} package(s)", total) as bar, ThreadPoolExecutor(
+        max_workers=max_workers
+    ) as ex:
+        offline = is_offline()
+        future_map = {ex.submit(fetch_blob, p, bar, offline=offline): p.name for p in pkgs}
+        for fut in as_completed(future_map):
             name = future_map[fut]
             try:
                 results[name] = fut.result()
@@ -2945,7 +4317,7 @@ def transaction(conn: sqlite3.Connection, action: str, dry: bool):
         return
 
     try:
-        with global_transaction_lock():
+        with global_transaction_lock(_root_lock_path()):
             conn.execute("BEGIN")
             try:
                 yield
@@ -2975,120 +4347,569 @@ def _normalize_package_list(values: Iterable[str]) -> List[str]:
     return result
 
 
-def do_install(
+def do_install(
+    pkgs: List[PkgMeta],
+    root: Path,
+    dry: bool,
+    verify: bool,
+    force: bool = False,
+    explicit: Optional[Set[str]] = None,
+    allow_fallback: bool = ALLOW_LPMBUILD_FALLBACK,
+    force_build: bool = False,
+    local_overrides: Optional[Mapping[str, Path]] = None,
+    hook_failure_mode: str = HookFailureMode.STRICT,
+    dependencies: bool = True,
+):
+    global PROTECTED
+    PROTECTED = load_protected()
+
+    root = Path(root)
+
+    explicit = set(explicit or [])
+
+    overrides: Dict[str, Tuple[Path, Optional[Path]]] = {}
+    if local_overrides:
+        for name, path in local_overrides.items():
+            try:
+                overrides[name] = (Path(path).resolve(), None)
+            except Exception:
+                overrides[name] = (Path(path), None)
+
+    to_fetch = [p for p in pkgs if not (p.name in PROTECTED and not force)]
+
+    window = _config.STREAM_WINDOW
+    if window and not dry and not force_build and not overrides:
+        for pkg in pkgs:
+            if pkg.name in PROTECTED and not force:
+                warn(f"{pkg.name} is protected (from {PROTECTED_FILE}) and cannot be installed/upgraded without --force")
+        try:
+            apply_plan_streaming(
+                to_fetch,
+                root,
+                window=window,
+                verify=verify,
+                force=force,
+                explicit=explicit,
+                allow_fallback=allow_fallback,
+                hook_failure_mode=hook_failure_mode,
+            )
+        except PlanApplyError as e:
+            die(str(e))
+        return
+
+    downloads: Dict[str, object] = {}
+    for pkg in to_fetch:
+        if pkg.name in overrides:
+            downloads[pkg.name] = overrides[pkg.name]
+
+    remaining = [p for p in to_fetch if p.name not in downloads]
+    if force_build:
+        for pkg in remaining:
+            downloads[pkg.name] = _FORCE_BUILD_SENTINEL
+    else:
+        downloads.update(fetch_all(remaining))
+        enforce_cache_limit(
+            res[0] for res in downloads.values() if isinstance(res, tuple)
+        )
+
+    hook_txn: Optional[HookTransactionManager] = None
+    installed_state: Dict[str, dict] = {}
+    if not dry:
+        hook_txn = HookTransactionManager(
+            hooks=load_hooks(LIBLPM_HOOK_DIRS),
+            root=root,
+            base_env={"LPM_ROOT": str(root)},
+            failure_mode=hook_failure_mode,
+            defer=_hook_deferrer(),
+        )
+        conn = db()
+        try:
+            installed_state = db_installed(conn)
+        finally:
+            conn.close()
+
+    jobs: List[Tuple[PkgMeta, Path]] = []
+    for pkg in pkgs:
+        if pkg.name in PROTECTED and not force:
+            warn(f"{pkg.name} is protected (from {PROTECTED_FILE}) and cannot be installed/upgraded without --force")
+            continue
+        res = downloads.get(pkg.name)
+        if res is _FORCE_BUILD_SENTINEL:
+            log(f"[build] Building {pkg.name} from source for installation")
+            tmp = Path(f"/tmp/lpm-dep-{pkg.name}.lpmbuild")
+            fetch_lpmbuild(pkg.name, tmp)
+            blob_path, _, _, _ = run_lpmbuild(tmp, prompt_install=False, is_dep=True, build_deps=True)
+        elif isinstance(res, Exception):
+            if not allow_fallback:
+                die(
+                    f"Failed to fetch {pkg.name}: {res}. GitLab fallback is disabled. "
+                    "Re-run with --allow-fallback or set ALLOW_LPMBUILD_FALLBACK=1 in lpm.conf"
+                )
+            warn(f"Could not fetch {pkg.name} from repos ({res}), trying GitLab fallback...")
+            tmp = Path(f"/tmp/lpm-dep-{pkg.name}.lpmbuild")
+            fetch_lpmbuild(pkg.name, tmp)
+            blob_path, _, _, _ = run_lpmbuild(tmp, prompt_install=False, is_dep=True, build_deps=True)
+        else:
+            if res is None:
+                blob_path, _ = fetch_blob(pkg)
+            else:
+                blob_path = res[0]
+        jobs.append((pkg, blob_path))
+
+        if hook_txn is not None:
+            meta, mani = read_package_meta(blob_path)
+            manifest_paths = _normalize_manifest_paths(mani)
+            operation = "Upgrade" if pkg.name in installed_state else "Install"
+            hook_txn.add_package_event(
+                name=meta.name,
+                operation=operation,
+                version=meta.version,
+                release=meta.release,
+                paths=manifest_paths,
+            )
+
+    if not dry:
+        try:
+            apply_plan(
+                jobs,
+                root,
+                verify=verify,
+                force=force,
+                explicit=explicit,
+                allow_fallback=allow_fallback,
+                hook_transaction=hook_txn,
+                hook_failure_mode=hook_failure_mode,
+            )
+        except PlanApplyError as e:
+            die(str(e))
+        return
+
+    for pkg, blob_path in progress_bar(jobs, desc="Installing", unit="pkg"):
+        try:
+            meta = installpkg(
+                blob_path,
+                root=root,
+                dry_run=dry,
+                verify=verify,
+                force=force,
+                explicit=(pkg.name in explicit),
+                allow_fallback=allow_fallback,
+                hook_transaction=hook_txn,
+                register_event=(hook_txn is None),
+                dependencies=dependencies,
+            )
+        except Exception as e:
+            warn(f"install {pkg.name}: {e}")
+            continue
+
+
+def install_to_roots(
+    pkgs: List[PkgMeta],
+    roots: Sequence[Path],
+    *,
+    verify: bool,
+    force: bool = False,
+    explicit: Optional[Set[str]] = None,
+    allow_fallback: bool = ALLOW_LPMBUILD_FALLBACK,
+    hook_failure_mode: str = HookFailureMode.STRICT,
+) -> Dict[Path, Optional[PlanApplyError]]:
+    """Apply one resolved plan to every root in *roots*, in parallel.
+
+    Archives are fetched once and shared.  Each root gets its own state
+    database (see :func:`root_state`) and its own :func:`apply_plan`
+    transaction, so a failure rolls back only the root it happened in.
+    Returns ``{root: None | error}``.
+    """
+
+    global PROTECTED
+    PROTECTED = load_protected()
+
+    jobs_pkgs: List[PkgMeta] = []
+    for pkg in pkgs:
+        if pkg.name in PROTECTED and not force:
+            warn(f"{pkg.name} is protected (from {PROTECTED_FILE}) and cannot be installed/upgraded without --force")
+            continue
+        jobs_pkgs.append(pkg)
+    downloads = fetch_all(jobs_pkgs)
+    for pkg in jobs_pkgs:
+        res = downloads.get(pkg.name)
+        if isinstance(res, Exception):
+            die(f"Failed to fetch {pkg.name}: {res}")
+    enforce_cache_limit(res[0] for res in downloads.values() if isinstance(res, tuple))
+    jobs = [(pkg, downloads[pkg.name][0]) for pkg in jobs_pkgs]
+
+    def _apply(root: Path) -> None:
+        with root_state(root):
+            apply_plan(
+                jobs,
+                root,
+                verify=verify,
+                force=force,
+                explicit=explicit,
+                allow_fallback=allow_fallback,
+                hook_failure_mode=hook_failure_mode,
+            )
+
+    roots = [Path(r) for r in roots]
+    results: Dict[Path, Optional[PlanApplyError]] = {}
+    with ThreadPoolExecutor(max_workers=max(1, min(len(roots), FETCH_MAX_WORKERS))) as ex:
+        futures = {ex.submit(_apply, root): root for root in roots}
+        for fut in as_completed(futures):
+            root = futures[fut]
+            try:
+                fut.result()
+            except PlanApplyError as e:
+                warn(f"{root}: {e}")
+                results[root] = e
+            else:
+                ok(f"{root}: installed {len(jobs)} package(s)")
+                results[root] = None
+    return results
+
+
+class PlanApplyError(RuntimeError):
+    """Raised when :func:`apply_plan` fails and the plan has been rolled back."""
+
+    def __init__(self, package: str, reason: str) -> None:
+        self.package = package
+        self.reason = reason
+        super().__init__(f"install {package} failed, rolled back the entire plan: {reason}")
+
+
+class FileConflict(PlanApplyError):
+    """Raised when a package in a plan ships a path owned by another package."""
+
+    def __init__(self, path: str, package: str, owner: str) -> None:
+        self.path = path
+        self.owner = owner
+        super().__init__(package, f"file conflict: {path} is owned by {owner}")
+
+
+def path_owners(installed: Mapping[str, dict]) -> Dict[str, str]:
+    """Return the path-ownership index ``{path: package}`` for *installed*."""
+
+    owners: Dict[str, str] = {}
+    for name, meta in installed.items():
+        for path in _normalize_manifest_paths(meta.get("manifest")):
+            owners[path] = name
+    return owners
+
+
+def check_file_conflicts(
+    entries: Iterable[Tuple[PkgMeta, List[dict]]],
+    installed: Mapping[str, dict],
+    planned: Optional[Iterable[str]] = None,
+) -> Dict[str, Set[str]]:
+    """Reject plans whose packages would overwrite files owned by other packages.
+
+    *entries* are ``(meta, manifest)`` pairs in install order.  A path may be
+    taken over from its owner only when the incoming package obsoletes
+    (``replaces``) that owner; such transfers are returned as
+    ``{owner: {path, ...}}``.  Installed packages that the plan upgrades give
+    up their old paths first.  Raises :class:`FileConflict` otherwise.
+
+    *planned* names the whole plan when only a prefix of it is in *entries*,
+    as when archives are checked one at a time while they stream in.
+    """
+
+    entries = list(entries)
+    planned = set(planned or ()) | {meta.name for meta, _ in entries}
+    owners = path_owners({n: m for n, m in installed.items() if n not in planned})
+    transfers: Dict[str, Set[str]] = {}
+    for meta, mani in entries:
+        replaced = _resolve_obsoletes_against_installed(meta.obsoletes, installed)
+        replaced.add(meta.name)
+        for path in _normalize_manifest_paths(mani):
+            if path.startswith("/.lpm"):
+                continue
+            owner = owners.get(path)
+            if owner is not None and owner not in replaced:
+                raise FileConflict(path, meta.name, owner)
+            if owner is not None and owner != meta.name:
+                transfers.setdefault(owner, set()).add(path)
+            owners[path] = meta.name
+    return transfers
+
+
+def _drop_manifest_paths(conn: sqlite3.Connection, name: str, paths: Set[str]) -> None:
+    row = conn.execute("SELECT manifest FROM installed WHERE name=?", (name,)).fetchone()
+    if not row:
+        return
+    manifest = json.loads(row[0]) if row[0] else []
+    kept = [
+        e for e in manifest
+        if (e.get("path") if isinstance(e, dict) else e) not in paths
+    ]
+    conn.execute("UPDATE installed SET manifest=? WHERE name=?", (json.dumps(kept), name))
+
+
+def apply_plan(
+    jobs: List[Tuple[PkgMeta, Path]],
+    root: Path,
+    *,
+    verify: bool = True,
+    force: bool = False,
+    explicit: Optional[Set[str]] = None,
+    allow_fallback: bool = ALLOW_LPMBUILD_FALLBACK,
+    hook_transaction: Optional[HookTransactionManager] = None,
+    hook_failure_mode: str = HookFailureMode.STRICT,
+    progress: Optional[_progress.Progress] = None,
+    preserve_config: bool = False,
+) -> List[PkgMeta]:
+    """Install every ``(pkg, blob)`` in *jobs* as one atomic unit.
+
+    Files are staged through a :class:`~lpm.fs_ops.Transaction` and all
+    manifest/database rows are written inside a single SQLite transaction.
+    Both are committed only once every package (including its post-install
+    hooks) succeeds; any failure restores the filesystem and database to
+    their state before the plan and raises :class:`PlanApplyError`.  File
+    conflicts are detected up front (see :func:`check_file_conflicts`) and
+    raise :class:`FileConflict` before anything is touched.
+
+    *progress* is advanced by one per installed package; by default it is
+    the CLI's ``Installing`` display.  *preserve_config* is passed on to
+    :func:`installpkg`.
+    """
+
+    return _apply_jobs(
+        [pkg.name for pkg, _ in jobs],
+        jobs,
+        root,
+        upfront=True,
+        verify=verify,
+        force=force,
+        explicit=explicit,
+        allow_fallback=allow_fallback,
+        hook_transaction=hook_transaction,
+        hook_failure_mode=hook_failure_mode,
+        progress=progress,
+        preserve_config=preserve_config,
+    )
+
+
+def _release_archive(blob_path: Path) -> None:
+    """Hand a staged archive back to the cache, trimming it to ``CACHE_MAX_SIZE``."""
+
+    enforce_cache_limit()
+
+
+def _streamed_jobs(
+    pkgs: List[PkgMeta],
+    fetch: Callable[[PkgMeta], Path],
+    window: int,
+    discard: Callable[[Path], None],
+) -> Iterator[Tuple[PkgMeta, Path]]:
+    """Yield ``(pkg, blob)`` in plan order with at most *window* archives fetched.
+
+    Fetched archives are protected from cache eviction until they have been
+    staged; each one is then passed to *discard*, once the consumer asks for
+    the next one, and only then is the next download started.
+    """
+
+    queue = iter(pkgs)
+    pending: deque = deque()
+
+    def _fetch_held(pkg: PkgMeta) -> Tuple[Path, contextlib.ExitStack]:
+        blob_path = fetch(pkg)
+        hold = contextlib.ExitStack()
+        hold.enter_context(_cache.in_use([blob_path]))
+        return blob_path, hold
+
+    with ThreadPoolExecutor(max_workers=max(1, min(window, FETCH_MAX_WORKERS))) as ex:
+
+        def _submit_next() -> None:
+            pkg = next(queue, None)
+            if pkg is not None:
+                pending.append((pkg, ex.submit(_fetch_held, pkg)))
+
+        try:
+            for _ in range(window):
+                _submit_next()
+            while pending:
+                pkg, fut = pending.popleft()
+                try:
+                    blob_path, hold = fut.result()
+                except Exception as exc:
+                    raise PlanApplyError(pkg.name, f"download failed: {exc}") from exc
+                with hold:
+                    yield pkg, blob_path
+                discard(blob_path)
+                _submit_next()
+        finally:
+            for _, fut in pending:
+                if not fut.cancel() and fut.exception() is None:
+                    fut.result()[1].close()
+
+
+def apply_plan_streaming(
     pkgs: List[PkgMeta],
     root: Path,
-    dry: bool,
-    verify: bool,
+    *,
+    window: int = 2,
+    fetch: Optional[Callable[[PkgMeta], Path]] = None,
+    discard: Callable[[Path], None] = _release_archive,
+    verify: bool = True,
     force: bool = False,
     explicit: Optional[Set[str]] = None,
     allow_fallback: bool = ALLOW_LPMBUILD_FALLBACK,
-    force_build: bool = False,
-    local_overrides: Optional[Mapping[str, Path]] = None,
+    hook_transaction: Optional[HookTransactionManager] = None,
     hook_failure_mode: str = HookFailureMode.STRICT,
-):
-    global PROTECTED
-    PROTECTED = load_protected()
-
-    root = Path(root)
-
-    explicit = set(explicit or [])
+    progress: Optional[_progress.Progress] = None,
+) -> List[PkgMeta]:
+    """Like :func:`apply_plan`, but download package N+1 while staging N.
+
+    At most *window* archives are fetched ahead: each one is passed to
+    *discard* after it has been staged, before the next download starts.  By
+    default that only trims the cache to ``CACHE_MAX_SIZE``, so the staged
+    archive stays cached unless the limit evicts it.
+    File conflicts are checked per package as it arrives rather than up
+    front.  A failed download or install rolls back the whole plan.
+    """
 
-    overrides: Dict[str, Tuple[Path, Optional[Path]]] = {}
-    if local_overrides:
-        for name, path in local_overrides.items():
-            try:
-                overrides[name] = (Path(path).resolve(), None)
-            except Exception:
-                overrides[name] = (Path(path), None)
+    window = max(1, int(window))
+    if fetch is None:
+        def fetch(pkg: PkgMeta) -> Path:
+            return fetch_blob(pkg)[0]
+
+    return _apply_jobs(
+        [pkg.name for pkg in pkgs],
+        _streamed_jobs(pkgs, fetch, window, discard),
+        root,
+        upfront=False,
+        verify=verify,
+        force=force,
+        explicit=explicit,
+        allow_fallback=allow_fallback,
+        hook_transaction=hook_transaction,
+        hook_failure_mode=hook_failure_mode,
+        progress=progress,
+    )
 
-    to_fetch = [p for p in pkgs if not (p.name in PROTECTED and not force)]
-    downloads: Dict[str, object] = {}
-    for pkg in to_fetch:
-        if pkg.name in overrides:
-            downloads[pkg.name] = overrides[pkg.name]
 
-    remaining = [p for p in to_fetch if p.name not in downloads]
-    if force_build:
-        for pkg in remaining:
-            downloads[pkg.name] = _FORCE_BUILD_SENTINEL
-    else:
-        downloads.update(fetch_all(remaining))
+def _apply_jobs(
+    names: List[str],
+    jobs: Iterable[Tuple[PkgMeta, Path]],
+    root: Path,
+    *,
+    upfront: bool,
+    verify: bool,
+    force: bool,
+    explicit: Optional[Set[str]],
+    allow_fallback: bool,
+    hook_transaction: Optional[HookTransactionManager],
+    hook_failure_mode: str,
+    progress: Optional[_progress.Progress] = None,
+    preserve_config: bool = False,
+) -> List[PkgMeta]:
+    from .locking import TransactionLockError, global_transaction_lock
 
-    hook_txn: Optional[HookTransactionManager] = None
-    installed_state: Dict[str, dict] = {}
-    if not dry:
+    if progress is None:
+        progress = _progress.track("Installing", unit="pkg")
+    root = Path(root)
+    explicit = set(explicit or [])
+    hook_txn = hook_transaction
+    if hook_txn is None:
         hook_txn = HookTransactionManager(
             hooks=load_hooks(LIBLPM_HOOK_DIRS),
             root=root,
             base_env={"LPM_ROOT": str(root)},
             failure_mode=hook_failure_mode,
+            defer=_hook_deferrer(),
         )
-        conn = db()
-        try:
-            installed_state = db_installed(conn)
-        finally:
-            conn.close()
-
-    jobs: List[Tuple[PkgMeta, Path]] = []
-    for pkg in pkgs:
-        if pkg.name in PROTECTED and not force:
-            warn(f"{pkg.name} is protected (from {PROTECTED_FILE}) and cannot be installed/upgraded without --force")
-            continue
-        res = downloads.get(pkg.name)
-        if res is _FORCE_BUILD_SENTINEL:
-            log(f"[build] Building {pkg.name} from source for installation")
-            tmp = Path(f"/tmp/lpm-dep-{pkg.name}.lpmbuild")
-            fetch_lpmbuild(pkg.name, tmp)
-            blob_path, _, _, _ = run_lpmbuild(tmp, prompt_install=False, is_dep=True, build_deps=True)
-        elif isinstance(res, Exception):
-            if not allow_fallback:
-                die(
-                    f"Failed to fetch {pkg.name}: {res}. GitLab fallback is disabled. "
-                    "Re-run with --allow-fallback or set ALLOW_LPMBUILD_FALLBACK=1 in lpm.conf"
-                )
-            warn(f"Could not fetch {pkg.name} from repos ({res}), trying GitLab fallback...")
-            tmp = Path(f"/tmp/lpm-dep-{pkg.name}.lpmbuild")
-            fetch_lpmbuild(pkg.name, tmp)
-            blob_path, _, _, _ = run_lpmbuild(tmp, prompt_install=False, is_dep=True, build_deps=True)
-        else:
-            if res is None:
-                blob_path, _ = fetch_blob(pkg)
-            else:
-                blob_path = res[0]
-        jobs.append((pkg, blob_path))
-
-        if hook_txn is not None:
-            meta, mani = read_package_meta(blob_path)
-            manifest_paths = _normalize_manifest_paths(mani)
-            operation = "Upgrade" if pkg.name in installed_state else "Install"
-            hook_txn.add_package_event(
-                name=meta.name,
-                operation=operation,
-                version=meta.version,
-                release=meta.release,
-                paths=manifest_paths,
-            )
 
-    for pkg, blob_path in progress_bar(jobs, desc="Installing", unit="pkg"):
-        try:
-            meta = installpkg(
-                blob_path,
-                root=root,
-                dry_run=dry,
-                verify=verify,
-                force=force,
-                explicit=(pkg.name in explicit),
-                allow_fallback=allow_fallback,
-                hook_transaction=hook_txn,
-                register_event=(hook_txn is None),
-            )
-        except Exception as e:
-            warn(f"install {pkg.name}: {e}")
-            continue
+    installed: List[PkgMeta] = []
+    label = ", ".join(names)
+    log(f"[tx] apply plan: {label}")
+    conn = db()
+    current = "<plan>"
+    try:
+        with global_transaction_lock(_root_lock_path()):
+            conn.execute("BEGIN")
+            keep = _config.MAX_TRANSACTIONS > 0
+            fs_txn = Transaction(_transaction_backup_dir() if keep else None)
+            try:
+                before = db_installed(conn)
+                cur = conn.execute("SELECT * FROM installed")
+                columns = [d[0] for d in cur.description]
+                before_rows = {row[0]: dict(zip(columns, row)) for row in cur}
+                handed_over: Dict[str, Set[str]] = {}
+
+                def _hand_over(transfers: Mapping[str, Set[str]]) -> None:
+                    for owner, paths in transfers.items():
+                        fresh = set(paths) - handed_over.setdefault(owner, set())
+                        if not fresh:
+                            continue
+                        log(f"[tx] {owner}: handing over {len(fresh)} replaced path(s)")
+                        _drop_manifest_paths(conn, owner, fresh)
+                        handed_over[owner] |= fresh
+
+                seen: List[Tuple[PkgMeta, List[dict]]] = []
+                if upfront:
+                    jobs = list(jobs)
+                    _hand_over(check_file_conflicts((read_package_meta(b) for _, b in jobs), before))
+                progress.start(len(names))
+                for pkg, blob_path in jobs:
+                    current = pkg.name
+                    _cancel.check(f"installation of {pkg.name}")
+                    _deadline.check(f"installation of {pkg.name}")
+                    if not upfront:
+                        seen.append(read_package_meta(blob_path))
+                        _hand_over(check_file_conflicts(seen, before, planned=names))
+                    installed.append(
+                        installpkg(
+                            blob_path,
+                            root=root,
+                            dry_run=False,
+                            verify=verify,
+                            force=force,
+                            explicit=(pkg.name in explicit),
+                            allow_fallback=allow_fallback,
+                            hook_failure_mode=hook_failure_mode,
+                            hook_transaction=hook_txn,
+                            register_event=(hook_transaction is None),
+                            fs_transaction=fs_txn,
+                            db_conn=conn,
+                            preserve_config=preserve_config,
+                        )
+                    )
+                    progress.inc(1)
+                _cancel.check("commit")
+                affected = sorted(set(names) | set(handed_over))
+                txn_id = conn.execute(
+                    "INSERT INTO transactions(ts,packages,previous,backup) VALUES(?,?,?,?)",
+                    (
+                        int(time.time()),
+                        json.dumps(affected),
+                        json.dumps({name: before_rows.get(name) for name in affected}),
+                        str(fs_txn.backup_dir) if keep else None,
+                    ),
+                ).lastrowid
+                conn.execute("COMMIT")
+            except BaseException as exc:
+                close = getattr(jobs, "close", None)
+                if close is not None:
+                    close()
+                conn.execute("ROLLBACK")
+                fs_txn.rollback()
+                warn(f"[tx] rollback plan: {label}")
+                if isinstance(exc, (PlanApplyError, UserAborted)):
+                    raise
+                if isinstance(exc, SystemExit):
+                    raise PlanApplyError(current, f"exit status {exc.code}") from exc
+                if isinstance(exc, Exception):
+                    raise PlanApplyError(current, str(exc)) from exc
+                raise
+            fs_txn.commit(keep=keep)
+            prune_transaction_backups(conn, _config.MAX_TRANSACTIONS)
+    except TransactionLockError as exc:
+        raise PlanApplyError(current, str(exc)) from exc
+    finally:
+        progress.finish()
+        conn.close()
 
-    if hook_txn is not None:
-        hook_txn.run_post_transaction()
+    ok(f"[tx] commit plan: {label} (transaction {txn_id})")
+    hook_txn.run_post_transaction()
+    return installed
 
 
 def _remove_installed_package(meta: dict, root: Path, dry_run: bool, conn):
@@ -3138,8 +4959,9 @@ def _remove_installed_package(meta: dict, root: Path, dry_run: bool, conn):
     # Remove deepest paths first (dirs last)
     files = sorted({p for p in files if isinstance(p, str)}, key=lambda s: s.count("/"), reverse=True)
 
+    ctx = RootContext(root)
     for f in progress_bar(files, desc=f"Removing {name}", unit="file", colour="purple"):
-        p = root / f.lstrip("/")
+        p = ctx.path(f)
         try:
             try:
                 st = p.lstat()
@@ -3166,12 +4988,12 @@ def _remove_installed_package(meta: dict, root: Path, dry_run: bool, conn):
         for dir_rel in dirs_to_cleanup:
             if not isinstance(dir_rel, str):
                 continue
-            p = root / dir_rel.lstrip("/")
+            p = ctx.path(dir_rel)
             current = p
             while True:
-                if current == root:
+                if current == ctx.root:
                     break
-                rel = "/" + current.relative_to(root).as_posix()
+                rel = ctx.relative(current)
                 if rel not in owned_dirs:
                     break
                 try:
@@ -3270,39 +5092,67 @@ def _cleanup_upgrade_service_files(
     with _privileged_default_root_mutation(root, dry_run=dry_run):
         remove_service_files(pkg.name, root, cur.get("manifest"))
 
+def _required_names(requires: Iterable[str]) -> Set[str]:
+    """Return every package or capability name mentioned in *requires*.
+
+    Version constraints are ignored and all alternatives of an ``a | b``
+    requirement count, so reachability errs on the side of keeping packages.
+    """
+
+    names: Set[str] = set()
+    for req in requires:
+        try:
+            parts = [alt for part in flatten_and(parse_dep_expr(req)) for alt in flatten_or(part)]
+        except ValueError:
+            token = re.match(r"[A-Za-z0-9._+\-]+", req.strip())
+            if token:
+                names.add(token.group(0))
+            continue
+        names.update(alt.atom.name for alt in parts if alt.kind == "atom" and alt.atom)
+    return names
+
+
 def _compute_needed_set(installed: Dict[str, dict]) -> Set[str]:
+    """Return the installed packages reachable from the explicitly installed ones."""
+
+    providers: Dict[str, Set[str]] = {}
+    for name, meta in installed.items():
+        providers.setdefault(name, set()).add(name)
+        for prov in meta.get("provides", []):
+            token = re.match(r"[A-Za-z0-9._+\-]+", (prov or "").strip())
+            if token:
+                providers.setdefault(token.group(0), set()).add(name)
+
     needed: Set[str] = {n for n, m in installed.items() if m.get("explicit")}
-    changed = True
-    while changed:
-        changed = False
-        for n in list(needed):
-            meta = installed.get(n)
-            if not meta:
-                continue
-            for req in meta.get("requires", []):
-                req_name = req.split()[0]
-                for mname, mmeta in installed.items():
-                    if mname in needed:
-                        continue
-                    if req_name == mname or req_name in mmeta.get("provides", []):
-                        if mname not in needed:
-                            needed.add(mname)
-                            changed = True
+    queue = deque(sorted(needed))
+    while queue:
+        meta = installed.get(queue.popleft()) or {}
+        for req in sorted(_required_names(meta.get("requires", []))):
+            for dep in sorted(providers.get(req, ())):
+                if dep not in needed:
+                    needed.add(dep)
+                    queue.append(dep)
     return needed
 
 
+def orphaned_packages(installed: Dict[str, dict]) -> List[str]:
+    """Return the dependency-installed packages no explicit package still needs."""
+
+    needed = _compute_needed_set(installed)
+    return sorted(n for n in installed if n not in needed)
+
+
 def autoremove(root: Path, dry: bool) -> None:
     conn = db()
     installed = db_installed(conn)
     conn.close()
 
-    needed = _compute_needed_set(installed)
-
-    to_remove = [n for n in installed.keys() if n not in needed]
+    to_remove = orphaned_packages(installed)
     if not to_remove:
         ok("Nothing to autoremove.")
         return
-    do_remove(sorted(to_remove), root, dry, force=False)
+    log(f"[autoremove] {'would remove' if dry else 'removing'} {len(to_remove)} orphan(s): {', '.join(to_remove)}")
+    do_remove(to_remove, root, dry, force=False)
 
 
 # =========================== Repo index generation =============================
@@ -3347,9 +5197,30 @@ def gen_index(repo_dir: Path, base_url: Optional[str], arch_filter: Optional[str
     out = repo_dir / "index.json"
     with operation_phase(privileged=True):
         write_json(out, index)
+        sign_index(out)
     ok(f"Wrote {out} with {len(packages)} packages")
 
 
+def sign_index(index: Path, key: Optional[Path] = None) -> Optional[Path]:
+    """Write a detached ``index.json.sig`` next to *index* if a signing key is available."""
+
+    key = Path(key or _resolve_lpm_attr("SIGN_KEY", SIGN_KEY))
+    sig = index.with_suffix(index.suffix + ".sig")
+    if not key.exists() or not os.access(key, os.R_OK):
+        warn(f"Signing key not available ({key}); {index.name} left unsigned")
+        with contextlib.suppress(FileNotFoundError):
+            sig.unlink()
+        return None
+    try:
+        subprocess.run(sign_command(key, index, sig), check=True)
+    except (OSError, subprocess.CalledProcessError) as exc:
+        warn(f"openssl failed to sign {index.name}; index will remain unsigned. ({exc})")
+        with contextlib.suppress(FileNotFoundError):
+            sig.unlink()
+        return None
+    return sig
+
+
 # =========================== .lpmbuild support ================================
 def _merge_provides_with_map(
     existing: List[str],
@@ -3484,6 +5355,7 @@ def _capture_lpmbuild_metadata(
         _emit_array_line("OBSOLETES", "obsoletes", "replaces"),
         _emit_array_line("RECOMMENDS", "recommends"),
         _emit_array_line("SUGGESTS", "suggests", "optional_depends"),
+        _emit_array_line("OPTIONS", "options"),
         _emit_map_line("META_PROVIDES", "meta_provides", "provides_by_package"),
     ]
 
@@ -3509,6 +5381,7 @@ def _capture_lpmbuild_metadata(
             "OBSOLETES",
             "RECOMMENDS",
             "SUGGESTS",
+            "OPTIONS",
         ]
     }
     maps: Dict[str, Dict[str, List[str]]] = {"META_PROVIDES": {}}
@@ -3610,6 +5483,20 @@ def _current_state_dir() -> Path:
     return STATE_DIR
 
 
+def _root_state_dir(root: Path) -> Path:
+    """The state directory of *root*: the host's for ``/``, else the one inside it."""
+
+    if Path(root).resolve() == Path("/"):
+        return _current_state_dir()
+    return _root_state_paths(root)[0].parent
+
+
+def _installed_script_path(name: str, root: Path) -> Path:
+    """Location of the retained ``.lpm-install.sh`` for a package installed into *root*."""
+
+    return _root_state_dir(root) / "scripts" / f"{name}.lpm-install.sh"
+
+
 def _current_cache_dir() -> Path:
     return _current_state_dir() / "cache"
 
@@ -3799,7 +5686,7 @@ def fetch_lpmbuild(pkgname: str, dst: Path) -> Path:
     ok(f"Fetching lpmbuild for {pkgname} from {url}")
     dst.parent.mkdir(parents=True, exist_ok=True)
     try:
-        data, _ = _resolve_lpm_attr("urlread", urlread)(url)
+        data, _ = repo_urlread(url)
     except Exception as e:
         die(f"Failed to fetch lpmbuild for {pkgname}: {e}")
     dst.write_bytes(data)
@@ -3831,6 +5718,34 @@ def build_from_gitlab(pkgname: str) -> Path:
     return cache_pkg
 
 
+# Set by the global ``-y/--yes`` flag: every confirmation is answered yes.
+ASSUME_YES = False
+
+
+def confirm(question: str, default: str = "n") -> bool:
+    """Ask *question* on stdin and return whether the answer is yes.
+
+    An empty answer, or end of input, picks *default* (``"y"`` or ``"n"``).
+    Tests replace this function to script the answers.
+    """
+
+    default = "y" if str(default).lower() in {"y", "yes"} else "n"
+    choices = "[Y/n]" if default == "y" else "[y/N]"
+    try:
+        resp = input(f"{_output.cyan('[PROMPT]', sys.stdout)} {question} {choices} ").strip().lower()
+    except EOFError:
+        resp = ""
+    return (resp or default) in {"y", "yes"}
+
+
+def ask(question: str, default: str = "n") -> bool:
+    """Return True without asking under ``--yes``, else defer to :func:`confirm`."""
+
+    if ASSUME_YES:
+        return True
+    return _resolve_lpm_attr("confirm", confirm)(question, default)
+
+
 def prompt_install_pkg(blob: Path, kind: str = "package", default: Optional[str] = None) -> None:
     """Prompt the user to install a built package.
 
@@ -3854,12 +5769,7 @@ def prompt_install_pkg(blob: Path, kind: str = "package", default: Optional[str]
 
     if default is None:
         default = INSTALL_PROMPT_DEFAULT
-    default = "y" if str(default).lower() in {"y", "yes"} else "n"
-    choices = "[Y/n]" if default == "y" else "[y/N]"
-    resp = input(f"{CYAN}[PROMPT]{RESET} Install {kind} {desc}? {choices} ").strip().lower()
-    if not resp:
-        resp = default
-    if resp in {"y", "yes"}:
+    if ask(f"Install {kind} {desc}?", default):
         _resolve_lpm_attr("installpkg", installpkg)(blob, explicit=(kind != "dependency"))
 
 
@@ -3922,6 +5832,112 @@ def _parse_cpu_overrides(values: Iterable[str]) -> Optional[CpuOverrides]:
     return None if normalized.is_empty() else normalized
 
 
+_BUILD_OPTION_RE = re.compile(r"^[A-Za-z0-9_][A-Za-z0-9_.+-]*$")
+_CONDITIONAL_DEP_RE = re.compile(r"^(!?)([A-Za-z0-9_][A-Za-z0-9_.+-]*)\?\s*(.*)$")
+_OPTION_ARRAYS = (
+    "REQUIRES",
+    "BUILD_REQUIRES",
+    "REQUIRES_PYTHON_DEPENDENCIES",
+    "PROVIDES",
+    "CONFLICTS",
+    "OBSOLETES",
+    "RECOMMENDS",
+    "SUGGESTS",
+)
+
+
+@dataclass
+class BuildOptions:
+    """Options toggled on the command line with ``--with``/``--without``."""
+
+    enable: List[str] = field(default_factory=list)
+    disable: List[str] = field(default_factory=list)
+
+    def is_empty(self) -> bool:
+        return not self.enable and not self.disable
+
+
+def _split_option_tokens(values: Iterable[str]) -> List[str]:
+    tokens: List[str] = []
+    for raw in values or ():
+        for token in re.split(r"[,\s]+", raw or ""):
+            if token:
+                tokens.append(token)
+    return tokens
+
+
+def _parse_build_options(
+    with_values: Optional[Iterable[str]], without_values: Optional[Iterable[str]]
+) -> Optional[BuildOptions]:
+    options = BuildOptions(
+        enable=_split_option_tokens(with_values or ()),
+        disable=_split_option_tokens(without_values or ()),
+    )
+    both = sorted(set(options.enable) & set(options.disable))
+    if both:
+        die(f"Build option(s) given to both --with and --without: {', '.join(both)}")
+    return None if options.is_empty() else options
+
+
+def _declared_build_options(values: Iterable[str]) -> Dict[str, bool]:
+    """Parse a recipe's ``options=`` entries into their default states.
+
+    Options are enabled by default; a leading ``-`` declares an option that is
+    off unless requested with ``--with``.
+    """
+
+    declared: Dict[str, bool] = {}
+    for token in _split_option_tokens(values):
+        default = True
+        if token[0] in "+-":
+            default = token[0] == "+"
+            token = token[1:]
+        if not _BUILD_OPTION_RE.match(token):
+            die(f"Invalid build option '{token}' in lpmbuild options")
+        declared[token] = default
+    return declared
+
+
+def _resolve_build_options(
+    declared: Mapping[str, bool], requested: Optional[BuildOptions], *, package: str = ""
+) -> Dict[str, bool]:
+    states = dict(declared)
+    if requested is None:
+        return states
+    for value, token in [(True, t) for t in requested.enable] + [(False, t) for t in requested.disable]:
+        if token not in states:
+            known = ", ".join(sorted(states)) or "none"
+            die(f"Unknown build option '{token}' for {package or 'package'} (declared: {known})")
+        states[token] = value
+    return states
+
+
+def _filter_conditional_entries(entries: Iterable[str], states: Mapping[str, bool]) -> List[str]:
+    """Drop ``opt? dep`` / ``!opt? dep`` entries whose condition does not hold."""
+
+    kept: List[str] = []
+    for entry in entries:
+        match = _CONDITIONAL_DEP_RE.match(entry.strip())
+        if not match:
+            kept.append(entry)
+            continue
+        negate, option, rest = match.groups()
+        if not rest:
+            continue
+        if states.get(option, False) != bool(negate):
+            kept.append(rest)
+    return kept
+
+
+def _build_option_environ(states: Mapping[str, bool]) -> Dict[str, str]:
+    env = {
+        f"LPM_OPTION_{re.sub(r'[^A-Za-z0-9_]', '_', option).upper()}": "1" if enabled else "0"
+        for option, enabled in states.items()
+    }
+    env["LPM_OPTIONS"] = " ".join(option for option, enabled in states.items() if enabled)
+    return env
+
+
 def run_lpmbuild(
     script: Path,
     outdir: Optional[Path] = None,
@@ -3936,6 +5952,7 @@ def run_lpmbuild(
     executor: Optional[ThreadPoolExecutor] = None,
     cpu_overrides: Optional[CpuOverrides] = None,
     on_built_package: Optional[Callable[[Path, PkgMeta], None]] = None,
+    build_options: Optional[BuildOptions] = None,
 ) -> Tuple[Path, float, int, List[Tuple[Path, PkgMeta]]]:
     script_path = script.resolve()
     script_dir = script_path.parent
@@ -3969,6 +5986,13 @@ def run_lpmbuild(
     if not name or not version:
         die("lpmbuild missing NAME or VERSION")
 
+    option_states = _resolve_build_options(
+        _declared_build_options(arr.get("OPTIONS", [])), build_options, package=name
+    )
+    for key in _OPTION_ARRAYS:
+        if key in arr:
+            arr[key] = _filter_conditional_entries(arr[key], option_states)
+
     building_stack = tuple(_building_stack or ())
     if name in building_stack:
         cycle = " -> ".join((*building_stack, name))
@@ -4415,6 +6439,7 @@ def run_lpmbuild(
         "LPM_SPLIT_RECORD": str(split_record_path),
         "LPM_SPLIT_OUTDIR": str(outdir or script_dir),
     })
+    env.update(_build_option_environ(option_states))
     def _is_x86(arch_name: str) -> bool:
         lowered = arch_name.lower()
         return lowered.startswith("x86_64") or lowered.startswith("x86-64") or lowered.startswith("amd64")
@@ -4757,23 +6782,7 @@ def run_lpmbuild(
 
             log(f"[lpm] Embedding install hooks from {candidate.name}")
             with install_sh.open("w", encoding="utf-8") as f:
-                f.write("#!/bin/bash\n")
-                f.write("set -euo pipefail\n\n")
-                f.write("action=${1:-install}\n")
-                f.write("new_full=${2:-}\n")
-                f.write("old_full=${3:-}\n")
-                f.write("source /dev/stdin <<'__LPM_INSTALL_BODY__'\n")
-                if body:
-                    f.write(body)
-                    if not body.endswith("\n"):
-                        f.write("\n")
-                f.write("__LPM_INSTALL_BODY__\n\n")
-                f.write("if declare -f post_install >/dev/null; then\n")
-                f.write("  post_install \"$new_full\"\n")
-                f.write("fi\n")
-                f.write("if [[ \"$action\" == \"upgrade\" ]] && declare -f post_upgrade >/dev/null; then\n")
-                f.write("  post_upgrade \"$new_full\" \"$old_full\"\n")
-                f.write("fi\n")
+                f.write(wrap_install_body(body))
             install_sh.chmod(0o755)
             install_embedded = True
             break
@@ -4899,7 +6908,9 @@ def run_lpmbuild(
     return out, duration, phase_count, split_records
 
 # =========================== CLI commands =====================================
-_PRIVILEGED_COMMANDS = {"install", "installpkg", "remove", "removepkg", "upgrade", "upgradepkg", "rollback"}
+# Commands that run before (or without) the first-run setup wizard.
+_NO_SETUP_COMMANDS = {"setup", "completions"}
+_PRIVILEGED_COMMANDS = {"install", "installpkg", "reinstall", "remove", "removepkg", "upgrade", "upgradepkg", "rollback"}
 _STATE_COMMANDS = {
     "autoremove",
     "bootstrap",
@@ -4908,6 +6919,7 @@ _STATE_COMMANDS = {
     "buildchroot",
     "buildpkg",
     "clean",
+    "download",
     "files",
     "history",
     "info",
@@ -4916,9 +6928,12 @@ _STATE_COMMANDS = {
     "list",
     "pins",
     "protected",
+    "provides",
     "rebuild",
+    "reinstall",
     "removepkg",
     "remove",
+    "repair",
     "repoadd",
     "repodel",
     "repolist",
@@ -4926,10 +6941,45 @@ _STATE_COMMANDS = {
     "search",
     "snapshots",
     "splitpkg",
+    "update",
     "verify",
+    "why",
 }
 
 
+def _privileged_command(args: argparse.Namespace) -> Optional[str]:
+    """Return the command name when *args* needs root, otherwise ``None``.
+
+    A dry run changes nothing, so it needs no privileges.  ``build`` only
+    writes an archive, which any user may do; it is privileged only when
+    ``--install-root`` asks for the result to be installed into the host root.
+    """
+
+    cmd = getattr(args, "cmd", None)
+    if getattr(args, "dry_run", False):
+        return None
+    if cmd in _PRIVILEGED_COMMANDS:
+        return cmd
+    if cmd == "build" and args.install_root is not None and _is_default_root(Path(args.install_root)):
+        return cmd
+    return None
+
+
+def configured_gate(argv: Optional[Sequence[str]] = None) -> PrivilegeGate:
+    """Return the privilege gate ``PRIVILEGE_ESCALATION`` and its allowlist select.
+
+    *argv* is the command line a re-executing gate restarts (see :func:`gate_for`).
+    """
+
+    return gate_for(
+        _config.PRIVILEGE_ESCALATION,
+        uids=_config.PRIVILEGE_ALLOW_UIDS,
+        gids=_config.PRIVILEGE_ALLOW_GIDS,
+        commands=_config.PRIVILEGE_ALLOW_COMMANDS,
+        argv=argv,
+    )
+
+
 def _state_setup_permission_message(exc: PermissionError) -> str:
     state_dir = _resolve_lpm_attr("STATE_DIR", STATE_DIR)
     conf_file = _resolve_lpm_attr("CONF_FILE", CONF_FILE)
@@ -4940,66 +6990,233 @@ def _state_setup_permission_message(exc: PermissionError) -> str:
     )
 
 
-def _initialize_cli_state() -> None:
-    with operation_phase(privileged=True):
-        _resolve_lpm_attr("initialize_state", initialize_state)()
+def _initialize_cli_state() -> None:
+    with operation_phase(privileged=True):
+        _resolve_lpm_attr("initialize_state", initialize_state)()
+
+
+def cmd_repolist(_):
+    for r in sorted(configured_repos(), key=lambda x:x.priority):
+        print(f"{r.name:15} {r.url} (prio {r.priority})")
+
+def cmd_update(_):
+    """Refresh every repository index and its offline copy."""
+    if _OFFLINE:
+        die("update needs the network; drop --offline")
+    repos = sorted(configured_repos(), key=lambda r: r.priority)
+    failed = 0
+    for repo in repos:
+        try:
+            pkgs = fetch_repo_index(repo)
+        except Exception as e:
+            warn(f"repo {repo.name}: {e}")
+            failed += 1
+            continue
+        ok(f"{repo.name}: {len(pkgs)} packages")
+    if repos and failed == len(repos):
+        die("no repository index could be refreshed")
+
+def cmd_repoadd(a): add_repo(a.name,a.url,a.priority)
+def cmd_repodel(a): del_repo(a.name)
+
+def search_packages(
+    index: Index,
+    patterns: Sequence[str],
+    *,
+    regex: bool = False,
+    limit: Optional[int] = None,
+) -> List[Tuple[str, str, str]]:
+    """Return sorted ``(name, version, summary)`` rows for names matching any pattern.
+
+    Matching ignores case.  A plain pattern matches as a substring and one
+    with shell wildcards must match the whole name; with *regex* every pattern
+    is a regular expression searched in the name.  No pattern matches all.
+    """
+
+    if regex:
+        try:
+            compiled = [re.compile(p, re.IGNORECASE) for p in patterns]
+        except re.error as exc:
+            raise ValueError(f"invalid search regex: {exc}") from exc
+        match = lambda name: any(rx.search(name) for rx in compiled)
+    else:
+        lowered = [p.lower() for p in patterns]
+        match = lambda name: any(
+            fnmatch.fnmatchcase(name.lower(), p) if any(ch in p for ch in "*?[") else p in name.lower()
+            for p in lowered
+        )
+    rows = [(p.name, p.version, p.summary) for p in index.query("") if not patterns or match(p.name)]
+    return rows if limit is None else rows[:limit]
+
+
+def cmd_search(a):
+    try:
+        rows = search_packages(repo_index(), a.patterns, regex=a.regex, limit=a.limit)
+    except ValueError as exc:
+        die(str(exc))
+        return
+
+    def text(records):
+        for r in records: print(f"{r['name']:30} {r['version']:10} {r['summary']}")
+
+    _output.report([{"name": n, "version": v, "summary": s} for n, v, s in rows], text)
+
+_INFO_FIELDS = (
+    "name", "version", "release", "arch", "summary", "url", "license", "provides", "requires",
+    "build_requires", "conflicts", "obsoletes", "recommends", "suggests", "size", "repo", "blob",
+)
+
+def cmd_info(a):
+    index=repo_index()
+    missing=[]
+    records=[]
+    for name in a.names:
+        p=index.get(name)
+        if p is None: missing.append(name); continue
+        records.append({key: getattr(p, key) for key in _INFO_FIELDS})
+
+    def text(records):
+        for r in records:
+            print(f"Name:       {r['name']}")
+            print(f"Version:    {r['version']}-{r['release']}.{r['arch']}")
+            print(f"Summary:    {r['summary']}")
+            print(f"Homepage:   {r['url']}")
+            print(f"License:    {r['license']}")
+            print(f"Provides:   {', '.join(r['provides']) or '-'}")
+            print(f"Requires:   {', '.join(r['requires']) or '-'}")
+            print(f"BuildReqs:  {', '.join(r['build_requires']) or '-'}")
+            print(f"Conflicts:  {', '.join(r['conflicts']) or '-'}")
+            print(f"Obsoletes:  {', '.join(r['obsoletes']) or '-'}")
+            print(f"Recommends: {', '.join(r['recommends']) or '-'}")
+            print(f"Suggests:   {', '.join(r['suggests']) or '-'}")
+            size = f"{r['size']} bytes" if r["size"] else "-"
+            print(f"Size:       {size}")
+            print(f"Repo:       {r['repo'] or '-'}")
+            print(f"Blob:       {r['blob'] or '-'}")
+
+    _output.report(records, text)
+    if missing:
+        die(f"package(s) not found: {', '.join(missing)}")
+
+def why_paths(trace: Mapping[str, object], name: str) -> List[List[str]]:
+    """Return the chains in a :func:`resolution_trace` that pull *name* in.
+
+    Each chain starts at a goal text and lists package ids down to the
+    selected *name*, following the ``selected_because`` edges backwards.
+    """
+
+    packages: Mapping[str, Mapping[str, object]] = trace.get("packages", {})  # type: ignore[assignment]
+    paths: List[List[str]] = []
+
+    def walk(pid: str, chain: List[str]) -> None:
+        for reason in packages[pid]["selected_because"]:
+            if reason["kind"] == "goal":
+                paths.append([reason["requirement"], pid, *chain])
+            elif reason.get("package") in packages and reason["package"] not in chain:
+                walk(reason["package"], [pid, *chain])
+
+    for pid, info in packages.items():
+        if info["name"] == name:
+            walk(pid, [])
+    return paths
 
+def cmd_why(a):
+    goals = a.goals
+    if not goals:
+        conn = db()
+        try:
+            goals = sorted(n for n, meta in db_installed(conn).items() if meta.get("explicit"))
+        finally:
+            conn.close()
+    if not goals:
+        die("nothing to explain: no explicitly installed packages (pass --goal NAME)")
+    trace: Dict[str, object] = {}
+    solve(goals, build_universe(), trace=trace)
+    paths = why_paths(trace, a.name)
+    if not paths:
+        die(f"{a.name} is not part of the plan for {', '.join(goals)}")
+    for goal, *chain in paths:
+        print(" <- ".join(reversed(chain)) + f" (requested as {goal})")
+
+def render_plan(plan: Iterable[PkgMeta], downgrades: Mapping[str, str], stream=None) -> List[str]:
+    """Return the ``[plan]`` lines for *plan*, styled for *stream*."""
+
+    stream = sys.stderr if stream is None else stream
+    lines = [_output.purple("[plan] install order:", stream)]
+    for p in plan:
+        line = f"  - {_output.bold(p.name, stream)}-{p.version}"
+        if p.name in downgrades:
+            line += " " + _output.cyan(f"(downgrade from {downgrades[p.name]})", stream)
+        lines.append(line)
+    return lines
 
-def cmd_repolist(_):
-    for r in sorted(list_repos(), key=lambda x:x.priority):
-        print(f"{r.name:15} {r.url} (prio {r.priority})")
 
-def cmd_repoadd(a): add_repo(a.name,a.url,a.priority)
-def cmd_repodel(a): del_repo(a.name)
+def print_plan(plan: Iterable[PkgMeta], downgrades: Mapping[str, str]) -> None:
+    for line in render_plan(plan, downgrades, sys.stderr):
+        print(line, file=sys.stderr)
 
-def cmd_search(a):
-    uni=load_universe()
-    pats=a.patterns or ["*"]
-    rows=[]
-    for name,lst in uni.items():
-        if any(fnmatch.fnmatch(name,p) for p in pats):
-            p=lst[0]
-            rows.append((name,p.version,p.summary))
-    for n,v,s in sorted(rows): print(f"{n:30} {v:10} {s}")
+def plan_downgrades(plan: Iterable[PkgMeta], installed: Mapping[str, dict]) -> Dict[str, str]:
+    """Return ``{name: installed_version}`` for plan entries older than what is installed.
+
+    Downgrades are applied in place by :func:`installpkg`, which replaces the
+    previous manifest exactly as it does for upgrades.
+    """
+
+    downgrades: Dict[str, str] = {}
+    for p in plan:
+        cur = installed.get(p.name)
+        if cur and cmp_semver(p.version, cur["version"]) < 0:
+            downgrades[p.name] = cur["version"]
+    return downgrades
 
-def cmd_info(a):
-    uni=load_universe()
-    for name in a.names:
-        lst=uni.get(name,[])
-        if not lst: print(f"{name}: not found"); continue
-        p=lst[0]
-        print(f"Name:       {p.name}")
-        print(f"Version:    {p.version}-{p.release}.{p.arch}")
-        print(f"Summary:    {p.summary}")
-        print(f"Homepage:   {p.url}")
-        print(f"License:    {p.license}")
-        print(f"Provides:   {', '.join(p.provides) or '-'}")
-        print(f"Requires:   {', '.join(p.requires) or '-'}")
-        print(f"BuildReqs:  {', '.join(p.build_requires) or '-'}")
-        print(f"Conflicts:  {', '.join(p.conflicts) or '-'}")
-        print(f"Obsoletes:  {', '.join(p.obsoletes) or '-'}")
-        print(f"Recommends: {', '.join(p.recommends) or '-'}")
-        print(f"Suggests:   {', '.join(p.suggests) or '-'}")
-        print(f"Blob:       {p.blob or '-'}")
 
 def cmd_install(a):
     mode = "never" if getattr(a, "no_delta", False) else _config.USE_DELTAS
-    with _delta_mode(mode):
-        root = Path(a.root or DEFAULT_ROOT)
+    roots = a.root if isinstance(a.root, list) else [a.root or DEFAULT_ROOT]
+    if len(roots) > 1 and getattr(a, "target_arch", None):
+        die("--target-arch cannot be combined with more than one --root")
+    root = Path(roots[0])
+    with _delta_mode(mode), target_arch(getattr(a, "target_arch", None), root):
         u = build_universe()
         goals = a.names
+        trace: Optional[Dict[str, object]] = {} if getattr(a, "trace", None) else None
         try:
-            plan = solve(goals, u)
+            plan = solve(
+                goals,
+                u,
+                recommends=not getattr(a, "no_recommends", False),
+                policy=getattr(a, "prefer", None),
+                trace=trace,
+                dependencies=not getattr(a, "no_deps", False),
+            )
         except ResolutionError as e:
             die(f"dependency resolution failed: {e}")
-        log("[plan] install order:")
+        if trace is not None:
+            Path(a.trace).write_text(json.dumps(trace, indent=2, sort_keys=True) + "\n", encoding="utf-8")
+        downgrades = plan_downgrades(plan, u.installed)
+        print_plan(plan, downgrades)
         for p in plan:
-            log(f"  - {p.name}-{p.version}")
+            if p.name in downgrades:
+                warn(f"Downgrading {p.name} from {downgrades[p.name]} to {p.version}")
         if a.dry_run:
             return
         noverify = a.no_verify or os.environ.get("LPM_NO_VERIFY") == "1"
         allow_fallback = ALLOW_LPMBUILD_FALLBACK if a.allow_fallback is None else a.allow_fallback
 
+        if len(roots) > 1:
+            results = install_to_roots(
+                plan,
+                [Path(r) for r in roots],
+                verify=(not noverify),
+                force=getattr(a, "force", False),
+                explicit=set(a.names),
+                allow_fallback=allow_fallback,
+            )
+            failed = [str(r) for r, err in results.items() if err is not None]
+            if failed:
+                die(f"install failed in {len(failed)} of {len(roots)} root(s): {', '.join(sorted(failed))}")
+            return
+
         snapshot_id = None
         snapshot_archive = None
         try:
@@ -5029,9 +7246,10 @@ def cmd_install(a):
                 root,
                 a.dry_run,
                 verify=(not noverify),
-                force=a.force,
+                force=getattr(a, "force", False),
                 explicit=set(a.names),
                 allow_fallback=allow_fallback,
+                dependencies=not getattr(a, "no_deps", False),
             )
         except SystemExit:
             if snapshot_id is not None:
@@ -5043,6 +7261,9 @@ def cmd_remove(a):
     root = Path(a.root or DEFAULT_ROOT)
     snapshot_id = None
     snapshot_archive = None
+    if not a.dry_run and not ask(f"Remove {', '.join(a.names)} from {root}?"):
+        die("remove cancelled", code=1)
+        return
     if not a.dry_run:
         conn = db()
         affected: Set[Path] = set()
@@ -5075,8 +7296,8 @@ def cmd_autoremove(a):
 
 def cmd_upgrade(a):
     mode = "never" if getattr(a, "no_delta", False) else _config.USE_DELTAS
-    with _delta_mode(mode):
-        root = Path(a.root or DEFAULT_ROOT)
+    root = Path(a.root or DEFAULT_ROOT)
+    with _delta_mode(mode), target_arch(getattr(a, "target_arch", None), root):
         noverify = a.no_verify or os.environ.get("LPM_NO_VERIFY") == "1"
         dry = a.dry_run
         force = a.force
@@ -5091,7 +7312,12 @@ def cmd_upgrade(a):
             goals += a.names
 
         try:
-            plan = solve(goals, u)
+            plan = solve(
+                goals,
+                u,
+                recommends=not getattr(a, "no_recommends", False),
+                policy=getattr(a, "prefer", None),
+            )
         except ResolutionError:
             if not allow_fallback:
                 die(
@@ -5188,6 +7414,29 @@ def cmd_files(a):
         path = e["path"] if isinstance(e, dict) else e
         print(path)
 
+def cmd_provides(a):
+    ctx = RootContext(Path(a.root or DEFAULT_ROOT).resolve())
+    conn = db()
+    try:
+        owners = path_owners(db_installed(conn))
+    finally:
+        conn.close()
+    unowned = []
+    for query in a.paths:
+        # A path on disk under --root maps to its package path; anything else
+        # is taken as a package path already.
+        try:
+            path = ctx.relative(os.path.abspath(query))
+        except FsError:
+            path = ctx.relative(ctx.path(query))
+        owner = owners.get(path)
+        if owner is None:
+            unowned.append(query)
+            continue
+        print(f"{path}: {owner}")
+    if unowned:
+        die(f"Not owned by any package: {', '.join(unowned)}")
+
 def _format_install_time(ts: Optional[int]) -> str:
     if not ts:
         return "unknown"
@@ -5197,31 +7446,58 @@ def _format_install_time(ts: Optional[int]) -> str:
         return "unknown"
 
 
-def cmd_list_installed(_):
+def cmd_list_installed(a):
+    if getattr(a, "available", False):
+        records = [{"name": p.name, "version": p.version} for p in repo_index().query("")]
+
+        def text(records):
+            for r in records:
+                print(f"{r['name']:30} {r['version']}")
+
+        _output.report(records, text)
+        return
+
+    # main() has already switched to the state database of --root.
     conn = db()
-    rows = list(
-        conn.execute(
-            "SELECT name,version,release,arch,install_time,explicit FROM installed ORDER BY name"
+    try:
+        rows = list(
+            conn.execute(
+                "SELECT name,version,release,arch,install_time,explicit FROM installed ORDER BY name"
+            )
         )
-    )
-    conn.close()
+    finally:
+        conn.close()
+    records = [
+        {
+            "name": name,
+            "version": version,
+            "release": release,
+            "arch": arch,
+            "install_time": installed_ts,
+            "explicit": bool(explicit),
+        }
+        for name, version, release, arch, installed_ts, explicit in rows
+    ]
+    _output.report(records, _print_installed_table)
 
-    if not rows:
+
+def _print_installed_table(records: Sequence[Mapping[str, Any]]) -> None:
+    if not records:
         print("No packages installed.")
         return
 
     table_rows = []
     explicit_count = 0
-    for name, version, release, arch, installed_ts, explicit in rows:
-        if explicit:
+    for r in records:
+        if r["explicit"]:
             explicit_count += 1
         table_rows.append(
             (
-                name,
-                f"{version}-{release}",
-                arch,
-                _format_install_time(installed_ts),
-                "explicit" if explicit else "dependency",
+                r["name"],
+                f"{r['version']}-{r['release']}",
+                r["arch"],
+                _format_install_time(r["install_time"]),
+                "explicit" if r["explicit"] else "dependency",
             )
         )
 
@@ -5233,13 +7509,13 @@ def cmd_list_installed(_):
     def fmt_row(row):
         return "  ".join(col.ljust(width) for col, width in zip(row, widths))
 
-    print(f"Installed packages: {len(rows)} total")
+    print(f"Installed packages: {len(records)} total")
     print(fmt_row(headers))
     print("  ".join("-" * w for w in widths))
     for row in table_rows:
         print(fmt_row(row))
 
-    deps = len(rows) - explicit_count
+    deps = len(records) - explicit_count
     print()
     print(f"Explicit: {explicit_count}    Dependencies: {deps}")
 
@@ -5270,8 +7546,60 @@ def cmd_snapshots(a):
             t = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(ts))
             print(f"{sid:4} {t} {tag} {archive}")
 
+def prune_transaction_backups(conn: sqlite3.Connection, limit: int) -> None:
+    """Drop the backups of all but the newest *limit* transactions."""
+    rows = conn.execute(
+        "SELECT id,backup FROM transactions WHERE backup IS NOT NULL ORDER BY id DESC LIMIT -1 OFFSET ?",
+        (max(0, limit),),
+    ).fetchall()
+    for txn_id, backup in rows:
+        shutil.rmtree(backup, ignore_errors=True)
+        conn.execute("UPDATE transactions SET backup=NULL WHERE id=?", (txn_id,))
+    conn.commit()
+
+def rollback_transaction(conn: sqlite3.Connection, txn_id: int) -> List[str]:
+    """Undo transaction *txn_id*, restoring its files and installed rows.
+
+    Only the newest transaction that has not been rolled back can be undone,
+    because later ones may have changed the same paths.  Returns the affected
+    package names.
+    """
+    row = conn.execute("SELECT packages,previous,backup,state FROM transactions WHERE id=?", (txn_id,)).fetchone()
+    if not row:
+        raise RuntimeError(f"transaction {txn_id} not found")
+    packages, previous, backup, state = row
+    if state != "committed":
+        raise RuntimeError(f"transaction {txn_id} is already {state}")
+    later = [r[0] for r in conn.execute("SELECT id FROM transactions WHERE id>? AND state='committed' ORDER BY id DESC", (txn_id,))]
+    if later:
+        raise RuntimeError(f"roll back transaction(s) {', '.join(map(str, later))} first")
+    if not backup or not Path(backup).is_dir():
+        raise RuntimeError(f"transaction {txn_id} has no backup left (see MAX_TRANSACTIONS)")
+    # Restore the files first: if that fails, the database still describes
+    # the tree and the rollback can be retried from the remaining backups.
+    Transaction.load(backup).rollback()
+    for name, prior in json.loads(previous).items():
+        conn.execute("DELETE FROM installed WHERE name=?", (name,))
+        if prior is not None:
+            cols = list(prior)
+            conn.execute(
+                f"INSERT INTO installed({','.join(cols)}) VALUES({','.join('?' * len(cols))})",
+                [prior[c] for c in cols],
+            )
+    conn.execute("UPDATE transactions SET state='rolled back', backup=NULL WHERE id=?", (txn_id,))
+    conn.execute(
+        "INSERT INTO history(ts,action,name,from_ver,to_ver,details) VALUES(?,?,?,?,?,?)",
+        (int(time.time()), "rollback", f"transaction {txn_id}", None, None, packages),
+    )
+    return json.loads(packages)
+
 def cmd_rollback(a):
     conn = db()
+    if getattr(a, "transaction", None) is not None:
+        with transaction(conn, f"rollback transaction {a.transaction}", False):
+            names = rollback_transaction(conn, a.transaction)
+        ok(f"Rolled back transaction {a.transaction} ({', '.join(names)})")
+        return
     if a.snapshot_id is not None:
         row = conn.execute("SELECT id,tag,archive FROM snapshots WHERE id=?", (a.snapshot_id,)).fetchone()
         if not row:
@@ -5289,8 +7617,15 @@ def cmd_rollback(a):
     conn.commit()
     ok(f"Rolled back to snapshot {sid} ({tag})")
 
-def cmd_history(_):
+def cmd_history(a):
     conn=db()
+    if getattr(a, "transactions", False):
+        rows = conn.execute("SELECT id,ts,packages,state,backup FROM transactions ORDER BY id DESC LIMIT 200")
+        for txn_id, ts, packages, state, backup in rows:
+            t = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(ts))
+            undo = "" if state != "committed" or backup else " (no backup)"
+            print(f"{txn_id:5} {t}  {state:11} {', '.join(json.loads(packages))}{undo}")
+        return
     for ts,act,name,frm,to in conn.execute("SELECT ts,action,name,from_ver,to_ver FROM history ORDER BY id DESC LIMIT 200"):
         t=time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(ts))
         if act=="install":
@@ -5302,49 +7637,183 @@ def cmd_history(_):
         else:
             print(f"{t}  {act}  {name}")
 
+def _verify_entry(entry: Any, root: Path) -> List[Dict[str, str]]:
+    path = entry["path"] if isinstance(entry, dict) else entry
+
+    def _problem(problem: str, detail: str = "") -> List[Dict[str, str]]:
+        return [{"path": path, "problem": problem, "detail": detail}]
+
+    f = RootContext(root).path(path)
+    if not os.path.lexists(f):
+        return _problem("missing")
+    if not isinstance(entry, dict):
+        return []
+    if "link" in entry:
+        if not f.is_symlink():
+            return _problem("link", f"expected a symlink to {entry['link']}")
+        if read_link_hash(f) != entry.get("sha256"):
+            return _problem("link", f"points to {os.readlink(f)}, expected {entry['link']}")
+        return []
+    if f.is_symlink():
+        return _problem("link", f"expected a regular file, found a symlink to {os.readlink(f)}")
+    actual_size = f.stat().st_size
+    if "size" in entry and actual_size != entry["size"]:
+        return _problem("size", f"expected {entry['size']}, got {actual_size}")
+    if "sha256" in entry:
+        try:
+            actual_hash = sha256_file(f)
+        except FsError as exc:
+            return _problem("unreadable", str(exc))
+        if actual_hash != entry["sha256"]:
+            return _problem("hash")
+    return []
+
+
+def verify_manifest(manifest: Iterable[Any], root: Path) -> List[Dict[str, str]]:
+    """Compare the files under *root* with an install *manifest*.
+
+    Returns one ``{"path", "problem", "detail"}`` record per discrepancy,
+    sorted by path; ``problem`` is ``missing``, ``size``, ``hash``, ``link``
+    (a symlink whose target changed, or that is no longer a symlink) or
+    ``unreadable``.  An empty list means the package is clean.
+    """
+
+    problems = [p for entry in manifest for p in _verify_entry(entry, root)]
+    return sorted(problems, key=lambda p: p["path"])
+
+
+def verify_parallel(manifest: Iterable[Any], root: Path, threads: int) -> List[Dict[str, str]]:
+    """Like :func:`verify_manifest`, but hash up to *threads* files at once.
+
+    The result is identical to the sequential one whatever the thread count.
+    """
+
+    if threads < 1:
+        raise ValueError(f"threads must be at least 1, got {threads}")
+    entries = list(manifest)
+    if threads == 1 or len(entries) < 2:
+        return verify_manifest(entries, root)
+    with ThreadPoolExecutor(max_workers=min(threads, len(entries))) as ex:
+        problems = [p for found in ex.map(lambda e: _verify_entry(e, root), entries) for p in found]
+    return sorted(problems, key=lambda p: p["path"])
+
+
+_VERIFY_LABELS = {
+    "missing": "MISSING",
+    "size": "SIZE MISMATCH",
+    "hash": "HASH MISMATCH",
+    "link": "LINK CHANGED",
+    "unreadable": "UNREADABLE",
+}
+
+
 def cmd_verify(a):
     root = Path(a.root or DEFAULT_ROOT)
     conn = db()
-    pkgs = [(n, json.loads(mani)) for n, mani in conn.execute("SELECT name,manifest FROM installed")]
-    bad = 0
-
-    def _verify_pkg(pkg):
-        n, mani = pkg
-        local_bad = 0
-        for entry in mani:
-            path = entry["path"] if isinstance(entry, dict) else entry
-            f = root / path.lstrip("/")
-            if not f.exists():
-                print(f"[MISSING] {n}: {path}")
-                local_bad += 1
-                continue
-            if isinstance(entry, dict):
-                actual_size = f.stat().st_size
-                if actual_size != entry["size"]:
-                    print(f"[SIZE MISMATCH] {n}: {path} expected {entry['size']}, got {actual_size}")
-                    local_bad += 1
-                actual_hash = sha256sum(f)
-                if actual_hash != entry["sha256"]:
-                    print(f"[HASH MISMATCH] {n}: {path}")
-                    local_bad += 1
-        return local_bad
-
-    with ThreadPoolExecutor(max_workers=min(8, len(pkgs) or 1)) as ex:
-        futures = [ex.submit(_verify_pkg, pkg) for pkg in pkgs]
-        for fut in progress_bar(
-            as_completed(futures),
-            total=len(futures),
-            desc="Verifying",
-            unit="pkg",
-        ):
-            bad += fut.result()
-
-    if bad == 0:
+    rows = {n: mani for n, mani in conn.execute("SELECT name,manifest FROM installed")}
+    names = list(dict.fromkeys(getattr(a, "names", None) or []))
+    unknown = [n for n in names if n not in rows]
+    if unknown:
+        die(f"Not installed: {', '.join(unknown)}")
+    pkgs = [(n, json.loads(rows[n])) for n in (names or sorted(rows))]
+    threads = min(8, os.cpu_count() or 1)
+    results: Dict[str, List[Dict[str, str]]] = {}
+    for n, mani in progress_bar(pkgs, desc="Verifying", unit="pkg"):
+        results[n] = verify_parallel(mani, root, threads)
+
+    records = [
+        {"name": n, "status": "dirty" if results[n] else "clean", "problems": results[n]}
+        for n, _mani in pkgs
+    ]
+    dirty = [r for r in records if r["problems"]]
+
+    def text(records):
+        for r in records:
+            for p in r["problems"]:
+                detail = f" {p['detail']}" if p["detail"] else ""
+                print(f"[{_VERIFY_LABELS[p['problem']]}] {r['name']}: {p['path']}{detail}")
+        for r in records:
+            print(f"[{r['status'].upper()}] {r['name']}")
+
+    _output.report(records, text)
+    if not dirty:
         ok("All files validated successfully")
-    else:
-        warn(f"{bad} validation errors")
+        return
+    bad = sum(len(r["problems"]) for r in dirty)
+    warn(f"{bad} validation errors in {len(dirty)} of {len(records)} packages")
+    sys.exit(1)
+
+
+def cmd_reinstall(a):
+    root = Path(a.root or DEFAULT_ROOT)
+    conn = db()
+    try:
+        installed = db_installed(conn)
+    finally:
+        conn.close()
+    missing = [n for n in a.names if n not in installed]
+    if missing:
+        die(f"Not installed: {', '.join(missing)}")
+
+    universe = load_universe()
+    pkgs: List[PkgMeta] = []
+    for name in a.names:
+        meta = installed[name]
+        for cand in universe.get(name, []):
+            if cand.version == meta.get("version") and cand.release == (meta.get("release") or cand.release):
+                pkgs.append(cand)
+                break
+        else:
+            die(f"{name}-{meta.get('version')}-{meta.get('release')} is not available from any repository")
+
+    if a.dry_run:
+        for pkg in pkgs:
+            log(f"[dry-run] Would reinstall {package_id(pkg)}")
+        return
+
+    downloads = fetch_all(pkgs)
+    for pkg in pkgs:
+        res = downloads.get(pkg.name)
+        if isinstance(res, Exception):
+            die(f"Failed to fetch {pkg.name}: {res}")
+    jobs = [(pkg, downloads[pkg.name][0]) for pkg in pkgs]
+    try:
+        apply_plan(
+            jobs,
+            root,
+            verify=not a.no_verify,
+            force=a.force,
+            explicit={n for n in a.names if installed[n].get("explicit")},
+            preserve_config=True,
+        )
+    except PlanApplyError as e:
+        die(str(e))
+    ok(f"Reinstalled {', '.join(package_id(p) for p in pkgs)}")
 
 
+def cmd_repair(a):
+    root = Path(a.root or DEFAULT_ROOT)
+    report = repair_db(root, fix=a.fix)
+    for name, paths in sorted(report.missing.items()):
+        for path in paths:
+            print(f"[MISSING] {name}: {path}")
+    for path in report.orphans:
+        print(f"[ORPHAN] {path}")
+    if report.clean:
+        ok("Package database is consistent; no repairs needed")
+        return
+    if a.fix:
+        for name in report.marked:
+            warn(f"Marked {name} as broken")
+        for name in report.cleared:
+            ok(f"Cleared broken flag on {name}")
+    else:
+        changes = len(report.marked) + len(report.cleared)
+        if changes:
+            warn(f"{changes} package(s) need their broken flag updated; rerun with --fix")
+    if report.orphans:
+        warn(f"{len(report.orphans)} file(s) are not owned by any installed package")
+
 def cmd_pins(a):
     pins=read_json(PIN_FILE)
     if a.action=="list":
@@ -5381,7 +7850,14 @@ def cmd_build(a):
         obsoletes=a.obsoletes, recommends=a.recommends, suggests=a.suggests
     )
     out = Path(a.output or f"{meta.name}-{meta.version}-{meta.release}.{meta.arch}{EXT}")
+    if getattr(a, "dry_run", False):
+        files = sum(1 for f in stagedir.rglob("*") if not f.is_dir())
+        print(f"Would build {out} from {stagedir} ({files} files)")
+        return
     _resolve_lpm_attr("build_package", build_package)(stagedir, meta, out, sign=(not a.no_sign))
+    if a.install_root is not None:
+        _resolve_lpm_attr("installpkg", installpkg)(out, root=Path(a.install_root), explicit=True)
+        return
     _resolve_lpm_attr("prompt_install_pkg", prompt_install_pkg)(out, default=a.install_default)
 
 def cmd_splitpkg(a):
@@ -5498,23 +7974,7 @@ def cmd_splitpkg(a):
 
                 try:
                     with install_sh.open("w", encoding="utf-8") as f:
-                        f.write("#!/bin/bash\n")
-                        f.write("set -euo pipefail\n\n")
-                        f.write("action=${1:-install}\n")
-                        f.write("new_full=${2:-}\n")
-                        f.write("old_full=${3:-}\n")
-                        f.write("source /dev/stdin <<'__LPM_INSTALL_BODY__'\n")
-                        if body:
-                            f.write(body)
-                            if not body.endswith("\n"):
-                                f.write("\n")
-                        f.write("__LPM_INSTALL_BODY__\n\n")
-                        f.write("if declare -f post_install >/dev/null; then\n")
-                        f.write("  post_install \"$new_full\"\n")
-                        f.write("fi\n")
-                        f.write("if [[ \"$action\" == \"upgrade\" ]] && declare -f post_upgrade >/dev/null; then\n")
-                        f.write("  post_upgrade \"$new_full\" \"$old_full\"\n")
-                        f.write("fi\n")
+                        f.write(wrap_install_body(body))
                     install_sh.chmod(0o755)
                     embedded = True
                 except Exception as exc:
@@ -5581,6 +8041,9 @@ def cmd_buildpkg(a):
 
     worker_count = _get_buildpkg_worker_count()
     cpu_override = _parse_cpu_overrides(getattr(a, "overrides", []))
+    build_options = _parse_build_options(
+        getattr(a, "with_options", None), getattr(a, "without_options", None)
+    )
 
     if a.python_pip:
         if a.script:
@@ -5633,6 +8096,7 @@ def cmd_buildpkg(a):
             prompt_default=a.install_default,
             executor=executor if worker_count > 1 else None,
             cpu_overrides=cpu_override,
+            build_options=build_options,
         )
         out, duration, phases, splits = future.result()
 
@@ -5919,16 +8383,87 @@ def cmd_createiso(a):
         die(f"ISO creation failed with exit code {res.returncode}")
     ok(f"Created ISO image at {output}")
 
-def cmd_clean_cache(_):
-    if CACHE_DIR.exists():
-        for p in CACHE_DIR.iterdir():
-            if p.is_dir():
-                shutil.rmtree(p)
+def _referenced_archives(
+    installed: Dict[str, dict], universe: Optional[Mapping[str, List[PkgMeta]]] = None
+) -> Set[str]:
+    """Return cache file names that belong to the *installed* packages.
+
+    An archive is cached under the name its repository URL gives it, so the
+    installed version is looked up in *universe*; packages no repository
+    lists any more fall back to the ``name-version-release.arch`` form.
+    """
+
+    names: Set[str] = set()
+    for name, meta in installed.items():
+        blobs = [
+            p.blob
+            for p in (universe or {}).get(name, ())
+            if p.blob and p.version == meta.get("version") and p.release == meta.get("release")
+        ]
+        if blobs:
+            names.update(_cache_path_for(blob).name for blob in blobs)
+        else:
+            names.add(f"{name}-{meta.get('version')}-{meta.get('release')}.{meta.get('arch')}{EXT}")
+        names.add(f"{name}.built{EXT}")
+    return names
+
+
+def cmd_download(a, fetcher: Optional[Fetcher] = None):
+    u = build_universe()
+    try:
+        plan = solve(a.names, u, recommends=not getattr(a, "no_recommends", False))
+    except ResolutionError as e:
+        die(f"dependency resolution failed: {e}")
+    dest = Path(a.dest)
+    try:
+        if fetcher is None:
+            if _config.RESUMABLE_DOWNLOADS:
+                fetcher = HttpFetcher(
+                    CACHE_DIR / "partial",
+                    progress=lambda name: _progress.track(f"Fetching {name}"),
+                    offline=is_offline(),
+                )
             else:
-                p.unlink()
-        ok("Removed cached blobs")
-    else:
+                fetcher = RepoFetcher()
+        noverify = getattr(a, "no_verify", False) or os.environ.get("LPM_NO_VERIFY") == "1"
+        written = download_packages(plan, dest, fetcher, keyring=None if noverify else trusted_keyring())
+    except (ChecksumMismatch, FetchError, VerifyError) as e:
+        die(f"download aborted: {e}")
+    ok(f"Downloaded {len(written)} package(s) to {dest}")
+
+
+def cmd_clean_cache(a):
+    if not CACHE_DIR.exists():
         log("No cache directory")
+        return
+    orphans = getattr(a, "orphans", False)
+    dry_run = getattr(a, "dry_run", False)
+    keep = None
+    if orphans:
+        try:
+            universe = load_universe()
+        except (OfflineError, OSError):
+            # Without the indexes only the conventional archive names are kept.
+            universe = {}
+        conn = db()
+        try:
+            referenced = _referenced_archives(db_installed(conn), universe)
+        finally:
+            conn.close()
+
+        def keep(entry: _cache.CacheEntry) -> bool:
+            return entry.path.name in referenced
+
+    result = _cache.prune(
+        _cache.DirectoryCache(CACHE_DIR), everything=not orphans, keep=keep, dry_run=dry_run
+    )
+    what = "orphaned archives" if orphans else "cached blobs"
+    if dry_run:
+        for path in result.removed:
+            print(path)
+        ok(f"Would remove {len(result.removed)} {what}, reclaiming {result.reclaimed} bytes")
+    else:
+        ok(f"Removed {len(result.removed)} {what}, reclaimed {result.reclaimed} bytes")
 
 def cmd_fileremove(a):
     root = Path(a.root or DEFAULT_ROOT)
@@ -6060,6 +8595,42 @@ def _resolve_obsoletes_against_installed(
 
     return matches
 
+def _modified_config_paths(
+    manifest: Iterable[Mapping[str, Any]],
+    installed_manifest: Iterable[Any],
+    root: RootContext,
+) -> Set[str]:
+    """Return the config paths of *manifest* that were edited since installation.
+
+    A path counts as edited when it is a regular file under *root* whose hash
+    differs from the one *installed_manifest* recorded for it.  Manifests
+    built before the ``config`` flag existed lack it; their config files are
+    taken from the installed manifest's flag, or else from living in ``/etc``.
+    """
+
+    recorded = {e.get("path"): e for e in installed_manifest if isinstance(e, dict)}
+    modified: Set[str] = set()
+    for entry in manifest:
+        path = entry.get("path")
+        if "link" in entry or path not in recorded:
+            continue
+        previous = recorded[path]
+        if "config" in entry:
+            is_config = bool(entry["config"])
+        elif "config" in previous:
+            is_config = bool(previous["config"])
+        else:
+            is_config = str(path).startswith("/etc/")
+        if not is_config:
+            continue
+        dest = root.path(path)
+        if dest.is_symlink() or not dest.is_file():
+            continue
+        if sha256sum(dest) != previous.get("sha256"):
+            modified.add(path)
+    return modified
+
+
 def installpkg(
     file: Path | Iterable[Path],
     root: Path = Path(DEFAULT_ROOT),
@@ -6071,6 +8642,10 @@ def installpkg(
     hook_failure_mode: str = HookFailureMode.STRICT,
     hook_transaction: Optional[HookTransactionManager] = None,
     register_event: bool = True,
+    fs_transaction: Optional[Transaction] = None,
+    db_conn: Optional[sqlite3.Connection] = None,
+    preserve_config: bool = False,
+    dependencies: bool = True,
 ) -> PkgMeta | List[PkgMeta]:
     """
     Production-grade .zst package installer with protected package + dep resolution.
@@ -6080,6 +8655,18 @@ def installpkg(
     manager and a list of installed :class:`PkgMeta` objects is returned. When a
     single path is provided, the installed :class:`PkgMeta` is returned as
     before.
+
+    When *fs_transaction* and *db_conn* are supplied (see :func:`apply_plan`)
+    every touched path is recorded in the filesystem transaction and the
+    database rows are written on *db_conn* without committing, leaving the
+    caller in charge of committing or rolling back the whole batch.
+
+    With *preserve_config*, manifest entries marked ``config`` whose file on
+    disk no longer matches the hash recorded for the installed package are
+    left as they are instead of being replaced from the archive.
+
+    With *dependencies* false a meta-package is accepted without resolving
+    and installing its requirements, as ``install --no-deps`` asks.
     """
     global PROTECTED
     PROTECTED = load_protected()
@@ -6099,10 +8686,16 @@ def installpkg(
                 root=root,
                 base_env={"LPM_ROOT": str(root)},
                 failure_mode=hook_failure_mode,
+                defer=_hook_deferrer(),
             )
             owns_txn = True
 
+        def _record(path: Path) -> None:
+            if fs_transaction is not None:
+                fs_transaction.record(path)
+
         def _replace_path(dest: Path) -> None:
+            _record(dest)
             try:
                 if dest.is_symlink() or dest.is_file():
                     dest.unlink()
@@ -6136,8 +8729,10 @@ def installpkg(
                 die(f"Invalid package: {pkg_file.name} (no metadata)")
             ok(f"Valid package: {meta.name}-{meta.version}-{meta.release}.{meta.arch}")
 
-            if not arch_compatible(meta.arch, ARCH):
-                die(f"Incompatible architecture: {meta.arch} (host: {ARCH})")
+            want_arch = effective_arch()
+            if not filter_arch([meta], want_arch):
+                label = "target" if _TARGET_ARCH else "host"
+                die(f"Incompatible architecture: {meta.arch} ({label}: {want_arch})")
 
             # --- Step 3b: Protected package guard ---
             if meta.name in PROTECTED and not force:
@@ -6148,13 +8743,15 @@ def installpkg(
             # If package has REQUIRES but no manifest payload → treat as meta-package
             if not mani or all(e["path"].startswith("/.lpm") for e in mani):
                 if meta.requires:
-                    log(f"[meta] {meta.name} is a meta-package, resolving deps: {', '.join(meta.requires)}")
-                    u = build_universe()
-                    try:
-                        plan = solve(meta.requires, u)
-                    except ResolutionError as e:
-                        raise ResolutionError(f"{meta.name}: {e}")
-                    do_install(plan, root, dry_run, verify, force, explicit=set(), allow_fallback=allow_fallback)
+                    # Inside apply_plan() the dependencies are already part of the plan.
+                    if db_conn is None and dependencies:
+                        log(f"[meta] {meta.name} is a meta-package, resolving deps: {', '.join(meta.requires)}")
+                        u = build_universe()
+                        try:
+                            plan = solve(meta.requires, u)
+                        except ResolutionError as e:
+                            raise ResolutionError(f"{meta.name}: {e}")
+                        do_install(plan, root, dry_run, verify, force, explicit=set(), allow_fallback=allow_fallback)
                     ok(f"Installed meta-package {meta.name}-{meta.version}-{meta.release}.{meta.arch}")
                     return meta
 
@@ -6169,7 +8766,7 @@ def installpkg(
                 return meta
 
             # --- Step 5: Transaction (unchanged below) ---
-            conn = db()
+            conn = db_conn if db_conn is not None else db()
             installed_state: Dict[str, dict] = {}
             if meta.obsoletes:
                 installed_state = db_installed(conn)
@@ -6208,7 +8805,12 @@ def installpkg(
 
                 txn.ensure_pre_transaction()
 
-            with transaction(conn, f"install {meta.name}", dry_run):
+            db_txn = (
+                contextlib.nullcontext()
+                if db_conn is not None
+                else transaction(conn, f"install {meta.name}", dry_run)
+            )
+            with db_txn:
 
                 hook_env = {
                     "LPM_PKG": meta.name,
@@ -6225,11 +8827,18 @@ def installpkg(
 
                 tmp_root = Path(tempfile.mkdtemp(prefix=f"lpm-{meta.name}-", dir="/tmp"))
                 try:
-                    manifest = extract_tar(pkg_file, tmp_root)
+                    manifest = extract_tar(
+                        pkg_file,
+                        tmp_root,
+                        total_bytes=sum(int(e.get("size") or 0) for e in mani) or None,
+                    )
+
+                    staged_root = RootContext(tmp_root)
+                    target_root = RootContext(root)
 
                     # Validate manifest files
                     for e in mani:
-                        f = tmp_root / e["path"].lstrip("/")
+                        f = staged_root.path(e["path"])
                         if not f.exists() and not f.is_symlink():
                             die(f"Manifest missing file: {e['path']}")
 
@@ -6247,11 +8856,7 @@ def installpkg(
                             link_hash = hashlib.sha256(target.encode()).hexdigest()
                             payload_hash = None
 
-                            payload_candidate: Optional[Path]
-                            if target.startswith("/"):
-                                payload_candidate = tmp_root / target.lstrip("/")
-                            else:
-                                payload_candidate = f.parent / target
+                            payload_candidate: Optional[Path] = staged_root.link_target(e["path"], target)
 
                             resolved_payload: Optional[Path] = None
                             if payload_candidate is not None:
@@ -6292,6 +8897,12 @@ def installpkg(
                                 f"Hash mismatch for {e['path']}: expected {expected_hash}, got {actual_hash}"
                             )
 
+                    kept_config = (
+                        _modified_config_paths(mani, previous_manifest, target_root)
+                        if preserve_config
+                        else set()
+                    )
+
                     with operation_phase(privileged=True):
                         # Atomic package replace for upgrades: remove all previously-owned
                         # files before materializing the new payload so stale paths and
@@ -6299,17 +8910,20 @@ def installpkg(
                         if previous_version is not None and previous_manifest:
                             for old_entry in previous_manifest:
                                 old_path = old_entry.get("path") if isinstance(old_entry, dict) else None
-                                if not old_path:
+                                if not old_path or old_path in kept_config:
                                     continue
-                                dest_old = root / str(old_path).lstrip("/")
+                                dest_old = target_root.path(str(old_path))
                                 _replace_path(dest_old)
 
                         # Atomic installs must replace existing files to avoid partial or half-updated payloads.
                         replace_all = True
                         for e in mani:
-                            rel = e["path"].lstrip("/")
-                            src = tmp_root / rel
-                            dest = root / rel
+                            if e["path"] in kept_config:
+                                log(f"[config] keeping locally modified {e['path']}")
+                                continue
+                            src = staged_root.path(e["path"])
+                            dest = target_root.path(e["path"])
+                            _record(dest)
                             dest.parent.mkdir(parents=True, exist_ok=True)
 
                             if src.is_dir():
@@ -6353,8 +8967,8 @@ def installpkg(
                         staged_script = None
                         installed_script = None
                         for candidate_rel in ("/.lpm-install.sh", "/.lpm/install.sh"):
-                            candidate_path = tmp_root / candidate_rel.lstrip("/")
-                            candidate_installed = root / candidate_rel.lstrip("/")
+                            candidate_path = staged_root.path(candidate_rel)
+                            candidate_installed = target_root.path(candidate_rel)
                             if candidate_path.exists():
                                 install_script_rel = candidate_rel
                                 staged_script = candidate_path
@@ -6365,7 +8979,9 @@ def installpkg(
                                 installed_script = candidate_installed
                                 break
 
-                        if replace_all and installed_script is not None:
+                        # A script already under root was just materialized from this
+                        # package's manifest; only clear the way for a staged one.
+                        if replace_all and installed_script is not None and staged_script is not None:
                             _replace_path(installed_script)
 
                         if install_script_rel is not None and staged_script is not None:
@@ -6373,6 +8989,7 @@ def installpkg(
                                 staged_script.chmod(staged_script.stat().st_mode | stat.S_IXUSR | stat.S_IXGRP | stat.S_IXOTH)
                             except OSError:
                                 pass
+                            _record(installed_script)
                             staged_script.rename(installed_script)
                             mani.append(
                                 {
@@ -6384,39 +9001,38 @@ def installpkg(
                                 }
                             )
 
-                        # Handle delta generation
-                        if staged_script is not None and installed_script is not None:
-                            try:
-                                generate_deltas(pkg_file, meta, mani, staged_script, installed_script)
-                            except Exception as e:
-                                warn(f"Delta generation failed: {e}")
-                                try:
-                                    for candidate in (installed_script, staged_script):
-                                        candidate.unlink()
-                                except FileNotFoundError:
-                                    pass
-                                if install_script_rel is not None:
-                                    mani = [e for e in mani if e["path"] != install_script_rel]
-
+                        retained_script = _installed_script_path(meta.name, root)
                         if install_script_rel is not None and installed_script is not None and installed_script.exists():
-                            install_action = "upgrade" if previous_version is not None else "install"
-                            install_env = {**os.environ, **hook_env, "LPM_INSTALL_ACTION": install_action}
-                            new_full = f"{meta.version}-{meta.release}"
-                            old_full = (
-                                f"{previous_version}-{previous_release}"
-                                if previous_version is not None and previous_release is not None
-                                else ""
+                            hook_context = HookContext(
+                                action="upgrade" if previous_version is not None else "install",
+                                root=root,
+                                name=meta.name,
+                                new_version=f"{meta.version}-{meta.release}",
+                                old_version=(
+                                    f"{previous_version}-{previous_release}"
+                                    if previous_version is not None and previous_release is not None
+                                    else ""
+                                ),
+                                env=hook_env,
                             )
                             try:
-                                subprocess.run(
-                                    [str(installed_script), install_action, new_full, old_full],
-                                    env=install_env,
-                                    check=True,
+                                # Keep a copy so removal can run post_remove().
+                                _record(retained_script)
+                                retained_script.parent.mkdir(parents=True, exist_ok=True)
+                                shutil.copy2(installed_script, retained_script)
+                                hook_result = _run_install_script(
+                                    installed_script, hook_context, durable=retained_script
                                 )
                             finally:
                                 with contextlib.suppress(FileNotFoundError):
                                     installed_script.unlink()
                                 mani = [e for e in mani if e["path"] != install_script_rel]
+                            if hook_result is not None:
+                                for line in hook_result.output.splitlines():
+                                    log(f"[{hook_context.function}] {line}")
+                        elif retained_script.exists():
+                            _record(retained_script)
+                            retained_script.unlink()
 
                     # Update DB
                     conn.execute(
@@ -6492,6 +9108,7 @@ def removepkg(
                 root=root,
                 base_env={"LPM_ROOT": str(root)},
                 failure_mode=hook_failure_mode,
+                defer=_hook_deferrer(),
             )
             owns_txn = True
 
@@ -6541,12 +9158,26 @@ def removepkg(
         with transaction(conn, f"remove {name}", dry_run):
             run_hook("pre_remove", {"LPM_PKG": name, "LPM_ROOT": str(root)}, failure_mode=hook_failure_mode, package_context=name)
             _remove_installed_package(meta, root, dry_run, conn)
+            retained_script = _installed_script_path(name, root)
+            if not dry_run and retained_script.exists():
+                hook_context = HookContext(
+                    action="remove",
+                    root=root,
+                    name=name,
+                    old_version=f"{version}-{release}",
+                )
+                hook_result = _run_install_script(retained_script, hook_context)
+                # A deferred post_remove() still runs the retained copy at first boot.
+                if hook_result is not None:
+                    for line in hook_result.output.splitlines():
+                        log(f"[{hook_context.function}] {line}")
+                    retained_script.unlink()
             run_hook("post_remove", {"LPM_PKG": name, "LPM_ROOT": str(root)}, failure_mode=hook_failure_mode, package_context=name)
 
         if not dry_run and is_meta_package:
             installed_after = db_installed(conn)
             needed = _compute_needed_set(installed_after)
-            candidates = {req.split()[0] for req in meta_requires}
+            candidates = _required_names(meta_requires)
             auto_remove = []
             for cand in sorted(candidates):
                 dep_meta = installed_after.get(cand)
@@ -6793,6 +9424,10 @@ def cmd_buildchroot(args) -> int:
     return chroot_helpers.run_buildchroot(args)
 
 
+def cmd_completions(a):
+    sys.stdout.write(_completions.generate(build_parser(), a.shell))
+
+
 def build_parser()->argparse.ArgumentParser:
     p=argparse.ArgumentParser(prog="lpm", description="Linux Package Manager with SAT solver, signatures, and .lpmbuild")
     p.add_argument(
@@ -6800,6 +9435,54 @@ def build_parser()->argparse.ArgumentParser:
         action="store_true",
         help="provision baseline system configuration files and exit",
     )
+    p.add_argument(
+        "--allow-unsigned-repo",
+        action="store_true",
+        help="accept repository indexes without a valid signature (local testing only)",
+    )
+    p.add_argument(
+        "--color",
+        choices=_output.COLOR_MODES,
+        default="auto",
+        help="colorize output: auto (only on a terminal, honouring NO_COLOR), always, or never",
+    )
+    p.add_argument(
+        "--format",
+        choices=_output.FORMATS,
+        default="text",
+        help="print results of search, list and info as text or as a JSON array",
+    )
+    p.add_argument(
+        "-q",
+        "--quiet",
+        action="store_true",
+        help="hide progress output",
+    )
+    p.add_argument(
+        "--offline",
+        action="store_true",
+        help="never use the network; resolve and install from cached indexes and archives only",
+    )
+    p.add_argument(
+        "-y",
+        "--yes",
+        "--assume-yes",
+        dest="assume_yes",
+        action="store_true",
+        help="answer yes to every confirmation prompt",
+    )
+    p.add_argument(
+        "--cache-dir",
+        dest="global_cache_dir",
+        metavar="DIR",
+        help="package cache directory, overriding cache_dir from config.toml",
+    )
+    p.add_argument(
+        "--dry-run",
+        dest="global_dry_run",
+        action="store_true",
+        help="print what install, remove, build and similar commands would do without changing anything",
+    )
     p.add_argument(
         "--sysconfig-root",
         type=Path,
@@ -6809,20 +9492,75 @@ def build_parser()->argparse.ArgumentParser:
     sub=p.add_subparsers(dest="cmd")
 
     sp=sub.add_parser("setup", help="Run the interactive configuration wizard"); sp.set_defaults(func=cmd_setup)
+    sp=sub.add_parser("completions", help="Print a shell completion script")
+    sp.add_argument("shell", choices=_completions.SHELLS)
+    sp.set_defaults(func=cmd_completions)
     sp=sub.add_parser("repolist", help="Show configured repositories"); sp.set_defaults(func=cmd_repolist)
     sp=sub.add_parser("repoadd", help="Add a repository"); sp.add_argument("name"); sp.add_argument("url");                   sp.add_argument("--priority",type=int,default=10); sp.set_defaults(func=cmd_repoadd)
     sp=sub.add_parser("repodel", help="Remove a repository"); sp.add_argument("name"); sp.set_defaults(func=cmd_repodel)
+    sp=sub.add_parser("update", help="Refresh repository indexes"); sp.set_defaults(func=cmd_update)
 
-    sp=sub.add_parser("clean", help="Remove cached blobs"); sp.set_defaults(func=cmd_clean_cache)
-
-    sp=sub.add_parser("search", help="Search packages"); sp.add_argument("patterns", nargs="*"); sp.set_defaults(func=cmd_search)
+    sp=sub.add_parser("download", help="Download packages and their dependencies without installing")
+    sp.add_argument("names", nargs="+")
+    sp.add_argument("--dest", default=".", metavar="DIR", help="directory to write archives to (default: current directory)")
+    sp.add_argument("--no-recommends", action="store_true", help="do not download recommended packages")
+    sp.add_argument("--no-verify", action="store_true", help="do not check package signatures")
+    sp.set_defaults(func=cmd_download)
+
+    sp=sub.add_parser("clean", help="Remove cached blobs")
+    scope = sp.add_mutually_exclusive_group()
+    scope.add_argument("--all", action="store_true", help="remove everything in the cache (default)")
+    scope.add_argument("--orphans", action="store_true", help="remove only archives of packages that are not installed")
+    sp.add_argument("--dry-run", action="store_true", help="list what would be removed without deleting it")
+    sp.set_defaults(func=cmd_clean_cache)
+
+    sp=sub.add_parser("search", help="Search packages"); sp.add_argument("patterns", nargs="*")
+    sp.add_argument("--regex", action="store_true", help="treat patterns as case-insensitive regular expressions")
+    sp.add_argument("--limit", type=int, metavar="N", help="show at most N matches")
+    sp.set_defaults(func=cmd_search)
     sp=sub.add_parser("info", help="Show package info"); sp.add_argument("names", nargs="+"); sp.set_defaults(func=cmd_info)
 
+    sp=sub.add_parser("why", help="Show which requested packages pull a package into the plan")
+    sp.add_argument("name")
+    sp.add_argument("--goal", dest="goals", action="append", metavar="NAME", help="resolve NAME instead of the explicitly installed packages; repeatable")
+    sp.set_defaults(func=cmd_why)
+
     sp=sub.add_parser("install", help="Install packages")
     sp.add_argument("names", nargs="+")
-    sp.add_argument("--root")
+    sp.add_argument(
+        "--root",
+        action="append",
+        help="install into this root; repeat to apply the same plan to several roots in parallel",
+    )
     sp.add_argument("--dry-run", action="store_true")
     sp.add_argument("--no-verify", action="store_true", help="skip signature verification (DANGEROUS)")
+    sp.add_argument("--no-recommends", action="store_true", help="do not pull in recommended packages")
+    sp.add_argument(
+        "--no-deps",
+        action="store_true",
+        help="install only the named packages, ignoring their dependencies (conflicts still apply)",
+    )
+    sp.add_argument(
+        "--prefer",
+        choices=[p.value for p in ResolutionPolicy],
+        help="which version to pick when several satisfy every constraint",
+    )
+    sp.add_argument(
+        "--trace",
+        metavar="FILE",
+        help="write a JSON explanation of why each package was selected to FILE",
+    )
+    sp.add_argument(
+        "--target-arch",
+        metavar="ARCH",
+        help="select packages for ARCH and defer hooks to first boot when it is not the host architecture",
+    )
+    sp.add_argument(
+        "--timeout",
+        type=float,
+        metavar="SECONDS",
+        help="abort and roll back if resolving, downloading and installing takes longer",
+    )
     sp.add_argument(
         "--no-delta",
         action="store_true",
@@ -6849,6 +9587,14 @@ def build_parser()->argparse.ArgumentParser:
     sp.add_argument("--force", action="store_true", help="override protected package list")
     sp.set_defaults(func=cmd_remove)
 
+    sp=sub.add_parser("reinstall", help="Reinstall packages at their installed version, keeping edited config files")
+    sp.add_argument("names", nargs="+")
+    sp.add_argument("--root")
+    sp.add_argument("--dry-run", action="store_true")
+    sp.add_argument("--no-verify", action="store_true", help="skip signature verification (DANGEROUS)")
+    sp.add_argument("--force", action="store_true", help="override protected package list")
+    sp.set_defaults(func=cmd_reinstall)
+
     sp=sub.add_parser("autoremove", help="Remove unneeded packages")
     sp.add_argument("--root")
     sp.add_argument("--dry-run", action="store_true")
@@ -6860,6 +9606,23 @@ def build_parser()->argparse.ArgumentParser:
         sp.add_argument("--root")
         sp.add_argument("--dry-run", action="store_true")
         sp.add_argument("--no-verify", action="store_true", help="skip signature verification (DANGEROUS)")
+        sp.add_argument("--no-recommends", action="store_true", help="do not pull in recommended packages")
+        sp.add_argument(
+            "--prefer",
+            choices=[p.value for p in ResolutionPolicy],
+            help="which version to pick when several satisfy every constraint",
+        )
+        sp.add_argument(
+            "--target-arch",
+            metavar="ARCH",
+            help="select packages for ARCH and defer hooks to first boot when it is not the host architecture",
+        )
+        sp.add_argument(
+            "--timeout",
+            type=float,
+            metavar="SECONDS",
+            help="abort and roll back if resolving, downloading and installing takes longer",
+        )
         sp.add_argument(
             "--no-delta",
             action="store_true",
@@ -6884,12 +9647,31 @@ def build_parser()->argparse.ArgumentParser:
     add_upgrade_subparser("upgrade", "Upgrade packages (targets or all)")
     add_upgrade_subparser("upgradepkg", "Alias for upgrade; upgrade packages (targets or all)")
 
-    sp=sub.add_parser("list", help="List installed packages"); sp.set_defaults(func=cmd_list_installed)
+    sp=sub.add_parser("list", help="List installed or available packages")
+    mode = sp.add_mutually_exclusive_group()
+    mode.add_argument("--installed", action="store_true", help="list installed packages (default)")
+    mode.add_argument("--available", action="store_true", help="list the newest version of every repository package")
+    sp.add_argument("--root", help="list the packages installed under this root instead")
+    sp.set_defaults(func=cmd_list_installed)
     sp=sub.add_parser("files", help="List files installed by package"); sp.add_argument("name"); sp.set_defaults(func=cmd_files)
+    sp=sub.add_parser("provides", help="Show which installed package owns each path")
+    sp.add_argument("paths", nargs="+")
+    sp.add_argument("--root")
+    sp.set_defaults(func=cmd_provides)
     sp=sub.add_parser("snapshots", help="List snapshots"); sp.add_argument("--delete", type=int, nargs="*", help="snapshot IDs to delete"); sp.add_argument("--prune", action="store_true", help="prune old snapshots"); sp.set_defaults(func=cmd_snapshots)
-    sp=sub.add_parser("rollback", help="Restore from snapshot"); sp.add_argument("snapshot_id", nargs="?", type=int, help="snapshot ID (default latest)"); sp.set_defaults(func=cmd_rollback)
-    sp=sub.add_parser("history", help="Show last transactions"); sp.set_defaults(func=cmd_history)
-    sp=sub.add_parser("verify", help="Verify installed files exist"); sp.add_argument("--root"); sp.set_defaults(func=cmd_verify)
+    sp=sub.add_parser("rollback", help="Restore from snapshot"); sp.add_argument("snapshot_id", nargs="?", type=int, help="snapshot ID (default latest)")
+    sp.add_argument("--transaction", type=int, metavar="ID", help="undo install transaction ID instead of restoring a snapshot")
+    sp.set_defaults(func=cmd_rollback)
+    sp=sub.add_parser("history", help="Show last transactions")
+    sp.add_argument("--transactions", action="store_true", help="list install transactions with their IDs and packages")
+    sp.set_defaults(func=cmd_history)
+    sp=sub.add_parser("verify", help="Verify installed files against their manifests")
+    sp.add_argument("names", nargs="*", help="Packages to check (default: all installed)")
+    sp.add_argument("--root"); sp.set_defaults(func=cmd_verify)
+    sp=sub.add_parser("repair", help="Reconcile the package database with installed files")
+    sp.add_argument("--root")
+    sp.add_argument("--fix", action="store_true", help="mark packages with missing files as broken")
+    sp.set_defaults(func=cmd_repair)
 
     sp=sub.add_parser("pins", help="Show or set holds/preferences")
     sp.add_argument("action", choices=["list","hold","unhold","prefer"])
@@ -6916,6 +9698,8 @@ def build_parser()->argparse.ArgumentParser:
     sp.add_argument("--output", help=f"output {EXT} file")
     sp.add_argument("--no-sign", action="store_true", help="do not sign even if key exists")
     sp.add_argument("--install-default", choices=["y", "n"], help="default answer for install prompt")
+    sp.add_argument("--install-root", metavar="PATH", help="install the built package into PATH without prompting (requires root for /)")
+    sp.add_argument("--dry-run", action="store_true", help="show the package that would be built without writing it")
     sp.set_defaults(func=cmd_build)
 
     sp=sub.add_parser("splitpkg", help=f"Package an additional staged root during .lpmbuild execution")
@@ -6956,6 +9740,20 @@ def build_parser()->argparse.ArgumentParser:
     )
     sp.add_argument("--install-default", choices=["y", "n"], help="default answer for install prompt")
     sp.add_argument("--python-pip", metavar="SPEC", help="build a package from a Python distribution fetched via pip")
+    sp.add_argument(
+        "--with",
+        dest="with_options",
+        action="append",
+        metavar="OPTION",
+        help="enable a build option declared in the recipe's options= (can be repeated)",
+    )
+    sp.add_argument(
+        "--without",
+        dest="without_options",
+        action="append",
+        metavar="OPTION",
+        help="disable a build option declared in the recipe's options= (can be repeated)",
+    )
     sp.set_defaults(func=cmd_buildpkg)
 
     sp=sub.add_parser("rebuild", help="Rebuild an installed package and all installed reverse dependencies")
@@ -7082,6 +9880,32 @@ def build_parser()->argparse.ArgumentParser:
 
     return p
 
+def _cli_config_overrides(args) -> Dict[str, object]:
+    """Return the command line flags that override ``config.toml`` keys."""
+    root = getattr(args, "root", None)
+    if isinstance(root, list):
+        # ``install`` takes --root repeatedly; the first one is the primary root.
+        root = root[0] if root else None
+    return {
+        "cache_dir": getattr(args, "global_cache_dir", None),
+        "default_root": root,
+        "verify": False if getattr(args, "no_verify", False) else None,
+    }
+
+
+def _use_config(settings: _config.Config, args) -> None:
+    """Apply the explicitly set ``config.toml`` keys to this run."""
+    global CACHE_DIR, DEFAULT_ROOT, CONFIG_REPOS
+    if "cache_dir" in settings.origins:
+        CACHE_DIR = settings.cache_dir
+    if settings.origins.get("default_root") not in (None, "command line"):
+        # An explicit --root is already honoured by each command.
+        DEFAULT_ROOT = str(settings.default_root)
+    CONFIG_REPOS = settings.repos
+    if not settings.verify and hasattr(args, "no_verify"):
+        args.no_verify = True
+
+
 def main(argv=None):
     parser=build_parser()
     args=parser.parse_args(argv)
@@ -7093,27 +9917,57 @@ def main(argv=None):
         return 0
     if cmd is None:
         parser.error("a subcommand is required")
+    if args.global_dry_run:
+        if not hasattr(args, "dry_run"):
+            parser.error(f"--dry-run is not supported by {cmd}")
+        args.dry_run = True
+    if getattr(args, "allow_unsigned_repo", False):
+        _config.ALLOW_UNSIGNED_REPO = True
+    global ASSUME_YES
+    ASSUME_YES = args.assume_yes
+    _output.set_color_mode(getattr(args, "color", "auto"))
+    _output.set_format(getattr(args, "format", "text"))
+    _progress.set_quiet(getattr(args, "quiet", False))
+    try:
+        settings = _config.load_config(overrides=_cli_config_overrides(args))
+    except _config.ConfigError as e:
+        die(f"bad configuration: {e}")
+    _use_config(settings, args)
     conf_file = _resolve_lpm_attr("CONF_FILE", CONF_FILE)
     try:
-        if cmd != "setup" and not conf_file.exists():
+        if cmd not in _NO_SETUP_COMMANDS and not conf_file.exists():
             with operation_phase(privileged=True):
                 _resolve_lpm_attr("run_first_run_wizard", run_first_run_wizard)()
                 _resolve_lpm_attr("initialize_state", initialize_state)()
         elif cmd in _STATE_COMMANDS:
             _initialize_cli_state()
-        if cmd in _PRIVILEGED_COMMANDS:
-            require_root(cmd)
-            with operation_phase(privileged=True):
+        timeout = getattr(args, "timeout", None)
+        deadline = Deadline.after(timeout) if timeout else None
+        with _deadline.active(deadline), _cancel.handlers(), offline_mode(getattr(args, "offline", False)), command_root_state(getattr(args, "root", None)):
+            if _privileged_command(args):
+                # Non-root callers go through the PRIVILEGE_ESCALATION gate (pkexec/sudo by
+                # default) unless set_privilege_gate() installed one explicitly.
+                # A main(argv) call restarts as ``python -m lpm`` with that argv.
+                restart = None if argv is None else [str(Path(__file__).with_name("__main__.py")), *argv]
+                use_configured_gate(configured_gate(restart))
                 require_root(cmd)
+                with operation_phase(privileged=True):
+                    require_root(cmd)
+                    args.func(args)
+            else:
                 args.func(args)
-        else:
-            args.func(args)
     except FirstRunSetupError as e:
         die(str(e))
     except PermissionError as e:
         die(_state_setup_permission_message(e))
     except ResolutionError as e:
         die(f"dependency resolution failed: {e}")
+    except DeadlineExceeded as e:
+        die(f"aborted: {e}", code=EXIT_DEADLINE)
+    except UserAborted as e:
+        die(f"aborted: {e}", code=130)
+    except OfflineError as e:
+        die(f"offline: {e}")
 
 if __name__=="__main__":
     main()
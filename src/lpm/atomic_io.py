@@ -1,5 +1,6 @@
 from __future__ import annotations
 
+import errno
 import os
 import tempfile
 import logging
@@ -18,15 +19,28 @@ def _coerce_bytes(data: Union[str, BytesLike], *, encoding: str = "utf-8") -> by
     return bytes(data)
 
 
+class FsError(OSError):
+    """Raised when a completed write could not be made durable."""
+
+
+# Filesystems that cannot fsync a directory report one of these; there is
+# nothing more durable we could do, so they are not treated as failures.
+_UNSUPPORTED_SYNC = {errno.EINVAL, errno.ENOTSUP, errno.EBADF}
+
+
 def _sync_directory(path: Path) -> None:
+    """Flush the directory entry for *path* so a rename into it survives a crash."""
+
     try:
         dir_fd = os.open(path, os.O_RDONLY)
-    except OSError:
-        return
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot open directory {path} for sync: {exc.strerror}") from exc
     try:
         os.fsync(dir_fd)
-    except OSError:
-        pass
+    except OSError as exc:
+        if exc.errno not in _UNSUPPORTED_SYNC:
+            raise FsError(exc.errno, f"failed to sync directory {path}: {exc.strerror}") from exc
+        logger.debug("directory sync unsupported for %s: %s", path, exc)
     finally:
         os.close(dir_fd)
 
@@ -134,7 +148,9 @@ def safe_write(
 
     The destination directory is created automatically. If *mode* is provided
     it will be applied to the resulting file (after the active umask is
-    honoured). Ownership can be adjusted via *owner* and *group*.
+    honoured). Ownership can be adjusted via *owner* and *group*. After the
+    rename the containing directory is fsynced as well; failure to do so
+    raises :class:`FsError` even though the new contents are in place.
     """
 
     payload = _coerce_bytes(data, encoding=encoding)
@@ -223,6 +239,7 @@ def atomic_replace(
 
 __all__ = [
     "BytesLike",
+    "FsError",
     "enforce_umask",
     "read_bytes",
     "safe_write",
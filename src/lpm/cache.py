@@ -0,0 +1,272 @@
+"""Size-bounded package cache with least-recently-used eviction.
+
+Downloaded archives live in :data:`lpm.config.CACHE_DIR`.  Every time an
+archive is served from (or stored into) the cache its access time is bumped
+via :func:`touch`, so :func:`enforce_limit` can evict the archives that have
+gone unused the longest.  Archives referenced by an in-progress transaction
+are registered with :func:`in_use` and are never evicted.
+
+:func:`prune` removes archives on demand, for ``lpm clean``.  It works
+against the :class:`CacheStore` protocol so callers can substitute a store
+other than :class:`DirectoryCache`.
+"""
+
+from __future__ import annotations
+
+import contextlib
+import os
+import shutil
+import threading
+import time
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Callable, Dict, Iterable, Iterator, List, Optional, Protocol
+
+from . import config
+
+_SIDECAR_SUFFIXES = (".sig",)
+
+_IN_USE: Dict[Path, int] = {}
+_IN_USE_LOCK = threading.Lock()
+
+
+@dataclass(frozen=True)
+class CacheEntry:
+    """An archive in the cache together with its sidecar files."""
+
+    path: Path
+    size: int
+    atime: float
+    sidecars: tuple[Path, ...] = ()
+
+
+def _cache_dir(cache_dir: Optional[Path]) -> Path:
+    return Path(cache_dir) if cache_dir is not None else config.CACHE_DIR
+
+
+def _key(path: Path) -> Path:
+    return Path(os.path.abspath(path))
+
+
+def touch(path: Path) -> None:
+    """Record an access to *path* without changing its modification time."""
+
+    try:
+        st = os.stat(path)
+    except OSError:
+        return
+    try:
+        os.utime(path, (time.time(), st.st_mtime))
+    except OSError:
+        pass
+
+
+@contextlib.contextmanager
+def in_use(paths: Iterable[Path]) -> Iterator[None]:
+    """Protect *paths* from eviction for the duration of the block."""
+
+    keys = [_key(Path(p)) for p in paths]
+    with _IN_USE_LOCK:
+        for key in keys:
+            _IN_USE[key] = _IN_USE.get(key, 0) + 1
+    try:
+        yield
+    finally:
+        with _IN_USE_LOCK:
+            for key in keys:
+                count = _IN_USE.get(key, 0) - 1
+                if count > 0:
+                    _IN_USE[key] = count
+                else:
+                    _IN_USE.pop(key, None)
+
+
+def is_in_use(path: Path) -> bool:
+    with _IN_USE_LOCK:
+        return _key(Path(path)) in _IN_USE
+
+
+def entries(cache_dir: Optional[Path] = None) -> List[CacheEntry]:
+    """Return the archives stored directly in the cache directory."""
+
+    base = _cache_dir(cache_dir)
+    try:
+        children = list(base.iterdir())
+    except OSError:
+        return []
+
+    out: List[CacheEntry] = []
+    for child in children:
+        if child.name.endswith(_SIDECAR_SUFFIXES):
+            continue
+        try:
+            if not child.is_file() or child.is_symlink():
+                continue
+            st = child.stat()
+        except OSError:
+            continue
+        size = st.st_size
+        sidecars: List[Path] = []
+        for suffix in _SIDECAR_SUFFIXES:
+            sidecar = child.with_name(child.name + suffix)
+            try:
+                size += sidecar.stat().st_size
+            except OSError:
+                continue
+            sidecars.append(sidecar)
+        out.append(CacheEntry(child, size, st.st_atime, tuple(sidecars)))
+    return out
+
+
+def total_size(cache_dir: Optional[Path] = None) -> int:
+    return sum(entry.size for entry in entries(cache_dir))
+
+
+def enforce_limit(max_bytes: int, cache_dir: Optional[Path] = None) -> List[Path]:
+    """Evict least-recently-used archives until the cache fits in *max_bytes*.
+
+    Archives registered through :func:`in_use` are skipped, so the cache may
+    remain above the limit if only protected archives are left.  Returns the
+    evicted archive paths, oldest access first.
+    """
+
+    current = entries(cache_dir)
+    total = sum(entry.size for entry in current)
+    evicted: List[Path] = []
+    if total <= max_bytes:
+        return evicted
+
+    for entry in sorted(current, key=lambda e: (e.atime, e.path.name)):
+        if total <= max_bytes:
+            break
+        if is_in_use(entry.path):
+            continue
+        try:
+            entry.path.unlink()
+        except FileNotFoundError:
+            pass
+        except OSError:
+            continue
+        for sidecar in entry.sidecars:
+            with contextlib.suppress(OSError):
+                sidecar.unlink()
+        total -= entry.size
+        evicted.append(entry.path)
+    return evicted
+
+
+class CacheStore(Protocol):
+    """What :func:`prune` needs from a package cache."""
+
+    def entries(self) -> List[CacheEntry]:
+        """Return the archives in the cache, each with its sidecars."""
+
+    def stale(self) -> List[CacheEntry]:
+        """Return everything else in the cache, such as leftover metadata."""
+
+    def remove(self, entry: CacheEntry) -> None:
+        """Delete *entry* and its sidecars."""
+
+
+def _tree_size(path: Path) -> int:
+    if path.is_symlink() or not path.is_dir():
+        return path.lstat().st_size
+    total = 0
+    for dirpath, _dirnames, filenames in os.walk(path):
+        for name in filenames:
+            with contextlib.suppress(OSError):
+                total += os.lstat(os.path.join(dirpath, name)).st_size
+    return total
+
+
+class DirectoryCache:
+    """A :class:`CacheStore` backed by a directory on disk."""
+
+    def __init__(self, cache_dir: Optional[Path] = None) -> None:
+        self.path = _cache_dir(cache_dir)
+
+    def entries(self) -> List[CacheEntry]:
+        return entries(self.path)
+
+    def stale(self) -> List[CacheEntry]:
+        claimed = set()
+        for entry in self.entries():
+            claimed.add(entry.path)
+            claimed.update(entry.sidecars)
+        try:
+            children = sorted(self.path.iterdir())
+        except OSError:
+            return []
+        out: List[CacheEntry] = []
+        for child in children:
+            if child in claimed:
+                continue
+            try:
+                out.append(CacheEntry(child, _tree_size(child), child.lstat().st_atime))
+            except OSError:
+                continue
+        return out
+
+    def remove(self, entry: CacheEntry) -> None:
+        if entry.path.is_dir() and not entry.path.is_symlink():
+            shutil.rmtree(entry.path)
+        else:
+            entry.path.unlink(missing_ok=True)
+        for sidecar in entry.sidecars:
+            sidecar.unlink(missing_ok=True)
+
+
+@dataclass(frozen=True)
+class PruneResult:
+    """Paths :func:`prune` removed (or would remove) and the bytes they held."""
+
+    removed: tuple[Path, ...]
+    reclaimed: int
+
+
+def prune(
+    store: CacheStore,
+    *,
+    everything: bool = False,
+    keep: Optional[Callable[[CacheEntry], bool]] = None,
+    dry_run: bool = False,
+) -> PruneResult:
+    """Remove archives from *store* that *keep* does not claim.
+
+    With *everything* set, :meth:`CacheStore.stale` items are removed too.
+    Archives registered through :func:`in_use` always survive.  In a
+    *dry_run* nothing is deleted, but the result still lists what would be
+    removed and how many bytes that would reclaim.
+    """
+
+    candidates = list(store.entries())
+    if everything:
+        candidates.extend(store.stale())
+    removed: List[Path] = []
+    reclaimed = 0
+    for entry in candidates:
+        if is_in_use(entry.path) or (keep is not None and keep(entry)):
+            continue
+        if not dry_run:
+            try:
+                store.remove(entry)
+            except OSError:
+                continue
+        removed.append(entry.path)
+        reclaimed += entry.size
+    return PruneResult(tuple(removed), reclaimed)
+
+
+__all__ = [
+    "CacheEntry",
+    "CacheStore",
+    "DirectoryCache",
+    "PruneResult",
+    "enforce_limit",
+    "entries",
+    "in_use",
+    "is_in_use",
+    "prune",
+    "total_size",
+    "touch",
+]
@@ -0,0 +1,81 @@
+"""Cooperative cancellation for Ctrl-C and SIGTERM.
+
+While :func:`handlers` is active, SIGINT and SIGTERM only raise a flag.  The
+transaction layer polls it with :func:`check` between steps and, once set,
+rolls back and raises :class:`UserAborted`.  A second signal falls back to an
+immediate :class:`KeyboardInterrupt` so a wedged step can still be escaped.
+"""
+
+from __future__ import annotations
+
+import contextlib
+import signal
+import threading
+from typing import Iterator
+
+_SIGNALS = tuple(s for s in (getattr(signal, "SIGINT", None), getattr(signal, "SIGTERM", None)) if s is not None)
+
+# A plain attribute write is atomic under the GIL, so the handler neither
+# allocates nor takes locks and is safe to re-enter.
+_requested = False
+
+
+class UserAborted(Exception):
+    """Raised at a step boundary after the user interrupted the operation."""
+
+    def __init__(self, stage: str = "") -> None:
+        self.stage = stage
+        message = "interrupted by user"
+        if stage:
+            message = f"{message} during {stage}"
+        super().__init__(message)
+
+
+def request() -> None:
+    global _requested
+    _requested = True
+
+
+def clear() -> None:
+    global _requested
+    _requested = False
+
+
+def requested() -> bool:
+    return _requested
+
+
+def check(stage: str = "") -> None:
+    if _requested:
+        raise UserAborted(stage)
+
+
+def _handle(signum, frame) -> None:
+    if _requested:
+        raise KeyboardInterrupt
+    request()
+
+
+@contextlib.contextmanager
+def handlers() -> Iterator[None]:
+    """Route SIGINT/SIGTERM to the cancellation flag while the block runs."""
+
+    if threading.current_thread() is not threading.main_thread():
+        yield
+        return
+    previous = {}
+    for sig in _SIGNALS:
+        try:
+            previous[sig] = signal.signal(sig, _handle)
+        except (OSError, ValueError):
+            continue
+    clear()
+    try:
+        yield
+    finally:
+        for sig, handler in previous.items():
+            signal.signal(sig, handler)
+        clear()
+
+
+__all__ = ["UserAborted", "check", "clear", "handlers", "request", "requested"]
@@ -0,0 +1,202 @@
+"""Shell completion scripts generated from the argparse parser.
+
+The scripts are built by walking :func:`lpm.app.build_parser` at run time, so
+every subcommand, option and choice list the parser knows about is completed
+without a hand-maintained copy that could drift.
+"""
+
+from __future__ import annotations
+
+import argparse
+from dataclasses import dataclass, field
+from typing import Dict, List, Optional, Sequence, Tuple
+
+SHELLS = ("bash", "zsh", "fish")
+
+
+@dataclass
+class _Option:
+    flags: List[str]
+    help: str = ""
+    takes_value: bool = False
+    choices: Optional[List[str]] = None
+
+
+@dataclass
+class _Command:
+    name: str
+    help: str = ""
+    options: List[_Option] = field(default_factory=list)
+    # Values of a positional argument restricted by ``choices``.
+    values: List[str] = field(default_factory=list)
+
+
+def _options(parser: argparse.ArgumentParser) -> List[_Option]:
+    out = []
+    for action in parser._actions:
+        if not action.option_strings or action.help == argparse.SUPPRESS:
+            continue
+        choices = [str(c) for c in action.choices] if action.choices else None
+        out.append(_Option(list(action.option_strings), action.help or "", action.nargs != 0, choices))
+    return out
+
+
+def _walk(parser: argparse.ArgumentParser) -> Tuple[List[_Option], List[_Command]]:
+    commands: List[_Command] = []
+    for action in parser._actions:
+        if not isinstance(action, argparse._SubParsersAction):
+            continue
+        helps: Dict[str, str] = {a.dest: a.help or "" for a in action._choices_actions}
+        for name, sub in action.choices.items():
+            values = [
+                str(c)
+                for a in sub._actions
+                if not a.option_strings and a.choices and not isinstance(a, argparse._SubParsersAction)
+                for c in a.choices
+            ]
+            commands.append(_Command(name, helps.get(name, ""), _options(sub), values))
+    return _options(parser), commands
+
+
+def _words(options: Sequence[_Option]) -> str:
+    return " ".join(flag for opt in options for flag in opt.flags)
+
+
+def bash(parser: argparse.ArgumentParser) -> str:
+    global_opts, commands = _walk(parser)
+    prog = parser.prog
+    valued = "|".join(f for opt in global_opts if opt.takes_value for f in opt.flags)
+    lines = [
+        f"# bash completion for {prog}; generated by `{prog} completions bash`",
+        f"_{prog}() {{",
+        '    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"',
+        '    local cmd="" i',
+        "    for ((i = 1; i < COMP_CWORD; i++)); do",
+        '        case "${COMP_WORDS[i]}" in',
+    ]
+    if valued:
+        lines.append(f"            {valued}) ((i++)) ;;")
+    lines += [
+        "            -*) ;;",
+        '            *) cmd="${COMP_WORDS[i]}"; break ;;',
+        "        esac",
+        "    done",
+        '    case "$prev" in',
+    ]
+    seen = set()
+    for opt in global_opts + [o for c in commands for o in c.options]:
+        if not opt.choices:
+            continue
+        for flag in opt.flags:
+            if flag not in seen:
+                seen.add(flag)
+                lines.append(f'        {flag}) COMPREPLY=($(compgen -W "{" ".join(opt.choices)}" -- "$cur")); return ;;')
+    lines += [
+        "    esac",
+        '    case "$cmd" in',
+        f'        "") COMPREPLY=($(compgen -W "{_words(global_opts)} {" ".join(c.name for c in commands)}" -- "$cur")) ;;',
+    ]
+    for cmd in commands:
+        words = " ".join([_words(cmd.options), *cmd.values])
+        lines.append(f'        {cmd.name}) COMPREPLY=($(compgen -W "{words}" -- "$cur")) ;;')
+    lines += [
+        "    esac",
+        "}",
+        f"complete -o default -F _{prog} {prog}",
+        "",
+    ]
+    return "\n".join(lines)
+
+
+def _zsh_escape(text: str) -> str:
+    for ch in "\\[]:":
+        text = text.replace(ch, "\\" + ch)
+    return text.replace("'", "'\\''")
+
+
+def _zsh_specs(options: Sequence[_Option]) -> List[str]:
+    specs = []
+    for opt in options:
+        for flag in opt.flags:
+            spec = f"{flag}[{_zsh_escape(opt.help)}]"
+            if opt.choices:
+                spec += f":value:({' '.join(opt.choices)})"
+            elif opt.takes_value:
+                spec += ":value:_files"
+            specs.append(f"'{spec}'")
+    return specs
+
+
+def zsh(parser: argparse.ArgumentParser) -> str:
+    global_opts, commands = _walk(parser)
+    prog = parser.prog
+    lines = [f"#compdef {prog}", f"# generated by `{prog} completions zsh`", f"_{prog}() {{", "    local -a commands", "    commands=("]
+    for cmd in commands:
+        lines.append(f"        '{_zsh_escape(cmd.name)}:{_zsh_escape(cmd.help)}'")
+    lines += ["    )", "    _arguments -C \\"]
+    for spec in _zsh_specs(global_opts):
+        lines.append(f"        {spec} \\")
+    lines += [
+        "        '1:command:->command' \\",
+        "        '*::arg:->args'",
+        "    case $state in",
+        "        command) _describe 'command' commands ;;",
+        "        args)",
+        "            case $words[1] in",
+    ]
+    for cmd in commands:
+        rest = f"'1:value:({' '.join(cmd.values)})'" if cmd.values else "'*:file:_files'"
+        specs = " ".join(_zsh_specs(cmd.options) + [rest])
+        lines.append(f"                {cmd.name}) _arguments {specs} ;;")
+    lines += ["            esac ;;", "    esac", "}", f'_{prog} "$@"', ""]
+    return "\n".join(lines)
+
+
+def _fish_escape(text: str) -> str:
+    return text.replace("\\", "\\\\").replace("'", "\\'")
+
+
+def _fish_option(prog: str, condition: str, opt: _Option) -> str:
+    parts = [f"complete -c {prog} -n '{condition}'"]
+    for flag in opt.flags:
+        if flag.startswith("--"):
+            parts.append(f"-l {flag[2:]}")
+        else:
+            parts.append(f"-s {flag[1:]}")
+    if opt.help:
+        parts.append(f"-d '{_fish_escape(opt.help)}'")
+    if opt.choices:
+        parts.append(f"-xa '{' '.join(opt.choices)}'")
+    elif opt.takes_value:
+        parts.append("-r")
+    return " ".join(parts)
+
+
+def fish(parser: argparse.ArgumentParser) -> str:
+    global_opts, commands = _walk(parser)
+    prog = parser.prog
+    lines = [f"# fish completion for {prog}; generated by `{prog} completions fish`"]
+    for opt in global_opts:
+        lines.append(_fish_option(prog, "__fish_use_subcommand", opt))
+    for cmd in commands:
+        desc = f" -d '{_fish_escape(cmd.help)}'" if cmd.help else ""
+        lines.append(f"complete -c {prog} -f -n '__fish_use_subcommand' -a {cmd.name}{desc}")
+        condition = f"__fish_seen_subcommand_from {cmd.name}"
+        if cmd.values:
+            lines.append(f"complete -c {prog} -f -n '{condition}' -a '{' '.join(cmd.values)}'")
+        for opt in cmd.options:
+            lines.append(_fish_option(prog, condition, opt))
+    lines.append("")
+    return "\n".join(lines)
+
+
+def generate(parser: argparse.ArgumentParser, shell: str) -> str:
+    """Return the completion script for *shell* (one of :data:`SHELLS`)."""
+
+    generators = {"bash": bash, "zsh": zsh, "fish": fish}
+    if shell not in generators:
+        raise ValueError(f"unsupported shell: {shell!r} (expected one of {', '.join(SHELLS)})")
+    return generators[shell](parser)
+
+
+__all__ = ["SHELLS", "bash", "fish", "generate", "zsh"]
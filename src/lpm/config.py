@@ -1,14 +1,24 @@
 from __future__ import annotations
 
+import grp
 import json
 import os
+import pwd
 import shutil
 import logging
+import sys
+from dataclasses import dataclass, field
 from pathlib import Path
-from typing import Dict, Mapping, Tuple
+from typing import Any, Callable, Dict, List, Mapping, Optional, Sequence, Tuple
+
+if sys.version_info >= (3, 11):
+    import tomllib
+else:  # pragma: no cover - exercised on Python 3.10
+    import tomli as tomllib
 
 # =========================== Config / Defaults ================================
 CONF_FILE = Path("/etc/lpm/lpm.conf")      # KEY=VALUE, e.g. ARCH=znver2
+SYSTEM_CONFIG = Path("/etc/lpm/config.toml")
 TEMPLATE_CONF = Path(__file__).resolve().parents[2] / "etc" / "lpm" / "lpm.conf"
 STATE_DIR = Path(os.environ.get("LPM_STATE_DIR", "/var/lib/lpm"))
 LOCK_PATH = Path(os.environ.get("LPM_LOCK_PATH", "/var/lib/lpm/lock"))
@@ -46,9 +56,16 @@ CONF: Dict[str, str] = {}
 ARCH = ""
 OPT_LEVEL = "-O2"
 MAX_SNAPSHOTS = 10
+MAX_TRANSACTIONS = 10
 MAX_LEARNT_CLAUSES = 200
+UNSAT_CORE_BUDGET = 10000
 INSTALL_PROMPT_DEFAULT = "n"
 ALLOW_LPMBUILD_FALLBACK = False
+ALLOW_UNSIGNED_REPO = False
+PRIVILEGE_ESCALATION = "auto"
+PRIVILEGE_ALLOW_UIDS: Tuple[int, ...] = ()
+PRIVILEGE_ALLOW_GIDS: Tuple[int, ...] = ()
+PRIVILEGE_ALLOW_COMMANDS: Tuple[str, ...] = ()
 ENABLE_CPU_OPTIMIZATIONS = True
 MARCH = "generic"
 MTUNE = "generic"
@@ -56,6 +73,17 @@ CPU_VENDOR = ""
 CPU_FAMILY = ""
 FETCH_MAX_WORKERS = 8
 IO_BUFFER_SIZE = 1 << 20
+CACHE_MAX_SIZE = 0
+STREAM_WINDOW = 0
+RESOLVE_CACHE = False
+RESUMABLE_DOWNLOADS = False
+SOLVER_OPTIMIZE = "off"
+SOLVER_SEED: Optional[int] = None
+HOOK_TIMEOUT = 300.0
+RETRY_MAX_ATTEMPTS = 3
+RETRY_BASE_DELAY = 0.5
+RETRY_MAX_DELAY = 10.0
+RETRY_JITTER = 0.25
 USE_DELTAS = "auto"
 ZSTD_MIN_VERSION = "1.5.5"
 
@@ -111,6 +139,23 @@ def _get_bool(key: str, default: bool) -> bool:
     return val.strip().lower() in {"1", "true", "yes", "on"}
 
 
+def _get_list(key: str) -> List[str]:
+    return CONF.get(key, "").replace(",", " ").split()
+
+
+def _account_ids(key: str, lookup: Callable[[str], int]) -> Tuple[int, ...]:
+    ids: List[int] = []
+    for item in _get_list(key):
+        if item.isdigit():
+            ids.append(int(item))
+            continue
+        try:
+            ids.append(lookup(item))
+        except KeyError:
+            logging.warning("Unknown account %r in %s; ignoring it", item, key)
+    return tuple(ids)
+
+
 def _detect_cpu() -> Tuple[str, str, str, str]:
     """Return (march, mtune, vendor, family)."""
     vendor = family = model = ""
@@ -195,10 +240,14 @@ def _init_cpu_settings() -> Tuple[str, str, str, str]:
 
 
 def _apply_conf(conf: Mapping[str, str]) -> None:
-    global CONF, ARCH, OPT_LEVEL, MAX_SNAPSHOTS, MAX_LEARNT_CLAUSES
+    global CONF, ARCH, OPT_LEVEL, MAX_SNAPSHOTS, MAX_TRANSACTIONS, MAX_LEARNT_CLAUSES, UNSAT_CORE_BUDGET
     global INSTALL_PROMPT_DEFAULT, ALLOW_LPMBUILD_FALLBACK, ENABLE_CPU_OPTIMIZATIONS
+    global ALLOW_UNSIGNED_REPO, PRIVILEGE_ESCALATION
+    global PRIVILEGE_ALLOW_UIDS, PRIVILEGE_ALLOW_GIDS, PRIVILEGE_ALLOW_COMMANDS
     global MARCH, MTUNE
-    global CPU_VENDOR, CPU_FAMILY, FETCH_MAX_WORKERS, IO_BUFFER_SIZE
+    global CPU_VENDOR, CPU_FAMILY, FETCH_MAX_WORKERS, IO_BUFFER_SIZE, CACHE_MAX_SIZE
+    global STREAM_WINDOW, RESOLVE_CACHE, RESUMABLE_DOWNLOADS, SOLVER_OPTIMIZE, SOLVER_SEED, HOOK_TIMEOUT
+    global RETRY_MAX_ATTEMPTS, RETRY_BASE_DELAY, RETRY_MAX_DELAY, RETRY_JITTER
     global DISTRO_MAINTAINER_MODE, DISTRO_NAME, DISTRO_REPO_ROOT
     global DISTRO_REPO_BASE_URL, DISTRO_SOURCE_ROOT, DISTRO_LPMBUILD_ROOT
     global DISTRO_GIT_ENABLED, DISTRO_GIT_REMOTE, DISTRO_GIT_BRANCH, DISTRO_GIT_ROOT
@@ -217,11 +266,21 @@ def _apply_conf(conf: Mapping[str, str]) -> None:
     except ValueError:
         MAX_SNAPSHOTS = 10
 
+    try:
+        MAX_TRANSACTIONS = max(0, int(CONF.get("MAX_TRANSACTIONS", "10")))
+    except ValueError:
+        MAX_TRANSACTIONS = 10
+
     try:
         MAX_LEARNT_CLAUSES = max(1, int(CONF.get("MAX_LEARNT_CLAUSES", "200")))
     except ValueError:
         MAX_LEARNT_CLAUSES = 200
 
+    try:
+        UNSAT_CORE_BUDGET = max(0, int(CONF.get("UNSAT_CORE_BUDGET", "10000")))
+    except ValueError:
+        UNSAT_CORE_BUDGET = 10000
+
     mode = CONF.get("USE_DELTAS", "auto").strip().lower()
     if mode not in {"auto", "always", "never"}:
         mode = "auto"
@@ -229,11 +288,34 @@ def _apply_conf(conf: Mapping[str, str]) -> None:
 
     ZSTD_MIN_VERSION = CONF.get("ZSTD_MIN_VERSION", "1.5.5").strip() or "1.5.5"
 
+    optimize = CONF.get("SOLVER_OPTIMIZE", "off").strip().lower()
+    if optimize not in {"off", "linear", "core"}:
+        optimize = "off"
+    SOLVER_OPTIMIZE = optimize
+
+    seed = CONF.get("SOLVER_SEED", "").strip()
+    try:
+        SOLVER_SEED = int(seed) if seed else None
+    except ValueError:
+        logging.warning("Invalid SOLVER_SEED %r; resolving without a seed", seed)
+        SOLVER_SEED = None
+
     INSTALL_PROMPT_DEFAULT = CONF.get("INSTALL_PROMPT_DEFAULT", "n").lower()
     if INSTALL_PROMPT_DEFAULT not in ("y", "n"):
         INSTALL_PROMPT_DEFAULT = "n"
 
     ALLOW_LPMBUILD_FALLBACK = _get_bool("ALLOW_LPMBUILD_FALLBACK", False)
+    ALLOW_UNSIGNED_REPO = _get_bool("ALLOW_UNSIGNED_REPO", False)
+
+    escalation = CONF.get("PRIVILEGE_ESCALATION", "auto").strip().lower()
+    if escalation not in {"auto", "pkexec", "sudo", "none", "allowlist", "capabilities"}:
+        escalation = "auto"
+    PRIVILEGE_ESCALATION = escalation
+    PRIVILEGE_ALLOW_UIDS = _account_ids("PRIVILEGE_ALLOW_USERS", lambda name: pwd.getpwnam(name).pw_uid)
+    PRIVILEGE_ALLOW_GIDS = _account_ids("PRIVILEGE_ALLOW_GROUPS", lambda name: grp.getgrnam(name).gr_gid)
+    PRIVILEGE_ALLOW_COMMANDS = tuple(_get_list("PRIVILEGE_ALLOW_COMMANDS"))
+    RESOLVE_CACHE = _get_bool("RESOLVE_CACHE", False)
+    RESUMABLE_DOWNLOADS = _get_bool("RESUMABLE_DOWNLOADS", False)
     ENABLE_CPU_OPTIMIZATIONS = _get_bool("ENABLE_CPU_OPTIMIZATIONS", True)
 
     if ENABLE_CPU_OPTIMIZATIONS:
@@ -256,6 +338,41 @@ def _apply_conf(conf: Mapping[str, str]) -> None:
     except ValueError:
         IO_BUFFER_SIZE = default_io_buffer
 
+    try:
+        CACHE_MAX_SIZE = max(0, int(CONF.get("CACHE_MAX_SIZE", "0")))
+    except ValueError:
+        CACHE_MAX_SIZE = 0
+
+    try:
+        STREAM_WINDOW = max(0, int(CONF.get("STREAM_WINDOW", "0")))
+    except ValueError:
+        STREAM_WINDOW = 0
+
+    try:
+        HOOK_TIMEOUT = max(0.0, float(CONF.get("HOOK_TIMEOUT", "300")))
+    except ValueError:
+        HOOK_TIMEOUT = 300.0
+
+    try:
+        RETRY_MAX_ATTEMPTS = max(1, int(CONF.get("RETRY_MAX_ATTEMPTS", "3")))
+    except ValueError:
+        RETRY_MAX_ATTEMPTS = 3
+
+    try:
+        RETRY_BASE_DELAY = max(0.0, float(CONF.get("RETRY_BASE_DELAY", "0.5")))
+    except ValueError:
+        RETRY_BASE_DELAY = 0.5
+
+    try:
+        RETRY_MAX_DELAY = max(0.0, float(CONF.get("RETRY_MAX_DELAY", "10")))
+    except ValueError:
+        RETRY_MAX_DELAY = 10.0
+
+    try:
+        RETRY_JITTER = min(1.0, max(0.0, float(CONF.get("RETRY_JITTER", "0.25"))))
+    except ValueError:
+        RETRY_JITTER = 0.25
+
     DISTRO_MAINTAINER_MODE = _get_bool("DISTRO_MAINTAINER_MODE", False)
     DISTRO_NAME = CONF.get("DISTRO_NAME", "")
 
@@ -379,6 +496,113 @@ def save_conf(settings: Mapping[str, object], path: Path = CONF_FILE) -> None:
 # Initialize globals on import
 _apply_conf(load_conf(CONF_FILE))
 
+# ================ Layered config.toml =========================================
+class ConfigError(ValueError):
+    """Raised when a ``config.toml`` layer is not valid TOML or has a bad key."""
+
+    def __init__(self, path: Path, message: str) -> None:
+        super().__init__(f"{path}: {message}")
+        self.path = path
+
+
+@dataclass(frozen=True)
+class Config:
+    """Settings merged from the ``config.toml`` layers and command line flags.
+
+    ``origins`` maps every key that was set explicitly to the file that set
+    it, or to ``"command line"``; keys missing from it hold the defaults.
+    """
+
+    cache_dir: Path = CACHE_DIR
+    repos: Tuple[str, ...] = ()
+    default_root: Path = Path(DEFAULT_ROOT)
+    verify: bool = True
+    origins: Mapping[str, str] = field(default_factory=dict)
+
+
+def _as_path(value: object) -> Optional[Path]:
+    return Path(os.path.expanduser(value)) if isinstance(value, str) and value.strip() else None
+
+
+def _as_repos(value: object) -> Optional[Tuple[str, ...]]:
+    if isinstance(value, list) and all(isinstance(url, str) and url.strip() for url in value):
+        return tuple(url.strip() for url in value)
+    return None
+
+
+def _as_bool(value: object) -> Optional[bool]:
+    return value if isinstance(value, bool) else None
+
+
+_CONFIG_KEYS = {
+    "cache_dir": (_as_path, "a directory path"),
+    "repos": (_as_repos, "an array of repository URLs"),
+    "default_root": (_as_path, "a directory path"),
+    "verify": (_as_bool, "true or false"),
+}
+
+
+def user_config_path(env: Optional[Mapping[str, str]] = None) -> Path:
+    """Return ``$XDG_CONFIG_HOME/lpm/config.toml`` (``~/.config`` when unset)."""
+
+    env = os.environ if env is None else env
+    base = env.get("XDG_CONFIG_HOME") or os.path.join(os.path.expanduser("~"), ".config")
+    return Path(base) / "lpm" / "config.toml"
+
+
+def read_config_layer(path: Path) -> Dict[str, Any]:
+    """Parse one ``config.toml`` layer; a missing file is an empty layer."""
+
+    try:
+        text = path.read_text(encoding="utf-8")
+    except FileNotFoundError:
+        return {}
+    except OSError as exc:
+        raise ConfigError(path, f"cannot read: {exc.strerror}") from exc
+    try:
+        data = tomllib.loads(text)
+    except tomllib.TOMLDecodeError as exc:
+        raise ConfigError(path, f"invalid TOML: {exc}") from exc
+    layer: Dict[str, Any] = {}
+    for key, raw in data.items():
+        if key not in _CONFIG_KEYS:
+            raise ConfigError(path, f"unknown key {key!r}")
+        convert, expected = _CONFIG_KEYS[key]
+        value = convert(raw)
+        if value is None:
+            raise ConfigError(path, f"{key} must be {expected}")
+        layer[key] = value
+    return layer
+
+
+def load_config(
+    paths: Optional[Sequence[Path]] = None,
+    overrides: Optional[Mapping[str, Any]] = None,
+) -> Config:
+    """Merge the config layers in *paths*, then the command line *overrides*.
+
+    *paths* defaults to :data:`SYSTEM_CONFIG` followed by
+    :func:`user_config_path`; later layers win.  Overrides whose value is
+    ``None`` (a flag that was not given) are ignored.
+    """
+
+    paths = [SYSTEM_CONFIG, user_config_path()] if paths is None else paths
+    values: Dict[str, Any] = {}
+    origins: Dict[str, str] = {}
+    for path in paths:
+        for key, value in read_config_layer(Path(path)).items():
+            values[key] = value
+            origins[key] = str(path)
+    for key, value in (overrides or {}).items():
+        if key not in _CONFIG_KEYS:
+            raise KeyError(key)
+        if value is None:
+            continue
+        values[key] = Path(value) if key in ("cache_dir", "default_root") else value
+        origins[key] = "command line"
+    return Config(origins=origins, **values)
+
+
 # ================ Init System Detection ===============================================
 def detect_init_system() -> str:
     """Detect which init system is active."""
@@ -395,6 +619,12 @@ def detect_init_system() -> str:
 
 __all__ = [
     "CONF_FILE",
+    "SYSTEM_CONFIG",
+    "Config",
+    "ConfigError",
+    "load_config",
+    "read_config_layer",
+    "user_config_path",
     "STATE_DIR",
     "DB_PATH",
     "CACHE_DIR",
@@ -413,9 +643,27 @@ __all__ = [
     "ARCH",
     "OPT_LEVEL",
     "MAX_SNAPSHOTS",
+    "MAX_TRANSACTIONS",
+    "CACHE_MAX_SIZE",
+    "STREAM_WINDOW",
+    "RESOLVE_CACHE",
+    "RESUMABLE_DOWNLOADS",
+    "SOLVER_OPTIMIZE",
+    "SOLVER_SEED",
+    "HOOK_TIMEOUT",
+    "RETRY_MAX_ATTEMPTS",
+    "RETRY_BASE_DELAY",
+    "RETRY_MAX_DELAY",
+    "RETRY_JITTER",
     "MAX_LEARNT_CLAUSES",
+    "UNSAT_CORE_BUDGET",
     "INSTALL_PROMPT_DEFAULT",
     "ALLOW_LPMBUILD_FALLBACK",
+    "ALLOW_UNSIGNED_REPO",
+    "PRIVILEGE_ESCALATION",
+    "PRIVILEGE_ALLOW_UIDS",
+    "PRIVILEGE_ALLOW_GIDS",
+    "PRIVILEGE_ALLOW_COMMANDS",
     "ENABLE_CPU_OPTIMIZATIONS",
     "MARCH",
     "MTUNE",
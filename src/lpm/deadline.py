@@ -0,0 +1,105 @@
+"""Overall deadline for a resolve + download + apply operation.
+
+A :class:`Deadline` is activated for the duration of a command with
+:func:`active`.  The solver, network reads and hook/script execution consult
+:func:`current` so they can stop early; when the deadline passes they raise
+:class:`DeadlineExceeded`, which the transaction layer treats like any other
+failure and rolls back.
+"""
+
+from __future__ import annotations
+
+import contextlib
+import threading
+import time
+from typing import Callable, Dict, Iterator, Optional
+
+
+class DeadlineExceeded(TimeoutError):
+    """Raised when an operation runs past its deadline."""
+
+    def __init__(self, stage: str = "") -> None:
+        self.stage = stage
+        message = "deadline exceeded"
+        if stage:
+            message = f"{message} during {stage}"
+        super().__init__(message)
+
+
+class Deadline:
+    """A point in time, measured on *clock*, after which work must stop."""
+
+    def __init__(self, expires_at: float, clock: Callable[[], float] = time.monotonic) -> None:
+        self.expires_at = expires_at
+        self.clock = clock
+
+    @classmethod
+    def after(cls, seconds: float, clock: Callable[[], float] = time.monotonic) -> "Deadline":
+        return cls(clock() + max(0.0, float(seconds)), clock)
+
+    def remaining(self) -> float:
+        return max(0.0, self.expires_at - self.clock())
+
+    def expired(self) -> bool:
+        return self.clock() >= self.expires_at
+
+    def check(self, stage: str = "") -> None:
+        if self.expired():
+            raise DeadlineExceeded(stage)
+
+    def timeout(self, default: Optional[float] = None) -> float:
+        """Timeout for a blocking call: *default* capped by the time remaining."""
+
+        remaining = self.remaining()
+        if default is None:
+            return remaining
+        return min(default, remaining)
+
+
+_lock = threading.Lock()
+_current: Optional[Deadline] = None
+
+
+def current() -> Optional[Deadline]:
+    return _current
+
+
+@contextlib.contextmanager
+def active(deadline: Optional[Deadline]) -> Iterator[Optional[Deadline]]:
+    """Make *deadline* the process-wide deadline while the block runs.
+
+    Downloads run on worker threads, so this is deliberately a module global
+    rather than a context variable.  ``None`` leaves the current one in place.
+    """
+
+    global _current
+    if deadline is None:
+        yield _current
+        return
+    with _lock:
+        previous = _current
+        _current = deadline
+    try:
+        yield deadline
+    finally:
+        with _lock:
+            _current = previous
+
+
+def check(stage: str = "") -> None:
+    deadline = _current
+    if deadline is not None:
+        deadline.check(stage)
+
+
+def subprocess_kwargs() -> Dict[str, float]:
+    """``timeout=`` for :func:`subprocess.run` when a deadline is active."""
+
+    deadline = _current
+    if deadline is None:
+        return {}
+    deadline.check("hook execution")
+    return {"timeout": deadline.remaining()}
+
+
+__all__ = ["Deadline", "DeadlineExceeded", "active", "check", "current", "subprocess_kwargs"]
@@ -1,15 +1,35 @@
 from __future__ import annotations
 
+import errno
+import gzip
+import hashlib
+import importlib.util
 import json
+import os
+import posixpath
+import re
+import secrets
 import shutil
+import stat
 import tempfile
 from contextlib import contextmanager
+from dataclasses import asdict, dataclass
+from enum import Enum
 from pathlib import Path
-from typing import Any, Dict, Iterable, Optional, Union
+from typing import Any, ClassVar, Dict, Iterable, List, Optional, Set, Tuple, Type, Union
 
-from .atomic_io import BytesLike, enforce_umask, read_bytes, safe_write
+from .atomic_io import BytesLike, FsError, _sync_directory, enforce_umask, read_bytes, safe_write
 from .privileges import privileged_section
 
+# ``from . import cancel`` would go through ``lpm.__getattr__``, which loads
+# ``lpm.app`` and with it this half-initialized module.
+_cancel = importlib.import_module(".cancel", __package__)
+
+if importlib.util.find_spec("zstandard") is None:  # pragma: no cover - fallback for test environment
+    zstd = importlib.import_module("._zstd_stub", __package__)
+else:
+    import zstandard as zstd
+
 
 @contextmanager
 def operation_phase(privileged: bool = True):
@@ -91,8 +111,293 @@ def write_manifest_file(
     return safe_write(dest, payload, mode=mode, owner=owner, group=group)
 
 
-def journal_append(journal_path: Union[str, Path], entry: Dict[str, Any]) -> Path:
-    """Append a JSON entry to the journal atomically."""
+class ChecksumMismatch(FsError):
+    """Raised when bytes on disk do not hash to the digest they were written for."""
+
+    def __init__(self, path: Union[str, Path], expected: str, actual: str) -> None:
+        super().__init__(f"checksum mismatch for {path}: expected {expected}, got {actual}")
+        self.path = str(path)
+        self.expected = expected
+        self.actual = actual
+
+    @property
+    def filename(self) -> str:  # type: ignore[override]
+        """The old name of :attr:`path`, kept for existing callers."""
+
+        return self.path
+
+
+def sha256_file(path: Union[str, Path]) -> str:
+    """Return the hex SHA-256 of the file at *path*."""
+
+    digest = hashlib.sha256()
+    try:
+        with open(path, "rb") as fh:
+            for chunk in iter(lambda: fh.read(1 << 20), b""):
+                digest.update(chunk)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot hash {path}: {exc.strerror}") from exc
+    return digest.hexdigest()
+
+
+def write_verified(
+    path: Union[str, Path],
+    data: BytesLike,
+    *,
+    mode: int,
+    expected_sha256: str,
+) -> Path:
+    """Write *data* with :func:`safe_write` and verify the on-disk SHA-256.
+
+    The file is re-read after the write so corruption between the page cache
+    and the disk is caught too. On mismatch the file is removed and
+    :class:`ChecksumMismatch` is raised.
+    """
+
+    target = safe_write(path, bytes(data), mode=mode)
+    expected = expected_sha256.lower()
+    actual = sha256_file(target)
+    if actual != expected:
+        try:
+            target.unlink()
+        except FileNotFoundError:
+            pass
+        raise ChecksumMismatch(target, expected, actual)
+    return target
+
+
+class Compression(str, Enum):
+    """Compression applied to a package payload as stored in the archive."""
+
+    ZSTD = "zstd"
+    GZIP = "gzip"
+    NONE = "none"
+
+
+def write_decompressed(
+    path: Union[str, Path],
+    compressed: BytesLike,
+    algo: Union[Compression, str],
+    *,
+    mode: int,
+) -> Path:
+    """Decompress *compressed* in memory and write the plaintext via :func:`safe_write`."""
+
+    algo = Compression(algo)
+    try:
+        if algo is Compression.ZSTD:
+            payload = zstd.ZstdDecompressor().decompress(bytes(compressed))
+        elif algo is Compression.GZIP:
+            payload = gzip.decompress(bytes(compressed))
+        else:
+            payload = bytes(compressed)
+    except (OSError, EOFError, zstd.ZstdError) as exc:
+        raise FsError(errno.EINVAL, f"cannot decompress {algo.value} payload for {path}: {exc}") from exc
+    return safe_write(path, payload, mode=mode)
+
+
+def promote(part: Union[str, Path], dest: Union[str, Path], *, mode: int = 0o644) -> Path:
+    """Durably rename a completed download at *part* over *dest*.
+
+    *part* is flushed to disk first and the directory entry is synced after
+    the rename, so *dest* is either its old content or the complete file.
+    """
+
+    part_path = Path(part)
+    dest_path = Path(dest)
+    try:
+        with open(part_path, "rb") as fh:
+            os.fsync(fh.fileno())
+        os.chmod(part_path, mode)
+        os.replace(part_path, dest_path)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot move {part_path} to {dest_path}: {exc.strerror}") from exc
+    _sync_directory(dest_path.parent)
+    return dest_path
+
+
+def read_compressed(path: Union[str, Path], algo: Union[Compression, str]) -> bytes:
+    """Return the contents of *path* compressed with *algo*."""
+
+    algo = Compression(algo)
+    try:
+        data = read_bytes(path)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot read {path}: {exc.strerror}") from exc
+    if algo is Compression.ZSTD:
+        return zstd.ZstdCompressor().compress(data)
+    if algo is Compression.GZIP:
+        return gzip.compress(data, mtime=0)
+    return data
+
+
+def safe_symlink(target: Union[str, Path], link: Union[str, Path]) -> Path:
+    """Atomically point *link* at *target*, replacing an existing symlink.
+
+    The link is created under a temporary name next to *link* and renamed into
+    place, so readers never observe a missing entry. A regular file or
+    directory already at *link* is left alone and :class:`FsError` is raised.
+    """
+
+    link_path = Path(link)
+    try:
+        existing = os.lstat(link_path)
+    except FileNotFoundError:
+        existing = None
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot inspect {link_path}: {exc.strerror}") from exc
+    if existing is not None and not stat.S_ISLNK(existing.st_mode):
+        kind = "directory" if stat.S_ISDIR(existing.st_mode) else "file"
+        raise FsError(errno.EEXIST, f"refusing to replace {kind} {link_path} with a symlink")
+
+    try:
+        link_path.parent.mkdir(parents=True, exist_ok=True)
+        while True:
+            tmp_link = link_path.with_name(f".{link_path.name}.{secrets.token_hex(4)}.link")
+            try:
+                os.symlink(os.fspath(target), tmp_link)
+                break
+            except FileExistsError:
+                continue
+        try:
+            os.replace(tmp_link, link_path)
+        except OSError:
+            tmp_link.unlink()
+            raise
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot create symlink {link_path}: {exc.strerror}") from exc
+    _sync_directory(link_path.parent)
+    return link_path
+
+
+@dataclass(frozen=True)
+class RootContext:
+    """Map package paths such as ``/usr/bin/foo`` onto an install *root*.
+
+    Paths are normalised lexically and ``..`` stops at the root, the way it
+    does inside a chroot, so no manifest entry can name a file outside it.
+    Symlink contents are never rewritten: an absolute target is written
+    verbatim and only interpreted relative to the root by :meth:`link_target`.
+    """
+
+    root: Path
+
+    def __post_init__(self) -> None:
+        object.__setattr__(self, "root", Path(self.root))
+
+    @staticmethod
+    def _parts(package_path: Union[str, Path]) -> List[str]:
+        parts: List[str] = []
+        for part in str(package_path).replace(os.sep, "/").split("/"):
+            if part in ("", "."):
+                continue
+            if part == "..":
+                if parts:
+                    parts.pop()
+                continue
+            parts.append(part)
+        return parts
+
+    def path(self, package_path: Union[str, Path]) -> Path:
+        """Return where *package_path* lives under the root."""
+
+        return self.root.joinpath(*self._parts(package_path))
+
+    def relative(self, path: Union[str, Path]) -> str:
+        """Return the package path (``/usr/bin/foo``) of *path* under the root."""
+
+        try:
+            rel = Path(path).relative_to(self.root)
+        except ValueError:
+            raise FsError(errno.EINVAL, f"{path} is outside {self.root}") from None
+        return "/" + "/".join(self._parts(rel.as_posix()))
+
+    def link_target(self, link: Union[str, Path], target: str) -> Path:
+        """Return where *target*, the contents of the symlink *link*, points under the root."""
+
+        if not target.startswith("/"):
+            target = posixpath.join(posixpath.dirname("/" + "/".join(self._parts(link))), target)
+        return self.path(target)
+
+
+class LinkStrategy(str, Enum):
+    """How :func:`link_or_copy` placed a file."""
+
+    HARDLINK = "hardlink"
+    COPY = "copy"
+
+
+# Errors from os.link() that mean "hardlinks are not possible here" rather
+# than a real failure: another filesystem, no hardlink support, or a full
+# link count.
+_NO_HARDLINK_ERRNOS = frozenset({errno.EXDEV, errno.EPERM, errno.EMLINK, errno.ENOTSUP, errno.EOPNOTSUPP})
+
+
+def link_or_copy(src: Union[str, Path], dst: Union[str, Path], mode: int = 0o644) -> LinkStrategy:
+    """Place the contents of *src* at *dst*, sharing the inode when possible.
+
+    *dst* is hardlinked to *src* under a temporary name and renamed into
+    place.  A hardlink shares permissions with *src*, so it is only used when
+    *src* already has *mode*; otherwise, or when the files are on different
+    filesystems, *dst* is written as a copy with :func:`safe_write`.  Returns
+    the strategy that was used.
+    """
+
+    src_path = Path(src)
+    dst_path = Path(dst)
+    try:
+        src_mode = stat.S_IMODE(os.stat(src_path).st_mode)
+        dst_path.parent.mkdir(parents=True, exist_ok=True)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot link {src_path} to {dst_path}: {exc.strerror}") from exc
+
+    if src_mode == mode:
+        tmp_link = dst_path.with_name(f".{dst_path.name}.{secrets.token_hex(4)}.link")
+        try:
+            os.link(src_path, tmp_link)
+        except OSError as exc:
+            if exc.errno not in _NO_HARDLINK_ERRNOS:
+                raise FsError(exc.errno, f"cannot link {src_path} to {dst_path}: {exc.strerror}") from exc
+        else:
+            try:
+                os.replace(tmp_link, dst_path)
+            except OSError as exc:
+                tmp_link.unlink()
+                raise FsError(exc.errno, f"cannot link {src_path} to {dst_path}: {exc.strerror}") from exc
+            _sync_directory(dst_path.parent)
+            return LinkStrategy.HARDLINK
+
+    try:
+        data = read_bytes(src_path)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot read {src_path}: {exc.strerror}") from exc
+    safe_write(dst_path, data, mode=mode)
+    return LinkStrategy.COPY
+
+
+def read_link_hash(link: Union[str, Path]) -> str:
+    """Return the SHA-256 of the target string of *link*, as recorded in manifests."""
+
+    try:
+        target = os.readlink(link)
+    except OSError as exc:
+        raise FsError(exc.errno, f"cannot read symlink {link}: {exc.strerror}") from exc
+    return hashlib.sha256(target.encode()).hexdigest()
+
+
+def journal_append(
+    journal_path: Union[str, Path],
+    entry: Dict[str, Any],
+    *,
+    mode: int = 0o640,
+    max_bytes: Optional[int] = None,
+    compress: bool = False,
+) -> Path:
+    """Append a JSON entry to the journal atomically.
+
+    Once the live journal reaches *max_bytes* it is handed to
+    :func:`journal_rotate`, zstd-compressing the archive when *compress* is set.
+    """
 
     path = Path(journal_path)
     try:
@@ -100,7 +405,155 @@ def journal_append(journal_path: Union[str, Path], entry: Dict[str, Any]) -> Pat
     except FileNotFoundError:
         existing = b""
     line = json.dumps(entry, ensure_ascii=False, sort_keys=True) + "\n"
-    return safe_write(path, existing + line.encode("utf-8"), mode=0o640)
+    written = safe_write(path, existing + line.encode("utf-8"), mode=mode)
+    if max_bytes is not None and path.stat().st_size >= max_bytes:
+        journal_rotate(path, compress=compress)
+    return written
+
+
+def _journal_archives(path: Path) -> List[Tuple[int, Path]]:
+    pattern = re.compile(re.escape(path.name) + r"\.(\d+)(\.zst)?$")
+    found = []
+    if path.parent.is_dir():
+        for child in path.parent.iterdir():
+            match = pattern.match(child.name)
+            if match:
+                found.append((int(match.group(1)), child))
+    return sorted(found)
+
+
+def journal_rotate(journal_path: Union[str, Path], *, compress: bool = False) -> Optional[Path]:
+    """Move the live journal aside as the next numbered archive.
+
+    Archives are named ``<journal>.<n>`` (``<journal>.<n>.zst`` when
+    *compress* is set) with *n* increasing, so older events sort first.
+    Returns the archive path, or ``None`` when there was nothing to rotate.
+    """
+
+    path = Path(journal_path)
+    try:
+        data = read_bytes(path)
+    except FileNotFoundError:
+        return None
+    if not data:
+        return None
+    archives = _journal_archives(path)
+    index = archives[-1][0] + 1 if archives else 1
+    archive = path.with_name(f"{path.name}.{index}")
+    if compress:
+        archive = archive.with_name(archive.name + ".zst")
+        safe_write(archive, zstd.ZstdCompressor().compress(data), mode=0o640)
+        path.unlink()
+    else:
+        os.replace(path, archive)
+    return archive
+
+
+def _journal_lines(path: Path, *, tolerate_truncated: bool = False) -> List[Dict[str, Any]]:
+    data = read_bytes(path)
+    if path.suffix == ".zst":
+        data = zstd.ZstdDecompressor().decompress(data)
+    text = data.decode("utf-8", errors="replace" if tolerate_truncated else "strict")
+    lines = [line for line in text.splitlines() if line.strip()]
+    entries = []
+    for idx, line in enumerate(lines):
+        try:
+            entries.append(json.loads(line))
+        except json.JSONDecodeError:
+            # A crash mid-append can leave a partial final line; earlier
+            # lines were each written whole by safe_write.
+            if tolerate_truncated and idx == len(lines) - 1 and not text.endswith("\n"):
+                break
+            raise
+    return entries
+
+
+def journal_read(journal_path: Union[str, Path], *, tolerate_truncated: bool = False) -> List[Dict[str, Any]]:
+    """Return the journal entries stored at *journal_path* in order.
+
+    A single archive, compressed or not, yields just its own entries.  The
+    live journal yields the full history: every rotated archive, oldest
+    first, followed by the plaintext tail.  With *tolerate_truncated* an
+    unterminated, unparsable final line in the live journal is dropped.
+    """
+
+    path = Path(journal_path)
+    if path.suffix == ".zst" or re.search(r"\.\d+$", path.name):
+        return _journal_lines(path)
+    entries: List[Dict[str, Any]] = []
+    for _, archive in _journal_archives(path):
+        entries.extend(_journal_lines(archive))
+    if path.exists():
+        entries.extend(_journal_lines(path, tolerate_truncated=tolerate_truncated))
+    return entries
+
+
+@dataclass(frozen=True)
+class JournalEntry:
+    """Base class for typed journal records; ``kind`` selects the subclass."""
+
+    kind: ClassVar[str] = ""
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {"kind": self.kind, **asdict(self)}
+
+
+@dataclass(frozen=True)
+class TxnBegin(JournalEntry):
+    kind: ClassVar[str] = "txn_begin"
+
+
+@dataclass(frozen=True)
+class TxnCommit(JournalEntry):
+    kind: ClassVar[str] = "txn_commit"
+
+
+@dataclass(frozen=True)
+class FileWritten(JournalEntry):
+    kind: ClassVar[str] = "file_written"
+    path: str
+    sha256: str
+
+
+@dataclass(frozen=True)
+class FileRemoved(JournalEntry):
+    kind: ClassVar[str] = "file_removed"
+    path: str
+
+
+_JOURNAL_KINDS: Dict[str, Type[JournalEntry]] = {
+    cls.kind: cls for cls in (TxnBegin, TxnCommit, FileWritten, FileRemoved)
+}
+
+
+def append_entry(journal_path: Union[str, Path], entry: JournalEntry, *, mode: int = 0o640) -> Path:
+    """Append a typed :class:`JournalEntry` to the journal at *journal_path*."""
+
+    return journal_append(journal_path, entry.to_dict(), mode=mode)
+
+
+def read_journal(journal_path: Union[str, Path]) -> List[JournalEntry]:
+    """Parse the typed entries written by :func:`append_entry`.
+
+    A trailing line truncated by a crash is ignored so recovery can replay
+    everything that was fully recorded.
+    """
+
+    try:
+        records = journal_read(journal_path, tolerate_truncated=True)
+    except (OSError, ValueError) as exc:
+        raise FsError(errno.EINVAL, f"cannot read journal {journal_path}: {exc}") from exc
+    entries: List[JournalEntry] = []
+    for record in records:
+        fields = dict(record)
+        cls = _JOURNAL_KINDS.get(fields.pop("kind", None))
+        if cls is None:
+            raise FsError(errno.EINVAL, f"unknown journal entry in {journal_path}: {record!r}")
+        try:
+            entries.append(cls(**fields))
+        except TypeError as exc:
+            raise FsError(errno.EINVAL, f"malformed journal entry in {journal_path}: {record!r}") from exc
+    return entries
 
 
 def materialize_from_manifest(
@@ -130,12 +583,279 @@ def materialize_from_manifest(
             )
 
 
+class StagedWrite:
+    """Collect package files and swap them into a tree together.
+
+    :meth:`push` queues ``(relative_path, data, mode)`` entries.  :meth:`commit`
+    first writes every entry into a hidden ``.lpm-stage-*`` directory under the
+    target root, so a failure while producing the new contents leaves the real
+    tree untouched, and then renames each staged file into place.  The staging
+    directory lives on the root's filesystem, but a destination on another
+    mount (for example a separate ``/usr``) cannot be reached by rename; those
+    files fall back to a copy through :func:`safe_write`, which syncs the data
+    and the containing directory.  Files that fail to move are collected and
+    reported in a single :class:`FsError` once every other file has been
+    committed.  An interrupt (see :mod:`lpm.cancel`) noticed once staging is
+    done raises :class:`~lpm.cancel.UserAborted` before any file is moved.
+    """
+
+    def __init__(self) -> None:
+        self.entries: List[Tuple[Path, bytes, int]] = []
+
+    def push(self, relpath: Union[str, Path], data: Union[str, BytesLike], mode: int) -> "StagedWrite":
+        rel = Path(relpath)
+        if rel.is_absolute() or ".." in rel.parts or not rel.parts:
+            raise ValueError(f"staged path must be relative to the root: {relpath}")
+        payload = data.encode("utf-8") if isinstance(data, str) else bytes(data)
+        self.entries.append((rel, payload, mode))
+        return self
+
+    def commit(self, root: Union[str, Path]) -> List[Path]:
+        root_path = Path(root)
+        root_path.mkdir(parents=True, exist_ok=True)
+        stage = Path(tempfile.mkdtemp(prefix=".lpm-stage-", dir=root_path))
+        try:
+            staged: List[Tuple[Path, Path, int]] = []
+            for rel, payload, mode in self.entries:
+                try:
+                    staged.append((safe_write(stage / rel, payload, mode=mode), root_path / rel, mode))
+                except OSError as exc:
+                    raise FsError(exc.errno, f"cannot stage {rel}: {exc.strerror or exc}") from exc
+            _cancel.check("staged commit")
+
+            committed: List[Path] = []
+            failed: List[Tuple[Path, OSError]] = []
+            for src, dest, mode in staged:
+                try:
+                    dest.parent.mkdir(parents=True, exist_ok=True)
+                    try:
+                        os.replace(src, dest)
+                        _sync_directory(dest.parent)
+                    except OSError as exc:
+                        if exc.errno != errno.EXDEV:
+                            raise
+                        safe_write(dest, read_bytes(src), mode=mode)
+                    committed.append(dest)
+                except OSError as exc:
+                    failed.append((dest, exc))
+        finally:
+            shutil.rmtree(stage, ignore_errors=True)
+
+        if failed:
+            detail = "; ".join(f"{dest}: {exc.strerror or exc}" for dest, exc in failed)
+            error = FsError(failed[0][1].errno, f"failed to commit {len(failed)} of {len(staged)} staged files: {detail}")
+            error.committed = committed
+            error.failed = [dest for dest, _ in failed]
+            raise error
+        return committed
+
+
+class Transaction:
+    """Record the prior state of filesystem paths so a batch can be undone.
+
+    Callers invoke :meth:`record` before mutating a path, or let :meth:`write`
+    and :meth:`rename` do it for them.  The first call for
+    a path saves what was there (a copy of the file, symlink or directory
+    tree, or the fact that nothing existed, including any missing parent
+    directories).  :meth:`rollback` restores every recorded
+    path in reverse order; :meth:`commit` discards the saved state.  Used as a
+    context manager the transaction commits on success and rolls back when
+    the block raises.
+
+    ``commit(keep=True)`` instead writes a journal next to the backups, so
+    :meth:`load` can later rebuild the transaction and undo it.
+
+    :meth:`record` is the safe point for interrupts: once Ctrl-C or SIGTERM
+    has been seen (see :mod:`lpm.cancel`) it rolls the transaction back and
+    raises :class:`~lpm.cancel.UserAborted` instead of letting the caller go
+    on to modify another path.
+    """
+
+    JOURNAL = "journal.json"
+
+    def __init__(self, backup_dir: Optional[Union[str, Path]] = None) -> None:
+        if backup_dir is None:
+            self.backup_dir = Path(tempfile.mkdtemp(prefix="lpm-txn-"))
+        else:
+            self.backup_dir = Path(backup_dir)
+            self.backup_dir.mkdir(parents=True, exist_ok=True)
+        self.state = "open"
+        self._entries: List[Tuple[Path, Optional[Path]]] = []
+        self._seen: Set[Path] = set()
+
+    def __enter__(self) -> "Transaction":
+        return self
+
+    def __exit__(self, exc_type, exc, tb) -> None:
+        if self.state != "open":
+            return
+        if exc_type is None:
+            self.commit()
+        else:
+            self.rollback()
+
+    @property
+    def paths(self) -> List[Path]:
+        return [path for path, _ in self._entries]
+
+    def _remember(self, path: Path, backup: Optional[Path]) -> None:
+        self._seen.add(path)
+        self._entries.append((path, backup))
+
+    def record(self, path: Union[str, Path]) -> None:
+        """Save the current state of *path* unless it was already recorded."""
+
+        if self.state != "open":
+            raise RuntimeError(f"transaction already {self.state}")
+        if _cancel.requested():
+            self.rollback()
+            raise _cancel.UserAborted("filesystem transaction")
+        target = Path(os.path.abspath(path))
+        if target in self._seen:
+            return
+
+        missing: List[Path] = []
+        parent = target.parent
+        while parent != parent.parent and not os.path.lexists(parent):
+            missing.append(parent)
+            parent = parent.parent
+        for directory in reversed(missing):
+            if directory not in self._seen:
+                self._remember(directory, None)
+
+        if not os.path.lexists(target):
+            self._remember(target, None)
+            return
+
+        backup = self.backup_dir / str(len(self._entries))
+        if target.is_dir() and not target.is_symlink():
+            shutil.copytree(target, backup, symlinks=True)
+        elif target.is_symlink():
+            os.symlink(os.readlink(target), backup)
+        else:
+            shutil.copy2(target, backup)
+        self._remember(target, backup)
+
+    def write(self, path: Union[str, Path], data: Union[str, BytesLike], **kwargs: Any) -> Path:
+        """:func:`safe_write` *data* to *path* after recording its prior state."""
+
+        self.record(path)
+        return safe_write(path, data, **kwargs)
+
+    def rename(self, src: Union[str, Path], dst: Union[str, Path]) -> Path:
+        """Move *src* over *dst*, recording both so a rollback moves it back."""
+
+        self.record(src)
+        self.record(dst)
+        dst = Path(dst)
+        dst.parent.mkdir(parents=True, exist_ok=True)
+        os.replace(src, dst)
+        return dst
+
+    @staticmethod
+    def _clear(path: Path) -> None:
+        if path.is_dir() and not path.is_symlink():
+            shutil.rmtree(path)
+        elif os.path.lexists(path):
+            path.unlink()
+
+    def rollback(self) -> None:
+        """Restore every recorded path to the state saved by :meth:`record`.
+
+        Backups may live on another filesystem than the paths they restore.
+        If restoring a path fails, :class:`FsError` is raised with the
+        transaction still open: the paths not yet restored keep their backups
+        (and journal, for a loaded transaction), so the rollback can be retried.
+        """
+
+        if self.state != "open":
+            return
+        while self._entries:
+            path, backup = self._entries[-1]
+            try:
+                self._clear(path)
+                if backup is not None:
+                    path.parent.mkdir(parents=True, exist_ok=True)
+                    # Falls back to copying when the backup is on another mount.
+                    shutil.move(str(backup), str(path))
+            except OSError as exc:
+                if (self.backup_dir / self.JOURNAL).exists():
+                    self._write_journal()
+                raise FsError(exc.errno, f"cannot restore {path} from {backup}: {exc.strerror}") from exc
+            self._entries.pop()
+            self._seen.discard(path)
+        self.state = "rolled back"
+        shutil.rmtree(self.backup_dir, ignore_errors=True)
+
+    def _write_journal(self) -> None:
+        journal = [
+            {"path": str(path), "backup": backup.name if backup is not None else None}
+            for path, backup in self._entries
+        ]
+        safe_write(self.backup_dir / self.JOURNAL, json.dumps(journal), mode=0o600)
+
+    def commit(self, *, keep: bool = False) -> None:
+        """Keep the current filesystem state and drop the saved backups.
+
+        With *keep* the backups stay in :attr:`backup_dir` together with a
+        journal of the recorded paths.
+        """
+
+        if self.state != "open":
+            return
+        self.state = "committed"
+        if keep:
+            self._write_journal()
+            return
+        shutil.rmtree(self.backup_dir, ignore_errors=True)
+
+    @classmethod
+    def load(cls, backup_dir: Union[str, Path]) -> "Transaction":
+        """Reopen a transaction committed with ``keep=True`` so it can be rolled back."""
+
+        backup_dir = Path(backup_dir)
+        try:
+            journal = json.loads(read_bytes(backup_dir / cls.JOURNAL))
+        except OSError as exc:
+            raise FsError(exc.errno, f"cannot read transaction journal in {backup_dir}: {exc.strerror}") from exc
+        except ValueError as exc:
+            raise FsError(errno.EINVAL, f"corrupt transaction journal in {backup_dir}: {exc}") from exc
+        txn = cls(backup_dir)
+        for entry in journal:
+            backup = entry["backup"]
+            txn._remember(Path(entry["path"]), backup_dir / backup if backup is not None else None)
+        return txn
+
+
 __all__ = [
+    "ChecksumMismatch",
+    "Compression",
+    "FileRemoved",
+    "FileWritten",
+    "JournalEntry",
+    "LinkStrategy",
+    "RootContext",
+    "StagedWrite",
+    "Transaction",
+    "TxnBegin",
+    "TxnCommit",
+    "append_entry",
     "operation_phase",
     "prepare_directory",
     "write_db_json",
     "write_db_bytes",
     "write_manifest_file",
+    "link_or_copy",
+    "promote",
+    "read_compressed",
+    "read_journal",
+    "read_link_hash",
+    "safe_symlink",
+    "sha256_file",
+    "write_decompressed",
+    "write_verified",
     "journal_append",
+    "journal_read",
+    "journal_rotate",
     "materialize_from_manifest",
 ]
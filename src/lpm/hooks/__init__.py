@@ -12,9 +12,10 @@ import threading
 from dataclasses import dataclass, field
 from fnmatch import fnmatch
 from pathlib import Path
-from typing import Dict, Iterable, Iterator, List, Mapping, MutableMapping, Optional, Sequence, Set
+from typing import Callable, Dict, Iterable, Iterator, List, Mapping, MutableMapping, Optional, Sequence, Set
 
 from ..privileges import privileged_section
+from .. import deadline as _deadline
 
 logger = logging.getLogger(__name__)
 
@@ -270,8 +271,12 @@ class HookTransactionManager:
         root: Path,
         base_env: Optional[Mapping[str, str]] = None,
         failure_mode: str = HookFailureMode.STRICT,
+        defer: Optional[Callable[..., None]] = None,
     ) -> None:
         self.hooks = dict(hooks)
+        # When set, hooks are handed to ``defer(name, argv, env, package)``
+        # instead of being executed (cross-arch roots run them on first boot).
+        self.defer = defer
         self.root = Path(root)
         self.base_env = dict(base_env or {})
         self.events: List[_TransactionEvent] = []
@@ -411,6 +416,15 @@ class HookTransactionManager:
                 "LPM_ROOT": str(self.root.resolve()),
             }
         )
+        if self.defer is not None:
+            if action.needs_targets:
+                env["LPM_TARGET_COUNT"] = str(len(targets))
+                env["LPM_TARGETS"] = "\n".join(targets)
+            argv = base_argv + targets if action.needs_targets else base_argv
+            extra_env = {k: v for k, v in env.items() if os.environ.get(k) != v}
+            package_context = ", ".join(sorted({e.name for e in self.events})) or "transaction"
+            self.defer(hook.name, argv, extra_env, package_context)
+            return
         if action.needs_targets:
             env["LPM_TARGET_COUNT"] = str(len(targets))
             env["LPM_TARGETS"] = "\n".join(targets)
@@ -433,7 +447,7 @@ class HookTransactionManager:
         package_context = ", ".join(sorted({e.name for e in self.events})) or "transaction"
         try:
             with privileged_section():
-                subprocess.run(argv, check=True, env=env)
+                subprocess.run(argv, check=True, env=env, **_deadline.subprocess_kwargs())
         except OSError as exc:
             if exc.errno == errno.E2BIG and action.needs_targets:
                 logger.warning(
@@ -518,7 +532,7 @@ def _run_hook_with_temp_targets(
         fallback_env["LPM_TARGETS_FILE"] = temp_path
         fallback_env["LPM_TARGET_COUNT"] = str(len(targets))
         with privileged_section():
-            subprocess.run(base_argv, check=True, env=fallback_env)
+            subprocess.run(base_argv, check=True, env=fallback_env, **_deadline.subprocess_kwargs())
     finally:
         if temp_path is not None:
             try:
@@ -0,0 +1,163 @@
+"""Resumable HTTP(S) downloads for package archives.
+
+:class:`HttpFetcher` streams each archive into ``<name>.part`` inside its
+work directory.  When a transfer breaks off, the partial file is kept and the
+next attempt asks the server for the remaining bytes with a ``Range`` header;
+a server that ignores the range is answered by starting over.  A completed
+file is moved into place with :func:`lpm.fs_ops.promote`.
+
+Byte progress of each download goes to a :class:`lpm.progress.Progress`
+obtained from the optional *progress* factory.  With *offline* (``--offline``)
+no request is made: only files already downloaded into the work directory
+are returned.  The fetcher is used by
+``lpm download`` when ``RESUMABLE_DOWNLOADS`` is enabled in ``lpm.conf``.
+"""
+
+from __future__ import annotations
+
+import http.client
+import os
+import re
+import socket
+import urllib.error
+import urllib.parse
+import urllib.request
+from pathlib import Path
+from typing import Any, Callable, Optional, Tuple, Union
+
+from .fs_ops import promote
+from .progress import NullProgress, Progress
+from .retry import RetryPolicy, is_retryable
+
+_CHUNK = 1 << 16
+_CONTENT_RANGE_RE = re.compile(r"bytes (\d+)-(\d+)/(\d+|\*)")
+
+
+class FetchError(RuntimeError):
+    """Raised when an archive cannot be downloaded.
+
+    ``status`` is the HTTP status code when the server answered, and
+    ``retryable`` tells whether another attempt may succeed.
+    """
+
+    def __init__(self, url: str, message: str, *, status: Optional[int] = None, retryable: bool = False) -> None:
+        super().__init__(f"{url}: {message}")
+        self.url = url
+        self.status = status
+        self.retryable = retryable
+
+
+def _is_retryable(exc: BaseException) -> bool:
+    return isinstance(exc, FetchError) and exc.retryable
+
+
+class _Transfer:
+    """Keeps a :class:`Progress` in step with the bytes in a ``.part`` file across attempts."""
+
+    def __init__(self, progress: Progress) -> None:
+        self.progress = progress
+        self.reported = 0
+
+    def resume(self, offset: int, total: Optional[int]) -> None:
+        self.progress.start(total)
+        self.advance(offset - self.reported)
+
+    def advance(self, n: int) -> None:
+        self.progress.inc(n)
+        self.reported += n
+
+
+class HttpFetcher:
+    """Fetcher that downloads ``pkg.blob`` over HTTP(S), resuming partial files."""
+
+    def __init__(
+        self,
+        work_dir: Union[str, Path],
+        *,
+        policy: Optional[RetryPolicy] = None,
+        timeout: float = 30.0,
+        opener: Callable[..., Any] = urllib.request.urlopen,
+        sleep: Optional[Callable[[float], None]] = None,
+        progress: Optional[Callable[[str], Progress]] = None,
+        offline: bool = False,
+    ) -> None:
+        self.work_dir = Path(work_dir)
+        self.policy = policy or RetryPolicy.from_config()
+        self.timeout = timeout
+        self.opener = opener
+        self.sleep = sleep
+        self.progress = progress
+        self.offline = offline
+
+    def fetch(self, pkg: Any) -> Tuple[bytes, Optional[bytes]]:
+        data = self.get(pkg.blob).read_bytes()
+        try:
+            sig: Optional[bytes] = self.get(pkg.blob + ".sig").read_bytes()
+        except FetchError as exc:
+            if exc.status != 404 and not self.offline:
+                raise
+            sig = None
+        return data, sig
+
+    def get(self, url: str) -> Path:
+        """Download *url* into the work directory and return the finished file."""
+
+        name = os.path.basename(urllib.parse.urlparse(url).path) or "download"
+        self.work_dir.mkdir(parents=True, exist_ok=True)
+        dest = self.work_dir / name
+        part = self.work_dir / (name + ".part")
+        if self.offline:
+            if dest.exists():
+                return dest
+            raise FetchError(url, "not downloaded yet and --offline forbids downloading it")
+        transfer = _Transfer(self.progress(name) if self.progress is not None else NullProgress())
+        try:
+            self.policy.call(self._attempt, url, part, transfer, sleep=self.sleep, retryable=_is_retryable)
+        finally:
+            transfer.progress.finish()
+        return promote(part, dest)
+
+    def _attempt(self, url: str, part: Path, transfer: _Transfer) -> None:
+        offset = part.stat().st_size if part.exists() else 0
+        headers = {"Range": f"bytes={offset}-"} if offset else {}
+        request = urllib.request.Request(url, headers=headers)
+        try:
+            response = self.opener(request, timeout=self.timeout)
+        except urllib.error.HTTPError as exc:
+            if exc.code == 416 and offset:
+                # The partial file no longer matches the remote one.
+                part.unlink()
+                raise FetchError(url, "stale partial download", status=416, retryable=True) from exc
+            raise FetchError(url, f"HTTP {exc.code} {exc.reason}", status=exc.code, retryable=is_retryable(exc)) from exc
+        except (urllib.error.URLError, OSError) as exc:
+            raise FetchError(url, str(getattr(exc, "reason", exc)), retryable=is_retryable(exc)) from exc
+
+        with response:
+            status = getattr(response, "status", 200)
+            expected: Optional[int] = None
+            if status == 206:
+                match = _CONTENT_RANGE_RE.match(response.headers.get("Content-Range", ""))
+                if not match or int(match.group(1)) != offset:
+                    part.unlink()
+                    raise FetchError(url, "server resumed at the wrong offset", status=status, retryable=True)
+                if match.group(3) != "*":
+                    expected = int(match.group(3))
+            else:
+                offset = 0
+                length = response.headers.get("Content-Length")
+                expected = int(length) if length and length.isdigit() else None
+            transfer.resume(offset, expected)
+            received = offset
+            try:
+                with open(part, "ab" if offset else "wb") as fh:
+                    for chunk in iter(lambda: response.read(_CHUNK), b""):
+                        fh.write(chunk)
+                        received += len(chunk)
+                        transfer.advance(len(chunk))
+            except (http.client.HTTPException, socket.timeout, ConnectionError) as exc:
+                raise FetchError(url, f"connection lost after {received} bytes", retryable=True) from exc
+        if expected is not None and received < expected:
+            raise FetchError(url, f"connection lost after {received} of {expected} bytes", retryable=True)
+
+
+__all__ = ["FetchError", "HttpFetcher"]
@@ -4,13 +4,21 @@ import os
 import threading
 from dataclasses import dataclass
 from pathlib import Path
-from typing import Optional
+from typing import Dict, Optional
 
 import fcntl
 
 from . import config
 
-_INPROC_TXN_LOCK = threading.RLock()
+_INPROC_LOCKS_GUARD = threading.Lock()
+_INPROC_TXN_LOCKS: Dict[Path, threading.RLock] = {}
+
+
+def _inproc_lock(path: Path) -> threading.RLock:
+    """Return the in-process lock serialising threads that use *path*."""
+
+    with _INPROC_LOCKS_GUARD:
+        return _INPROC_TXN_LOCKS.setdefault(Path(path), threading.RLock())
 
 
 class TransactionLockError(RuntimeError):
@@ -87,13 +95,14 @@ class GlobalTransactionLock:
     def __init__(self, path: Optional[Path] = None) -> None:
         self.path = path or config.LOCK_PATH
         self._handle: Optional[_LockHandle] = None
+        self._inproc = _inproc_lock(self.path)
 
     def __enter__(self) -> "GlobalTransactionLock":
-        _INPROC_TXN_LOCK.acquire()
+        self._inproc.acquire()
         try:
             self._handle = _acquire(self.path)
         except Exception:
-            _INPROC_TXN_LOCK.release()
+            self._inproc.release()
             raise
         return self
 
@@ -103,7 +112,7 @@ class GlobalTransactionLock:
                 self._handle.release()
                 self._handle = None
         finally:
-            _INPROC_TXN_LOCK.release()
+            self._inproc.release()
 
 
 def global_transaction_lock(path: Optional[Path] = None) -> GlobalTransactionLock:
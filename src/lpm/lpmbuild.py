@@ -0,0 +1,214 @@
+"""Execution of the ``.lpm-install.sh`` scripts embedded by ``.lpmbuild`` recipes.
+
+``run_lpmbuild`` embeds an install script in every package.  The script is
+invoked as ``.lpm-install.sh <action> <new-version> <old-version>`` where
+``action`` is ``install``, ``upgrade`` or ``remove``; the wrapper generated
+from a recipe's ``install=`` file dispatches to the matching
+``post_install``/``post_upgrade``/``post_remove`` function.
+
+Scripts run in the target root with a scrubbed environment (only ``PATH``,
+``HOME`` and the ``LPM_*`` variables describing the transition) and are killed,
+together with anything they spawned, once ``HOOK_TIMEOUT`` expires.
+"""
+
+from __future__ import annotations
+
+import os
+import signal
+import subprocess
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import Dict, Mapping, Optional, Union
+
+from . import config as _config
+from . import deadline as _deadline
+
+_HOOK_FUNCTIONS = {
+    "install": "post_install",
+    "upgrade": "post_upgrade",
+    "remove": "post_remove",
+}
+
+# Host variables an install script may see; everything else is dropped.
+HOST_ENV_ALLOWLIST = ("PATH", "HOME")
+_DEFAULT_ENV = {"PATH": "/usr/sbin:/usr/bin:/sbin:/bin", "HOME": "/root"}
+
+
+@dataclass(frozen=True)
+class HookContext:
+    """Describe the package transition an install script is run for.
+
+    ``new_version`` and ``old_version`` use the ``<version>-<release>`` form;
+    the side that does not apply (old for installs, new for removals) is left
+    empty.
+    """
+
+    action: str
+    root: Path
+    name: str = ""
+    new_version: str = ""
+    old_version: str = ""
+    env: Mapping[str, str] = field(default_factory=dict)
+
+    def __post_init__(self) -> None:
+        if self.action not in _HOOK_FUNCTIONS:
+            raise ValueError(f"unknown install script action: {self.action}")
+
+    @property
+    def function(self) -> str:
+        return _HOOK_FUNCTIONS[self.action]
+
+    def argv(self, script_path: Union[str, Path]) -> list[str]:
+        return [str(script_path), self.action, self.new_version, self.old_version]
+
+    def environ(self) -> Dict[str, str]:
+        env = {key: os.environ.get(key) or _DEFAULT_ENV[key] for key in HOST_ENV_ALLOWLIST}
+        env.update(self.env)
+        env["LPM_ROOT"] = str(self.root)
+        env["LPM_NEW_VERSION"] = self.new_version
+        env["LPM_OLD_VERSION"] = self.old_version
+        env["LPM_INSTALL_ACTION"] = self.action
+        env["LPM_HOOK"] = self.function
+        if self.name:
+            env["LPM_PKG"] = self.name
+        return env
+
+
+@dataclass(frozen=True)
+class HookResult:
+    context: HookContext
+    returncode: int
+    output: str
+
+
+class HookScriptError(RuntimeError):
+    """Raised when an install script exits with a nonzero status."""
+
+    def __init__(self, script_path: Path, result: HookResult) -> None:
+        self.script_path = script_path
+        self.result = result
+        label = result.context.name or script_path.name
+        message = f"{result.context.function} for {label} failed with exit code {result.returncode}"
+        output = result.output.strip()
+        if output:
+            message = f"{message}: {output}"
+        super().__init__(message)
+
+
+class HookTimeoutError(HookScriptError):
+    """Raised when an install script outlives its timeout and is killed."""
+
+    def __init__(self, script_path: Path, result: HookResult, timeout: float) -> None:
+        super().__init__(script_path, result)
+        self.timeout = timeout
+        label = result.context.name or script_path.name
+        message = f"{result.context.function} for {label} timed out after {timeout:g}s"
+        output = result.output.strip()
+        if output:
+            message = f"{message}: {output}"
+        self.args = (message,)
+
+
+def wrap_install_body(body: str) -> str:
+    """Return the ``.lpm-install.sh`` wrapper around a recipe's install file.
+
+    The body is sourced and the hook function matching the action is called:
+    ``post_remove <old>`` for removals, otherwise ``post_install <new>`` and,
+    for upgrades, ``post_upgrade <new> <old>`` as well.
+    """
+
+    lines = [
+        "#!/bin/bash",
+        "set -euo pipefail",
+        "",
+        "action=${1:-install}",
+        "new_full=${2:-}",
+        "old_full=${3:-}",
+        "source /dev/stdin <<'__LPM_INSTALL_BODY__'",
+    ]
+    text = "\n".join(lines) + "\n"
+    if body:
+        text += body if body.endswith("\n") else body + "\n"
+    text += "__LPM_INSTALL_BODY__\n\n"
+    text += "\n".join(
+        [
+            'if [[ "$action" == "remove" ]]; then',
+            "  if declare -f post_remove >/dev/null; then",
+            '    post_remove "$old_full"',
+            "  fi",
+            "  exit 0",
+            "fi",
+            "if declare -f post_install >/dev/null; then",
+            '  post_install "$new_full"',
+            "fi",
+            'if [[ "$action" == "upgrade" ]] && declare -f post_upgrade >/dev/null; then',
+            '  post_upgrade "$new_full" "$old_full"',
+            "fi",
+        ]
+    )
+    return text + "\n"
+
+
+def _kill_group(proc: subprocess.Popen) -> None:
+    try:
+        os.killpg(proc.pid, signal.SIGKILL)
+    except (ProcessLookupError, PermissionError):
+        proc.kill()
+
+
+def run_hook(script_path: Union[str, Path], context: HookContext, *, timeout: Optional[float] = None) -> HookResult:
+    """Run *script_path* for *context* inside the target root.
+
+    Output (stdout and stderr combined) is captured and returned.  A nonzero
+    exit status raises :class:`HookScriptError` so the surrounding transaction
+    is rolled back.  The script runs in its own process group; if it is still
+    running after *timeout* seconds (``HOOK_TIMEOUT`` by default, ``0`` for no
+    limit) the whole group is killed and :class:`HookTimeoutError` is raised.
+    """
+
+    script = Path(script_path)
+    root = Path(context.root)
+    if timeout is None:
+        timeout = _config.HOOK_TIMEOUT
+    limit = timeout if timeout and timeout > 0 else None
+    remaining = _deadline.subprocess_kwargs().get("timeout")
+    if remaining is not None and (limit is None or remaining < limit):
+        limit = remaining
+    proc = subprocess.Popen(
+        context.argv(script),
+        cwd=str(root) if root.is_dir() else None,
+        env=context.environ(),
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+        start_new_session=True,
+    )
+    try:
+        output, _ = proc.communicate(timeout=limit)
+    except subprocess.TimeoutExpired:
+        _kill_group(proc)
+        output, _ = proc.communicate()
+        result = HookResult(context=context, returncode=proc.returncode, output=output or "")
+        deadline = _deadline.current()
+        if deadline is not None:
+            deadline.check("hook execution")
+        raise HookTimeoutError(script, result, limit) from None
+    except BaseException:
+        _kill_group(proc)
+        proc.wait()
+        raise
+    result = HookResult(context=context, returncode=proc.returncode, output=output or "")
+    if proc.returncode != 0:
+        raise HookScriptError(script, result)
+    return result
+
+
+__all__ = [
+    "HOST_ENV_ALLOWLIST",
+    "HookContext",
+    "HookResult",
+    "HookScriptError",
+    "HookTimeoutError",
+    "run_hook",
+    "wrap_install_body",
+]
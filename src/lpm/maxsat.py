@@ -0,0 +1,335 @@
+"""Unweighted MaxSAT on top of :class:`~lpm.resolver.CDCLSolver`.
+
+Soft constraints are literals that should hold; the cost of a model is how
+many of them it falsifies.  For dependency resolution the soft literals are
+"do not select this older version", so the optimum is the plan that needs the
+fewest non-newest packages.
+
+Two strategies share the same ingredients, assumption-based solving and
+totalizer at-most-k encodings:
+
+* :func:`minimize_linear` solves, then repeatedly bounds the cost below the
+  last model until the bound becomes unsatisfiable.
+* :func:`minimize_core_guided` follows OLL/RC2: it assumes every soft literal,
+  and each UNSAT core it gets back raises the lower bound by one and is
+  relaxed into a new totalizer instead of being searched around.
+
+:class:`WeightedCNF` adds integer weights on top, optimized by
+:func:`minimize_weighted`, the weighted form of the core-guided search: each
+core costs its lightest weight rather than one, so weights are never expanded
+into repeated literals.
+"""
+
+from __future__ import annotations
+
+from dataclasses import dataclass, field
+from itertools import count
+from typing import Callable, Dict, Iterable, List, Mapping, Optional, Sequence, Tuple
+
+from .deadline import Deadline
+from .resolver import CDCLSolver, CNF, SATResult, SolverEvent
+
+_ids = count()
+
+
+def totalizer(cnf: CNF, lits: Sequence[int], name: str = "tot") -> List[int]:
+    """Encode the count of true *lits* and return its unary outputs.
+
+    ``outputs[j]`` is forced true whenever at least ``j + 1`` of *lits* are
+    true, so assuming (or asserting) ``-outputs[k]`` limits the count to *k*.
+    Only that upward direction is encoded, which is all an upper bound needs.
+    """
+
+    nodes: List[List[int]] = [[lit] for lit in lits]
+    if not nodes:
+        return []
+    tag = f"{name}#{next(_ids)}"
+    while len(nodes) > 1:
+        merged: List[List[int]] = []
+        for i in range(0, len(nodes) - 1, 2):
+            left, right = nodes[i], nodes[i + 1]
+            out = [cnf.new_var(f"{tag}_{len(merged)}_{len(nodes)}_{j}") for j in range(len(left) + len(right))]
+            for a in range(len(left) + 1):
+                for b in range(len(right) + 1):
+                    if a + b == 0:
+                        continue
+                    clause = [out[a + b - 1]]
+                    if a:
+                        clause.append(-left[a - 1])
+                    if b:
+                        clause.append(-right[b - 1])
+                    cnf.add_clause(clause)
+            merged.append(out)
+        if len(nodes) % 2:
+            merged.append(nodes[-1])
+        nodes = merged
+    return nodes[0]
+
+
+def at_most_k(cnf: CNF, lits: Sequence[int], k: int, name: str = "amk") -> List[int]:
+    """Assert that at most *k* of *lits* are true; return the totalizer outputs."""
+
+    outputs = totalizer(cnf, lits, name)
+    if k < len(outputs):
+        cnf.add_clause([-outputs[k]])
+    return outputs
+
+
+@dataclass
+class Optimum:
+    """Best model found for a set of soft literals."""
+
+    sat: bool
+    cost: int = 0
+    assign: Dict[int, bool] = field(default_factory=dict)
+    # Number of solver calls, to compare strategies.
+    calls: int = 0
+
+
+def _cost(assign: Dict[int, bool], soft: Sequence[int]) -> int:
+    return sum(1 for lit in soft if assign.get(abs(lit), False) != (lit > 0))
+
+
+def minimize_linear(
+    cnf: CNF,
+    soft: Sequence[int],
+    solver: Optional[CDCLSolver] = None,
+    *,
+    assumptions: Sequence[int] = (),
+    deadline: Optional[Deadline] = None,
+    on_event: Optional[Callable[[SolverEvent], None]] = None,
+) -> Optimum:
+    """Find the minimum-cost model by tightening an upper bound (SAT-UNSAT search).
+
+    Every solve runs under *assumptions*, which hold for this search only.
+    """
+
+    solver = solver or CDCLSolver(cnf)
+    assumptions = list(assumptions)
+    res = solver.solve(assumptions, deadline=deadline, on_event=on_event)
+    calls = 1
+    if not res.sat:
+        return Optimum(False, calls=calls)
+    best = res
+    cost = _cost(res.assign, soft)
+    outputs: List[int] = []
+    while cost > 0:
+        if not outputs:
+            outputs = totalizer(cnf, [-lit for lit in soft], "linear")
+        res = solver.solve([*assumptions, -outputs[cost - 1]], deadline=deadline, on_event=on_event)
+        calls += 1
+        if not res.sat:
+            break
+        best = res
+        cost = _cost(res.assign, soft)
+    return Optimum(True, cost, best.assign, calls)
+
+
+def minimize_core_guided(
+    cnf: CNF,
+    soft: Sequence[int],
+    solver: Optional[CDCLSolver] = None,
+    *,
+    deadline: Optional[Deadline] = None,
+    on_event: Optional[Callable[[SolverEvent], None]] = None,
+) -> Optimum:
+    """Find the minimum-cost model with OLL core relaxation (UNSAT-SAT search).
+
+    Every soft literal starts out assumed.  A core of failed assumptions means
+    at least one of them must be given up, so the cost is at least one more
+    than before: the core's assumptions are dropped and a totalizer over their negations is
+    allowed one violation.  Assumptions that are themselves totalizer outputs
+    are replaced by the next output, allowing one more violation there.
+    """
+
+    solver = solver or CDCLSolver(cnf)
+    assumed: List[int] = list(dict.fromkeys(soft))
+    # assumption literal -> (totalizer outputs, index of the assumed bound)
+    bounds: Dict[int, tuple] = {}
+    calls = 0
+    while True:
+        res: SATResult = solver.solve(assumed, deadline=deadline, on_event=on_event)
+        calls += 1
+        if res.sat:
+            return Optimum(True, _cost(res.assign, soft), res.assign, calls)
+        core = set(res.failed_assumptions or ())
+        if not core:
+            return Optimum(False, calls=calls)
+        assumed = [lit for lit in assumed if lit not in core]
+        for lit in sorted(core):
+            relaxed = bounds.pop(lit, None)
+            if relaxed is None:
+                continue
+            outputs, k = relaxed
+            if k + 1 < len(outputs):
+                nxt = -outputs[k + 1]
+                bounds[nxt] = (outputs, k + 1)
+                assumed.append(nxt)
+        if len(core) > 1:
+            outputs = totalizer(cnf, [-lit for lit in sorted(core)], "oll")
+            nxt = -outputs[1]
+            bounds[nxt] = (outputs, 1)
+            assumed.append(nxt)
+
+
+def minimize_weighted(
+    cnf: CNF,
+    weights: Mapping[int, int],
+    solver: Optional[CDCLSolver] = None,
+    *,
+    deadline: Optional[Deadline] = None,
+    on_event: Optional[Callable[[SolverEvent], None]] = None,
+) -> Optimum:
+    """Find the model of least violated weight, mapping soft literals to weights.
+
+    This is :func:`minimize_core_guided` with weights (weighted OLL): a core
+    raises the lower bound by the lightest weight *w* among its assumptions,
+    each of them gives up *w* and is dropped once nothing is left, and the
+    looser bound (a new totalizer over the core, or the next output of one
+    already in it) is assumed with weight *w*.
+    """
+
+    solver = solver or CDCLSolver(cnf)
+    remaining: Dict[int, int] = {lit: w for lit, w in weights.items() if w > 0}
+    # assumption literal -> (totalizer outputs, index of the assumed bound)
+    bounds: Dict[int, tuple] = {}
+    calls = 0
+    while True:
+        res: SATResult = solver.solve(list(remaining), deadline=deadline, on_event=on_event)
+        calls += 1
+        if res.sat:
+            cost = sum(w for lit, w in weights.items() if res.assign.get(abs(lit), False) != (lit > 0))
+            return Optimum(True, cost, res.assign, calls)
+        core = sorted(set(res.failed_assumptions or ()) & set(remaining))
+        if not core:
+            return Optimum(False, calls=calls)
+        step = min(remaining[lit] for lit in core)
+        for lit in core:
+            remaining[lit] -= step
+            if not remaining[lit]:
+                del remaining[lit]
+            relaxed = bounds.get(lit)
+            if relaxed is None:
+                continue
+            if lit not in remaining:
+                del bounds[lit]
+            outputs, k = relaxed
+            # One step of the weight already pays for a violated bound
+            # k, so loosening it to k + 1 is what costs *step* more.
+            if k + 1 < len(outputs):
+                nxt = -outputs[k + 1]
+                bounds[nxt] = (outputs, k + 1)
+                remaining[nxt] = remaining.get(nxt, 0) + step
+        if len(core) > 1:
+            outputs = totalizer(cnf, [-lit for lit in core], "wpm")
+            nxt = -outputs[1]
+            bounds[nxt] = (outputs, 1)
+            remaining[nxt] = step
+
+
+def minimize_lexicographic(
+    cnf: CNF,
+    levels: Sequence[Sequence[int]],
+    solver: Optional[CDCLSolver] = None,
+    *,
+    deadline: Optional[Deadline] = None,
+    on_event: Optional[Callable[[SolverEvent], None]] = None,
+) -> Optimum:
+    """Minimize the violated soft literals of each level in turn.
+
+    The optimum cost of a level is assumed as a :func:`totalizer` bound while
+    the later levels are optimized, so an earlier level always takes priority
+    over any number of later violations.  The bounds are only assumptions:
+    once this returns, *cnf* admits the same models as before.  The returned
+    cost is the sum over all levels.
+    """
+
+    solver = solver or CDCLSolver(cnf)
+    best = Optimum(True)
+    calls = 0
+    total = 0
+    bounds: List[int] = []
+    for soft in levels:
+        best = minimize_linear(cnf, soft, solver, assumptions=bounds, deadline=deadline, on_event=on_event)
+        calls += best.calls
+        if not best.sat:
+            return Optimum(False, calls=calls)
+        total += best.cost
+        outputs = totalizer(cnf, [-lit for lit in soft], "lex")
+        if best.cost < len(outputs):
+            bounds.append(-outputs[best.cost])
+    if not levels:
+        res = solver.solve([], deadline=deadline, on_event=on_event)
+        return Optimum(res.sat, 0, res.assign if res.sat else {}, 1)
+    return Optimum(True, total, best.assign, calls)
+
+
+class WeightedCNF:
+    """Hard clauses plus weighted soft clauses, optimized by :meth:`solve_maxsat`.
+
+    A multi-literal soft clause is stored as a hard clause with a fresh
+    relaxation variable; keeping that variable false is the soft literal.
+    """
+
+    def __init__(self, cnf: Optional[CNF] = None) -> None:
+        self.cnf = cnf or CNF()
+        self.soft: List[Tuple[List[int], int, int]] = []
+
+    def add_clause(self, lits: Iterable[int]) -> bool:
+        return self.cnf.add_clause(list(lits))
+
+    def add_soft_clause(self, lits: Iterable[int], weight: int) -> None:
+        """Prefer models satisfying *lits*; violating them costs *weight*."""
+
+        lits = list(lits)
+        if weight < 1:
+            raise ValueError(f"soft clause weight must be positive, got {weight}")
+        if len(lits) == 1:
+            soft = lits[0]
+        else:
+            relax = self.cnf.new_var(f"soft#{next(_ids)}")
+            self.cnf.add_clause([*lits, relax])
+            soft = -relax
+        self.soft.append((lits, weight, soft))
+
+    def cost(self, assign: Dict[int, bool]) -> int:
+        """Total weight of the soft clauses *assign* violates."""
+
+        return sum(
+            weight
+            for lits, weight, _ in self.soft
+            if not any(assign.get(abs(l), False) == (l > 0) for l in lits)
+        )
+
+    def solve_maxsat(
+        self,
+        solver: Optional[CDCLSolver] = None,
+        *,
+        deadline: Optional[Deadline] = None,
+        on_event: Optional[Callable[[SolverEvent], None]] = None,
+    ) -> Optimum:
+        """Find a model of the hard clauses with the least violated soft weight.
+
+        Soft clauses sharing a literal add up their weights; the search is
+        :func:`minimize_weighted`, whose encoding does not grow with them.
+        """
+
+        weights: Dict[int, int] = {}
+        for _, weight, lit in self.soft:
+            weights[lit] = weights.get(lit, 0) + weight
+        best = minimize_weighted(self.cnf, weights, solver, deadline=deadline, on_event=on_event)
+        if best.sat:
+            best.cost = self.cost(best.assign)
+        return best
+
+
+__all__ = [
+    "Optimum",
+    "WeightedCNF",
+    "at_most_k",
+    "minimize_core_guided",
+    "minimize_lexicographic",
+    "minimize_linear",
+    "minimize_weighted",
+    "totalizer",
+]
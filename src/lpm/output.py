@@ -0,0 +1,146 @@
+"""Terminal styling for CLI messages.
+
+Colour is applied only when the target stream is a terminal, unless the user
+asked for ``--color=always``.  ``--color=never`` and the ``NO_COLOR``
+environment variable (https://no-color.org) turn it off, so piped or
+redirected output is always plain text.
+
+:func:`report` is the one place commands hand their results to: the global
+``--format json`` turns them into a JSON array on stdout instead of text.
+"""
+
+from __future__ import annotations
+
+import json
+import os
+import sys
+from typing import Any, Callable, Mapping, Optional, Sequence, TextIO
+
+CYAN = "\033[1;36m"
+PURPLE = "\033[1;35m"
+GREEN = "\033[1;32m"
+RED = "\033[1;31m"
+BOLD = "\033[1m"
+RESET = "\033[0m"
+
+COLOR_MODES = ("auto", "always", "never")
+
+_mode = "auto"
+
+FORMATS = ("text", "json")
+
+_format = "text"
+
+
+def set_color_mode(mode: str) -> None:
+    global _mode
+    if mode not in COLOR_MODES:
+        raise ValueError(f"invalid color mode: {mode!r} (expected one of {', '.join(COLOR_MODES)})")
+    _mode = mode
+
+
+def color_mode() -> str:
+    return _mode
+
+
+def set_format(fmt: str) -> None:
+    global _format
+    if fmt not in FORMATS:
+        raise ValueError(f"invalid output format: {fmt!r} (expected one of {', '.join(FORMATS)})")
+    _format = fmt
+
+
+def output_format() -> str:
+    return _format
+
+
+def report(
+    records: Sequence[Mapping[str, Any]],
+    render_text: Callable[[Sequence[Mapping[str, Any]]], None],
+    stream: Optional[TextIO] = None,
+) -> None:
+    """Emit a command's result *records*, as JSON or through *render_text*."""
+
+    if _format == "json":
+        out = sys.stdout if stream is None else stream
+        json.dump(list(records), out, indent=2, sort_keys=True, default=str)
+        out.write("\n")
+    else:
+        render_text(records)
+
+
+def is_terminal(stream: Optional[TextIO]) -> bool:
+    isatty = getattr(stream, "isatty", None)
+    if isatty is None:
+        return False
+    try:
+        return bool(isatty())
+    except (ValueError, OSError):
+        return False
+
+
+def color_enabled(stream: Optional[TextIO] = None) -> bool:
+    """Return ``True`` when escape codes should be written to *stream*.
+
+    An explicit ``always`` wins over ``NO_COLOR``, matching the convention
+    that per-invocation flags override environment preferences.
+    """
+
+    if _mode == "never":
+        return False
+    if _mode == "always":
+        return True
+    if os.environ.get("NO_COLOR"):
+        return False
+    return is_terminal(sys.stderr if stream is None else stream)
+
+
+def style(text: str, code: str, stream: Optional[TextIO] = None) -> str:
+    if not code or not color_enabled(stream):
+        return text
+    return f"{code}{text}{RESET}"
+
+
+def cyan(text: str, stream: Optional[TextIO] = None) -> str:
+    return style(text, CYAN, stream)
+
+
+def purple(text: str, stream: Optional[TextIO] = None) -> str:
+    return style(text, PURPLE, stream)
+
+
+def green(text: str, stream: Optional[TextIO] = None) -> str:
+    return style(text, GREEN, stream)
+
+
+def red(text: str, stream: Optional[TextIO] = None) -> str:
+    return style(text, RED, stream)
+
+
+def bold(text: str, stream: Optional[TextIO] = None) -> str:
+    return style(text, BOLD, stream)
+
+
+__all__ = [
+    "BOLD",
+    "COLOR_MODES",
+    "CYAN",
+    "FORMATS",
+    "GREEN",
+    "PURPLE",
+    "RED",
+    "RESET",
+    "bold",
+    "color_enabled",
+    "color_mode",
+    "cyan",
+    "green",
+    "is_terminal",
+    "output_format",
+    "purple",
+    "red",
+    "report",
+    "set_color_mode",
+    "set_format",
+    "style",
+]
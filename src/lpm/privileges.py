@@ -2,11 +2,12 @@ from __future__ import annotations
 
 import os
 import shlex
+import shutil
 import sys
 import threading
 from contextlib import contextmanager
 from dataclasses import dataclass
-from typing import Iterator, Optional, Tuple
+from typing import Callable, Iterable, Iterator, List, Mapping, Optional, Sequence, Tuple
 
 
 def _env_int(name: str) -> Optional[int]:
@@ -136,12 +137,307 @@ def format_rerun_hint() -> str:
     return "sudo " + " ".join(args)
 
 
+@dataclass(frozen=True)
+class DenyReason:
+    """Why a gate refused *command*; ``str()`` gives the message shown to the user."""
+
+    command: str
+
+    def __str__(self) -> str:
+        return f"{self.command} is not permitted"
+
+
+@dataclass(frozen=True)
+class NotRoot(DenyReason):
+    def __str__(self) -> str:
+        return f"{self.command} requires root privileges"
+
+
+@dataclass(frozen=True)
+class NotInAllowlist(DenyReason):
+    uid: int = -1
+    gid: int = -1
+
+    def __str__(self) -> str:
+        return f"{self.command} requires root privileges: uid {self.uid} (gid {self.gid}) is not in the allowlist"
+
+
+@dataclass(frozen=True)
+class CommandDisabled(DenyReason):
+    def __str__(self) -> str:
+        return f"{self.command} is not enabled for allowlisted users and requires root privileges"
+
+
+@dataclass(frozen=True)
+class NoEscalationHelper(DenyReason):
+    helpers: Tuple[str, ...] = ()
+
+    def __str__(self) -> str:
+        return f"{self.command} requires root privileges (no {' or '.join(self.helpers)} found)"
+
+
+@dataclass(frozen=True)
+class MissingCapabilities(DenyReason):
+    missing: Tuple[str, ...] = ()
+
+    def __str__(self) -> str:
+        return f"{self.command} requires {', '.join(self.missing)} (or root privileges)"
+
+
+class EscalationError(RuntimeError):
+    """Raised when a privileged command may not run and cannot be elevated."""
+
+    def __init__(self, reason: DenyReason) -> None:
+        super().__init__(str(reason))
+        self.reason = reason
+
+
+class PrivilegeGate:
+    """Decide whether a privileged command (``"install"``, ``"remove"``, ...) may run.
+
+    :meth:`explain` returns ``None`` when the command is allowed and a
+    :class:`DenyReason` otherwise.  :meth:`escalate` is called before the
+    command proceeds: it returns when the command is allowed, raises
+    :class:`EscalationError` carrying the reason when it is not, and
+    subclasses may instead elevate the process.
+    """
+
+    def explain(self, command: str) -> Optional[DenyReason]:
+        return None if is_root() else NotRoot(command)
+
+    def is_allowed(self, command: str) -> bool:
+        return self.explain(command) is None
+
+    def preflight(self, command: str) -> Optional[DenyReason]:
+        """Return why *command* would be refused even after :meth:`escalate`.
+
+        Unlike :meth:`explain` this accounts for escalation, so a front end
+        can tell a command that will prompt for a password from one that
+        cannot run at all.
+        """
+
+        return self.explain(command)
+
+    def escalate(self, command: str) -> None:
+        reason = self.explain(command)
+        if reason is not None:
+            raise EscalationError(reason)
+
+
+class DefaultPrivilegeGate(PrivilegeGate):
+    """Allow privileged commands only when already running as root."""
+
+
+class AllowlistPrivilegeGate(PrivilegeGate):
+    """Let listed users or groups run selected privileged commands without root.
+
+    A caller whose effective uid is in *uids* or effective gid is in *gids*
+    may run the commands in *commands*; root may run everything.  *ids*
+    returns the ``(euid, egid)`` pair to check and defaults to the process
+    credentials.
+    """
+
+    def __init__(
+        self,
+        *,
+        uids: Iterable[int] = (),
+        gids: Iterable[int] = (),
+        commands: Iterable[str] = (),
+        ids: Optional[Callable[[], Tuple[int, int]]] = None,
+    ) -> None:
+        self.uids = frozenset(uids)
+        self.gids = frozenset(gids)
+        self.commands = frozenset(commands)
+        self._ids = ids or (lambda: (os.geteuid(), os.getegid()))
+
+    def explain(self, command: str) -> Optional[DenyReason]:
+        euid, egid = self._ids()
+        if euid == 0:
+            return None
+        if euid not in self.uids and egid not in self.gids:
+            return NotInAllowlist(command, euid, egid)
+        if command not in self.commands:
+            return CommandDisabled(command)
+        return None
+
+
+# Bit numbers from <linux/capability.h>.
+CAPABILITIES = {
+    "CAP_CHOWN": 0,
+    "CAP_DAC_OVERRIDE": 1,
+    "CAP_DAC_READ_SEARCH": 2,
+    "CAP_FOWNER": 3,
+    "CAP_FSETID": 4,
+    "CAP_SETGID": 6,
+    "CAP_SETUID": 7,
+    "CAP_SYS_ADMIN": 21,
+}
+
+_INSTALL_CAPS = ("CAP_CHOWN", "CAP_DAC_OVERRIDE", "CAP_FOWNER", "CAP_FSETID")
+
+COMMAND_CAPABILITIES = {
+    "install": _INSTALL_CAPS,
+    "installpkg": _INSTALL_CAPS,
+    "upgrade": _INSTALL_CAPS,
+    "upgradepkg": _INSTALL_CAPS,
+    "rollback": _INSTALL_CAPS,
+    "build": _INSTALL_CAPS,
+    "remove": ("CAP_DAC_OVERRIDE", "CAP_FOWNER"),
+    "removepkg": ("CAP_DAC_OVERRIDE", "CAP_FOWNER"),
+}
+
+
+def effective_capabilities() -> int:
+    """Return the ``CapEff`` bitmask of the running process (0 when unknown)."""
+
+    try:
+        with open("/proc/self/status", encoding="ascii") as fh:
+            for line in fh:
+                if line.startswith("CapEff:"):
+                    return int(line.split()[1], 16)
+    except (OSError, ValueError, IndexError):
+        pass
+    return 0
+
+
+class CapabilityPrivilegeGate(PrivilegeGate):
+    """Allow a command when the process holds the capabilities it needs.
+
+    Hardened systems may run lpm with, for example, ``CAP_DAC_OVERRIDE``
+    instead of full root.  *requirements* maps each command to the capability
+    names it needs (default :data:`COMMAND_CAPABILITIES`); commands missing
+    from the map still need root.  *effective* returns the capability bitmask
+    and defaults to :func:`effective_capabilities`.
+    """
+
+    def __init__(
+        self,
+        requirements: Optional[Mapping[str, Sequence[str]]] = None,
+        *,
+        effective: Callable[[], int] = effective_capabilities,
+    ) -> None:
+        self.requirements = dict(COMMAND_CAPABILITIES if requirements is None else requirements)
+        self._effective = effective
+
+    def explain(self, command: str) -> Optional[DenyReason]:
+        needed = self.requirements.get(command)
+        if needed is None:
+            return super().explain(command)
+        held = self._effective()
+        missing = tuple(cap for cap in needed if not held >> CAPABILITIES[cap] & 1)
+        return MissingCapabilities(command, missing) if missing else None
+
+
+ESCALATION_HELPERS = ("pkexec", "sudo")
+
+
+class PolkitPrivilegeGate(PrivilegeGate):
+    """Re-execute the current command through ``pkexec`` (or ``sudo``) when not root.
+
+    *helpers* are tried in order.  *argv*, *which* and *execv* default to the
+    running process, :func:`shutil.which` and :func:`os.execv`; tests inject
+    replacements so no helper is actually spawned.
+    """
+
+    def __init__(
+        self,
+        helpers: Sequence[str] = ESCALATION_HELPERS,
+        *,
+        argv: Optional[Sequence[str]] = None,
+        which: Callable[[str], Optional[str]] = shutil.which,
+        execv: Callable[[str, List[str]], object] = os.execv,
+    ) -> None:
+        self.helpers = tuple(helpers)
+        self._argv = argv
+        self._which = which
+        self._execv = execv
+
+    def command_line(self) -> List[str]:
+        """Return the argument vector that restarts this process unchanged."""
+
+        argv = list(sys.argv if self._argv is None else self._argv)
+        program = argv[0] if argv else "lpm"
+        if os.path.basename(program) == "__main__.py":
+            head = [sys.executable, "-m", "lpm"]
+        else:
+            head = [sys.executable, os.path.abspath(program)]
+        return head + argv[1:]
+
+    def preflight(self, command: str) -> Optional[DenyReason]:
+        if self.is_allowed(command) or any(self._which(helper) for helper in self.helpers):
+            return None
+        return NoEscalationHelper(command, self.helpers)
+
+    def escalate(self, command: str) -> None:
+        if self.is_allowed(command):
+            return
+        for helper in self.helpers:
+            path = self._which(helper)
+            if path:
+                sys.stdout.flush()
+                sys.stderr.flush()
+                self._execv(path, [path, *self.command_line()])
+                return
+        raise EscalationError(NoEscalationHelper(command, self.helpers))
+
+
+def gate_for(
+    mode: str,
+    *,
+    uids: Iterable[int] = (),
+    gids: Iterable[int] = (),
+    commands: Iterable[str] = (),
+    argv: Optional[Sequence[str]] = None,
+) -> PrivilegeGate:
+    """Return the gate for a ``PRIVILEGE_ESCALATION`` setting.
+
+    *uids*, *gids* and *commands* configure the ``allowlist`` mode; *argv* is
+    the command line ``pkexec``/``sudo`` restart (default: ``sys.argv``).
+    """
+
+    if mode == "auto":
+        return PolkitPrivilegeGate(argv=argv)
+    if mode in ESCALATION_HELPERS:
+        return PolkitPrivilegeGate((mode,), argv=argv)
+    if mode == "allowlist":
+        return AllowlistPrivilegeGate(uids=uids, gids=gids, commands=commands)
+    if mode == "capabilities":
+        return CapabilityPrivilegeGate()
+    return DefaultPrivilegeGate()
+
+
+_GATE: PrivilegeGate = DefaultPrivilegeGate()
+_EXPLICIT_GATE: Optional[PrivilegeGate] = None
+
+
+def set_privilege_gate(gate: Optional[PrivilegeGate]) -> None:
+    """Use *gate* for privileged commands, overriding the configured one.
+
+    ``None`` goes back to the gate installed by :func:`use_configured_gate`.
+    """
+
+    global _EXPLICIT_GATE
+    _EXPLICIT_GATE = gate
+
+
+def use_configured_gate(gate: PrivilegeGate) -> None:
+    """Install the gate chosen by configuration; an explicit gate still wins."""
+
+    global _GATE
+    _GATE = gate
+
+
+def privilege_gate() -> PrivilegeGate:
+    return _EXPLICIT_GATE if _EXPLICIT_GATE is not None else _GATE
+
+
 def require_root(action: str) -> None:
-    if is_root():
-        return
-    print(f"lpm: {action} requires root privileges", file=sys.stderr)
-    print(f"try: {format_rerun_hint()}", file=sys.stderr)
-    raise SystemExit(1)
+    try:
+        privilege_gate().escalate(action)
+    except EscalationError as exc:
+        print(f"lpm: {exc}", file=sys.stderr)
+        print(f"try: {format_rerun_hint()}", file=sys.stderr)
+        raise SystemExit(1)
 
 
 _MANAGER = _PrivilegeManager()
@@ -175,6 +471,25 @@ def state_owner_ids() -> tuple[Optional[int], Optional[int]]:
 
 
 __all__ = [
+    "AllowlistPrivilegeGate",
+    "CAPABILITIES",
+    "COMMAND_CAPABILITIES",
+    "CapabilityPrivilegeGate",
+    "CommandDisabled",
+    "DefaultPrivilegeGate",
+    "DenyReason",
+    "EscalationError",
+    "MissingCapabilities",
+    "NoEscalationHelper",
+    "NotInAllowlist",
+    "NotRoot",
+    "PolkitPrivilegeGate",
+    "PrivilegeGate",
+    "effective_capabilities",
+    "gate_for",
+    "privilege_gate",
+    "set_privilege_gate",
+    "use_configured_gate",
     "is_root",
     "format_rerun_hint",
     "require_root",
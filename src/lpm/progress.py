@@ -0,0 +1,260 @@
+"""Progress reporting for downloads, extraction and installs.
+
+Long operations report through the :class:`Progress` protocol: ``start`` with
+the expected total, ``inc`` as work completes and ``finish`` at the end.
+:func:`track` picks the implementation the CLI uses: a :class:`NullProgress`
+under ``--quiet``, otherwise a :class:`TerminalProgress` that drives a ``tqdm``
+bar on a terminal and prints a plain status line at most every few seconds
+when stderr is not one.
+
+Both implementations are also callables accepting deltas, so they can be
+handed straight to the ``on_progress`` parameters of :func:`fs.urlread`,
+:func:`lpm.app.fetch_blob` and :func:`lpm.app.extract_tar`.
+"""
+
+from __future__ import annotations
+
+import importlib
+import sys
+import threading
+import time
+from typing import Callable, Optional, Protocol, TextIO
+
+# Not ``from . import output``: that goes through ``lpm.__getattr__``, which
+# loads ``lpm.app`` and with it this half-initialized module.
+_output = importlib.import_module(".output", __package__)
+
+LINE_INTERVAL = 5.0
+
+_quiet = False
+
+
+def set_quiet(quiet: bool) -> None:
+    global _quiet
+    _quiet = bool(quiet)
+
+
+def is_quiet() -> bool:
+    return _quiet
+
+
+def format_bytes(n: float) -> str:
+    for unit in ("B", "KiB", "MiB", "GiB"):
+        if abs(n) < 1024 or unit == "GiB":
+            return f"{n:.0f} {unit}" if unit == "B" else f"{n:.1f} {unit}"
+        n /= 1024
+    return f"{n:.1f} GiB"  # pragma: no cover - loop always returns
+
+
+class ProgressModel:
+    """Thread-safe count of completed bytes against an optional total."""
+
+    def __init__(self, total: Optional[int] = None) -> None:
+        self.total = total if total and total > 0 else None
+        self.done = 0
+        self._lock = threading.Lock()
+
+    def add_total(self, n: int) -> None:
+        if n <= 0:
+            return
+        with self._lock:
+            self.total = (self.total or 0) + n
+
+    def advance(self, n: int) -> int:
+        """Add *n* bytes (negative to rewind a failed attempt); returns the new count."""
+
+        with self._lock:
+            self.done = max(0, self.done + n)
+            return self.done
+
+    @property
+    def fraction(self) -> Optional[float]:
+        """Completed fraction in ``[0, 1]``, or ``None`` when the total is unknown."""
+
+        if self.total is None:
+            return None
+        return min(1.0, self.done / self.total)
+
+
+class Progress(Protocol):
+    """Receiver of progress for one long operation."""
+
+    def start(self, total: Optional[int]) -> None:
+        """Begin (or restart) with *total* units expected, ``None`` if unknown."""
+
+    def inc(self, delta: int) -> None:
+        """Record *delta* more units done; negative rewinds a failed attempt."""
+
+    def finish(self) -> None:
+        """Mark the operation as over, successful or not."""
+
+
+class NullProgress:
+    """:class:`Progress` that reports nothing; used under ``--quiet`` and in tests."""
+
+    def start(self, total: Optional[int]) -> None:
+        pass
+
+    def inc(self, delta: int) -> None:
+        pass
+
+    def finish(self) -> None:
+        pass
+
+    def __call__(self, delta: int) -> None:
+        pass
+
+    def close(self) -> None:
+        pass
+
+    def __enter__(self) -> "NullProgress":
+        return self
+
+    def __exit__(self, exc_type, exc, tb) -> None:
+        pass
+
+
+def _format_amount(n: float, unit: str) -> str:
+    return format_bytes(n) if unit == "B" else f"{n:.0f} {unit}"
+
+
+class _BarRenderer:
+    def __init__(self, desc: str, model: ProgressModel, stream: TextIO, unit: str) -> None:
+        from tqdm import tqdm
+
+        self._bar = tqdm(
+            total=model.total,
+            desc=desc,
+            unit=unit,
+            unit_scale=unit == "B",
+            ncols=80,
+            colour="cyan",
+            file=stream,
+        )
+
+    def reset(self, model: ProgressModel) -> None:
+        self._bar.total = model.total
+        self._bar.refresh()
+
+    def update(self, model: ProgressModel, delta: int) -> None:
+        # Follow the model rather than *delta*: a rewind after a failed
+        # attempt moves the bar back, clamped at zero like the model.
+        self._bar.update(model.done - self._bar.n)
+
+    def close(self, model: ProgressModel) -> None:
+        self._bar.close()
+
+
+class _LineRenderer:
+    def __init__(
+        self,
+        desc: str,
+        model: ProgressModel,
+        stream: TextIO,
+        clock: Callable[[], float],
+        interval: float,
+        unit: str,
+    ) -> None:
+        self.desc = desc
+        self.unit = unit
+        self.stream = stream
+        self.clock = clock
+        self.interval = interval
+        self._last = clock()
+
+    def _status(self, model: ProgressModel) -> str:
+        done = _format_amount(model.done, self.unit)
+        fraction = model.fraction
+        if fraction is None:
+            return f"{self.desc}: {done}"
+        return f"{self.desc}: {fraction * 100:3.0f}% ({done} of {_format_amount(model.total or 0, self.unit)})"
+
+    def reset(self, model: ProgressModel) -> None:
+        pass
+
+    def update(self, model: ProgressModel, delta: int) -> None:
+        now = self.clock()
+        if now - self._last < self.interval:
+            return
+        self._last = now
+        print(self._status(model), file=self.stream)
+
+    def close(self, model: ProgressModel) -> None:
+        print(f"{self._status(model)}, done", file=self.stream)
+
+
+class TerminalProgress:
+    """:class:`Progress` shown on *stream*; use as a context manager so the bar is closed.
+
+    Amounts are bytes unless *unit* says otherwise (``"pkg"``, ``"file"`` ...).
+    """
+
+    def __init__(
+        self,
+        desc: str,
+        total: Optional[int] = None,
+        *,
+        unit: str = "B",
+        stream: Optional[TextIO] = None,
+        clock: Callable[[], float] = time.monotonic,
+        interval: float = LINE_INTERVAL,
+    ) -> None:
+        stream = sys.stderr if stream is None else stream
+        self.model = ProgressModel(total)
+        self._lock = threading.Lock()
+        self._closed = False
+        if _output.is_terminal(stream):
+            self._renderer = _BarRenderer(desc, self.model, stream, unit)
+        else:
+            self._renderer = _LineRenderer(desc, self.model, stream, clock, interval, unit)
+
+    @property
+    def fraction(self) -> Optional[float]:
+        return self.model.fraction
+
+    def start(self, total: Optional[int]) -> None:
+        with self._lock:
+            self.model.total = total if total and total > 0 else None
+            self._renderer.reset(self.model)
+
+    def inc(self, delta: int) -> None:
+        self.model.advance(delta)
+        with self._lock:
+            self._renderer.update(self.model, delta)
+
+    def finish(self) -> None:
+        with self._lock:
+            if self._closed:
+                return
+            self._closed = True
+            self._renderer.close(self.model)
+
+    __call__ = inc
+    close = finish
+
+    def __enter__(self) -> "TerminalProgress":
+        return self
+
+    def __exit__(self, exc_type, exc, tb) -> None:
+        self.finish()
+
+
+def track(desc: str, total: Optional[int] = None, *, quiet: Optional[bool] = None, **kwargs):
+    """Return the :class:`Progress` for *desc*: silent under ``--quiet``, else a terminal one."""
+
+    if _quiet if quiet is None else quiet:
+        return NullProgress()
+    return TerminalProgress(desc, total, **kwargs)
+
+
+__all__ = [
+    "LINE_INTERVAL",
+    "NullProgress",
+    "Progress",
+    "ProgressModel",
+    "TerminalProgress",
+    "format_bytes",
+    "is_quiet",
+    "set_quiet",
+    "track",
+]
@@ -0,0 +1,110 @@
+"""Cache of dependency resolution results keyed by an input fingerprint.
+
+The fingerprint covers everything :func:`lpm.app.solve` reads: the requested
+specs, the installed set, pins and holds, the repository index versions (or,
+for universes built in memory, the candidate metadata itself) and the solver
+options.  A change to any of them yields a new key, so stale plans are never
+returned; old entries are simply left behind for ``lpm clean`` to remove.
+"""
+
+from __future__ import annotations
+
+import dataclasses
+import hashlib
+import json
+import os
+from pathlib import Path
+from typing import Any, Dict, List, Mapping, Optional, Sequence
+
+FORMAT_VERSION = 1
+
+
+def _canonical(value: Any) -> str:
+    return json.dumps(value, sort_keys=True, separators=(",", ":"), default=str)
+
+
+def _candidates_digest(candidates_by_name: Mapping[str, Sequence[Any]]) -> str:
+    digest = hashlib.sha256()
+    for name in sorted(candidates_by_name):
+        for pkg in candidates_by_name[name]:
+            digest.update(_canonical(dataclasses.asdict(pkg)).encode("utf-8"))
+    return digest.hexdigest()
+
+
+def fingerprint(goals: Sequence[str], universe: Any, options: Optional[Mapping[str, Any]] = None) -> str:
+    """Return a stable hex digest of the inputs to one resolution."""
+
+    index_versions = dict(getattr(universe, "index_versions", None) or {})
+    installed = {
+        name: [meta.get("version"), meta.get("release"), meta.get("arch"), bool(meta.get("explicit"))]
+        for name, meta in universe.installed.items()
+    }
+    payload = {
+        "format": FORMAT_VERSION,
+        "goals": list(goals),
+        "installed": installed,
+        "pins": dict(universe.pins),
+        "holds": sorted(universe.holds),
+        "options": dict(options or {}),
+    }
+    if index_versions:
+        payload["indexes"] = index_versions
+    else:
+        payload["candidates"] = _candidates_digest(universe.candidates_by_name)
+    return hashlib.sha256(_canonical(payload).encode("utf-8")).hexdigest()
+
+
+def _identity(pkg: Any) -> Dict[str, str]:
+    return {"name": pkg.name, "version": pkg.version, "release": pkg.release, "arch": pkg.arch, "repo": pkg.repo}
+
+
+class ResolutionCache:
+    """Plans stored as one JSON file per fingerprint under *directory*."""
+
+    def __init__(self, directory: Path) -> None:
+        self.directory = Path(directory)
+        self.hits = 0
+        self.misses = 0
+
+    def fingerprint(self, goals: Sequence[str], universe: Any, options: Optional[Mapping[str, Any]] = None) -> str:
+        return fingerprint(goals, universe, options)
+
+    def path_for(self, key: str) -> Path:
+        return self.directory / f"{key}.json"
+
+    def load(self, key: str, universe: Any) -> Optional[List[Any]]:
+        """Return the cached plan for *key* as candidates of *universe*, or ``None``."""
+
+        try:
+            data = json.loads(self.path_for(key).read_text(encoding="utf-8"))
+        except (OSError, ValueError):
+            self.misses += 1
+            return None
+        if data.get("fingerprint") != key:
+            self.misses += 1
+            return None
+        plan: List[Any] = []
+        for entry in data.get("plan", []):
+            match = next(
+                (p for p in universe.candidates_by_name.get(entry.get("name"), []) if _identity(p) == entry),
+                None,
+            )
+            if match is None:
+                self.misses += 1
+                return None
+            plan.append(match)
+        self.hits += 1
+        return plan
+
+    def store(self, key: str, plan: Sequence[Any]) -> None:
+        path = self.path_for(key)
+        try:
+            path.parent.mkdir(parents=True, exist_ok=True)
+            tmp = path.with_suffix(f".{os.getpid()}.tmp")
+            tmp.write_text(_canonical({"fingerprint": key, "plan": [_identity(p) for p in plan]}), encoding="utf-8")
+            os.replace(tmp, path)
+        except OSError:
+            pass
+
+
+__all__ = ["FORMAT_VERSION", "ResolutionCache", "fingerprint"]
@@ -1,17 +1,90 @@
 from __future__ import annotations
 
+import random
+from array import array
 from collections import deque
 from heapq import heappop, heappush
-from dataclasses import dataclass, field
+from dataclasses import asdict, dataclass, field
 from itertools import count
-from typing import Dict, Iterable, List, Optional, Set, Tuple
+from typing import Any, Callable, Dict, Iterable, Iterator, List, Mapping, Optional, Protocol, Sequence, Set, Tuple
+
+from .deadline import Deadline
+
+
+class SolverError(ValueError):
+    """Raised for malformed solver input, such as a zero or unknown literal."""
+
+
+class DimacsError(SolverError):
+    """Raised by :meth:`CNF.from_dimacs` for input that is not valid DIMACS CNF.
+
+    ``kind`` is one of the class constants and ``line`` the 1-based input
+    line the problem was found on (``None`` at end of input).
+    """
+
+    HEADER = "header"
+    LITERAL = "literal"
+    TERMINATOR = "terminator"
+
+    def __init__(self, kind: str, message: str, line: Optional[int] = None) -> None:
+        self.kind = kind
+        self.line = line
+        super().__init__(f"line {line}: {message}" if line is not None else message)
+
+
+def literal(value: int) -> int:
+    """Return *value* as a literal, rejecting zero and non-integers.
+
+    ``0`` has no variable (``abs(0)`` collides with the unused index) and
+    would otherwise corrupt the watch lists silently.
+    """
+
+    if isinstance(value, bool) or not isinstance(value, int):
+        raise SolverError(f"literal must be an int, got {value!r}")
+    if value == 0:
+        raise SolverError("literal 0 is not a valid literal")
+    return value
+
+
+class ClauseView:
+    """Read-only list-like view of the clauses stored in a :class:`CNF` arena."""
+
+    def __init__(self, cnf: "CNF") -> None:
+        self._cnf = cnf
+
+    def __len__(self) -> int:
+        return len(self._cnf.lengths)
+
+    def __getitem__(self, idx: int) -> List[int]:
+        if idx < 0:
+            idx += len(self)
+        return self._cnf.clause(idx)
+
+    def __iter__(self) -> Iterator[List[int]]:
+        for idx in range(len(self)):
+            yield self._cnf.clause(idx)
+
+    def __eq__(self, other: object) -> bool:
+        if isinstance(other, ClauseView):
+            other = list(other)
+        return list(self) == other
+
+    def __repr__(self) -> str:
+        return f"ClauseView({list(self)!r})"
 
 
 class CNF:
-    """Simple CNF container with watched literal management."""
+    """Simple CNF container with watched literal management.
+
+    Literals of all clauses live in one flat ``array('i')`` arena; clause
+    *idx* is the slice ``lits[starts[idx]:starts[idx] + lengths[idx]]``.
+    Removed clauses keep their slot with length 0 so indices stay stable.
+    """
 
     def __init__(self) -> None:
-        self.clauses: List[List[int]] = []
+        self.lits = array("i")
+        self.starts = array("q")
+        self.lengths = array("i")
         self.next_var = 1
         self.varname: Dict[int, str] = {}
         self.namevar: Dict[str, int] = {}
@@ -20,6 +93,18 @@ class CNF:
         self.activity: List[float] = []
         self.lbd: List[int] = []
         self.learnts: Set[int] = set()
+        self._removed: Set[int] = set()
+        self._keys: Dict[Tuple[int, ...], int] = {}
+
+    @property
+    def clauses(self) -> ClauseView:
+        return ClauseView(self)
+
+    def clause(self, idx: int) -> List[int]:
+        """Return a copy of the literals of clause *idx*."""
+
+        start = self.starts[idx]
+        return self.lits[start : start + self.lengths[idx]].tolist()
 
     def new_var(self, name: str) -> int:
         if name in self.namevar:
@@ -30,9 +115,39 @@ class CNF:
         self.varname[v] = name
         return v
 
-    def add_clause(self, clause: List[int], learnt: bool = False, lbd: int = 0) -> int:
-        idx = len(self.clauses)
-        self.clauses.append(clause)
+    def add_clause(self, clause: List[int], learnt: bool = False, lbd: int = 0) -> bool:
+        """Add *clause*, raising :class:`SolverError` for zero or unallocated literals.
+
+        Repeated literals are dropped (keeping first-seen order), and
+        tautologies and clauses already stored are skipped.  An empty clause
+        is kept and makes the instance UNSAT.  Returns whether the clause was
+        stored.
+        """
+
+        lits: List[int] = []
+        for lit in clause:
+            literal(lit)
+            if abs(lit) >= self.next_var:
+                raise SolverError(f"literal {lit} refers to unallocated variable {abs(lit)}")
+            if lit not in lits:
+                lits.append(lit)
+        if any(-lit in lits for lit in lits):
+            return False
+        key = tuple(sorted(lits))
+        if not learnt:
+            if key in self._keys:
+                return False
+            self._keys[key] = len(self.lengths)
+        self.add_clause_unchecked(lits, learnt=learnt, lbd=lbd)
+        return True
+
+    def add_clause_unchecked(self, clause: List[int], learnt: bool = False, lbd: int = 0) -> int:
+        """Add *clause* without validation; for clauses the solver derives itself."""
+
+        idx = len(self.lengths)
+        self.starts.append(len(self.lits))
+        self.lengths.append(len(clause))
+        self.lits.extend(clause)
         self.activity.append(0.0)
         self.lbd.append(lbd)
         if learnt:
@@ -57,9 +172,79 @@ class CNF:
             if clause:
                 self.add_clause(clause)
 
+    def to_dimacs(self) -> str:
+        """Return the problem clauses in DIMACS ``p cnf`` format.
+
+        Learnt and removed clauses are left out; variable names are written
+        as ``c`` comments so a dump can be mapped back to packages.
+        """
+
+        nvars = self.next_var - 1
+        rows = [
+            self.clause(idx)
+            for idx in range(len(self.lengths))
+            if idx not in self.learnts and idx not in self._removed
+        ]
+        out = [f"c {v} {self.varname[v]}" for v in range(1, nvars + 1) if v in self.varname]
+        out.append(f"p cnf {nvars} {len(rows)}")
+        out.extend(" ".join(str(lit) for lit in row + [0]) for row in rows)
+        return "\n".join(out) + "\n"
+
+    @classmethod
+    def from_dimacs(cls, text: str) -> "CNF":
+        """Parse DIMACS CNF *text*, raising :class:`DimacsError` when malformed.
+
+        Comment lines are ignored and a clause may span several lines up to
+        its terminating ``0``.  Variables are named ``x1`` … ``xN``.
+        """
+
+        cnf: Optional[CNF] = None
+        declared = 0
+        pending: List[int] = []
+        clauses = 0
+        lineno = 0
+        for lineno, raw in enumerate(text.splitlines(), start=1):
+            line = raw.strip()
+            if not line or line.startswith("c"):
+                continue
+            if line.startswith("%"):
+                break
+            if line.startswith("p"):
+                parts = line.split()
+                if cnf is not None:
+                    raise DimacsError(DimacsError.HEADER, "duplicate problem line", lineno)
+                if len(parts) != 4 or parts[1] != "cnf" or not all(p.isdigit() for p in parts[2:]):
+                    raise DimacsError(DimacsError.HEADER, f"expected 'p cnf <vars> <clauses>', got {line!r}", lineno)
+                cnf = cls()
+                for v in range(1, int(parts[2]) + 1):
+                    cnf.new_var(f"x{v}")
+                declared = int(parts[3])
+                continue
+            if cnf is None:
+                raise DimacsError(DimacsError.HEADER, "clause before the 'p cnf' problem line", lineno)
+            for token in line.split():
+                try:
+                    lit = int(token)
+                except ValueError:
+                    raise DimacsError(DimacsError.LITERAL, f"{token!r} is not a literal", lineno) from None
+                if lit == 0:
+                    cnf.add_clause(pending)
+                    pending = []
+                    clauses += 1
+                elif abs(lit) >= cnf.next_var:
+                    raise DimacsError(DimacsError.LITERAL, f"literal {lit} exceeds the declared variable count", lineno)
+                else:
+                    pending.append(lit)
+        if cnf is None:
+            raise DimacsError(DimacsError.HEADER, "missing 'p cnf' problem line")
+        if pending:
+            raise DimacsError(DimacsError.TERMINATOR, f"last clause {pending} is not terminated by 0")
+        if clauses != declared:
+            raise DimacsError(DimacsError.HEADER, f"problem line declares {declared} clauses, found {clauses}")
+        return cnf
+
     def remove_clause(self, idx: int) -> None:
-        clause = self.clauses[idx]
-        if not clause:
+        if not self.lengths[idx]:
             return
         w1, w2 = self.watchers[idx]
         def unlink(lit: int) -> None:
@@ -77,7 +262,11 @@ class CNF:
 
         unlink(w1)
         unlink(w2)
-        self.clauses[idx] = []
+        key = tuple(sorted(self.clause(idx)))
+        if self._keys.get(key) == idx:
+            del self._keys[key]
+        self.lengths[idx] = 0
+        self._removed.add(idx)
         self.watchers[idx] = (0, 0)
         self.activity[idx] = 0.0
         self.lbd[idx] = 0
@@ -85,10 +274,37 @@ class CNF:
 
 
 class SATResult:
-    def __init__(self, sat: bool, assign: Dict[int, bool], unsat_core: Optional[List[int]] = None):
+    def __init__(
+        self,
+        sat: bool,
+        assign: Dict[int, bool],
+        unsat_core: Optional[List[int]] = None,
+        failed_assumptions: Optional[List[int]] = None,
+        unknown: bool = False,
+    ):
         self.sat = sat
         self.assign = assign
         self.unsat_core = unsat_core
+        # Subset of the assumptions that cannot hold together; empty when the
+        # clauses are unsatisfiable on their own.
+        self.failed_assumptions = failed_assumptions
+        # Set when the search gave up on its conflict budget; ``sat`` is then
+        # False but says nothing about satisfiability.
+        self.unknown = unknown
+
+    def true_vars(self) -> Iterator[int]:
+        """Yield the variables set true in the model, in ascending order; nothing unless SAT."""
+
+        if not self.sat:
+            return iter(())
+        return (v for v in sorted(self.assign) if self.assign[v])
+
+    def assigned_value(self, var: int) -> Optional[bool]:
+        """Return the model's value for *var*, or ``None`` without a model or for an unknown variable."""
+
+        if not self.sat:
+            return None
+        return self.assign.get(var)
 
 
 @dataclass
@@ -100,6 +316,138 @@ class Implication:
     preds: List[int] = field(default_factory=list)
 
 
+@dataclass(frozen=True)
+class ConflictNode:
+    """An assigned literal in a :class:`ConflictGraph`."""
+
+    literal: int
+    level: int
+    # Clause that implied the literal; ``None`` for decisions and assumptions.
+    reason: Optional[int] = None
+
+
+@dataclass(frozen=True)
+class ConflictGraph:
+    """The part of the implication graph behind an unsatisfiable result.
+
+    ``conflict`` holds the literals of the clause that ended up falsified (or
+    the assumption that could not hold).  ``nodes`` are the assignments that
+    falsified it, traced back to decisions, assumptions and unit clauses, and
+    an edge ``(a, b)`` says that literal ``a`` was an antecedent of ``b``.
+    :meth:`to_dict` and :meth:`from_dict` give a JSON-friendly form.
+    """
+
+    conflict: Tuple[int, ...] = ()
+    nodes: Tuple[ConflictNode, ...] = ()
+    edges: Tuple[Tuple[int, int], ...] = ()
+
+    def variables(self) -> Set[int]:
+        return {abs(node.literal) for node in self.nodes}
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "conflict": list(self.conflict),
+            "nodes": [asdict(node) for node in self.nodes],
+            "edges": [list(edge) for edge in self.edges],
+        }
+
+    @classmethod
+    def from_dict(cls, data: Mapping[str, Any]) -> "ConflictGraph":
+        return cls(
+            conflict=tuple(int(lit) for lit in data.get("conflict", ())),
+            nodes=tuple(ConflictNode(**node) for node in data.get("nodes", ())),
+            edges=tuple((int(a), int(b)) for a, b in data.get("edges", ())),
+        )
+
+
+class Trail:
+    """Assigned literals in assignment order, split into decision levels.
+
+    ``limits[k]`` is where level ``k + 1`` starts, so backjumping to a level
+    only touches the literals assigned above it.
+    """
+
+    def __init__(self) -> None:
+        self.lits: List[int] = []
+        self.limits: List[int] = []
+
+    def __len__(self) -> int:
+        return len(self.lits)
+
+    def __getitem__(self, idx: int) -> int:
+        return self.lits[idx]
+
+    def __iter__(self) -> Iterator[int]:
+        return iter(self.lits)
+
+    def __reversed__(self) -> Iterator[int]:
+        return reversed(self.lits)
+
+    @property
+    def level(self) -> int:
+        return len(self.limits)
+
+    def new_level(self) -> None:
+        self.limits.append(len(self.lits))
+
+    def push(self, lit: int) -> None:
+        self.lits.append(lit)
+
+    def level_lits(self, level: int) -> List[int]:
+        """Return the literals assigned at decision *level*."""
+
+        start = self.limits[level - 1] if level else 0
+        end = self.limits[level] if level < len(self.limits) else len(self.lits)
+        return self.lits[start:end]
+
+    def backjump(self, level: int) -> List[int]:
+        """Drop every level above *level* and return its literals, newest first."""
+
+        if level >= len(self.limits):
+            return []
+        start = self.limits[level]
+        undone = self.lits[start:]
+        del self.lits[start:]
+        del self.limits[level:]
+        undone.reverse()
+        return undone
+
+
+@dataclass(frozen=True)
+class SolverEvent:
+    """Progress notification passed to the ``on_event`` callback of :meth:`CDCLSolver.solve`.
+
+    ``kind`` is one of the class constants; ``literal`` is set for decisions
+    and ``sat`` for the final :attr:`RESULT` event.
+    """
+
+    DECISION = "decision"
+    CONFLICT = "conflict"
+    RESTART = "restart"
+    LEVEL = "level"
+    RESULT = "result"
+
+    kind: str
+    level: int
+    conflicts: int
+    decisions: int
+    literal: Optional[int] = None
+    sat: Optional[bool] = None
+
+
+@dataclass
+class SolverStats:
+    """Search effort of the last :meth:`CDCLSolver.solve` call."""
+
+    conflicts: int = 0
+    decisions: int = 0
+    # Literals taken off the propagation queue.
+    propagations: int = 0
+    learnt: int = 0
+    max_level: int = 0
+    restarts: int = 0
+
+
 def luby(i: int) -> int:
     """Return the i-th value of the Luby sequence."""
     k = 1
@@ -110,8 +458,117 @@ def luby(i: int) -> int:
     return luby(i - (1 << (k - 1)) + 1)
 
 
+class DecisionState:
+    """Read-only view of a running search, handed to :meth:`DecisionHeuristic.choose`."""
+
+    def __init__(
+        self,
+        assigns: Mapping[int, Optional[bool]],
+        level: Callable[[], int],
+        activity: Callable[[int], float],
+        phase: Callable[[int], bool],
+        by_activity: Callable[[], int],
+    ) -> None:
+        self._assigns = assigns
+        self._level = level
+        self._activity = activity
+        self._phase = phase
+        self._by_activity = by_activity
+
+    @property
+    def num_vars(self) -> int:
+        return len(self._assigns)
+
+    @property
+    def level(self) -> int:
+        """Current decision level; the chosen literal opens the next one."""
+
+        return self._level()
+
+    def value(self, var: int) -> Optional[bool]:
+        return self._assigns[var]
+
+    def unassigned(self) -> Iterator[int]:
+        """Yield the unassigned variables in ascending order."""
+
+        return (v for v, val in self._assigns.items() if val is None)
+
+    def activity(self, var: int) -> float:
+        """Return the current (decayed) VSIDS activity of *var*."""
+
+        return self._activity(var)
+
+    def phase(self, var: int) -> bool:
+        """Return the polarity the solver would give *var*: its saved phase, else its preference."""
+
+        return self._phase(var)
+
+    def most_active(self) -> int:
+        """Return the unassigned variable with the highest activity, or ``0`` if none is left.
+
+        Ties are broken in the solver's seeded order (see :meth:`CDCLSolver.with_seed`).
+        """
+
+        return self._by_activity()
+
+
+class DecisionHeuristic(Protocol):
+    """Strategy picking the next decision literal of :meth:`CDCLSolver.solve`."""
+
+    def choose(self, state: DecisionState) -> Optional[int]:
+        """Return a literal over an unassigned variable, or ``None`` once every variable is assigned."""
+
+
+class Vsids:
+    """Decide on the most active variable; the solver's default heuristic."""
+
+    def choose(self, state: DecisionState) -> Optional[int]:
+        v = state.most_active()
+        if v == 0:
+            return None
+        return v if state.phase(v) else -v
+
+
+class StaticOrder:
+    """Decide on the first unassigned variable of *order* (by default, the lowest index)."""
+
+    def __init__(self, order: Optional[Sequence[int]] = None) -> None:
+        self.order = list(order) if order is not None else None
+
+    def choose(self, state: DecisionState) -> Optional[int]:
+        candidates = state.unassigned() if self.order is None else (v for v in self.order if state.value(v) is None)
+        v = next(candidates, None)
+        if v is None:
+            # Variables missing from ``order`` still have to be decided.
+            v = next(state.unassigned(), None)
+        if v is None:
+            return None
+        return v if state.phase(v) else -v
+
+
+class Random:
+    """Decide on an unassigned variable drawn from a generator seeded with *seed*."""
+
+    def __init__(self, seed: Optional[int] = None) -> None:
+        self._rng = random.Random(seed)
+
+    def choose(self, state: DecisionState) -> Optional[int]:
+        free = list(state.unassigned())
+        if not free:
+            return None
+        v = self._rng.choice(free)
+        return v if state.phase(v) else -v
+
+
 class CDCLSolver:
-    """Conflict-Driven Clause Learning SAT solver."""
+    """Conflict-Driven Clause Learning SAT solver.
+
+    Decisions reuse the polarity a variable last had (phase saving), which
+    is kept across :meth:`solve` calls in ``saved_phase``.  A variable never
+    assigned before takes ``True`` if it is in *prefer_true*, ``False`` if it
+    is in *prefer_false*, and *default_phase* otherwise.  Which variable is
+    decided next is up to *heuristic*, :class:`Vsids` unless given.
+    """
 
     def __init__(
         self,
@@ -123,8 +580,16 @@ class CDCLSolver:
         var_decay: float = 0.95,
         cla_decay: float = 0.999,
         max_learnts: int = 200,
+        pure_literals: bool = False,
+        seed: Optional[int] = None,
+        default_phase: bool = True,
+        heuristic: Optional[DecisionHeuristic] = None,
     ) -> None:
         self.cnf = cnf
+        self.default_phase = default_phase
+        self.heuristic: DecisionHeuristic = heuristic if heuristic is not None else Vsids()
+        self.seed = seed
+        self.pure_literals = pure_literals
         self.prefer_true = prefer_true or set()
         self.prefer_false = prefer_false or set()
         self.decay_map = decay_map or {}
@@ -139,10 +604,391 @@ class CDCLSolver:
         self.max_learnts = max_learnts
         self.var_decay_steps = 0
         self.var_last_decay_step: Dict[int, int] = {}
+        self._proof: Optional[List[str]] = None
+        self.conflict_budget: Optional[int] = None
+        self.restart_base = 100
+        # Auxiliary variables of cardinality encodings, left out of enumerate().
+        self.aux_vars: Set[int] = set()
+        # Assignments found by :meth:`eliminate_pure_literals`.
+        self.fixed: Dict[int, bool] = {}
+        self.last_restarts = 0
+        self.last_decisions = 0
+        self._stats = SolverStats()
+        self._conflict_graph: Optional[ConflictGraph] = None
+
+    def eliminate_pure_literals(self) -> int:
+        """Fix every variable that occurs with one polarity only, and return how many were fixed.
+
+        Clauses satisfied by a fixed literal no longer count, so the scan
+        repeats until nothing new turns pure.  The choices are recorded in
+        :attr:`fixed` and asserted at level 0 by later :meth:`solve` calls,
+        except for variables an assumption of that call mentions and any
+        choice that a clause added since, or such an exception, would no
+        longer leave satisfied; those variables are searched as usual.
+        """
+
+        fixed = self.fixed
+        before = len(fixed)
+        while True:
+            polarity: Dict[int, Optional[bool]] = {}
+            for clause in self.cnf.clauses:
+                if not clause or any(fixed.get(abs(l)) == (l > 0) for l in clause):
+                    continue
+                for l in clause:
+                    v = abs(l)
+                    if v in fixed:
+                        continue
+                    seen_pol = polarity.get(v, l > 0)
+                    polarity[v] = seen_pol if seen_pol == (l > 0) else None
+            pure = {v: pol for v, pol in polarity.items() if pol is not None}
+            if not pure:
+                return len(fixed) - before
+            fixed.update(pure)
+
+    # Above this many literals add_at_most_one switches from pairwise clauses
+    # to the sequential counter encoding.
+    AMO_PAIRWISE_LIMIT = 6
+
+    def add_at_most_one(self, lits: Iterable[int]) -> None:
+        """Add clauses allowing at most one of *lits* to be true.
+
+        Small groups get the pairwise clauses; larger ones use a sequential
+        counter (Sinz 2005) over fresh auxiliary variables, which needs a
+        linear number of clauses.
+        """
+
+        lits = [literal(lit) for lit in lits]
+        for lit in lits:
+            if abs(lit) >= self.cnf.next_var:
+                raise SolverError(f"literal {lit} refers to unallocated variable {abs(lit)}")
+        cnf = self.cnf
+        n = len(lits)
+        if n <= self.AMO_PAIRWISE_LIMIT:
+            for i in range(n):
+                for j in range(i + 1, n):
+                    cnf.add_clause([-lits[i], -lits[j]])
+            return
+        prefix = f"amo#{cnf.next_var}"
+        aux = [cnf.new_var(f"{prefix}_{i}") for i in range(n - 1)]
+        self.aux_vars.update(aux)
+        cnf.add_clause([-lits[0], aux[0]])
+        for i in range(1, n - 1):
+            cnf.add_clause([-lits[i], aux[i]])
+            cnf.add_clause([-aux[i - 1], aux[i]])
+            cnf.add_clause([-lits[i], -aux[i - 1]])
+        cnf.add_clause([-lits[-1], -aux[-1]])
+
+    def add_exactly_one(self, lits: Iterable[int]) -> None:
+        """Add clauses forcing exactly one of *lits* to be true."""
+
+        lits = list(lits)
+        self.add_at_most_one(lits)
+        self.cnf.add_clause(lits)
+
+    def enumerate(self, limit: int, over: Optional[Iterable[int]] = None) -> List[Dict[int, bool]]:
+        """Return up to *limit* distinct models, stopping early once none is left.
+
+        Models are projected onto *over*, by default every variable except the
+        auxiliaries of :meth:`add_at_most_one`.  Each one found is blocked with
+        a clause guarded by a selector literal that is only assumed during this
+        call and retired afterwards, so later solves and enumerations see the
+        CNF's models as before.  With nothing to project onto there is at most
+        the one empty model.
+        """
+
+        if over is None:
+            over = [v for v in range(1, self.cnf.next_var) if v not in self.aux_vars]
+        else:
+            over = list(over)
+        models: List[Dict[int, bool]] = []
+        if not over:
+            # Blocking the empty model would need an empty clause.
+            return [{}] if limit > 0 and self.solve([]).sat else []
+        sel = self.cnf.new_var(f"enum#{self.cnf.next_var}")
+        self.aux_vars.add(sel)
+        try:
+            while len(models) < limit:
+                res = self.solve_under_assumptions([sel])
+                if not res.sat:
+                    break
+                model = {v: res.assign[v] for v in over}
+                models.append(model)
+                self.cnf.add_clause([-sel] + [-v if val else v for v, val in model.items()])
+        finally:
+            self.cnf.add_clause([-sel])
+        return models
+
+    def minimal_unsat_core(
+        self,
+        candidates: Optional[Iterable[int]] = None,
+        *,
+        deadline: Optional[Deadline] = None,
+        budget: Optional[int] = None,
+    ) -> Optional[List[int]]:
+        """Return indices into ``cnf.clauses`` of a minimal unsatisfiable subset.
+
+        Each clause in *candidates* (by default every problem clause) gets a
+        selector literal in a private copy of the CNF; the other clauses stay
+        in unconditionally.  The search starts from the failed assumptions of
+        one :meth:`solve_under_assumptions` call over all selectors, then drops
+        clauses one at a time (deletion-based MUS extraction) while the rest
+        stays UNSAT.  Removing any clause of the result makes it satisfiable.
+        Returns ``[]`` when the instance is SAT.
+
+        Each solve runs under *deadline* and the solver's conflict budget;
+        *budget* caps the propagations of all of them together and is checked
+        between solves.  ``None`` is returned once either budget runs out.
+        """
+
+        cnf = self.cnf
+        wanted = None if candidates is None else set(candidates)
+        copy = CNF()
+        for v in range(1, cnf.next_var):
+            copy.new_var(cnf.varname.get(v, f"x{v}"))
+        selector: Dict[int, int] = {}
+        for idx in range(len(cnf.lengths)):
+            if idx in cnf.learnts or idx in cnf._removed:
+                continue
+            if wanted is not None and idx not in wanted:
+                copy.add_clause_unchecked(cnf.clause(idx))
+                continue
+            sel = copy.new_var(f"sel#{idx}")
+            selector[idx] = sel
+            copy.add_clause_unchecked([*cnf.clause(idx), -sel])
+        owner = {sel: idx for idx, sel in selector.items()}
+        solver = CDCLSolver(copy)
+        solver.set_conflict_budget(self.conflict_budget)
+        spent = 0
+
+        def attempt(keep: List[int]) -> Optional[SATResult]:
+            nonlocal spent
+            if budget is not None and spent >= budget:
+                return None
+            res = solver.solve_under_assumptions([selector[idx] for idx in keep], deadline=deadline)
+            spent += solver.stats().propagations
+            return None if res.unknown else res
+
+        def shrink(res: SATResult, keep: List[int]) -> List[int]:
+            failed = {owner[l] for l in res.failed_assumptions or () if l in owner}
+            return [idx for idx in keep if idx in failed]
+
+        core = list(selector)
+        res = attempt(core)
+        if res is None:
+            return None
+        if res.sat:
+            return []
+        core = shrink(res, core)
+        i = 0
+        while i < len(core):
+            rest = core[:i] + core[i + 1 :]
+            res = attempt(rest)
+            if res is None:
+                return None
+            if res.sat:
+                i += 1
+            else:
+                core = shrink(res, rest)
+        return sorted(core)
+
+    def export_learnt(self) -> List[List[int]]:
+        """Return the learnt clauses currently kept, for :meth:`import_learnt` on a later solver."""
+
+        cnf = self.cnf
+        return [cnf.clause(idx) for idx in sorted(cnf.learnts) if cnf.lengths[idx]]
+
+    # Conflicts import_learnt may spend proving that one imported clause follows
+    # from the CNF before it gives the clause up.
+    IMPORT_CHECK_CONFLICTS = 100
+
+    def import_learnt(self, clauses: Iterable[Iterable[int]]) -> int:
+        """Add *clauses* exported by another solver as learnt clauses; return how many were stored.
+
+        Every literal must refer to an allocated variable, otherwise
+        :class:`SolverError` is raised before anything is added.  The CNF may
+        have changed since the export, so a clause is only kept once solving
+        under its negation proves it implied within
+        :attr:`IMPORT_CHECK_CONFLICTS` conflicts; the others are dropped and
+        cannot make a satisfiable instance UNSAT.  Kept clauses are stored as
+        learnt, so the clause database reduction may drop them like any other.
+        Empty clauses are ignored.
+        """
+
+        cnf = self.cnf
+        pending = [[literal(lit) for lit in clause] for clause in clauses]
+        for clause in pending:
+            for lit in clause:
+                if abs(lit) >= cnf.next_var:
+                    raise SolverError(f"learnt clause {clause} refers to unallocated variable {abs(lit)}")
+        saved = (
+            self.conflict_budget,
+            self._stats,
+            self._conflict_graph,
+            self.last_restarts,
+            self.last_decisions,
+        )
+        stored = 0
+        try:
+            self.conflict_budget = self.IMPORT_CHECK_CONFLICTS
+            for clause in pending:
+                if not clause:
+                    continue
+                known = set(cnf.learnts)
+                res = self.solve([-lit for lit in clause])
+                # What the check itself learnt would only duplicate the import.
+                for idx in cnf.learnts - known:
+                    cnf.remove_clause(idx)
+                if res.sat or res.unknown:
+                    continue
+                if cnf.add_clause(clause, learnt=True, lbd=len(clause)):
+                    stored += 1
+        finally:
+            (
+                self.conflict_budget,
+                self._stats,
+                self._conflict_graph,
+                self.last_restarts,
+                self.last_decisions,
+            ) = saved
+        return stored
+
+    def set_learnt_limit(self, limit: int) -> None:
+        """Halve the learnt clause database whenever it grows past *limit* clauses."""
+
+        if limit < 1:
+            raise ValueError("learnt clause limit must be positive")
+        self.max_learnts = limit
+
+    def set_restart_base(self, base: int) -> None:
+        """Set the unit run length of the Luby restart schedule.
+
+        A restart happens after ``luby(n) * base`` conflicts since the previous
+        one; ``0`` turns restarts off.
+        """
+
+        if base < 0:
+            raise ValueError("restart base must be non-negative")
+        self.restart_base = base
+
+    def stats(self) -> SolverStats:
+        """Counters of the last :meth:`solve` call; a new call starts from zero."""
+
+        return self._stats
+
+    @property
+    def restarts(self) -> int:
+        """Number of restarts performed by the last :meth:`solve` call."""
 
-    def solve(self, assumptions: List[int]) -> SATResult:
-        """Solve the stored CNF instance under optional assumptions."""
+        return self.last_restarts
+
+    def set_conflict_budget(self, max_conflicts: Optional[int]) -> None:
+        """Give up once a :meth:`solve` call processes more than *max_conflicts* conflicts.
+
+        The call then returns a result with ``unknown`` set.  ``None``, the
+        default, searches without limit.
+        """
+
+        if max_conflicts is not None and max_conflicts < 0:
+            raise ValueError("conflict budget must be non-negative")
+        self.conflict_budget = max_conflicts
+
+    def enable_proof(self) -> None:
+        """Record a DRAT proof of learnt and deleted clauses for :meth:`take_proof`.
+
+        Each line is a clause as space-separated literals ending in ``0``,
+        prefixed with ``d`` for deletions.  An UNSAT answer without
+        assumptions ends the proof with the empty clause ``0``.
+        """
+
+        if self._proof is None:
+            self._proof = []
+
+    def take_proof(self) -> List[str]:
+        """Return the proof lines recorded so far and start a new, empty proof."""
+
+        proof = self._proof or []
+        if self._proof is not None:
+            self._proof = []
+        return proof
+
+    def _log_proof(self, lits: Iterable[int], delete: bool = False) -> None:
+        if self._proof is not None:
+            line = " ".join([*(str(l) for l in lits), "0"])
+            self._proof.append(f"d {line}" if delete else line)
+
+    def conflict_explanation(self) -> ConflictGraph:
+        """Return the :class:`ConflictGraph` of the last :meth:`solve` call.
+
+        Raises :class:`SolverError` unless that call proved the instance
+        unsatisfiable.
+        """
+
+        if self._conflict_graph is None:
+            raise SolverError("no conflict to explain: the last solve was not unsatisfiable")
+        return self._conflict_graph
+
+    @classmethod
+    def with_seed(cls, cnf: CNF, seed: int, **kwargs) -> "CDCLSolver":
+        """Build a solver that breaks VSIDS activity ties in an order derived from *seed*.
+
+        Without a seed ties go to the variable queued first; either way the
+        same input always produces the same model.
+        """
+
+        return cls(cnf, seed=seed, **kwargs)
+
+    def _tie_rank(self, nvars: int) -> Dict[int, int]:
+        if self.seed is None:
+            return {}
+        order = list(range(1, nvars + 1))
+        random.Random(self.seed).shuffle(order)
+        return {v: rank for rank, v in enumerate(order)}
+
+    def solve_under_assumptions(
+        self,
+        assumptions: Iterable[int],
+        deadline: Optional[Deadline] = None,
+        on_event: Optional[Callable[[SolverEvent], None]] = None,
+    ) -> SATResult:
+        """Answer a what-if query: solve with *assumptions* forced for this call only.
+
+        The assumptions are validated like clause literals and retracted when
+        the call returns; only learnt clauses carry over, so one solver can
+        serve many queries.  When the assumptions clash with the clauses the
+        result's ``failed_assumptions`` (and ``unsat_core``, negated) name just
+        the assumptions responsible.
+        """
+
+        lits = [literal(lit) for lit in assumptions]
+        for lit in lits:
+            if abs(lit) >= self.cnf.next_var:
+                raise SolverError(f"assumption {lit} refers to unallocated variable {abs(lit)}")
+        return self.solve(lits, deadline=deadline, on_event=on_event)
+
+    def solve(
+        self,
+        assumptions: List[int],
+        deadline: Optional[Deadline] = None,
+        on_event: Optional[Callable[[SolverEvent], None]] = None,
+    ) -> SATResult:
+        """Solve the stored CNF instance under optional assumptions.
+
+        Each assumption occupies its own decision level below any search
+        decision, so learnt clauses stay valid for later calls with different
+        assumptions.  When the assumptions cannot all hold, the result lists
+        the responsible ones in ``failed_assumptions``.
+        With a *deadline*, search stops with :class:`DeadlineExceeded` once it
+        has passed.  *on_event* receives a :class:`SolverEvent` for every
+        decision, conflict and restart, a periodic ``level`` update, and a
+        final ``result``.  See :meth:`set_conflict_budget` for bounding the
+        search instead.
+
+        Degenerate instances need no search: without clauses the result is SAT
+        (an empty model when no variable is allocated either), and a stored
+        empty clause makes it UNSAT with an empty ``unsat_core``.
+        """
         cnf = self.cnf
+        lits, starts, lengths = cnf.lits, cnf.starts, cnf.lengths
         nvars = cnf.next_var - 1
         # ensure activity arrays cover all variables
         for v in range(1, nvars + 1):
@@ -154,11 +1000,11 @@ class CDCLSolver:
         assigns: Dict[int, Optional[bool]] = {i: None for i in range(1, nvars + 1)}
         levels: Dict[int, int] = {i: 0 for i in range(1, nvars + 1)}
         reason: Dict[int, Optional[int]] = {i: None for i in range(1, nvars + 1)}
-        trail: List[int] = []
-        trail_lim: List[int] = []
+        trail = Trail()
         queue = deque()
-        branch_heap: List[Tuple[float, int, int]] = []
+        branch_heap: List[Tuple[float, int, int, int]] = []
         heap_counter = count()
+        tie_rank = self._tie_rank(nvars)
         active_vars: Set[int] = set(range(1, nvars + 1))
         imp_graph: Dict[int, Implication] = {i: Implication() for i in range(1, nvars + 1)}
 
@@ -187,7 +1033,7 @@ class CDCLSolver:
                 return
             if priority is None:
                 priority = -normalize_var(v)
-            heappush(branch_heap, (priority, next(heap_counter), v))
+            heappush(branch_heap, (priority, tie_rank.get(v, 0), next(heap_counter), v))
 
         for v in active_vars:
             push_var(v)
@@ -195,6 +1041,9 @@ class CDCLSolver:
         # stats for testing/benchmarking
         self.last_conflicts = 0
         self.last_restarts = 0
+        self.last_decisions = 0
+        stats = self._stats = SolverStats()
+        self._conflict_graph = None
 
         def bump_var(v: int) -> None:
             nonlocal var_inc
@@ -225,17 +1074,21 @@ class CDCLSolver:
             cla_inc /= cla_decay
 
         def reduce_db() -> None:
-            learnts = [idx for idx in cnf.learnts if cnf.clauses[idx]]
+            # Keep the better-scoring half (low LBD, then high activity); a
+            # clause that is the reason of an assigned variable, and any
+            # binary clause, must stay.
+            learnts = [idx for idx in cnf.learnts if lengths[idx]]
             if len(learnts) <= max_learnts:
                 return
             learnts.sort(key=lambda idx: (cnf.lbd[idx], -cnf.activity[idx]))
             reasons = set(reason.values())
-            for idx in learnts[max_learnts:]:
-                if idx not in reasons and len(cnf.clauses[idx]) > 2:
+            for idx in learnts[len(learnts) // 2 :]:
+                if idx not in reasons and lengths[idx] > 2:
+                    self._log_proof(cnf.clause(idx), delete=True)
                     cnf.remove_clause(idx)
 
         def current_level() -> int:
-            return len(trail_lim)
+            return trail.level
 
         def value(lit: int) -> Optional[bool]:
             val = assigns[abs(lit)]
@@ -253,26 +1106,20 @@ class CDCLSolver:
             saved_phase[v] = val
             levels[v] = current_level()
             reason[v] = rsn
-            trail.append(lit)
+            trail.push(lit)
             queue.append(lit)
             node = imp_graph[v]
             node.level = levels[v]
             node.reason = rsn
             if rsn is not None:
-                node.preds = [l for l in cnf.clauses[rsn] if abs(l) != v]
+                node.preds = [l for l in cnf.clause(rsn) if abs(l) != v]
             else:
                 node.preds = []
 
-        for i, cl in enumerate(cnf.clauses):
-            if len(cl) == 1:
-                enqueue(cl[0], i)
-
-        for lit in assumptions:
-            enqueue(lit, None)
-
         def propagate() -> Optional[int]:
             while queue:
                 lit = queue.popleft()
+                stats.propagations += 1
                 watchers = cnf.watch_list.get(-lit)
                 if not watchers:
                     cnf.watch_list.pop(-lit, None)
@@ -280,8 +1127,8 @@ class CDCLSolver:
                 i = 0
                 while i < len(watchers):
                     ci = watchers[i]
-                    clause = cnf.clauses[ci]
-                    if not clause:
+                    n = lengths[ci]
+                    if not n:
                         watchers[i] = watchers[-1]
                         watchers.pop()
                         if not watchers:
@@ -298,7 +1145,9 @@ class CDCLSolver:
                         i += 1
                         continue
                     found = False
-                    for new_lit in clause:
+                    start = starts[ci]
+                    for j in range(start, start + n):
+                        new_lit = lits[j]
                         if new_lit == other or new_lit == -lit:
                             continue
                         if value(new_lit) is not False:
@@ -323,7 +1172,7 @@ class CDCLSolver:
 
         def pick_branch_var() -> int:
             while branch_heap:
-                priority, _, v = heappop(branch_heap)
+                priority, _, _, v = heappop(branch_heap)
                 if assigns[v] is not None or v not in active_vars:
                     continue
                 current_priority = -normalize_var(v)
@@ -333,14 +1182,28 @@ class CDCLSolver:
                 return v
             return 0
 
+        def phase_of(v: int) -> bool:
+            phase = saved_phase.get(v)
+            if phase is None:
+                if v in self.prefer_true:
+                    phase = True
+                elif v in self.prefer_false:
+                    phase = False
+                else:
+                    phase = self.default_phase
+            return phase
+
+        heuristic = self.heuristic
+        decision_state = DecisionState(assigns, current_level, normalize_var, phase_of, pick_branch_var)
+
         def analyze(conflict_idx: int) -> Tuple[List[int], int]:
             bump_clause(conflict_idx)
-            for lit in cnf.clauses[conflict_idx]:
+            clause = cnf.clause(conflict_idx)
+            for lit in clause:
                 bump_var(abs(lit))
             seen: Set[int] = set()
             learnt: List[int] = []
             counter = 0
-            clause = cnf.clauses[conflict_idx][:]
             i = len(trail) - 1
             while True:
                 for lit in clause:
@@ -362,64 +1225,213 @@ class CDCLSolver:
                 clause_idx = imp_graph[v].reason
                 bump_clause(clause_idx)
                 if clause_idx is not None:
-                    for l in cnf.clauses[clause_idx]:
-                        bump_var(abs(l))
+                    start = starts[clause_idx]
+                    for j in range(start, start + lengths[clause_idx]):
+                        bump_var(abs(lits[j]))
                 clause = imp_graph[v].preds.copy() if clause_idx is not None else []
                 counter -= 1
                 if counter <= 0:
                     learnt.append(-lit)
                     break
+            # Put the asserting (UIP) literal first and a literal from the
+            # backjump level second so both watches are correct after the
+            # backjump and the clause propagates immediately.
+            learnt.insert(0, learnt.pop())
             back_lvl = 0
             if len(learnt) > 1:
-                back_lvl = max(imp_graph[abs(l)].level for l in learnt[:-1])
+                top = max(range(1, len(learnt)), key=lambda j: imp_graph[abs(learnt[j])].level)
+                learnt[1], learnt[top] = learnt[top], learnt[1]
+                back_lvl = imp_graph[abs(learnt[1])].level
             for lit in learnt:
                 bump_var(abs(lit))
             return learnt, back_lvl
 
-        def backtrack(level: int) -> None:
-            while current_level() > level:
-                start = trail_lim.pop()
-                while len(trail) > start:
-                    lit = trail.pop()
+        def eliminate_pure_literals() -> None:
+            """Assign, at level 0, every variable that occurs with one polarity only
+            among the clauses not yet satisfied."""
+            while True:
+                polarity: Dict[int, Optional[bool]] = {}
+                for clause in cnf.clauses:
+                    if not clause or any(value(l) is True for l in clause):
+                        continue
+                    for l in clause:
+                        v = abs(l)
+                        if assigns[v] is not None:
+                            continue
+                        seen_pol = polarity.get(v, l > 0)
+                        polarity[v] = seen_pol if seen_pol == (l > 0) else None
+                for l in assumptions:
+                    if assigns[abs(l)] is None:
+                        seen_pol = polarity.get(abs(l), l > 0)
+                        polarity[abs(l)] = seen_pol if seen_pol == (l > 0) else None
+                pure = [v if pol else -v for v, pol in polarity.items() if pol is not None]
+                if not pure:
+                    return
+                for lit in pure:
+                    enqueue(lit, None)
+
+        def usable_fixed() -> List[int]:
+            """The :attr:`fixed` choices that are still safe to assert in this call.
+
+            They must satisfy every clause they touch, so a choice goes once
+            an assumption mentions its variable or a clause containing its
+            negation is left unsatisfied by the others."""
+            assumed = {abs(l) for l in assumptions}
+            keep = {v: val for v, val in self.fixed.items() if v not in assumed and assigns[v] is None}
+            changed = True
+            while changed:
+                changed = False
+                for clause in cnf.clauses:
+                    if not clause or any(value(l) is True or keep.get(abs(l)) == (l > 0) for l in clause):
+                        continue
+                    for l in clause:
+                        if abs(l) in keep:
+                            del keep[abs(l)]
+                            changed = True
+            return [v if val else -v for v, val in keep.items()]
+
+        def emit(kind: str, literal: Optional[int] = None, sat: Optional[bool] = None) -> None:
+            if on_event is not None:
+                on_event(SolverEvent(kind, current_level(), conflicts, self.last_decisions, literal, sat))
+
+        def save_state() -> None:
+            self.var_inc = var_inc
+            self.var_decay_steps = var_decay_steps
+            self.cla_inc = cla_inc
+            self.last_conflicts = conflicts
+            stats.conflicts = conflicts
+            stats.decisions = self.last_decisions
+            stats.restarts = self.last_restarts
+
+        def explain(conflict: List[int]) -> None:
+            nodes: List[ConflictNode] = []
+            edges: List[Tuple[int, int]] = []
+            seen: Set[int] = set()
+            pending = [abs(lit) for lit in conflict]
+            while pending:
+                v = pending.pop()
+                if v in seen or assigns.get(v) is None:
+                    continue
+                seen.add(v)
+                lit = v if assigns[v] else -v
+                node = imp_graph[v]
+                nodes.append(ConflictNode(lit, node.level, node.reason))
+                for pred in node.preds:
+                    edges.append((-pred, lit))
+                    pending.append(abs(pred))
+            nodes.sort(key=lambda n: (n.level, abs(n.literal)))
+            edges.sort(key=lambda e: (abs(e[1]), abs(e[0])))
+            self._conflict_graph = ConflictGraph(tuple(conflict), tuple(nodes), tuple(edges))
+
+        def unsat(confl: int) -> SATResult:
+            emit(SolverEvent.RESULT, sat=False)
+            explain(cnf.clause(confl))
+            self._log_proof([])
+            core_clause = cnf.clause(confl)
+            changed = True
+            while changed:
+                changed = False
+                for lit in list(core_clause):
                     v = abs(lit)
-                    assigns[v] = None
-                    reason[v] = None
-                    levels[v] = 0
-                    imp_graph[v] = Implication()
-                    active_vars.add(v)
-                    push_var(v)
-                queue.clear()
+                    rsn = reason[v]
+                    if rsn is not None and lengths[rsn] > 1:
+                        core_clause.remove(lit)
+                        for l in cnf.clause(rsn):
+                            if abs(l) != v and l not in core_clause:
+                                core_clause.append(l)
+                        changed = True
+            save_state()
+            return SATResult(False, {v: False for v in assigns}, core_clause, [])
+
+        def failed(lit: int) -> SATResult:
+            """UNSAT because assumption *lit* is already false: collect the
+            assumptions whose propagation forced that."""
+            emit(SolverEvent.RESULT, sat=False)
+            explain([lit])
+            core = [lit]
+            seen = {abs(lit)}
+            for t in reversed(trail):
+                v = abs(t)
+                if v not in seen or levels[v] == 0:
+                    continue
+                rsn = reason[v]
+                if rsn is None:
+                    if t not in core:
+                        core.append(t)
+                else:
+                    seen.update(abs(l) for l in cnf.clause(rsn))
+            save_state()
+            return SATResult(False, {v: False for v in assigns}, [-l for l in core], core)
+
+        def give_up() -> SATResult:
+            emit(SolverEvent.RESULT)
+            backtrack(0)
+            save_state()
+            return SATResult(False, {}, None, None, unknown=True)
+
+        def backtrack(level: int) -> None:
+            if trail.level <= level:
+                return
+            for lit in trail.backjump(level):
+                v = abs(lit)
+                assigns[v] = None
+                reason[v] = None
+                levels[v] = 0
+                imp_graph[v] = Implication()
+                active_vars.add(v)
+                push_var(v)
+            queue.clear()
 
         conflicts = 0
+        budget = self.conflict_budget
+        restart_base = self.restart_base
         restart_count = 1
-        restart_limit = luby(restart_count) * 100
+        restart_limit = luby(restart_count) * restart_base
+        since_restart = 0
+
+        # Level-0 preprocessing: assert the unit clauses and propagate their
+        # consequences before the first decision.  A conflict here means the
+        # instance is UNSAT without any search.  Assumptions are decided
+        # afterwards, one level each, ahead of the free variables.
+        for i, n in enumerate(lengths):
+            if n == 1:
+                enqueue(lits[starts[i]], i)
+            elif n == 0 and i not in cnf._removed:
+                conflicts += 1
+                emit(SolverEvent.CONFLICT)
+                return unsat(i)
 
+        confl = propagate()
+        if confl is not None:
+            conflicts += 1
+            emit(SolverEvent.CONFLICT)
+            return unsat(confl)
+        if self.fixed:
+            for lit in usable_fixed():
+                enqueue(lit, None)
+        if self.pure_literals:
+            eliminate_pure_literals()
+
+        steps = 0
         while True:
+            steps += 1
+            if steps % 64 == 1:
+                if deadline is not None:
+                    deadline.check("dependency resolution")
+                emit(SolverEvent.LEVEL)
             confl = propagate()
             if confl is not None:
                 conflicts += 1
+                emit(SolverEvent.CONFLICT)
                 if current_level() == 0:
-                    core_clause = cnf.clauses[confl][:]
-                    changed = True
-                    while changed:
-                        changed = False
-                        for lit in list(core_clause):
-                            v = abs(lit)
-                            rsn = reason[v]
-                            if rsn is not None and len(cnf.clauses[rsn]) > 1:
-                                core_clause.remove(lit)
-                                for l in cnf.clauses[rsn]:
-                                    if abs(l) != v and l not in core_clause:
-                                        core_clause.append(l)
-                                changed = True
-                    self.var_inc = var_inc
-                    self.var_decay_steps = var_decay_steps
-                    self.cla_inc = cla_inc
-                    self.last_conflicts = conflicts
-                    return SATResult(False, {v: False for v in assigns}, core_clause)
+                    return unsat(confl)
+                if budget is not None and conflicts > budget:
+                    return give_up()
                 learnt, back_lvl = analyze(confl)
                 lbd = len({levels[abs(l)] for l in learnt})
-                ci = cnf.add_clause(learnt, learnt=True, lbd=lbd)
+                ci = cnf.add_clause_unchecked(learnt, learnt=True, lbd=lbd)
+                stats.learnt += 1
+                self._log_proof(learnt)
                 bump_clause(ci)
                 backtrack(back_lvl)
                 enqueue(learnt[0], ci)
@@ -427,25 +1439,37 @@ class CDCLSolver:
                 decay_var_activity()
                 if len(cnf.learnts) > max_learnts:
                     reduce_db()
-                if conflicts >= restart_limit:
+                since_restart += 1
+                if restart_base and since_restart >= restart_limit:
+                    # Undo every decision but keep the learnt clauses.
+                    since_restart = 0
                     restart_count += 1
-                    restart_limit = luby(restart_count) * 100
+                    restart_limit = luby(restart_count) * restart_base
                     self.last_restarts += 1
                     backtrack(0)
+                    emit(SolverEvent.RESTART)
             else:
-                v = pick_branch_var()
-                if v == 0:
-                    self.var_inc = var_inc
-                    self.var_decay_steps = var_decay_steps
-                    self.cla_inc = cla_inc
-                    self.last_conflicts = conflicts
+                if current_level() < len(assumptions):
+                    lit = assumptions[current_level()]
+                    if value(lit) is False:
+                        return failed(lit)
+                    # An assumption already implied still opens a (dummy)
+                    # level so level n always corresponds to assumption n.
+                    trail.new_level()
+                    stats.max_level = max(stats.max_level, trail.level)
+                    enqueue(lit, None)
+                    continue
+                lit = heuristic.choose(decision_state)
+                if lit is None:
+                    save_state()
                     final = {var: (assigns[var] if assigns[var] is not None else False) for var in assigns}
+                    emit(SolverEvent.RESULT, sat=True)
                     return SATResult(True, final, None)
-                trail_lim.append(len(trail))
-                phase = saved_phase.get(v)
-                if phase is None:
-                    lit = -v if v in self.prefer_false and v not in self.prefer_true else v
-                else:
-                    lit = v if phase else -v
+                if assigns.get(abs(lit), True) is not None:
+                    raise SolverError(f"heuristic chose {lit}, which is not an unassigned variable")
+                self.last_decisions += 1
+                trail.new_level()
+                stats.max_level = max(stats.max_level, trail.level)
                 enqueue(lit, None)
+                emit(SolverEvent.DECISION, literal=lit)
 
@@ -0,0 +1,122 @@
+"""Retry policy shared by repository network operations.
+
+Transient failures (timeouts, refused connections, HTTP 5xx, 408 and 429) are
+retried with exponential backoff and jitter.  Anything else — a 404, a
+checksum mismatch, a missing local file — fails on the first attempt.
+"""
+
+from __future__ import annotations
+
+import random
+import socket
+import time
+import urllib.error
+from dataclasses import dataclass
+from typing import Any, Callable, Iterator, Optional, TypeVar
+
+T = TypeVar("T")
+
+_RETRYABLE_HTTP = {408, 429}
+
+
+class ChecksumError(RuntimeError):
+    """Raised when downloaded content does not match its expected digest."""
+
+
+def _causes(exc: BaseException) -> Iterator[BaseException]:
+    seen = set()
+    current: Optional[BaseException] = exc
+    while current is not None and id(current) not in seen:
+        seen.add(id(current))
+        yield current
+        current = current.__cause__ or current.__context__
+
+
+def is_retryable(exc: BaseException) -> bool:
+    """Return ``True`` when *exc* (or an exception it wraps) is transient."""
+
+    for err in _causes(exc):
+        if isinstance(err, ChecksumError):
+            return False
+        if isinstance(err, urllib.error.HTTPError):
+            return err.code >= 500 or err.code in _RETRYABLE_HTTP
+        if isinstance(err, (socket.timeout, TimeoutError, ConnectionError)):
+            return True
+        if isinstance(err, urllib.error.URLError):
+            reason = err.reason
+            return isinstance(reason, (socket.timeout, TimeoutError, ConnectionError, socket.gaierror))
+    return False
+
+
+@dataclass(frozen=True)
+class RetryPolicy:
+    """Exponential backoff: ``base_delay * 2**(attempt-1)`` capped at ``max_delay``.
+
+    ``jitter`` is the fraction of each delay that is randomised, so ``0.25``
+    waits anywhere between 75% and 125% of the nominal delay.
+    """
+
+    max_attempts: int = 3
+    base_delay: float = 0.5
+    max_delay: float = 10.0
+    jitter: float = 0.25
+
+    def __post_init__(self) -> None:
+        object.__setattr__(self, "max_attempts", max(1, int(self.max_attempts)))
+        object.__setattr__(self, "base_delay", max(0.0, float(self.base_delay)))
+        object.__setattr__(self, "max_delay", max(0.0, float(self.max_delay)))
+        object.__setattr__(self, "jitter", min(1.0, max(0.0, float(self.jitter))))
+
+    @classmethod
+    def from_config(cls, config: Any = None) -> "RetryPolicy":
+        if config is None:
+            from . import config as config_module
+
+            config = config_module
+        return cls(
+            max_attempts=getattr(config, "RETRY_MAX_ATTEMPTS", cls.max_attempts),
+            base_delay=getattr(config, "RETRY_BASE_DELAY", cls.base_delay),
+            max_delay=getattr(config, "RETRY_MAX_DELAY", cls.max_delay),
+            jitter=getattr(config, "RETRY_JITTER", cls.jitter),
+        )
+
+    def delay(self, attempt: int, rng: Callable[[], float] = random.random) -> float:
+        """Delay to wait after failed attempt number *attempt* (1-based)."""
+
+        nominal = min(self.max_delay, self.base_delay * (2 ** max(0, attempt - 1)))
+        if not self.jitter:
+            return nominal
+        spread = nominal * self.jitter
+        return max(0.0, nominal - spread + 2 * spread * rng())
+
+    def call(
+        self,
+        func: Callable[..., T],
+        *args: Any,
+        sleep: Optional[Callable[[float], None]] = None,
+        rng: Callable[[], float] = random.random,
+        retryable: Callable[[BaseException], bool] = is_retryable,
+        on_retry: Optional[Callable[[int, float, BaseException], None]] = None,
+        **kwargs: Any,
+    ) -> T:
+        """Call ``func(*args, **kwargs)``, retrying transient failures.
+
+        *sleep* defaults to :func:`time.sleep`; tests pass a fake clock.
+        """
+
+        sleep = sleep or time.sleep
+        attempt = 1
+        while True:
+            try:
+                return func(*args, **kwargs)
+            except Exception as exc:
+                if attempt >= self.max_attempts or not retryable(exc):
+                    raise
+                wait = self.delay(attempt, rng)
+                if on_retry is not None:
+                    on_retry(attempt, wait, exc)
+                sleep(wait)
+                attempt += 1
+
+
+__all__ = ["ChecksumError", "RetryPolicy", "is_retryable"]
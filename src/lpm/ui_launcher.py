@@ -3,7 +3,9 @@
 from __future__ import annotations
 
 import importlib.util
+import shlex
 import sys
+from typing import List, Optional, TextIO
 
 
 def _qt_available() -> bool:
@@ -12,13 +14,74 @@ def _qt_available() -> bool:
     return importlib.util.find_spec("PySide6") is not None
 
 
-def main() -> None:
-    """Launch the graphical frontend."""
+def run_text_loop(
+    backend=None,
+    stdin: TextIO = sys.stdin,
+    stdout: TextIO = sys.stdout,
+    *,
+    timeout: Optional[float] = None,
+) -> int:
+    """Read CLI actions line by line and dispatch them through the UI backend.
+
+    This is the text-mode counterpart of the graphical frontend: each line
+    (``install foo``, ``remove bar`` ...) goes through
+    :meth:`ui.backend.LPMBackend.dispatch` exactly as a button press would.
+    ``quit`` or end of input stops the loop.  With *timeout* each action is
+    reported as blocked once it runs longer than that many seconds.  Returns
+    the number of actions that did not succeed.
+    """
+
+    if backend is None:
+        from ui.backend import LPMBackend
+
+        backend = LPMBackend(timeout=timeout)
+    failures = 0
+    while True:
+        stdout.write("lpm> ")
+        stdout.flush()
+        line = stdin.readline()
+        if not line:
+            stdout.write("\n")
+            break
+        try:
+            args = shlex.split(line)
+        except ValueError as exc:
+            stdout.write(f"[invalid] {exc}\n")
+            failures += 1
+            continue
+        if not args:
+            continue
+        if args[0] in {"quit", "exit"}:
+            break
+        result = backend.dispatch(args)
+        stdout.write(f"[{result.status.value}] {result.command}: {result.message}\n")
+        failures += not result.ok
+    return failures
+
+
+def main(argv: Optional[List[str]] = None) -> None:
+    """Launch the graphical frontend, or the text loop with ``--text``.
+
+    ``--timeout SECS`` bounds every action run from the text loop.
+    """
+
+    argv = sys.argv[1:] if argv is None else argv
+    if "--text" in argv:
+        timeout = None
+        if "--timeout" in argv:
+            idx = argv.index("--timeout")
+            try:
+                timeout = float(argv[idx + 1])
+            except (IndexError, ValueError):
+                sys.stderr.write("--timeout needs a number of seconds\n")
+                raise SystemExit(2)
+        raise SystemExit(1 if run_text_loop(timeout=timeout) else 0)
 
     if not _qt_available():
         sys.stderr.write(
             "The LPM graphical interface requires PySide6.\n"
             "Install the dependencies listed in requirements-ui.txt, "
+            "run `lpm-ui --text` for the text-mode frontend, "
             "or use the command line client via `python -m lpm`.\n"
         )
         raise SystemExit(1)
@@ -0,0 +1,164 @@
+"""Detached signature verification against a keyring of trusted public keys.
+
+Keys are PEM files, normally the ``*.pem`` files in :data:`lpm.config.TRUST_DIR`.
+Ed25519 keys verify raw 64-byte signatures over the whole payload (what
+``openssl pkeyutl -sign -rawin`` produces); RSA and EC keys verify
+``openssl dgst -sha256 -sign`` signatures.  Both go through the ``openssl``
+command line, so no Python crypto library is needed.
+"""
+
+from __future__ import annotations
+
+import base64
+import binascii
+import hashlib
+import logging
+import re
+import subprocess
+import tempfile
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Iterable, Iterator, List, Tuple, Union
+
+ED25519 = "ed25519"
+OTHER = "openssl"
+
+# DER prefixes of an Ed25519 SubjectPublicKeyInfo and PKCS#8 private key.
+_ED25519_SPKI = bytes.fromhex("302a300506032b6570032100")
+_ED25519_PKCS8 = bytes.fromhex("302e020100300506032b657004220420")
+_ED25519_SIG_LEN = 64
+
+logger = logging.getLogger(__name__)
+
+_PEM_RE = re.compile(rb"-----BEGIN ([A-Z ]+)-----(.*?)-----END \1-----", re.DOTALL)
+
+
+class VerifyError(RuntimeError):
+    """Raised when a signature is missing, malformed or not made by a trusted key."""
+
+
+def _pem_der(text: bytes, path: Union[str, Path]) -> Tuple[str, bytes]:
+    match = _PEM_RE.search(text)
+    if not match:
+        raise VerifyError(f"{path}: not a PEM file")
+    try:
+        return match.group(1).decode("ascii"), base64.b64decode(b"".join(match.group(2).split()), validate=True)
+    except binascii.Error as exc:
+        raise VerifyError(f"{path}: corrupt PEM data") from exc
+
+
+@dataclass(frozen=True)
+class PublicKey:
+    """A trusted public key; ``fingerprint`` is a short SHA-256 of its DER form."""
+
+    path: Path
+    algorithm: str
+    fingerprint: str
+
+    @classmethod
+    def load(cls, path: Union[str, Path]) -> "PublicKey":
+        path = Path(path)
+        try:
+            text = path.read_bytes()
+        except OSError as exc:
+            raise VerifyError(f"cannot read public key {path}: {exc.strerror}") from exc
+        label, der = _pem_der(text, path)
+        if label != "PUBLIC KEY":
+            raise VerifyError(f"{path}: expected a PUBLIC KEY, found {label}")
+        algorithm = ED25519 if der.startswith(_ED25519_SPKI) and len(der) == len(_ED25519_SPKI) + 32 else OTHER
+        return cls(path, algorithm, hashlib.sha256(der).hexdigest()[:16])
+
+
+def _openssl(args: List[str]) -> bool:
+    try:
+        subprocess.run(["openssl", *args], check=True, stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+    except subprocess.CalledProcessError:
+        return False
+    except OSError as exc:
+        raise VerifyError(f"cannot run openssl: {exc}") from exc
+    return True
+
+
+def verify_detached(data: bytes, signature: bytes, key: PublicKey) -> None:
+    """Raise :class:`VerifyError` unless *signature* is *key*'s signature of *data*."""
+
+    if key.algorithm == ED25519 and len(signature) != _ED25519_SIG_LEN:
+        raise VerifyError(f"malformed ed25519 signature ({len(signature)} bytes)")
+    with tempfile.TemporaryDirectory(prefix="lpm-verify-") as tmp:
+        blob = Path(tmp) / "data"
+        sig = Path(tmp) / "data.sig"
+        blob.write_bytes(data)
+        sig.write_bytes(signature)
+        if key.algorithm == ED25519:
+            args = ["pkeyutl", "-verify", "-pubin", "-inkey", str(key.path), "-rawin", "-in", str(blob), "-sigfile", str(sig)]
+        else:
+            args = ["dgst", "-sha256", "-verify", str(key.path), "-signature", str(sig), str(blob)]
+        if not _openssl(args):
+            raise VerifyError(f"signature does not match key {key.fingerprint} ({key.path.name})")
+
+
+class Keyring:
+    """The set of public keys whose signatures are trusted."""
+
+    def __init__(self, keys: Iterable[PublicKey] = ()) -> None:
+        self.keys: Tuple[PublicKey, ...] = tuple(keys)
+
+    @classmethod
+    def load(cls, directory: Union[str, Path]) -> "Keyring":
+        """Load every ``*.pem`` in *directory*; a missing directory gives an empty keyring.
+
+        A file that is not a readable public key is skipped with a warning,
+        so one stray file does not revoke trust in the others.
+        """
+
+        directory = Path(directory)
+        paths = sorted(directory.glob("*.pem")) if directory.is_dir() else []
+        keys: List[PublicKey] = []
+        for path in paths:
+            try:
+                keys.append(PublicKey.load(path))
+            except VerifyError as exc:
+                logger.warning("skipping unusable trusted key: %s", exc)
+        return cls(keys)
+
+    def __iter__(self) -> Iterator[PublicKey]:
+        return iter(self.keys)
+
+    def __len__(self) -> int:
+        return len(self.keys)
+
+    def verify(self, data: bytes, signature: bytes) -> PublicKey:
+        """Return the trusted key that signed *data*, or raise :class:`VerifyError`."""
+
+        if not self.keys:
+            raise VerifyError("no trusted public keys")
+        for key in self.keys:
+            try:
+                verify_detached(data, signature, key)
+            except VerifyError:
+                continue
+            return key
+        raise VerifyError("signature is not from a trusted key")
+
+
+def sign_command(key: Union[str, Path], data: Union[str, Path], sig: Union[str, Path]) -> List[str]:
+    """Return the ``openssl`` command that writes *key*'s signature of *data* to *sig*."""
+
+    try:
+        _label, der = _pem_der(Path(key).read_bytes(), key)
+        ed25519 = der.startswith(_ED25519_PKCS8)
+    except (OSError, VerifyError):
+        ed25519 = False
+    if ed25519:
+        return ["openssl", "pkeyutl", "-sign", "-inkey", str(key), "-rawin", "-in", str(data), "-out", str(sig)]
+    return ["openssl", "dgst", "-sha256", "-sign", str(key), "-out", str(sig), str(data)]
+
+
+__all__ = [
+    "ED25519",
+    "Keyring",
+    "PublicKey",
+    "VerifyError",
+    "sign_command",
+    "verify_detached",
+]
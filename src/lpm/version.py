@@ -0,0 +1,178 @@
+"""Package versions and the dependency constraints that match them.
+
+A version string is ``[EPOCH:]MAIN[~PRE|-PRE|-RELEASE][+BUILD]``:
+
+* ``EPOCH`` is an integer that overrides everything else (default ``0``).
+* ``MAIN`` is split into digit and letter runs, ignoring the dots between
+  them, much like ``rpmvercmp``: digits compare numerically, a number sorts
+  after letters in the same position (``1.0a < 1.0.1``), and trailing zeros
+  are insignificant (``1.2 == 1.2.0``).
+* ``~PRE``, or ``-PRE`` starting with a letter, marks a pre-release which
+  sorts before the plain version.  Identifiers follow semver precedence:
+  numeric below alphanumeric, and a shorter list below a longer one it
+  prefixes (``1.0-rc < 1.0-rc.1 < 1.0``).
+* ``-RELEASE`` starting with a digit is a packaging release that sorts
+  after the plain version (``1.0 < 1.0-1``).
+* ``+BUILD`` metadata is ignored.
+
+Parsing is lenient: any string yields a :class:`Version`, so malformed
+repository data still sorts deterministically instead of failing.
+"""
+
+from __future__ import annotations
+
+import re
+from dataclasses import dataclass
+from functools import total_ordering
+from typing import Tuple
+
+_RUN_RE = re.compile(r"\d+|[A-Za-z]+")
+_EPOCH_RE = re.compile(r"^(\d+):(.*)$")
+
+Token = Tuple[int, object]
+
+
+def _tokens(text: str) -> Tuple[Token, ...]:
+    tokens = [(1, int(run)) if run.isdigit() else (0, run) for run in _RUN_RE.findall(text)]
+    while tokens and tokens[-1] == (1, 0):
+        tokens.pop()
+    return tuple(tokens)
+
+
+def _identifiers(text: str) -> Tuple[Token, ...]:
+    return tuple((0, int(ident)) if ident.isdigit() else (1, ident) for ident in text.split("."))
+
+
+@total_ordering
+@dataclass(frozen=True, eq=False)
+class Version:
+    text: str
+    epoch: int
+    main: Tuple[Token, ...]
+    pre: Tuple[Token, ...]
+    release: Tuple[Token, ...]
+
+    @classmethod
+    def parse(cls, text: str) -> "Version":
+        raw = str(text).strip()
+        rest = raw
+        epoch = 0
+        match = _EPOCH_RE.match(rest)
+        if match:
+            epoch, rest = int(match.group(1)), match.group(2)
+        rest = rest.split("+", 1)[0]
+        pre = ""
+        release = ""
+        if "~" in rest:
+            rest, pre = rest.split("~", 1)
+        elif "-" in rest:
+            rest, suffix = rest.split("-", 1)
+            if suffix[:1].isdigit():
+                release = suffix
+            else:
+                pre = suffix
+        return cls(
+            raw,
+            epoch,
+            _tokens(rest),
+            _identifiers(pre) if pre else (),
+            _tokens(release),
+        )
+
+    @property
+    def is_prerelease(self) -> bool:
+        return bool(self.pre)
+
+    @property
+    def major(self) -> Token:
+        return self.main[0] if self.main else (1, 0)
+
+    def _key(self) -> tuple:
+        # A release without a pre-release tag sorts after all of its pre-releases.
+        pre_key = (0, self.pre) if self.pre else (1, ())
+        return (self.epoch, self.main, pre_key, self.release)
+
+    def __eq__(self, other: object) -> bool:
+        if not isinstance(other, Version):
+            return NotImplemented
+        return self._key() == other._key()
+
+    def __lt__(self, other: "Version") -> bool:
+        if not isinstance(other, Version):
+            return NotImplemented
+        return self._key() < other._key()
+
+    def __hash__(self) -> int:
+        return hash(self._key())
+
+    def __str__(self) -> str:
+        return self.text
+
+
+def compare(a: str, b: str) -> int:
+    """Return -1, 0 or 1 as version string *a* sorts before, equal to or after *b*."""
+
+    va, vb = Version.parse(a), Version.parse(b)
+    return (va > vb) - (va < vb)
+
+
+_OPS = ("==", ">=", "<=", "~=", "!=", ">", "<", "=")
+
+
+@dataclass(frozen=True)
+class Constraint:
+    """A conjunction of ``(operator, version)`` requirements.
+
+    :meth:`parse` accepts ``==``, ``=``, ``!=``, ``>=``, ``<=``, ``>``, ``<``,
+    ``~=`` (same major version and at least the given one), ``X.*`` (short
+    for ``~=X``) and a bare version (``==``).  Comma-separated parts are
+    ANDed, and ``a & b`` intersects two constraints.  An empty constraint or
+    ``*`` matches everything.
+    """
+
+    parts: Tuple[Tuple[str, Version], ...] = ()
+
+    @classmethod
+    def parse(cls, text: str) -> "Constraint":
+        parts = []
+        for piece in (p.strip() for p in str(text or "").split(",")):
+            if not piece or piece == "*":
+                continue
+            if piece.endswith(".*"):
+                op, value = "~=", piece[:-2]
+            else:
+                op = next((o for o in _OPS if piece.startswith(o)), "==")
+                value = piece[len(op):] if piece.startswith(op) else piece
+                if op == "=":
+                    op = "=="
+            parts.append((op, Version.parse(value.strip())))
+        return cls(tuple(parts))
+
+    def __and__(self, other: "Constraint") -> "Constraint":
+        return Constraint(self.parts + other.parts)
+
+    def matches(self, version: "Version | str") -> bool:
+        if not isinstance(version, Version):
+            version = Version.parse(version)
+        for op, want in self.parts:
+            if op == "==" and not version == want:
+                return False
+            if op == "!=" and version == want:
+                return False
+            if op == ">=" and not version >= want:
+                return False
+            if op == "<=" and not version <= want:
+                return False
+            if op == ">" and not version > want:
+                return False
+            if op == "<" and not version < want:
+                return False
+            if op == "~=" and not (version.major == want.major and version >= want):
+                return False
+        return True
+
+    def __str__(self) -> str:
+        return ",".join(f"{op}{version}" for op, version in self.parts) or "*"
+
+
+__all__ = ["Constraint", "Version", "compare"]
@@ -213,6 +213,9 @@ def generate_indexes(result: Optional[MaintainerResult], generator) -> None:
             continue
         index_path = update.repo_dir / "index.json"
         result.staged_after_index.append(index_path)
+        sig_path = index_path.with_suffix(index_path.suffix + ".sig")
+        if sig_path.exists():
+            result.staged_after_index.append(sig_path)
 
 
 def finalize_git(result: Optional[MaintainerResult]) -> None:
@@ -1,18 +1,37 @@
 """High level helpers for the PySide6-based LPM UI."""
 from __future__ import annotations
 
+import contextlib
 import fnmatch
+import io
 import os
 import subprocess
 import sys
 import threading
 import time
 from dataclasses import dataclass
+from enum import Enum
 from pathlib import Path
-from typing import Iterable, List, Mapping, Sequence
+from typing import Iterable, List, Mapping, Optional, Sequence
 
 from lpm import config as lpm_config
-from lpm.app import PkgMeta, Repo, db, list_repos, load_universe, save_repos
+from lpm.app import (
+    EXIT_DEADLINE,
+    PkgMeta,
+    Repo,
+    _privileged_command,
+    build_parser,
+    configured_gate,
+    db,
+    list_repos,
+    load_universe,
+    save_repos,
+)
+from lpm.privileges import PrivilegeGate
+
+
+CANCEL_GRACE = 5.0
+"""Seconds a timed-out CLI gets to roll back after ``SIGTERM`` before it is killed."""
 
 
 @dataclass(frozen=True)
@@ -73,10 +92,46 @@ class Repository:
     decay: float
 
 
+class DispatchStatus(str, Enum):
+    SUCCESS = "success"
+    FAILED = "failed"
+    BLOCKED = "blocked"
+    INVALID = "invalid"
+
+
+@dataclass(frozen=True)
+class DispatchResult:
+    """Outcome of :meth:`LPMBackend.dispatch`.
+
+    ``BLOCKED`` means the privilege gate refused the command before it ran, or
+    that it ran out of time (``message`` is then ``timed out``), and
+    ``INVALID`` that the CLI parser rejected the arguments; ``message`` says
+    why.  Otherwise ``returncode`` and ``output`` come from the CLI run.
+    """
+
+    status: DispatchStatus
+    command: str
+    message: str = ""
+    returncode: Optional[int] = None
+    output: str = ""
+
+    @property
+    def ok(self) -> bool:
+        return self.status is DispatchStatus.SUCCESS
+
+
 class LPMBackend:
     """Facilitates read/write operations for the graphical UI."""
 
-    def __init__(self, universe_ttl: int = 60):
+    def __init__(
+        self,
+        universe_ttl: int = 60,
+        *,
+        gate: PrivilegeGate | None = None,
+        timeout: float | None = None,
+    ):
+        self._gate = gate
+        self._timeout = timeout
         self._lock = threading.Lock()
         self._universe: Mapping[str, List[PkgMeta]] | None = None
         self._universe_timestamp: float = 0.0
@@ -282,14 +337,95 @@ class LPMBackend:
 
     # ------------------------------------------------------------------
     # Mutating operations
-    def run_cli(self, args: Sequence[str], *, root: Path | None = None) -> subprocess.CompletedProcess[str]:
-        """Invoke the CLI in a subprocess and capture its output."""
+    def _cli_command(self, args: Sequence[str]) -> List[str]:
+        return [sys.executable, "-m", "lpm.app", *args]
+
+    def run_cli(
+        self,
+        args: Sequence[str],
+        *,
+        root: Path | None = None,
+        timeout: float | None = None,
+    ) -> subprocess.CompletedProcess[str]:
+        """Invoke the CLI in a subprocess and capture its output.
+
+        When *timeout* expires the CLI is sent ``SIGTERM``, which it treats
+        like Ctrl-C: the running transaction is rolled back at the next safe
+        point.  Only a CLI that ignores this for :data:`CANCEL_GRACE` seconds
+        is killed.  :class:`subprocess.TimeoutExpired` is then raised with
+        whatever output was captured.
+        """
 
-        cmd = [sys.executable, "-m", "lpm.app", *args]
+        cmd = self._cli_command(args)
         env = dict(os.environ)
         if root is not None:
             env.setdefault("LPM_ROOT", str(root))
-        return subprocess.run(cmd, check=False, capture_output=True, text=True, env=env)
+        if timeout is None:
+            return subprocess.run(cmd, check=False, capture_output=True, text=True, env=env)
+        with subprocess.Popen(cmd, stdout=subprocess.PIPE, stderr=subprocess.PIPE, text=True, env=env) as proc:
+            try:
+                stdout, stderr = proc.communicate(timeout=timeout)
+            except subprocess.TimeoutExpired:
+                proc.terminate()
+                try:
+                    stdout, stderr = proc.communicate(timeout=CANCEL_GRACE)
+                except subprocess.TimeoutExpired:
+                    proc.kill()
+                    stdout, stderr = proc.communicate()
+                raise subprocess.TimeoutExpired(cmd, timeout, output=stdout, stderr=stderr) from None
+        return subprocess.CompletedProcess(cmd, proc.returncode, stdout, stderr)
+
+    def dispatch(
+        self,
+        args: Sequence[str],
+        *,
+        root: Path | None = None,
+        timeout: float | None = None,
+    ) -> DispatchResult:
+        """Run a CLI command on behalf of a GUI action and classify the outcome.
+
+        *args* are parsed with the CLI's own parser and privileged commands are
+        checked against the privilege gate first, so a command that could not
+        run even after escalation is reported as blocked without starting it.
+
+        *timeout* (default: the backend's) bounds the run in seconds.  Commands
+        with their own ``--timeout`` get it too so the solver and downloads
+        stop cleanly; a command that runs out of time is reported as blocked
+        with the message ``timed out``.
+        """
+
+        args = list(args)
+        timeout = self._timeout if timeout is None else timeout
+        errors = io.StringIO()
+        try:
+            with contextlib.redirect_stderr(errors):
+                parsed = build_parser().parse_args(args)
+        except SystemExit:
+            message = errors.getvalue().strip().splitlines()
+            return DispatchResult(DispatchStatus.INVALID, " ".join(args), message[-1] if message else "invalid command")
+        command = getattr(parsed, "cmd", None) or ""
+        privileged = _privileged_command(parsed)
+        if privileged is not None:
+            gate = self._gate or configured_gate()
+            reason = gate.preflight(privileged)
+            if reason is not None:
+                return DispatchResult(DispatchStatus.BLOCKED, command, str(reason))
+        if timeout is None:
+            proc = self.run_cli(args, root=root)
+        else:
+            if hasattr(parsed, "timeout") and parsed.timeout is None:
+                args += ["--timeout", f"{timeout:g}"]
+            try:
+                proc = self.run_cli(args, root=root, timeout=timeout)
+            except subprocess.TimeoutExpired as exc:
+                output = (exc.output or "") + (exc.stderr or "")
+                return DispatchResult(DispatchStatus.BLOCKED, command, "timed out", None, output)
+        output = (proc.stdout or "") + (proc.stderr or "")
+        lines = [line for line in output.splitlines() if line.strip()]
+        if timeout is not None and proc.returncode == EXIT_DEADLINE:
+            return DispatchResult(DispatchStatus.BLOCKED, command, "timed out", proc.returncode, output)
+        status = DispatchStatus.SUCCESS if proc.returncode == 0 else DispatchStatus.FAILED
+        return DispatchResult(status, command, lines[-1] if lines else "", proc.returncode, output)
 
     def install(self, names: Iterable[str]) -> subprocess.CompletedProcess[str]:
         return self.run_cli(["install", *names])
@@ -0,0 +1,107 @@
+import dataclasses
+import importlib
+import json
+import shutil
+import sys
+import tarfile
+
+import pytest
+
+
+@pytest.fixture
+def lpm(tmp_path, monkeypatch):
+    """A freshly imported ``lpm`` keeping its state and lock under *tmp_path*."""
+
+    monkeypatch.setenv("LPM_STATE_DIR", str(tmp_path / "state"))
+    monkeypatch.setenv("LPM_LOCK_PATH", str(tmp_path / "state" / "lock"))
+    for mod in ["lpm", "src.config"]:
+        if mod in sys.modules:
+            del sys.modules[mod]
+    return importlib.import_module("lpm")
+
+
+@pytest.fixture
+def lpm_app(lpm, tmp_path, monkeypatch):
+    """``lpm.app`` ready for ``main()``: an ``ARCH=noarch`` config and no state setup."""
+
+    from lpm import app as lpm_app
+
+    conf = tmp_path / "lpm.conf"
+    conf.write_text("ARCH=noarch\n", encoding="utf-8")
+    monkeypatch.setattr(lpm_app, "CONF_FILE", conf, raising=False)
+    monkeypatch.setattr(lpm, "CONF_FILE", conf, raising=False)
+    monkeypatch.setattr(lpm_app, "initialize_state", lambda: None)
+    return lpm_app
+
+
+@pytest.fixture
+def make_pkg(lpm, tmp_path):
+    """Build ``.zst`` package archives.
+
+    ``make_pkg(name, version, files)`` stages *files* (relative path to text or
+    bytes) with the permissions in *modes*, and *links* (relative path to
+    target), and returns ``(meta, archive)``.
+    Other keywords go to :class:`PkgMeta`; ``config_flags=False`` drops the
+    ``config`` flags from the manifest, as archives built before them have it.
+    """
+
+    def build(name, version, files, *, arch="noarch", links=None, modes=None, config_flags=True, **fields):
+        staged = tmp_path / f"stage-{name}-{version}"
+        staged.mkdir()
+        for rel, content in files.items():
+            target = staged / rel
+            target.parent.mkdir(parents=True, exist_ok=True)
+            if isinstance(content, bytes):
+                target.write_bytes(content)
+            else:
+                target.write_text(content)
+            if modes and rel in modes:
+                target.chmod(modes[rel])
+        for rel, dest in (links or {}).items():
+            link = staged / rel
+            link.parent.mkdir(parents=True, exist_ok=True)
+            link.symlink_to(dest)
+
+        manifest = lpm.collect_manifest(staged)
+        if not config_flags:
+            for entry in manifest:
+                entry.pop("config", None)
+        meta = lpm.PkgMeta(name=name, version=version, release="1", arch=arch, **fields)
+        (staged / ".lpm-meta.json").write_text(json.dumps(dataclasses.asdict(meta)))
+        (staged / ".lpm-manifest.json").write_text(json.dumps(manifest))
+
+        out = tmp_path / f"{name}-{version}-1.{arch}.zst"
+        with out.open("wb") as f:
+            with lpm.zstd.ZstdCompressor().stream_writer(f) as compressor:
+                with tarfile.open(fileobj=compressor, mode="w|") as tf:
+                    for p in staged.iterdir():
+                        tf.add(p, arcname=p.name)
+        shutil.rmtree(staged)
+        return meta, out
+
+    return build
+
+
+class RecordingProgress:
+    """:class:`lpm.progress.Progress` that records every call."""
+
+    def __init__(self):
+        self.totals = []
+        self.increments = []
+        self.finished = False
+
+    def start(self, total):
+        self.totals.append(total)
+
+    def inc(self, delta):
+        self.increments.append(delta)
+
+    def finish(self):
+        self.finished = True
+
+
+@pytest.fixture
+def recording_progress():
+    """Return :class:`RecordingProgress`; call it for each recorder a test needs."""
+
+    return RecordingProgress
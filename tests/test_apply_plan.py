@@ -0,0 +1,456 @@
+import json
+import shutil
+import sqlite3
+
+import pytest
+
+
+def _installed_rows(tmp_path):
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        return dict(conn.execute("SELECT name, version FROM installed"))
+    finally:
+        conn.close()
+
+
+def test_apply_plan_failure_on_last_package_rolls_back_whole_plan(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+
+    hook_dir = tmp_path / "hooks"
+    hook_dir.mkdir()
+    hook = hook_dir / "post_install"
+    hook.write_text('#!/bin/sh\n[ "$LPM_PKG" != "gamma" ]\n')
+    hook.chmod(0o755)
+    monkeypatch.setattr(lpm, "HOOK_DIR", hook_dir)
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    _, alpha_v1 = make_pkg("alpha", "1.0", {"usr/share/alpha/data": "v1\n"})
+    lpm.installpkg(alpha_v1, root=root, dry_run=False, verify=False, explicit=True)
+    assert (root / "usr/share/alpha/data").read_text() == "v1\n"
+
+    jobs = [
+        make_pkg("alpha", "2.0", {"usr/share/alpha/data": "v2\n"}),
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+
+    with pytest.raises(lpm.PlanApplyError) as excinfo:
+        lpm.apply_plan(jobs, root, verify=False, explicit={"gamma"})
+
+    assert excinfo.value.package == "gamma"
+    assert (root / "usr/share/alpha/data").read_text() == "v1\n"
+    assert not (root / "usr/lib/beta").exists()
+    assert not (root / "usr/bin").exists()
+    assert _installed_rows(tmp_path) == {"alpha": "1.0"}
+
+
+def test_apply_plan_commits_when_every_package_succeeds(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    jobs = [
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+
+    installed = lpm.apply_plan(jobs, root, verify=False)
+
+    assert [meta.name for meta in installed] == ["beta", "gamma"]
+    assert (root / "usr/bin/gamma").read_text() == "gamma\n"
+    assert _installed_rows(tmp_path) == {"beta": "1.0", "gamma": "1.0"}
+
+
+def test_apply_plan_reports_one_increment_per_package(lpm, make_pkg, tmp_path, monkeypatch, recording_progress):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    jobs = [
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+    progress = recording_progress()
+
+    lpm.apply_plan(jobs, root, verify=False, progress=progress)
+
+    assert progress.totals == [2]
+    assert sum(progress.increments) == 2
+    assert progress.finished
+
+
+def _installed_manifest(tmp_path, name):
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        row = conn.execute("SELECT manifest FROM installed WHERE name=?", (name,)).fetchone()
+    finally:
+        conn.close()
+    return [entry["path"] for entry in json.loads(row[0])]
+
+
+def test_apply_plan_rejects_packages_colliding_on_same_path(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    _, foo = make_pkg("foo", "1.0", {"usr/bin/foo": "foo\n"})
+    lpm.installpkg(foo, root=root, dry_run=False, verify=False, explicit=True)
+
+    jobs = [make_pkg("bar", "1.0", {"usr/bin/foo": "bar\n"})]
+    with pytest.raises(lpm.FileConflict) as excinfo:
+        lpm.apply_plan(jobs, root, verify=False)
+
+    assert excinfo.value.path == "/usr/bin/foo"
+    assert excinfo.value.owner == "foo"
+    assert excinfo.value.package == "bar"
+    assert (root / "usr/bin/foo").read_text() == "foo\n"
+    assert _installed_rows(tmp_path) == {"foo": "1.0"}
+
+    jobs = [
+        make_pkg("baz", "1.0", {"usr/bin/baz": "baz\n"}),
+        make_pkg("qux", "1.0", {"usr/bin/baz": "qux\n"}),
+    ]
+    with pytest.raises(lpm.FileConflict) as excinfo:
+        lpm.apply_plan(jobs, root, verify=False)
+
+    assert excinfo.value.owner == "baz"
+    assert not (root / "usr/bin/baz").exists()
+
+
+def test_apply_plan_allows_replacing_package_to_take_over_paths(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    _, foo = make_pkg(
+        "foo", "1.0", {"usr/bin/foo": "foo\n", "usr/share/doc/foo": "doc\n"}
+    )
+    lpm.installpkg(foo, root=root, dry_run=False, verify=False, explicit=True)
+
+    jobs = [
+        make_pkg(
+            "foo-ng", "2.0", {"usr/bin/foo": "foo-ng\n"}, obsoletes=["foo"]
+        )
+    ]
+    lpm.apply_plan(jobs, root, verify=False)
+
+    assert (root / "usr/bin/foo").read_text() == "foo-ng\n"
+    assert _installed_manifest(tmp_path, "foo") == ["/usr/share/doc/foo"]
+    assert _installed_manifest(tmp_path, "foo-ng") == ["/usr/bin/foo"]
+
+
+class _Ticker:
+    """Clock that advances one second per reading."""
+
+    def __init__(self):
+        self.now = 0.0
+
+    def __call__(self):
+        self.now += 1.0
+        return self.now
+
+
+def test_short_deadline_aborts_resolution_and_leaves_database_unchanged(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    _, foo = make_pkg("foo", "1.0", {"usr/bin/foo": "foo\n"})
+    lpm.installpkg(foo, root=root, dry_run=False, verify=False, explicit=True)
+
+    universe = lpm.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for version in ("1.0", "2.0"):
+        lpm.register_universe_candidate(universe, lpm.PkgMeta(name="foo", version=version))
+
+    clock = _Ticker()
+    deadline = lpm.Deadline(expires_at=1.5, clock=clock)
+    with pytest.raises(lpm.DeadlineExceeded) as excinfo:
+        lpm.solve(["foo"], universe, deadline=deadline)
+
+    assert excinfo.value.stage == "dependency resolution"
+    assert clock.now == 2.0
+    assert _installed_rows(tmp_path) == {"foo": "1.0"}
+
+
+def test_deadline_passing_mid_apply_rolls_back(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    jobs = [
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+
+    from lpm import deadline as deadline_mod
+
+    with deadline_mod.active(lpm.Deadline(expires_at=1.5, clock=_Ticker())):
+        with pytest.raises(lpm.PlanApplyError) as excinfo:
+            lpm.apply_plan(jobs, root, verify=False)
+
+    assert excinfo.value.package == "gamma"
+    assert "deadline exceeded" in str(excinfo.value)
+    assert not (root / "usr/lib/beta").exists()
+    assert _installed_rows(tmp_path) == {}
+
+
+def _streaming_fetcher(built, cache, present):
+    import threading
+
+    lock = threading.Lock()
+
+    def fetch(pkg):
+        dst = cache / built[pkg.name].name
+        shutil.copy2(built[pkg.name], dst)
+        with lock:
+            present.append(len(list(cache.iterdir())))
+        return dst
+
+    return fetch
+
+
+def test_streaming_apply_keeps_at_most_window_archives(lpm, make_pkg, tmp_path, monkeypatch):
+    from lpm import app as lpm_app
+
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    names = ["alpha", "beta", "gamma", "delta", "epsilon"]
+    built = {}
+    for name in names:
+        meta, blob = make_pkg(name, "1.0", {f"usr/share/{name}/data": f"{name}\n"})
+        built[name] = blob
+    cache = tmp_path / "cache"
+    cache.mkdir()
+    monkeypatch.setattr(lpm_app, "CACHE_DIR", cache)
+    monkeypatch.setattr(lpm_app._config, "CACHE_MAX_SIZE", 1)
+    present = []
+
+    installed = lpm.apply_plan_streaming(
+        [lpm.PkgMeta(name=name, version="1.0") for name in names],
+        root,
+        window=2,
+        fetch=_streaming_fetcher(built, cache, present),
+        verify=False,
+    )
+
+    assert [meta.name for meta in installed] == names
+    assert len(present) == len(names)
+    assert max(present) <= 2
+    assert list(cache.iterdir()) == []
+    assert _installed_rows(tmp_path) == {name: "1.0" for name in names}
+
+
+def test_streaming_apply_leaves_archives_cached_without_a_size_limit(lpm, make_pkg, tmp_path, monkeypatch):
+    from lpm import app as lpm_app
+
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+    built = {
+        name: make_pkg(name, "1.0", {f"usr/share/{name}/data": f"{name}\n"})[1]
+        for name in ("alpha", "beta", "gamma")
+    }
+    cache = tmp_path / "cache"
+    cache.mkdir()
+    monkeypatch.setattr(lpm_app, "CACHE_DIR", cache)
+    monkeypatch.setattr(lpm_app._config, "CACHE_MAX_SIZE", 0)
+
+    lpm.apply_plan_streaming(
+        [lpm.PkgMeta(name=name, version="1.0") for name in built],
+        root,
+        window=1,
+        fetch=_streaming_fetcher(built, cache, []),
+        verify=False,
+    )
+
+    assert sorted(p.name for p in cache.iterdir()) == sorted(blob.name for blob in built.values())
+
+
+def test_streaming_apply_rolls_back_on_download_failure(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    built = {
+        "beta": make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"})[1],
+        "gamma": make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"})[1],
+    }
+    cache = tmp_path / "cache"
+    cache.mkdir()
+    fetch = _streaming_fetcher(built, cache, [])
+
+    def flaky(pkg):
+        if pkg.name == "gamma":
+            raise RuntimeError("connection reset")
+        return fetch(pkg)
+
+    with pytest.raises(lpm.PlanApplyError) as excinfo:
+        lpm.apply_plan_streaming(
+            [lpm.PkgMeta(name="beta", version="1.0"), lpm.PkgMeta(name="gamma", version="1.0")],
+            root,
+            window=1,
+            fetch=flaky,
+            verify=False,
+        )
+
+    assert excinfo.value.package == "gamma"
+    assert "connection reset" in str(excinfo.value)
+    assert not (root / "usr/lib/beta").exists()
+    assert _installed_rows(tmp_path) == {}
+
+
+def test_cancellation_before_commit_rolls_back(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    from lpm import app as lpm_app
+    from lpm import cancel
+
+    installpkg = lpm_app.installpkg
+
+    def interrupted_after_install(*args, **kwargs):
+        meta = installpkg(*args, **kwargs)
+        if meta.name == "gamma":
+            cancel.request()
+        return meta
+
+    monkeypatch.setattr(lpm_app, "installpkg", interrupted_after_install)
+    jobs = [
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+
+    try:
+        with pytest.raises(lpm.UserAborted) as excinfo:
+            lpm.apply_plan(jobs, root, verify=False)
+    finally:
+        cancel.clear()
+
+    assert excinfo.value.stage == "commit"
+    assert not (root / "usr/lib/beta").exists()
+    assert not (root / "usr/bin/gamma").exists()
+    assert _installed_rows(tmp_path) == {}
+
+    from lpm.locking import global_transaction_lock
+
+    with global_transaction_lock():
+        pass
+
+
+def test_install_to_roots_keeps_each_root_independent(lpm, make_pkg, tmp_path, monkeypatch):
+    good, bad = tmp_path / "good", tmp_path / "bad"
+
+    hook_dir = tmp_path / "hooks"
+    hook_dir.mkdir()
+    hook = hook_dir / "post_install"
+    hook.write_text(f'#!/bin/sh\n[ "$LPM_ROOT" != "{bad}" ]\n')
+    hook.chmod(0o755)
+    monkeypatch.setattr(lpm, "HOOK_DIR", hook_dir)
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+
+    jobs = [
+        make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"}),
+    ]
+    blobs = {meta.name: (blob, None) for meta, blob in jobs}
+
+    from lpm import app as lpm_app
+
+    monkeypatch.setattr(lpm_app, "fetch_all", lambda pkgs: {p.name: blobs[p.name] for p in pkgs})
+
+    results = lpm.install_to_roots([meta for meta, _ in jobs], [good, bad], verify=False)
+
+    assert results[good] is None
+    assert isinstance(results[bad], lpm.PlanApplyError)
+    assert (good / "usr/bin/gamma").read_text() == "gamma\n"
+    assert (good / "usr/lib/beta/libbeta.txt").read_text() == "beta\n"
+    assert not (bad / "usr/bin").exists()
+    assert not (bad / "usr/lib/beta").exists()
+
+    def rows(root):
+        with lpm.root_state(root):
+            conn = lpm.db()
+            try:
+                return dict(conn.execute("SELECT name, version FROM installed"))
+            finally:
+                conn.close()
+
+    assert rows(good) == {"beta": "1.0", "gamma": "1.0"}
+    assert rows(bad) == {}
+
+
+def test_remove_with_one_root_sees_what_a_multi_root_install_recorded(lpm, lpm_app, make_pkg, tmp_path, monkeypatch):
+    from types import SimpleNamespace
+
+    first, second = tmp_path / "r1", tmp_path / "r2"
+    monkeypatch.setattr(lpm_app, "require_root", lambda cmd: None)
+    monkeypatch.setattr(lpm_app, "create_snapshot", lambda tag, paths: str(tmp_path / "snap.tar"))
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+    meta, blob = make_pkg("gamma", "1.0", {"usr/bin/gamma": "gamma\n"})
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: SimpleNamespace(installed={}))
+    monkeypatch.setattr(lpm_app, "solve", lambda goals, u, **kwargs: [meta])
+    monkeypatch.setattr(lpm_app, "fetch_all", lambda pkgs: {p.name: (blob, None) for p in pkgs})
+
+    lpm.main(["--yes", "install", "gamma", "--root", str(first), "--root", str(second), "--no-verify"])
+    lpm.main(["--yes", "remove", "gamma", "--root", str(first)])
+
+    assert not (first / "usr/bin/gamma").exists()
+    assert (second / "usr/bin/gamma").read_text() == "gamma\n"
+
+    def rows(root):
+        with lpm.root_state(root):
+            conn = lpm.db()
+            try:
+                return dict(conn.execute("SELECT name, version FROM installed"))
+            finally:
+                conn.close()
+
+    assert rows(first) == {}
+    assert rows(second) == {"gamma": "1.0"}
+    assert not (tmp_path / "state" / "state.db").exists()
+
+
+def test_install_to_roots_keeps_each_root_s_install_script(lpm, lpm_app, make_pkg, tmp_path, monkeypatch):
+    from lpm.lpmbuild import wrap_install_body
+
+    first, second = tmp_path / "r1", tmp_path / "r2"
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+    script = wrap_install_body("post_remove() {\n    :\n}\n")
+    v1, blob1 = make_pkg(
+        "scripted",
+        "1.0",
+        {"usr/bin/scripted": "v1\n", ".lpm-install.sh": script},
+        modes={".lpm-install.sh": 0o755},
+    )
+    v2, blob2 = make_pkg("scripted", "2.0", {"usr/bin/scripted": "v2\n"})
+    monkeypatch.setattr(lpm_app, "fetch_all", lambda pkgs: {p.name: (blob1, None) for p in pkgs})
+
+    results = lpm.install_to_roots([v1], [first, second], verify=False)
+    assert results == {first: None, second: None}
+    kept = {root: lpm_app._installed_script_path("scripted", root) for root in (first, second)}
+    assert kept[first] != kept[second]
+    for root, path in kept.items():
+        assert path.is_relative_to(root)
+        assert path.read_text() == script
+
+    # Dropping the script in one root leaves the other root's copy alone.
+    with lpm.root_state(first):
+        lpm.apply_plan([(v2, blob2)], first, verify=False)
+    assert not kept[first].exists()
+    assert kept[second].read_text() == script
@@ -0,0 +1,42 @@
+
+import pytest
+
+
+def _setup(lpm_app, tmp_path, monkeypatch, answer):
+    monkeypatch.setattr(lpm_app, "require_root", lambda cmd: None)
+    monkeypatch.setattr(lpm_app, "create_snapshot", lambda tag, paths: str(tmp_path / "snap.tar"))
+    monkeypatch.setattr(lpm_app, "ASSUME_YES", False)
+
+    calls = {"asked": [], "removed": []}
+
+    def fake_confirm(question, default="n"):
+        calls["asked"].append(question)
+        return answer
+
+    monkeypatch.setattr(lpm_app, "confirm", fake_confirm)
+    monkeypatch.setattr(lpm_app, "do_remove", lambda names, root, dry, force=False: calls["removed"].append(names))
+    return calls
+
+
+def test_yes_skips_the_confirmer(lpm, lpm_app, tmp_path, monkeypatch):
+    calls = _setup(lpm_app, tmp_path, monkeypatch, answer=False)
+    lpm.main(["--yes", "remove", "foo"])
+    assert calls == {"asked": [], "removed": [["foo"]]}
+
+    lpm.main(["-y", "remove", "bar"])
+    assert calls["removed"] == [["foo"], ["bar"]]
+    assert calls["asked"] == []
+
+
+def test_declined_remove_is_cancelled(lpm, lpm_app, tmp_path, monkeypatch, capsys):
+    calls = _setup(lpm_app, tmp_path, monkeypatch, answer=False)
+    with pytest.raises(SystemExit) as excinfo:
+        lpm.main(["remove", "foo"])
+    assert excinfo.value.code == 1
+    assert calls["removed"] == []
+    assert "foo" in calls["asked"][0]
+    assert "cancelled" in capsys.readouterr().err
+
+    calls = _setup(lpm_app, tmp_path, monkeypatch, answer=True)
+    lpm.main(["remove", "foo"])
+    assert calls["removed"] == [["foo"]]
@@ -63,3 +63,75 @@ def test_autoremove_removes_unused_dependency(tmp_path, monkeypatch):
     remaining = conn.execute("SELECT name FROM installed").fetchall()
     conn.close()
     assert remaining == []
+
+
+def _installed_names(lpm):
+    conn = lpm.db()
+    names = sorted(r[0] for r in conn.execute("SELECT name FROM installed"))
+    conn.close()
+    return names
+
+
+def test_autoremove_keeps_dependency_still_needed_by_another_package(tmp_path, monkeypatch):
+    lpm = _import_lpm(tmp_path, monkeypatch)
+    monkeypatch.setattr(lpm.shutil, "which", lambda cmd: "/usr/bin/true")
+    root = tmp_path / "root"
+    root.mkdir()
+
+    for name, requires, explicit in (
+        ("libshared", [], False),
+        ("libonly", [], False),
+        ("app", ["libshared >= 1", "libonly"], True),
+        ("tool", ["libshared"], True),
+    ):
+        lpm.installpkg(
+            _make_pkg(lpm, tmp_path, name, requires),
+            root=root,
+            dry_run=False,
+            verify=False,
+            force=True,
+            explicit=explicit,
+        )
+
+    conn = lpm.db()
+    assert lpm.orphaned_packages(lpm.db_installed(conn)) == []
+    conn.close()
+
+    lpm.do_remove(["app"], root=root, dry=False, force=True)
+
+    conn = lpm.db()
+    assert lpm.orphaned_packages(lpm.db_installed(conn)) == ["libonly"]
+    conn.close()
+
+    lpm.autoremove(root=root, dry=True)
+    assert _installed_names(lpm) == ["libonly", "libshared", "tool"]
+
+    lpm.autoremove(root=root, dry=False)
+    assert _installed_names(lpm) == ["libshared", "tool"]
+
+
+def test_orphans_follow_provides_and_alternatives():
+    from src.lpm.app import orphaned_packages
+
+    installed = {
+        "app": {"explicit": True, "requires": ["tls-backend | gnutls", "zlib>=1.2"], "provides": []},
+        "openssl": {"explicit": False, "requires": [], "provides": ["tls-backend=3"]},
+        "zlib": {"explicit": False, "requires": ["zlib-data"], "provides": []},
+        "zlib-data": {"explicit": False, "requires": [], "provides": []},
+        "leftover": {"explicit": False, "requires": ["leftover-dep"], "provides": []},
+        "leftover-dep": {"explicit": False, "requires": [], "provides": []},
+    }
+
+    assert orphaned_packages(installed) == ["leftover", "leftover-dep"]
+
+
+def test_orphans_keep_the_name_of_an_unparseable_requirement():
+    from src.lpm.app import orphaned_packages
+
+    installed = {
+        "app": {"explicit": True, "requires": ["libx ("], "provides": []},
+        "libx": {"explicit": False, "requires": [], "provides": []},
+        "leftover": {"explicit": False, "requires": [], "provides": []},
+    }
+
+    assert orphaned_packages(installed) == ["leftover"]
@@ -0,0 +1,40 @@
+import pytest
+
+
+class _NotRoot(Exception):
+    pass
+
+
+def _setup(lpm_app, tmp_path, monkeypatch):
+    def require_root(cmd):
+        raise _NotRoot(cmd)
+
+    calls = {"built": [], "installed": [], "prompted": []}
+    monkeypatch.setattr(lpm_app, "require_root", require_root)
+    monkeypatch.setattr(lpm_app, "build_package", lambda stage, meta, out, sign=True: calls["built"].append(out))
+    monkeypatch.setattr(lpm_app, "installpkg", lambda out, root, explicit: calls["installed"].append(root))
+    monkeypatch.setattr(lpm_app, "prompt_install_pkg", lambda out, default=None: calls["prompted"].append(out))
+
+    stage = tmp_path / "stage"
+    (stage / "usr/bin").mkdir(parents=True)
+    (stage / "usr/bin/foo").write_text("#!/bin/sh\n")
+    argv = ["build", str(stage), "--name", "foo", "--version", "1", "--output", str(tmp_path / "foo.zst")]
+    return calls, argv
+
+
+def test_plain_build_is_not_gated(lpm, lpm_app, tmp_path, monkeypatch):
+    calls, argv = _setup(lpm_app, tmp_path, monkeypatch)
+    lpm.main(argv)
+    assert calls["built"] == [tmp_path / "foo.zst"]
+    assert calls["prompted"] == [tmp_path / "foo.zst"]
+
+    sysroot = tmp_path / "sysroot"
+    lpm.main(argv + ["--install-root", str(sysroot)])
+    assert calls["installed"] == [sysroot]
+
+
+def test_build_into_host_root_requires_root(lpm, lpm_app, tmp_path, monkeypatch):
+    calls, argv = _setup(lpm_app, tmp_path, monkeypatch)
+    with pytest.raises(_NotRoot):
+        lpm.main(argv + ["--install-root", "/"])
+    assert calls["built"] == []
@@ -0,0 +1,45 @@
+import os
+
+from lpm import cache
+
+
+def _fill(cache_dir, name, size, atime):
+    path = cache_dir / name
+    path.write_bytes(b"x" * size)
+    os.utime(path, (atime, atime))
+    return path
+
+
+def test_enforce_limit_evicts_least_recently_used_first(tmp_path):
+    oldest = _fill(tmp_path, "a-1.0.zst", 400, 1_000)
+    middle = _fill(tmp_path, "b-1.0.zst", 400, 2_000)
+    newest = _fill(tmp_path, "c-1.0.zst", 400, 3_000)
+    sig = tmp_path / "a-1.0.zst.sig"
+    sig.write_bytes(b"s" * 10)
+
+    evicted = cache.enforce_limit(900, tmp_path)
+
+    assert evicted == [oldest]
+    assert not oldest.exists()
+    assert not sig.exists()
+    assert middle.exists() and newest.exists()
+    assert cache.total_size(tmp_path) == 800
+
+    cache.touch(middle)
+    evicted = cache.enforce_limit(400, tmp_path)
+
+    assert evicted == [newest]
+    assert middle.exists()
+    assert cache.total_size(tmp_path) <= 400
+
+
+def test_enforce_limit_skips_archives_in_use(tmp_path):
+    busy = _fill(tmp_path, "busy-1.0.zst", 500, 1_000)
+    idle = _fill(tmp_path, "idle-1.0.zst", 500, 2_000)
+
+    with cache.in_use([busy]):
+        evicted = cache.enforce_limit(500, tmp_path)
+
+    assert evicted == [idle]
+    assert busy.exists()
+    assert not cache.is_in_use(busy)
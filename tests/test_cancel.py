@@ -0,0 +1,59 @@
+import os
+import signal
+
+import pytest
+
+from lpm import cancel
+
+
+def test_signal_sets_flag_and_second_signal_interrupts():
+    with cancel.handlers():
+        os.kill(os.getpid(), signal.SIGINT)
+        assert cancel.requested()
+        with pytest.raises(cancel.UserAborted, match="interrupted by user during download"):
+            cancel.check("download")
+        with pytest.raises(KeyboardInterrupt):
+            os.kill(os.getpid(), signal.SIGTERM)
+    assert not cancel.requested()
+    assert signal.getsignal(signal.SIGINT) is not cancel._handle
+
+
+def test_interrupt_between_writes_rolls_the_transaction_back(tmp_path):
+    from lpm.fs_ops import Transaction
+
+    first = tmp_path / "usr/bin/tool"
+    first.parent.mkdir(parents=True)
+    first.write_text("old tool")
+    second = tmp_path / "usr/share/tool/data"
+
+    with cancel.handlers():
+        txn = Transaction(tmp_path / "backup")
+        txn.write(first, "new tool")
+        assert first.read_text() == "new tool"
+
+        os.kill(os.getpid(), signal.SIGINT)
+        with pytest.raises(cancel.UserAborted, match="filesystem transaction"):
+            txn.write(second, "payload")
+
+    assert txn.state == "rolled back"
+    assert first.read_text() == "old tool"
+    assert not second.exists()
+    assert not (tmp_path / "usr/share").exists()
+    assert not (tmp_path / "backup").exists()
+
+
+def test_interrupt_after_staging_leaves_the_tree_untouched(tmp_path):
+    from lpm.fs_ops import StagedWrite
+
+    (tmp_path / "etc").mkdir()
+    (tmp_path / "etc/motd").write_text("hello")
+    staged = StagedWrite().push("etc/motd", "replaced", 0o644).push("etc/issue", "new", 0o644)
+
+    with cancel.handlers():
+        cancel.request()
+        with pytest.raises(cancel.UserAborted):
+            staged.commit(tmp_path)
+
+    assert (tmp_path / "etc/motd").read_text() == "hello"
+    assert sorted(p.name for p in tmp_path.iterdir()) == ["etc"]
+    assert sorted(p.name for p in (tmp_path / "etc").iterdir()) == ["motd"]
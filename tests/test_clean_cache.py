@@ -31,3 +31,81 @@ def test_clean_cache_removes_only_cached_files(tmp_path, monkeypatch):
     assert not subdir.exists()
     assert other_file.exists()
     assert list(cache.iterdir()) == []
+
+
+def _fill_cache(cache: Path) -> None:
+    cache.mkdir(parents=True, exist_ok=True)
+    (cache / "bash-5.2-1.x86_64.zst").write_bytes(b"a" * 10)
+    (cache / "bash-5.2-1.x86_64.zst.sig").write_bytes(b"s" * 2)
+    (cache / "bash-5.1-1.x86_64.zst").write_bytes(b"b" * 20)
+    (cache / "bash-5.1-1.x86_64.zst.sig").write_bytes(b"s" * 3)
+    (cache / "zlib-1.3-1.x86_64.zst").write_bytes(b"c" * 40)
+    (cache / "meta").mkdir()
+    (cache / "meta" / "index.json").write_bytes(b"{}")
+
+
+def test_prune_orphans_removes_only_unreferenced_archives(tmp_path):
+    from src.lpm import cache as lpm_cache
+
+    cache = tmp_path / "cache"
+    _fill_cache(cache)
+    result = lpm_cache.prune(
+        lpm_cache.DirectoryCache(cache),
+        keep=lambda entry: entry.path.name == "bash-5.2-1.x86_64.zst",
+    )
+
+    assert sorted(p.name for p in result.removed) == ["bash-5.1-1.x86_64.zst", "zlib-1.3-1.x86_64.zst"]
+    assert result.reclaimed == 63
+    assert sorted(p.name for p in cache.iterdir()) == ["bash-5.2-1.x86_64.zst", "bash-5.2-1.x86_64.zst.sig", "meta"]
+
+
+def test_clean_dry_run_reports_size_without_deleting(tmp_path, monkeypatch, capsys):
+    lpm = _import_lpm(tmp_path, monkeypatch)
+    from lpm import app as lpm_app
+
+    cache = tmp_path / "cache"
+    _fill_cache(cache)
+    before = sorted(p.relative_to(cache) for p in cache.rglob("*"))
+    monkeypatch.setattr(lpm_app, "CACHE_DIR", cache)
+    monkeypatch.setattr(lpm_app, "db", lambda: SimpleNamespace(close=lambda: None))
+    monkeypatch.setattr(
+        lpm_app,
+        "db_installed",
+        lambda conn: {"bash": {"version": "5.2", "release": "1", "arch": "x86_64"}},
+    )
+
+    lpm_app.cmd_clean_cache(SimpleNamespace(orphans=True, dry_run=True))
+    assert "reclaiming 63 bytes" in capsys.readouterr().err
+    lpm_app.cmd_clean_cache(SimpleNamespace(orphans=False, dry_run=True))
+    assert "reclaiming 77 bytes" in capsys.readouterr().err
+    assert sorted(p.relative_to(cache) for p in cache.rglob("*")) == before
+
+    lpm_app.cmd_clean_cache(SimpleNamespace(orphans=True, dry_run=False))
+    assert "reclaimed 63 bytes" in capsys.readouterr().err
+    assert not (cache / "zlib-1.3-1.x86_64.zst").exists()
+    assert (cache / "bash-5.2-1.x86_64.zst").exists()
+
+
+def test_orphans_keeps_the_archive_named_by_the_repository_url(tmp_path, monkeypatch, capsys):
+    lpm = _import_lpm(tmp_path, monkeypatch)
+    from lpm import app as lpm_app
+
+    cache = tmp_path / "cache"
+    cache.mkdir()
+    (cache / "bash_5.2_x86_64.zst").write_bytes(b"a" * 10)
+    (cache / "bash-5.2-1.x86_64.zst").write_bytes(b"b" * 20)
+    pkg = lpm.PkgMeta(
+        name="bash", version="5.2", release="1", arch="x86_64", blob="https://repo.example/pool/bash_5.2_x86_64.zst"
+    )
+    monkeypatch.setattr(lpm_app, "CACHE_DIR", cache)
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: {"bash": [pkg]})
+    monkeypatch.setattr(lpm_app, "db", lambda: SimpleNamespace(close=lambda: None))
+    monkeypatch.setattr(
+        lpm_app,
+        "db_installed",
+        lambda conn: {"bash": {"version": "5.2", "release": "1", "arch": "x86_64"}},
+    )
+
+    lpm_app.cmd_clean_cache(SimpleNamespace(orphans=True, dry_run=False))
+
+    assert sorted(p.name for p in cache.iterdir()) == ["bash_5.2_x86_64.zst"]
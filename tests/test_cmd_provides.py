@@ -0,0 +1,41 @@
+import json
+from types import SimpleNamespace
+
+import pytest
+
+
+def _record(lpm, name, manifest):
+    conn = lpm.db()
+    conn.execute(
+        "INSERT INTO installed (name,version,release,arch,provides,symbols,requires,manifest,explicit,install_time)"
+        " VALUES (?,?,?,?,?,?,?,?,?,?)",
+        (name, "1", "1", "noarch", "[]", "[]", "[]", json.dumps(manifest), 1, 0),
+    )
+    conn.commit()
+    conn.close()
+
+
+def test_cmd_provides_reports_the_owning_package(lpm, tmp_path, capsys):
+    root = tmp_path / "root"
+    _record(lpm, "coreutils", [{"path": "/usr/bin/ls"}, "/usr/bin/cat"])
+    _record(lpm, "bash", [{"path": "/usr/bin/bash", "sha256": "x"}])
+
+    lpm.cmd_provides(SimpleNamespace(root=str(root), paths=["/usr/bin/bash", str(root / "usr/bin/cat"), "/usr/./bin//ls"]))
+
+    assert capsys.readouterr().out.splitlines() == [
+        "/usr/bin/bash: bash",
+        "/usr/bin/cat: coreutils",
+        "/usr/bin/ls: coreutils",
+    ]
+
+
+def test_cmd_provides_fails_for_unowned_paths(lpm, tmp_path, capsys):
+    _record(lpm, "bash", ["/usr/bin/bash"])
+
+    with pytest.raises(SystemExit) as excinfo:
+        lpm.cmd_provides(SimpleNamespace(root=str(tmp_path / "root"), paths=["/usr/bin/bash", "/etc/unknown"]))
+
+    assert excinfo.value.code != 0
+    captured = capsys.readouterr()
+    assert captured.out.splitlines() == ["/usr/bin/bash: bash"]
+    assert "Not owned by any package: /etc/unknown" in captured.err
@@ -0,0 +1,44 @@
+import shutil
+import subprocess
+
+import pytest
+
+
+def _bash_complete(script, *words):
+    line = " ".join(words)
+    program = (
+        f"{script}\n"
+        f"COMP_WORDS=({line}); COMP_CWORD={len(words) - 1}\n"
+        '_lpm; printf "%s\\n" "${COMPREPLY[@]}"\n'
+    )
+    return subprocess.run(["bash", "-c", program], capture_output=True, text=True, check=True).stdout.split()
+
+
+def test_bash_completions_cover_parser_subcommands(lpm, tmp_path, monkeypatch, capsys):
+    from lpm import app as lpm_app
+
+    # Generating completions must not require a configured system.
+    monkeypatch.setattr(lpm_app, "CONF_FILE", tmp_path / "missing.conf", raising=False)
+    monkeypatch.setattr(lpm, "CONF_FILE", tmp_path / "missing.conf", raising=False)
+    lpm.main(["completions", "bash"])
+    script = capsys.readouterr().out
+    assert "install)" in script and "remove)" in script
+    for name in lpm_app.build_parser()._subparsers._group_actions[0].choices:
+        assert f"        {name})" in script
+
+    if shutil.which("bash") is None:
+        pytest.skip("bash not available")
+    assert set(_bash_complete(script, "lpm", "re")) >= {"remove", "repair", "repolist"}
+    assert "--no-recommends" in _bash_complete(script, "lpm", "--color", "never", "install", "--no")
+    assert _bash_complete(script, "lpm", "--format", "") == ["text", "json"]
+
+
+@pytest.mark.parametrize("shell", ["zsh", "fish"])
+def test_other_shells_list_subcommands_and_flags(shell):
+    from src.lpm import app, completions
+
+    script = completions.generate(app.build_parser(), shell)
+    assert "install" in script and "remove" in script
+    assert "dry-run" in script
+    with pytest.raises(ValueError):
+        completions.generate(app.build_parser(), "tcsh")
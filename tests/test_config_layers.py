@@ -0,0 +1,126 @@
+import os
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, os.path.dirname(os.path.dirname(__file__)))
+import src.config as config
+
+
+def _layers(tmp_path, system, user):
+    system_file = tmp_path / "etc" / "config.toml"
+    user_file = tmp_path / "xdg" / "lpm" / "config.toml"
+    for path, text in ((system_file, system), (user_file, user)):
+        if text is not None:
+            path.parent.mkdir(parents=True, exist_ok=True)
+            path.write_text(text, encoding="utf-8")
+    return system_file, user_file
+
+
+def test_user_config_overrides_system_config(tmp_path):
+    paths = _layers(
+        tmp_path,
+        'cache_dir = "/var/cache/lpm"\nrepos = ["https://repo.example/core"]\nverify = true\n',
+        'cache_dir = "/home/me/.cache/lpm"\nverify = false\n',
+    )
+
+    settings = config.load_config(paths)
+
+    assert settings.cache_dir == Path("/home/me/.cache/lpm")
+    assert settings.repos == ("https://repo.example/core",)
+    assert settings.verify is False
+    assert settings.default_root == Path(config.DEFAULT_ROOT)
+    assert settings.origins == {
+        "cache_dir": str(paths[1]),
+        "repos": str(paths[0]),
+        "verify": str(paths[1]),
+    }
+
+
+def test_cli_flags_override_every_layer(tmp_path):
+    paths = _layers(
+        tmp_path,
+        'cache_dir = "/var/cache/lpm"\ndefault_root = "/mnt/system"\n',
+        'cache_dir = "/home/me/.cache/lpm"\ndefault_root = "/mnt/user"\n',
+    )
+
+    settings = config.load_config(paths, {"cache_dir": str(tmp_path / "cli"), "default_root": None})
+
+    assert settings.cache_dir == tmp_path / "cli"
+    assert settings.default_root == Path("/mnt/user")
+    assert settings.origins["cache_dir"] == "command line"
+
+
+def test_missing_layers_give_defaults(tmp_path):
+    settings = config.load_config(_layers(tmp_path, None, None))
+    assert settings == config.Config()
+
+
+def test_malformed_layers_raise_config_error(tmp_path):
+    bad_toml = tmp_path / "broken.toml"
+    bad_toml.write_text("cache_dir = [unterminated\n", encoding="utf-8")
+    with pytest.raises(config.ConfigError) as excinfo:
+        config.load_config([bad_toml])
+    assert excinfo.value.path == bad_toml
+    assert "invalid TOML" in str(excinfo.value)
+
+    for text, message in (
+        ("verify = 'yes'\n", "verify must be true or false"),
+        ("repos = 'https://repo.example'\n", "repos must be an array"),
+        ("cache = '/tmp'\n", "unknown key 'cache'"),
+    ):
+        bad_toml.write_text(text, encoding="utf-8")
+        with pytest.raises(config.ConfigError, match=message):
+            config.load_config([bad_toml])
+
+
+def test_user_config_path_follows_xdg_config_home(tmp_path):
+    assert config.user_config_path({"XDG_CONFIG_HOME": str(tmp_path)}) == tmp_path / "lpm" / "config.toml"
+    home = Path(os.path.expanduser("~"))
+    assert config.user_config_path({}) == home / ".config" / "lpm" / "config.toml"
+
+
+def test_main_applies_config_layers_below_cli_flags(lpm, lpm_app, tmp_path, monkeypatch):
+    system_file, _user_file = _layers(
+        tmp_path,
+        f'cache_dir = "{tmp_path / "system-cache"}"\nrepos = ["file:///srv/repo"]\n',
+        None,
+    )
+    monkeypatch.setattr(lpm_app._config, "SYSTEM_CONFIG", system_file)
+    monkeypatch.setenv("XDG_CONFIG_HOME", str(tmp_path / "xdg"))
+    monkeypatch.setattr(lpm_app, "list_repos", lambda: [])
+    seen = []
+    monkeypatch.setattr(lpm_app, "cmd_repolist", lambda a: seen.append((lpm_app.CACHE_DIR, lpm_app.configured_repos())))
+
+    lpm.main(["repolist"])
+    lpm.main(["--cache-dir", str(tmp_path / "cli-cache"), "repolist"])
+
+    assert seen[0][0] == tmp_path / "system-cache"
+    assert [(r.name, r.url) for r in seen[0][1]] == [("config1", "file:///srv/repo")]
+    assert seen[1][0] == tmp_path / "cli-cache"
+
+
+def test_main_rejects_malformed_config(lpm, tmp_path, monkeypatch, capsys):
+    from lpm import app as lpm_app
+
+    system_file, _user_file = _layers(tmp_path, "verify = maybe\n", None)
+    monkeypatch.setattr(lpm_app._config, "SYSTEM_CONFIG", system_file)
+    monkeypatch.setenv("XDG_CONFIG_HOME", str(tmp_path / "xdg"))
+
+    with pytest.raises(SystemExit):
+        lpm.main(["repolist"])
+    assert "bad configuration" in capsys.readouterr().err
+
+
+def test_main_accepts_repeated_install_roots(lpm, lpm_app, tmp_path, monkeypatch):
+    monkeypatch.setattr(lpm_app._config, "SYSTEM_CONFIG", tmp_path / "missing.toml")
+    monkeypatch.setenv("XDG_CONFIG_HOME", str(tmp_path / "xdg"))
+    monkeypatch.setattr(lpm_app.os, "geteuid", lambda: 0)
+    seen = []
+    monkeypatch.setattr(lpm_app, "cmd_install", lambda a: seen.append(a.root))
+
+    first, second = str(tmp_path / "r1"), str(tmp_path / "r2")
+    lpm.main(["install", "--root", first, "--root", second, "hello"])
+
+    assert seen == [[first, second]]
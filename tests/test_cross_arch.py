@@ -0,0 +1,133 @@
+import os
+import sqlite3
+from pathlib import Path
+
+
+def _foreign_arch():
+    return "riscv64" if os.uname().machine != "riscv64" else "aarch64"
+
+
+def test_filter_arch_selects_target_and_noarch_packages(lpm):
+    pkgs = [
+        lpm.PkgMeta(name="native", version="1", arch="x86_64"),
+        lpm.PkgMeta(name="arm", version="1", arch="aarch64"),
+        lpm.PkgMeta(name="data", version="1", arch="noarch"),
+    ]
+
+    assert [p.name for p in lpm.filter_arch(pkgs, "aarch64")] == ["arm", "data"]
+    assert [p.name for p in lpm.filter_arch(pkgs, "noarch")] == ["data"]
+    assert not lpm.is_cross_arch("noarch")
+    assert not lpm.is_cross_arch(os.uname().machine)
+    assert lpm.is_cross_arch(_foreign_arch())
+
+
+def test_cross_arch_install_defers_hooks_to_database(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    marker = tmp_path / "hook-ran"
+
+    hook_dir = tmp_path / "hooks"
+    hook_dir.mkdir()
+    script = hook_dir / "post_install"
+    script.write_text(f"#!/bin/sh\ntouch {marker}\n")
+    script.chmod(0o755)
+    system_hooks = tmp_path / "system-hooks"
+    system_hooks.mkdir()
+    (system_hooks / "ldconfig.hook").write_text(
+        "[Trigger]\nType = Path\nOperation = Install\nTarget = usr/lib/*\n\n"
+        f"[Action]\nWhen = PostTransaction\nExec = /bin/sh -c 'touch {marker}'\n"
+    )
+    from lpm import app as lpm_app
+
+    monkeypatch.setattr(lpm, "HOOK_DIR", hook_dir)
+    monkeypatch.setattr(lpm_app, "LIBLPM_HOOK_DIRS", (system_hooks,))
+
+    arch = _foreign_arch()
+    _, blob = make_pkg("libfoo", "1.0", {"usr/lib/libfoo.so": "elf\n"}, arch=arch)
+
+    with lpm.target_arch(arch, root) as session:
+        lpm.apply_plan([(lpm.PkgMeta(name="libfoo", version="1.0", arch=arch), blob)], root, verify=False)
+
+    assert session is not None
+    assert (root / "usr/lib/libfoo.so").read_text() == "elf\n"
+    assert not marker.exists()
+
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        deferred = lpm.load_deferred_hooks(conn, root)
+        arches = {row[0] for row in conn.execute("SELECT arch FROM deferred_hooks")}
+    finally:
+        conn.close()
+
+    assert arches == {arch}
+    by_hook = {h.hook: h for h in deferred}
+    assert by_hook["post_install"].command == [str(script)]
+    assert by_hook["post_install"].env["LPM_PKG"] == "libfoo"
+    assert by_hook["ldconfig"].command[:2] == ["/bin/sh", "-c"]
+    assert by_hook["ldconfig"].package == "libfoo"
+
+
+def test_is_cross_arch_treats_distribution_aliases_as_the_host(lpm, monkeypatch):
+    from lpm import app as lpm_app
+
+    monkeypatch.setattr(lpm_app.os, "uname", lambda: type("U", (), {"machine": "x86_64"})())
+
+    assert not lpm.is_cross_arch("amd64")
+    assert not lpm.is_cross_arch("X86_64")
+    assert lpm.is_cross_arch("arm64")
+
+
+def test_cross_arch_install_defers_the_embedded_install_script(lpm, make_pkg, tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    marker = tmp_path / "script-ran"
+
+    arch = _foreign_arch()
+    _, blob = make_pkg(
+        "scripted",
+        "1.0",
+        {"usr/bin/scripted": "bin\n", ".lpm-install.sh": f"#!/bin/sh\ntouch {marker}\n"},
+        arch=arch,
+    )
+
+    with lpm.target_arch(arch, root):
+        lpm.apply_plan([(lpm.PkgMeta(name="scripted", version="1.0", arch=arch), blob)], root, verify=False)
+
+    assert not marker.exists()
+    assert not (root / ".lpm-install.sh").exists()
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        deferred = {h.hook: h for h in lpm.load_deferred_hooks(conn, root)}
+    finally:
+        conn.close()
+
+    hook = deferred["post_install"]
+    # Recorded as the target will see it, with the copy kept inside the root.
+    retained = Path(hook.command[0])
+    assert retained.is_absolute() and not retained.is_relative_to(root)
+    assert hook.command[1:] == ["install", "1.0-1", ""]
+    assert (root / retained.relative_to("/")).read_text() == f"#!/bin/sh\ntouch {marker}\n"
+    assert hook.package == "scripted"
+
+
+def test_cross_arch_remove_defers_post_remove_to_the_copy_inside_the_root(lpm, make_pkg, tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = f"#!/bin/sh\ntouch {tmp_path / 'script-ran'}\n"
+    arch = _foreign_arch()
+    _, blob = make_pkg("scripted", "1.0", {"usr/bin/scripted": "bin\n", ".lpm-install.sh": script}, arch=arch)
+
+    with lpm.target_arch(arch, root):
+        lpm.apply_plan([(lpm.PkgMeta(name="scripted", version="1.0", arch=arch), blob)], root, verify=False)
+        lpm.removepkg("scripted", root=root)
+
+    assert not (tmp_path / "script-ran").exists()
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        deferred = {h.hook: h for h in lpm.load_deferred_hooks(conn, root)}
+    finally:
+        conn.close()
+
+    hook = deferred["post_remove"]
+    assert hook.command[1:] == ["remove", "", "1.0-1"]
+    assert (root / Path(hook.command[0]).relative_to("/")).read_text() == script
@@ -0,0 +1,29 @@
+from src.lpm.app import (
+    PkgMeta,
+    Universe,
+    decode_model,
+    encode_resolution,
+    parse_dep_expr,
+    register_universe_candidate,
+)
+
+
+def test_decode_model_selects_packages_and_ignores_aux_vars():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for name, version in [("foo", "1.0"), ("foo", "2.0"), ("foo", "3.0"), ("bar", "1.0")]:
+        register_universe_candidate(universe, PkgMeta(name=name, version=version))
+
+    cnf, var_of, *_ = encode_resolution(universe, [parse_dep_expr("foo")])
+    aux = [v for name, v in cnf.namevar.items() if name.startswith("amo_")]
+    assert aux
+
+    model = {v: False for v in range(1, cnf.next_var)}
+    model[var_of[("foo", "2.0")]] = True
+    model[var_of[("bar", "1.0")]] = True
+    for v in aux:
+        model[v] = True
+
+    selection = decode_model(model, universe, var_of)
+
+    assert selection.ids() == {("foo", "2.0"), ("bar", "1.0")}
+    assert "foo" in selection and len(selection) == 2
@@ -0,0 +1,49 @@
+import os
+import sys
+
+import pytest
+
+sys.path.insert(0, os.path.dirname(os.path.dirname(__file__)))
+from src import CNF, CDCLSolver
+from src.lpm.resolver import DimacsError
+
+
+def test_dimacs_round_trip_preserves_clauses():
+    cnf = CNF()
+    a, b, c = cnf.new_var('foo-1.0'), cnf.new_var('foo-2.0'), cnf.new_var('bar-1.0')
+    cnf.add_clause([-a, -b])
+    cnf.add_clause([a, b])
+    cnf.add_clause([-c, a])
+    CDCLSolver(cnf).solve([])
+
+    text = cnf.to_dimacs()
+    assert "p cnf 3 3" in text.splitlines()
+    assert "c 1 foo-1.0" in text.splitlines()
+
+    back = CNF.from_dimacs(text)
+    assert back.next_var == cnf.next_var
+    assert list(back.clauses) == [[-a, -b], [a, b], [-c, a]]
+    assert CNF.from_dimacs(back.to_dimacs()).clauses == back.clauses
+
+
+def test_dimacs_accepts_comments_and_split_clauses():
+    cnf = CNF.from_dimacs("c generated\np cnf 3 2\n1 -2\n 3 0\nc mid\n-1 0\n")
+    assert cnf.clauses == [[1, -2, 3], [-1]]
+    assert CDCLSolver(cnf).solve([]).sat
+
+
+@pytest.mark.parametrize(
+    "text, kind",
+    [
+        ("1 2 0\n", DimacsError.HEADER),
+        ("p cnf x 1\n1 0\n", DimacsError.HEADER),
+        ("p cnf 2 2\n1 2 0\n", DimacsError.HEADER),
+        ("p cnf 2 1\n1 foo 0\n", DimacsError.LITERAL),
+        ("p cnf 2 1\n1 3 0\n", DimacsError.LITERAL),
+        ("p cnf 2 1\n1 2\n", DimacsError.TERMINATOR),
+    ],
+)
+def test_dimacs_rejects_malformed_input(text, kind):
+    with pytest.raises(DimacsError) as excinfo:
+        CNF.from_dimacs(text)
+    assert excinfo.value.kind == kind
@@ -0,0 +1,87 @@
+import pytest
+
+from src.lpm.app import (
+    PkgMeta,
+    ResolutionError,
+    Universe,
+    plan_downgrades,
+    register_universe_candidate,
+    solve,
+)
+
+
+def _installed(version, requires=None):
+    return {
+        "version": version,
+        "release": "1",
+        "arch": "noarch",
+        "provides": [],
+        "symbols": [],
+        "requires": list(requires or []),
+        "manifest": [],
+        "explicit": True,
+    }
+
+
+def _universe(installed):
+    return Universe(
+        candidates_by_name={},
+        providers={},
+        installed=installed,
+        pins={},
+        holds=set(),
+    )
+
+
+def test_explicit_version_downgrades_installed_package():
+    universe = _universe({"foo": _installed("2.0")})
+    register_universe_candidate(universe, PkgMeta(name="foo", version="1.0"))
+    register_universe_candidate(universe, PkgMeta(name="foo", version="2.0"))
+
+    plan = solve(["foo=1.0"], universe)
+
+    assert [(p.name, p.version) for p in plan] == [("foo", "1.0")]
+    assert plan_downgrades(plan, universe.installed) == {"foo": "2.0"}
+
+
+def test_downgrade_blocked_by_installed_reverse_dependency():
+    universe = _universe(
+        {
+            "foo": _installed("2.0"),
+            "bar": _installed("1.0", requires=["foo>=2.0"]),
+        }
+    )
+    register_universe_candidate(universe, PkgMeta(name="foo", version="1.0"))
+    register_universe_candidate(universe, PkgMeta(name="foo", version="2.0"))
+    register_universe_candidate(
+        universe, PkgMeta(name="bar", version="1.0", requires=["foo>=2.0"])
+    )
+
+    with pytest.raises(ResolutionError) as excinfo:
+        solve(["foo=1.0"], universe)
+
+    msg = str(excinfo.value)
+    assert "foo-1.0 would break installed bar-1.0" in msg
+    assert "foo>=2.0" in msg
+
+
+def test_downgrade_allowed_when_reverse_dependency_is_replaced():
+    universe = _universe(
+        {
+            "foo": _installed("2.0"),
+            "bar": _installed("2.0", requires=["foo>=2.0"]),
+        }
+    )
+    register_universe_candidate(universe, PkgMeta(name="foo", version="1.0"))
+    register_universe_candidate(universe, PkgMeta(name="foo", version="2.0"))
+    register_universe_candidate(universe, PkgMeta(name="bar", version="1.0", requires=["foo"]))
+    register_universe_candidate(
+        universe, PkgMeta(name="bar", version="2.0", requires=["foo>=2.0"])
+    )
+
+    plan = solve(["foo=1.0"], universe)
+    chosen = {p.name: p.version for p in plan}
+
+    assert chosen["foo"] == "1.0"
+    assert chosen["bar"] == "1.0"
+    assert plan_downgrades(plan, universe.installed) == {"foo": "2.0", "bar": "2.0"}
@@ -0,0 +1,108 @@
+import hashlib
+import subprocess
+
+import pytest
+
+
+def _setup(lpm_app, tmp_path, monkeypatch, archives):
+    universe = lpm_app.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in (
+        lpm_app.PkgMeta(name="app", version="1.0", requires=["lib"], blob="https://repo/app-1.0-1.noarch.zst"),
+        lpm_app.PkgMeta(name="lib", version="2.0", requires=["base"], blob="https://repo/lib-2.0-1.noarch.zst"),
+        lpm_app.PkgMeta(name="base", version="1.0", blob="https://repo/base-1.0-1.noarch.zst"),
+        lpm_app.PkgMeta(name="unrelated", version="1.0", blob="https://repo/unrelated-1.0-1.noarch.zst"),
+    ):
+        pkg.sha256 = hashlib.sha256(archives[pkg.name]).hexdigest()
+        lpm_app.register_universe_candidate(universe, pkg)
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: universe)
+
+    fetched = []
+
+    class FakeFetcher:
+        def fetch(self, pkg):
+            fetched.append(pkg.name)
+            return archives.get(f"{pkg.name}:served", archives[pkg.name]), archives.get(f"{pkg.name}:sig", b"sig")
+
+    monkeypatch.setattr(lpm_app, "RepoFetcher", FakeFetcher)
+    return fetched
+
+
+def _archives():
+    return {name: f"{name} archive".encode() for name in ("app", "lib", "base", "unrelated")}
+
+
+def test_download_fetches_the_dependency_closure(lpm, lpm_app, tmp_path, monkeypatch):
+    fetched = _setup(lpm_app, tmp_path, monkeypatch, _archives())
+    dest = tmp_path / "mirror"
+
+    lpm.main(["download", "app", "--dest", str(dest), "--no-verify"])
+
+    assert sorted(fetched) == ["app", "base", "lib"]
+    assert sorted(p.name for p in dest.iterdir()) == [
+        "app-1.0-1.noarch.zst",
+        "app-1.0-1.noarch.zst.sig",
+        "base-1.0-1.noarch.zst",
+        "base-1.0-1.noarch.zst.sig",
+        "lib-2.0-1.noarch.zst",
+        "lib-2.0-1.noarch.zst.sig",
+    ]
+    assert (dest / "lib-2.0-1.noarch.zst").read_bytes() == b"lib archive"
+
+
+def test_download_packages_reports_progress_per_package(lpm, lpm_app, tmp_path, monkeypatch, recording_progress):
+    fetched = _setup(lpm_app, tmp_path, monkeypatch, _archives())
+
+    plan = lpm_app.solve(["app"], lpm_app.build_universe())
+    progress = recording_progress()
+
+    written = lpm_app.download_packages(plan, tmp_path / "mirror", lpm_app.RepoFetcher(), progress=progress)
+
+    assert len(written) == 3
+    assert progress.totals == [3]
+    assert sum(progress.increments) == 3
+    assert progress.finished
+
+
+def test_download_aborts_on_checksum_mismatch(lpm, lpm_app, tmp_path, monkeypatch, capsys):
+    archives = _archives()
+    archives["lib:served"] = b"tampered"
+    _setup(lpm_app, tmp_path, monkeypatch, archives)
+    dest = tmp_path / "mirror"
+
+    with pytest.raises(SystemExit):
+        lpm.main(["download", "app", "--dest", str(dest), "--no-verify"])
+
+    err = capsys.readouterr().err
+    assert "download aborted: checksum mismatch" in err
+    assert "lib-2.0-1.noarch.zst" in err
+    assert not (dest / "lib-2.0-1.noarch.zst").exists()
+
+
+def test_download_refuses_packages_without_a_trusted_signature(lpm, lpm_app, tmp_path, monkeypatch, capsys):
+    priv = tmp_path / "repo.key"
+    trust = tmp_path / "trust"
+    trust.mkdir()
+    subprocess.run(["openssl", "genpkey", "-algorithm", "ed25519", "-out", str(priv)], check=True, capture_output=True)
+    subprocess.run(
+        ["openssl", "pkey", "-in", str(priv), "-pubout", "-out", str(trust / "repo.pem")], check=True, capture_output=True
+    )
+    archives = _archives()
+    for name in ("app", "lib", "base"):
+        blob = tmp_path / name
+        blob.write_bytes(archives[name])
+        subprocess.run(
+            ["openssl", "pkeyutl", "-sign", "-inkey", str(priv), "-rawin", "-in", str(blob), "-out", str(tmp_path / "sig")],
+            check=True,
+        )
+        archives[f"{name}:sig"] = (tmp_path / "sig").read_bytes()
+    _setup(lpm_app, tmp_path, monkeypatch, archives)
+
+    monkeypatch.setattr(lpm_app, "TRUST_DIR", trust)
+    lpm.main(["download", "app", "--dest", str(tmp_path / "good")])
+    assert (tmp_path / "good" / "lib-2.0-1.noarch.zst.sig").read_bytes() == archives["lib:sig"]
+
+    archives["lib:sig"] = archives["base:sig"]
+    with pytest.raises(SystemExit):
+        lpm.main(["download", "app", "--dest", str(tmp_path / "bad")])
+    assert "Signature verification failed for lib-2.0-1.noarch.zst" in capsys.readouterr().err
+    assert not (tmp_path / "bad" / "lib-2.0-1.noarch.zst").exists()
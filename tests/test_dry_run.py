@@ -0,0 +1,45 @@
+from types import SimpleNamespace
+
+import pytest
+
+
+@pytest.fixture
+def guarded(lpm, lpm_app, monkeypatch):
+    """``lpm`` whose root check and system changes fail the test."""
+
+    def not_root(cmd):
+        raise AssertionError(f"{cmd} asked for root during a dry run")
+
+    def side_effect(*args, **kwargs):
+        raise AssertionError("dry run modified the system")
+
+    monkeypatch.setattr(lpm_app, "require_root", not_root)
+    for name in ("do_install", "installpkg", "build_package", "prompt_install_pkg"):
+        monkeypatch.setattr(lpm_app, name, side_effect)
+    return lpm
+
+
+def test_global_dry_run_install_prints_plan_without_root(guarded, lpm_app, monkeypatch, capsys):
+    plan = [guarded.PkgMeta(name="zlib", version="1.3"), guarded.PkgMeta(name="foo", version="2.0")]
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: SimpleNamespace(installed={}))
+    monkeypatch.setattr(lpm_app, "solve", lambda goals, u, **kwargs: plan)
+
+    assert guarded.main(["--dry-run", "install", "foo"]) is None
+    plan_out = capsys.readouterr().err
+    assert plan_out.index("zlib") < plan_out.index("foo")
+
+
+def test_global_dry_run_build_writes_nothing(guarded, tmp_path, capsys):
+    stage = tmp_path / "stage"
+    (stage / "usr/bin").mkdir(parents=True)
+    (stage / "usr/bin/foo").write_text("#!/bin/sh\n")
+    out = tmp_path / "foo.zst"
+
+    guarded.main(["--dry-run", "build", str(stage), "--name", "foo", "--version", "1", "--output", str(out)])
+    assert f"Would build {out}" in capsys.readouterr().out
+    assert not out.exists()
+
+
+def test_global_dry_run_rejects_commands_without_a_dry_run(guarded):
+    with pytest.raises(SystemExit):
+        guarded.main(["--dry-run", "repolist"])
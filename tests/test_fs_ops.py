@@ -1,16 +1,45 @@
 from __future__ import annotations
 
+import errno
+import hashlib
 import json
+import os
+import shutil
+import stat
+import subprocess
+import sys
 from pathlib import Path
 
 import pytest
 
+from lpm.atomic_io import FsError
 from lpm.fs_ops import (
+    ChecksumMismatch,
+    Compression,
+    FileRemoved,
+    FileWritten,
+    LinkStrategy,
+    StagedWrite,
+    Transaction,
+    TxnBegin,
+    TxnCommit,
+    append_entry,
     journal_append,
+    journal_read,
+    journal_rotate,
+    link_or_copy,
     operation_phase,
+    promote,
+    read_compressed,
+    read_journal,
+    read_link_hash,
+    safe_symlink,
+    sha256_file,
     write_db_bytes,
     write_db_json,
+    write_decompressed,
     write_manifest_file,
+    write_verified,
 )
 
 
@@ -60,6 +89,34 @@ def test_journal_ldjson(tmp_path: Path):
     assert (journal.stat().st_mode & 0o777) == 0o640
 
 
+def test_journal_rotate_compresses_archive(tmp_path: Path):
+    journal = tmp_path / "journal.ldjson"
+    journal_append(journal, {"op": "install", "pkg": "foo"})
+    journal_append(journal, {"op": "remove", "pkg": "foo"})
+
+    archive = journal_rotate(journal, compress=True)
+
+    assert archive == tmp_path / "journal.ldjson.1.zst"
+    assert not journal.exists()
+    assert archive.read_bytes().startswith(b"\x28\xb5\x2f\xfd")
+    assert journal_read(archive) == [
+        {"op": "install", "pkg": "foo"},
+        {"op": "remove", "pkg": "foo"},
+    ]
+
+
+def test_journal_read_joins_archives_and_live_tail(tmp_path: Path):
+    journal = tmp_path / "journal.ldjson"
+    for i in range(7):
+        journal_append(journal, {"seq": i}, max_bytes=30, compress=True)
+    journal_append(journal, {"seq": 7})
+
+    archives = sorted(p.name for p in tmp_path.glob("journal.ldjson.*.zst"))
+    assert len(archives) >= 2
+    assert journal.exists()
+    assert [e["seq"] for e in journal_read(journal)] == list(range(8))
+
+
 @pytest.mark.parametrize("privileged", [True, False])
 def test_operation_phase_context(tmp_path: Path, privileged: bool):
     target = tmp_path / "file"
@@ -73,3 +130,338 @@ def test_operation_phase_context(tmp_path: Path, privileged: bool):
         assert resulting_mode == 0o644
     else:
         assert resulting_mode == (0o666 & ~current)
+
+
+def test_transaction_rollback_restores_recorded_paths(tmp_path: Path):
+    existing = tmp_path / "etc" / "app.conf"
+    existing.parent.mkdir()
+    existing.write_text("old\n")
+    created = tmp_path / "usr" / "lib" / "app" / "data"
+
+    txn = Transaction(tmp_path / "backup")
+    txn.record(existing)
+    existing.write_text("new\n")
+    txn.record(created)
+    created.parent.mkdir(parents=True)
+    created.write_text("data\n")
+    txn.rollback()
+
+    assert existing.read_text() == "old\n"
+    assert not (tmp_path / "usr").exists()
+    assert not (tmp_path / "backup").exists()
+    assert txn.state == "rolled back"
+
+
+
+def test_transaction_rollback_copies_backups_from_another_filesystem(tmp_path: Path, monkeypatch):
+    existing = tmp_path / "etc" / "app.conf"
+    existing.parent.mkdir()
+    existing.write_text("old\n")
+    txn = Transaction(tmp_path / "backup")
+    txn.record(existing)
+    existing.write_text("new\n")
+
+    def cross_device(src, dst):
+        raise OSError(errno.EXDEV, os.strerror(errno.EXDEV))
+
+    monkeypatch.setattr(os, "rename", cross_device)
+    monkeypatch.setattr(os, "replace", cross_device)
+    txn.rollback()
+
+    assert existing.read_text() == "old\n"
+    assert txn.state == "rolled back"
+
+
+def test_transaction_rollback_failure_keeps_backups_of_unrestored_paths(tmp_path: Path, monkeypatch):
+    first = tmp_path / "etc" / "a.conf"
+    second = tmp_path / "etc" / "b.conf"
+    first.parent.mkdir()
+    first.write_text("a\n")
+    second.write_text("b\n")
+    txn = Transaction(tmp_path / "backup")
+    txn.record(first)
+    txn.record(second)
+    first.write_text("changed\n")
+    second.write_text("changed\n")
+
+    real_move = shutil.move
+
+    def failing_move(src, dst):
+        if Path(dst) == first:
+            raise OSError(errno.EIO, os.strerror(errno.EIO))
+        return real_move(src, dst)
+
+    monkeypatch.setattr(shutil, "move", failing_move)
+    with pytest.raises(FsError):
+        txn.rollback()
+
+    assert second.read_text() == "b\n"
+    assert txn.state == "open"
+    assert txn.paths == [first]
+
+    monkeypatch.setattr(shutil, "move", real_move)
+    txn.rollback()
+    assert first.read_text() == "a\n"
+    assert not (tmp_path / "backup").exists()
+
+
+def test_transaction_write_failure_rolls_back_earlier_writes(tmp_path: Path):
+    original = tmp_path / "etc" / "app.conf"
+    original.parent.mkdir()
+    original.write_text("old\n")
+    staged = tmp_path / "stage" / "app.bin"
+    staged.parent.mkdir()
+    staged.write_text("binary\n")
+    fresh = tmp_path / "usr" / "share" / "app" / "README"
+    target = tmp_path / "usr" / "bin" / "app"
+
+    txn = Transaction(tmp_path / "backup")
+    with pytest.raises(OSError):
+        with txn:
+            txn.write(original, "new\n")
+            txn.write(fresh, "readme\n")
+            txn.rename(staged, target)
+            txn.write(tmp_path / "etc" / "app.conf" / "not-a-dir", "boom")
+
+    assert txn.state == "rolled back"
+    assert original.read_text() == "old\n"
+    assert staged.read_text() == "binary\n"
+    assert not target.exists()
+    assert not (tmp_path / "usr").exists()
+    assert sorted(p.name for p in (tmp_path / "etc").iterdir()) == ["app.conf"]
+
+
+def test_write_verified_checks_digest(tmp_path: Path):
+    payload = b"package payload"
+    digest = hashlib.sha256(payload).hexdigest()
+    target = tmp_path / "pkgs" / "foo.lpm"
+
+    assert write_verified(target, payload, mode=0o600, expected_sha256=digest.upper()) == target.resolve()
+    assert sha256_file(target) == digest
+    assert (target.stat().st_mode & 0o777) == 0o600
+
+    bad = tmp_path / "pkgs" / "bar.lpm"
+    with pytest.raises(ChecksumMismatch) as excinfo:
+        write_verified(bad, payload, mode=0o644, expected_sha256="0" * 64)
+    assert excinfo.value.expected == "0" * 64
+    assert excinfo.value.actual == digest
+    assert excinfo.value.filename == excinfo.value.path == str(bad.resolve())
+    assert str(excinfo.value).startswith(f"checksum mismatch for {bad}")
+    assert not bad.exists()
+
+
+def test_safe_symlink_creates_replaces_and_hashes(tmp_path: Path):
+    link = tmp_path / "usr" / "lib" / "libfoo.so"
+
+    safe_symlink("libfoo.so.1", link)
+    assert os.readlink(link) == "libfoo.so.1"
+    assert read_link_hash(link) == hashlib.sha256(b"libfoo.so.1").hexdigest()
+
+    safe_symlink("libfoo.so.2", link)
+    assert os.readlink(link) == "libfoo.so.2"
+    assert read_link_hash(link) == hashlib.sha256(b"libfoo.so.2").hexdigest()
+    assert sorted(p.name for p in link.parent.iterdir()) == ["libfoo.so"]
+
+    regular = tmp_path / "usr" / "lib" / "libbar.so"
+    regular.write_text("elf")
+    with pytest.raises(FsError, match="refusing to replace file"):
+        safe_symlink("libbar.so.1", regular)
+    assert regular.read_text() == "elf"
+    with pytest.raises(FsError):
+        read_link_hash(regular)
+
+
+@pytest.mark.parametrize("algo", [Compression.ZSTD, Compression.GZIP, Compression.NONE])
+def test_write_decompressed_round_trip(tmp_path: Path, algo: Compression):
+    plaintext = b"#!/bin/sh\necho hello\n" * 64
+    source = tmp_path / "source"
+    source.write_bytes(plaintext)
+    compressed = read_compressed(source, algo)
+    if algo is not Compression.NONE:
+        assert compressed != plaintext
+
+    target = tmp_path / "usr" / "bin" / "hello"
+    write_decompressed(target, compressed, algo, mode=0o755)
+    assert target.read_bytes() == plaintext
+    assert (target.stat().st_mode & 0o777) == 0o755
+
+
+def test_write_decompressed_rejects_corrupt_payload(tmp_path: Path):
+    target = tmp_path / "broken"
+    with pytest.raises(FsError):
+        write_decompressed(target, b"not gzip", "gzip", mode=0o644)
+    assert not target.exists()
+
+
+def test_typed_journal_round_trip_ignores_truncated_tail(tmp_path: Path):
+    journal = tmp_path / "txn.ldjson"
+    entries = [
+        TxnBegin(),
+        FileWritten(path="/usr/bin/foo", sha256="ab" * 32),
+        FileRemoved(path="/usr/bin/old-foo"),
+        TxnCommit(),
+    ]
+    for entry in entries:
+        append_entry(journal, entry, mode=0o600)
+    assert (journal.stat().st_mode & 0o777) == 0o600
+    assert json.loads(journal.read_text().splitlines()[1]) == {
+        "kind": "file_written",
+        "path": "/usr/bin/foo",
+        "sha256": "ab" * 32,
+    }
+    assert read_journal(journal) == entries
+
+    with journal.open("a") as fh:
+        fh.write('{"kind": "file_wri')
+    assert read_journal(journal) == entries
+    with pytest.raises(json.JSONDecodeError):
+        journal_read(journal)
+
+    journal.write_text('{"kind": "file_written", "path": "/x"}\n')
+    with pytest.raises(FsError):
+        read_journal(journal)
+
+
+def test_staged_write_commits_all_files(tmp_path: Path):
+    root = tmp_path / "root"
+    staged = (
+        StagedWrite()
+        .push("usr/bin/foo", b"\x7fELF", 0o755)
+        .push("etc/foo.conf", "key=value\n", 0o644)
+        .push("var/lib/foo/secret", b"token", 0o600)
+    )
+
+    committed = staged.commit(root)
+
+    assert [p.relative_to(root).as_posix() for p in committed] == ["usr/bin/foo", "etc/foo.conf", "var/lib/foo/secret"]
+    assert (root / "usr/bin/foo").read_bytes() == b"\x7fELF"
+    assert (root / "etc/foo.conf").read_text() == "key=value\n"
+    assert [(root / rel).stat().st_mode & 0o777 for rel in ("usr/bin/foo", "etc/foo.conf", "var/lib/foo/secret")] == [
+        0o755,
+        0o644,
+        0o600,
+    ]
+    assert sorted(p.name for p in root.iterdir()) == ["etc", "usr", "var"]
+
+    with pytest.raises(ValueError):
+        StagedWrite().push("../escape", b"", 0o644)
+
+
+def test_staged_write_reports_partial_failure(tmp_path: Path):
+    root = tmp_path / "root"
+    (root / "etc" / "foo.conf").mkdir(parents=True)
+    staged = StagedWrite().push("usr/bin/foo", b"bin", 0o755).push("etc/foo.conf", b"conf", 0o644)
+
+    with pytest.raises(FsError) as excinfo:
+        staged.commit(root)
+
+    assert excinfo.value.committed == [root / "usr/bin/foo"]
+    assert excinfo.value.failed == [root / "etc/foo.conf"]
+    assert (root / "usr/bin/foo").read_bytes() == b"bin"
+    assert not list(root.glob(".lpm-stage-*"))
+
+
+def test_promote_replaces_destination_with_completed_part(tmp_path):
+    dest = tmp_path / "pkg.zst"
+    dest.write_bytes(b"old")
+    part = tmp_path / "pkg.zst.part"
+    part.write_bytes(b"new archive")
+
+    assert promote(part, dest, mode=0o600) == dest
+    assert dest.read_bytes() == b"new archive"
+    assert stat.S_IMODE(dest.stat().st_mode) == 0o600
+    assert not part.exists()
+
+    with pytest.raises(FsError):
+        promote(part, dest)
+
+
+def test_kept_transaction_can_be_reloaded_and_undone(tmp_path):
+    existing = tmp_path / "etc" / "app.conf"
+    existing.parent.mkdir()
+    existing.write_text("old")
+    created = tmp_path / "usr" / "bin" / "app"
+
+    txn = Transaction(tmp_path / "backup")
+    txn.write(existing, "new")
+    txn.write(created, "binary")
+    txn.commit(keep=True)
+    assert (tmp_path / "backup" / Transaction.JOURNAL).exists()
+
+    Transaction.load(tmp_path / "backup").rollback()
+    assert existing.read_text() == "old"
+    assert not (tmp_path / "usr").exists()
+    assert not (tmp_path / "backup").exists()
+    with pytest.raises(FsError):
+        Transaction.load(tmp_path / "backup")
+
+
+def test_link_or_copy_hardlinks_on_the_same_filesystem(tmp_path):
+    store = tmp_path / "store" / "ab12"
+    store.parent.mkdir()
+    store.write_bytes(b"GPL text")
+    os.chmod(store, 0o644)
+    dst = tmp_path / "root" / "usr/share/licenses/foo/COPYING"
+
+    assert link_or_copy(store, dst, 0o644) is LinkStrategy.HARDLINK
+
+    assert dst.read_bytes() == b"GPL text"
+    assert os.stat(dst).st_ino == os.stat(store).st_ino
+    assert os.stat(store).st_nlink == 2
+    assert [p.name for p in dst.parent.iterdir()] == ["COPYING"]
+
+    # A different mode would change the shared inode, so it is copied instead.
+    other = tmp_path / "root" / "usr/bin/foo"
+    assert link_or_copy(store, other, 0o755) is LinkStrategy.COPY
+    assert stat.S_IMODE(os.stat(store).st_mode) == 0o644
+    assert os.stat(store).st_nlink == 2
+
+
+def test_link_or_copy_falls_back_to_a_copy_across_filesystems(tmp_path, monkeypatch):
+    store = tmp_path / "store"
+    store.write_bytes(b"shared lib")
+    os.chmod(store, 0o644)
+    dst = tmp_path / "root" / "usr/lib/libfoo.so"
+    dst.parent.mkdir(parents=True)
+    dst.write_bytes(b"old")
+
+    def cross_device(src, dst, *args, **kwargs):
+        raise OSError(errno.EXDEV, "Invalid cross-device link")
+
+    monkeypatch.setattr(os, "link", cross_device)
+
+    assert link_or_copy(store, dst, 0o644) is LinkStrategy.COPY
+    assert dst.read_bytes() == b"shared lib"
+    assert os.stat(dst).st_ino != os.stat(store).st_ino
+    assert os.stat(store).st_nlink == 1
+    assert stat.S_IMODE(os.stat(dst).st_mode) == 0o644
+
+
+def _import_in_clean_interpreter(code, tmp_path):
+    env = dict(os.environ)
+    src_root = Path(__file__).resolve().parent.parent / "src"
+    env["PYTHONPATH"] = os.pathsep.join(filter(None, [str(src_root), env.get("PYTHONPATH", "")]))
+    env["LPM_STATE_DIR"] = str(tmp_path / "state")
+    return subprocess.run(
+        [sys.executable, "-c", code],
+        env=env,
+        cwd=tmp_path,
+        capture_output=True,
+        text=True,
+    )
+
+
+def test_fs_ops_imports_in_a_fresh_interpreter(tmp_path):
+    result = _import_in_clean_interpreter("import lpm.fs_ops", tmp_path)
+    assert result.returncode == 0, result.stderr
+
+
+def test_fs_ops_imports_without_zstandard_installed(tmp_path):
+    code = (
+        "import sys\n"
+        "sys.modules['zstandard'] = None\n"
+        "import lpm.fs_ops\n"
+        "assert lpm.fs_ops.zstd.__name__ == 'lpm._zstd_stub'\n"
+    )
+    result = _import_in_clean_interpreter(code, tmp_path)
+    assert result.returncode == 0, result.stderr
@@ -28,6 +28,25 @@ def test_restart_behavior(monkeypatch):
     assert solver.last_restarts > 0
 
 
+@pytest.mark.heuristics
+def test_restart_base_keeps_simple_instances_correct():
+    for base in (1, 0):
+        solver = CDCLSolver(make_unsat_cnf())
+        solver.set_restart_base(base)
+        assert not solver.solve([]).sat
+        assert (solver.restarts > 0) == (base > 0)
+
+        cnf = _random_cnf(0)
+        original = list(cnf.clauses)
+        solver = CDCLSolver(cnf)
+        solver.set_restart_base(base)
+        res = solver.solve([])
+        assert res.sat
+        assert all(any(res.assign[abs(l)] == (l > 0) for l in c) for c in original)
+    with pytest.raises(ValueError):
+        CDCLSolver(CNF()).set_restart_base(-1)
+
+
 @pytest.mark.heuristics
 def test_phase_saving():
     cnf = CNF()
@@ -38,3 +57,125 @@ def test_phase_saving():
     solver.saved_phase[v] = False
     res2 = solver.solve([])
     assert res2.assign[v] is False
+
+
+def _random_cnf(seed):
+    import random
+
+    rng = random.Random(seed)
+    cnf = CNF()
+    vars = [cnf.new_var(f'X{i}') for i in range(30)]
+    for _ in range(90):
+        cnf.add_clause([v if rng.random() < 0.5 else -v for v in rng.sample(vars, 3)])
+    return cnf
+
+
+@pytest.mark.heuristics
+def test_seeded_solver_reproduces_models_run_to_run():
+    models = {seed: CDCLSolver.with_seed(_random_cnf(0), seed).solve([]).assign for seed in (1, 2)}
+    assert CDCLSolver.with_seed(_random_cnf(0), 1).solve([]).assign == models[1]
+    assert CDCLSolver.with_seed(_random_cnf(0), 2).solve([]).assign == models[2]
+    assert CDCLSolver(_random_cnf(0)).solve([]).assign == CDCLSolver(_random_cnf(0)).solve([]).assign
+
+
+@pytest.mark.heuristics
+def test_default_phase_applies_only_without_hints():
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    c = cnf.new_var('C')
+    res = CDCLSolver(cnf, prefer_true={b}, prefer_false={c}, default_phase=False).solve([])
+    assert res.assign == {a: False, b: True, c: False}
+    assert CDCLSolver(cnf, prefer_false={c}).solve([]).assign == {a: True, b: True, c: False}
+
+
+@pytest.mark.heuristics
+def test_redecisions_after_backjump_reuse_the_saved_phase(monkeypatch):
+    unassigned = {}
+
+    class RecordingTrail(solver_module.Trail):
+        def backjump(self, level):
+            undone = super().backjump(level)
+            for lit in undone:
+                unassigned[abs(lit)] = lit > 0
+            return undone
+
+    redecided = []
+
+    def on_event(event):
+        if event.kind == solver_module.SolverEvent.DECISION and abs(event.literal) in unassigned:
+            redecided.append((event.literal > 0, unassigned[abs(event.literal)]))
+
+    def pigeonhole():
+        cnf = CNF()
+        x = [[cnf.new_var(f'p{p}h{h}') for h in range(4)] for p in range(5)]
+        for row in x:
+            cnf.add_clause(row)
+        for h in range(4):
+            for p in range(5):
+                for q in range(p + 1, 5):
+                    cnf.add_clause([-x[p][h], -x[q][h]])
+        return cnf
+
+    monkeypatch.setattr(solver_module, 'Trail', RecordingTrail)
+    for default in (True, False):
+        unassigned.clear()
+        redecided.clear()
+        assert not CDCLSolver(pigeonhole(), default_phase=default).solve([], on_event=on_event).sat
+        assert redecided
+        assert all(phase == saved for phase, saved in redecided)
+
+
+@pytest.mark.heuristics
+def test_static_order_decides_the_lowest_unassigned_variable():
+    picks = []
+
+    class Recording(solver_module.StaticOrder):
+        def choose(self, state):
+            lit = super().choose(state)
+            free = list(state.unassigned())
+            picks.append((lit, free))
+            return lit
+
+    res = CDCLSolver(_random_cnf(3), heuristic=Recording()).solve([])
+    assert res.sat
+    assert len(picks) > 1
+    for lit, free in picks:
+        assert (abs(lit) if lit is not None else None) == (min(free) if free else None)
+
+
+@pytest.mark.heuristics
+def test_every_heuristic_gives_correct_verdicts():
+    heuristics = [
+        lambda: solver_module.Vsids(),
+        lambda: solver_module.StaticOrder(),
+        lambda: solver_module.StaticOrder(order=[2, 1]),
+        lambda: solver_module.Random(7),
+    ]
+    for make in heuristics:
+        assert not CDCLSolver(make_unsat_cnf(), heuristic=make()).solve([]).sat
+        for seed in range(3):
+            cnf = _random_cnf(seed)
+            original = list(cnf.clauses)
+            expected = CDCLSolver(_random_cnf(seed)).solve([]).sat
+            res = CDCLSolver(cnf, heuristic=make()).solve([])
+            assert res.sat == expected
+            if res.sat:
+                assert all(any(res.assign[abs(l)] == (l > 0) for l in c) for c in original)
+
+    assert CDCLSolver(_random_cnf(0)).solve([]).assign == CDCLSolver(
+        _random_cnf(0), heuristic=solver_module.Vsids()
+    ).solve([]).assign
+
+
+@pytest.mark.heuristics
+def test_heuristic_choosing_an_assigned_variable_is_rejected():
+    class Stubborn:
+        def choose(self, state):
+            return 1
+
+    cnf = CNF()
+    cnf.new_var('A')
+    cnf.new_var('B')
+    with pytest.raises(solver_module.SolverError):
+        CDCLSolver(cnf, heuristic=Stubborn()).solve([])
@@ -0,0 +1,136 @@
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from types import SimpleNamespace
+
+import pytest
+
+from src.lpm.httpfetch import FetchError, HttpFetcher
+from src.lpm.retry import RetryPolicy
+
+PAYLOAD = bytes(range(256)) * 64
+
+
+def _serve(drops=0):
+    """Serve PAYLOAD at /pkg.zst, closing the first *drops* responses halfway."""
+
+    state = {"drops": drops, "ranges": []}
+
+    class Handler(BaseHTTPRequestHandler):
+        def log_message(self, *args):
+            pass
+
+        def do_GET(self):
+            if self.path != "/pkg.zst":
+                self.send_error(404)
+                return
+            rng = self.headers.get("Range")
+            state["ranges"].append(rng)
+            start = int(rng[len("bytes="):].rstrip("-")) if rng else 0
+            body = PAYLOAD[start:]
+            self.send_response(206 if rng else 200)
+            if rng:
+                self.send_header("Content-Range", f"bytes {start}-{len(PAYLOAD) - 1}/{len(PAYLOAD)}")
+            self.send_header("Content-Length", str(len(body)))
+            self.end_headers()
+            if state["drops"]:
+                state["drops"] -= 1
+                self.wfile.write(body[: len(body) // 2])
+                self.close_connection = True
+                return
+            self.wfile.write(body)
+
+    server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    return server, state
+
+
+def _fetcher(tmp_path, attempts):
+    return HttpFetcher(tmp_path / "work", policy=RetryPolicy(max_attempts=attempts, base_delay=0, jitter=0), timeout=5)
+
+
+def test_http_fetcher_progress_sums_to_size_across_a_resume(tmp_path, recording_progress):
+    server, _state = _serve(drops=1)
+    reports = {}
+
+    def progress(name):
+        return reports.setdefault(name, recording_progress())
+
+    try:
+        url = f"http://127.0.0.1:{server.server_port}/pkg.zst"
+        fetcher = HttpFetcher(
+            tmp_path / "work",
+            policy=RetryPolicy(max_attempts=2, base_delay=0, jitter=0),
+            timeout=5,
+            progress=progress,
+        )
+        fetcher.get(url)
+    finally:
+        server.shutdown()
+    report = reports["pkg.zst"]
+    assert report.totals == [len(PAYLOAD), len(PAYLOAD)]
+    assert sum(report.increments) == len(PAYLOAD)
+    assert report.finished
+
+
+def test_http_fetcher_downloads_archive_and_optional_signature(tmp_path):
+    server, state = _serve()
+    try:
+        url = f"http://127.0.0.1:{server.server_port}/pkg.zst"
+        data, sig = _fetcher(tmp_path, 1).fetch(SimpleNamespace(blob=url))
+    finally:
+        server.shutdown()
+    assert data == PAYLOAD
+    assert sig is None
+    assert state["ranges"] == [None]
+    assert sorted(p.name for p in (tmp_path / "work").iterdir()) == ["pkg.zst"]
+
+
+def test_http_fetcher_resumes_after_dropped_connection(tmp_path):
+    server, state = _serve(drops=1)
+    try:
+        url = f"http://127.0.0.1:{server.server_port}/pkg.zst"
+        path = _fetcher(tmp_path, 2).get(url)
+    finally:
+        server.shutdown()
+    half = len(PAYLOAD) // 2
+    assert state["ranges"] == [None, f"bytes={half}-"]
+    assert path.read_bytes() == PAYLOAD
+    assert not (tmp_path / "work" / "pkg.zst.part").exists()
+
+
+def test_http_fetcher_keeps_partial_file_between_calls(tmp_path):
+    server, state = _serve(drops=1)
+    try:
+        url = f"http://127.0.0.1:{server.server_port}/pkg.zst"
+        fetcher = _fetcher(tmp_path, 1)
+        with pytest.raises(FetchError) as excinfo:
+            fetcher.get(url)
+        assert excinfo.value.retryable
+        assert (tmp_path / "work" / "pkg.zst.part").stat().st_size == len(PAYLOAD) // 2
+        assert fetcher.get(url).read_bytes() == PAYLOAD
+
+        with pytest.raises(FetchError) as excinfo:
+            fetcher.get(f"http://127.0.0.1:{server.server_port}/missing.zst")
+        assert excinfo.value.status == 404
+        assert not excinfo.value.retryable
+    finally:
+        server.shutdown()
+    assert state["ranges"] == [None, f"bytes={len(PAYLOAD) // 2}-"]
+
+
+def test_http_fetcher_makes_no_requests_offline(tmp_path):
+    server, state = _serve()
+    try:
+        url = f"http://127.0.0.1:{server.server_port}/pkg.zst"
+        offline = HttpFetcher(tmp_path / "work", offline=True)
+        with pytest.raises(FetchError, match="--offline"):
+            offline.get(url)
+        assert state["ranges"] == []
+
+        _fetcher(tmp_path, 1).get(url)
+        data, sig = offline.fetch(SimpleNamespace(blob=url))
+    finally:
+        server.shutdown()
+    assert data == PAYLOAD
+    assert sig is None
+    assert state["ranges"] == [None]
@@ -0,0 +1,81 @@
+import json
+
+import pytest
+
+from src.lpm.app import IndexFormatError, JsonIndex
+from src.lpm.fs_ops import FsError
+
+
+def _write(path, doc):
+    path.write_text(json.dumps(doc), encoding="utf-8")
+    return path
+
+
+def test_json_index_looks_up_and_searches_packages(tmp_path):
+    path = _write(
+        tmp_path / "repo.json",
+        [
+            {"name": "zlib", "version": "1.2.13", "summary": "Compression library"},
+            {"name": "zlib", "version": "1.3", "summary": "Compression library"},
+            {"name": "zstd", "version": "1.5.5", "summary": "Fast compression", "requires": ["zlib"]},
+            {"name": "bash", "version": "5.2", "summary": "The GNU shell"},
+        ],
+    )
+    index = JsonIndex.load(path, repo_name="local")
+
+    assert index.get("zlib").version == "1.3"
+    assert index.get("zlib").repo == "local"
+    assert index.get("zstd").requires == ["zlib"]
+    assert index.get("missing") is None
+    assert [(p.name, p.version) for p in index.all()] == [
+        ("bash", "5.2"), ("zlib", "1.3"), ("zlib", "1.2.13"), ("zstd", "1.5.5"),
+    ]
+    assert [p.name for p in index.query("ZL")] == ["zlib"]
+    assert [p.name for p in index.query("compression")] == ["zlib", "zstd"]
+    assert index.query("nothing") == []
+
+    wrapped = JsonIndex.load(_write(tmp_path / "index.json", {"packages": [{"name": "bash", "version": "5.2"}]}))
+    assert [p.name for p in wrapped.all()] == ["bash"]
+
+
+def test_json_index_reports_missing_and_corrupt_files(tmp_path):
+    with pytest.raises(FsError):
+        JsonIndex.load(tmp_path / "absent.json")
+
+    corrupt = tmp_path / "corrupt.json"
+    corrupt.write_text("[{", encoding="utf-8")
+    with pytest.raises(IndexFormatError, match="invalid JSON"):
+        JsonIndex.load(corrupt)
+
+    bad = _write(tmp_path / "bad.json", [{"name": "bash", "version": "5.2"}, {"name": "zlib"}])
+    with pytest.raises(IndexFormatError) as excinfo:
+        JsonIndex.load(bad)
+    assert excinfo.value.entry == 1
+    assert "missing or invalid 'version'" in str(excinfo.value)
+
+    with pytest.raises(IndexFormatError, match="'requires' must be a list"):
+        JsonIndex.load(_write(tmp_path / "list.json", [{"name": "a", "version": "1", "requires": "b"}]))
+    with pytest.raises(IndexFormatError, match="array of package records"):
+        JsonIndex.load(_write(tmp_path / "obj.json", {"name": "a"}))
+
+
+def test_search_info_and_list_read_the_repository_index(monkeypatch, capsys):
+    from src.lpm import app as lpm_app
+    from src.lpm.app import PkgMeta
+
+    universe = {
+        "zlib": [
+            PkgMeta(name="zlib", version="1.2", summary="stable", prio=20),
+            PkgMeta(name="zlib", version="1.3", summary="testing", prio=10),
+        ],
+    }
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: universe)
+
+    index = lpm_app.repo_index()
+    assert index.get("zlib").version == "1.2"
+    assert lpm_app.search_packages(index, ["zl"]) == [("zlib", "1.2", "stable")]
+
+    lpm_app.main(["--format", "json", "list", "--available"])
+    assert json.loads(capsys.readouterr().out) == [{"name": "zlib", "version": "1.2"}]
+    lpm_app.main(["--format", "json", "info", "zlib"])
+    assert json.loads(capsys.readouterr().out)[0]["summary"] == "stable"
@@ -0,0 +1,46 @@
+import pytest
+
+
+@pytest.fixture
+def info(lpm, lpm_app, monkeypatch):
+    universe = {
+        "openssl": [
+            lpm.PkgMeta(
+                name="openssl",
+                version="3.3.1",
+                arch="x86_64",
+                summary="TLS/SSL cryptography library",
+                requires=["zlib"],
+                size=4096,
+                repo="core",
+            )
+        ],
+        "zlib": [lpm.PkgMeta(name="zlib", version="1.3", summary="Compression library")],
+    }
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: universe)
+    return lpm
+
+
+def _fields(out):
+    return dict(line.split(":", 1) for line in out.splitlines() if ":" in line)
+
+
+def test_info_prints_metadata_for_each_package(info, capsys):
+    info.main(["info", "openssl", "zlib"])
+    out = capsys.readouterr().out
+    assert out.count("Name:") == 2
+    fields = _fields(out.split("Name:       zlib")[0])
+    assert fields["Version"].strip() == "3.3.1-1.x86_64"
+    assert fields["Summary"].strip() == "TLS/SSL cryptography library"
+    assert fields["Requires"].strip() == "zlib"
+    assert fields["Size"].strip() == "4096 bytes"
+    assert fields["Repo"].strip() == "core"
+
+
+def test_info_fails_naming_unknown_packages(info, capsys):
+    with pytest.raises(SystemExit) as excinfo:
+        info.main(["info", "zlib", "nosuchpkg"])
+    assert excinfo.value.code != 0
+    captured = capsys.readouterr()
+    assert "Name:       zlib" in captured.out
+    assert "nosuchpkg" in captured.err
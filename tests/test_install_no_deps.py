@@ -0,0 +1,16 @@
+def test_meta_package_pulls_in_its_requirements_unless_no_deps(lpm, make_pkg, tmp_path, monkeypatch):
+    from lpm import app as lpm_app
+
+    root = tmp_path / "root"
+    root.mkdir()
+    _, blob = make_pkg("desktop", "1", {".lpm/README": "meta\n"}, requires=["editor", "browser"])
+    resolved = []
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: None)
+    monkeypatch.setattr(lpm_app, "solve", lambda goals, u: resolved.append(list(goals)) or [])
+    monkeypatch.setattr(lpm_app, "do_install", lambda plan, *args, **kwargs: None)
+
+    lpm.installpkg(blob, root=root, dry_run=False, verify=False, dependencies=False)
+    assert resolved == []
+
+    lpm.installpkg(blob, root=root, dry_run=False, verify=False)
+    assert resolved == [["editor", "browser"]]
@@ -0,0 +1,66 @@
+import json
+
+import pytest
+
+
+def _install(lpm, name, version):
+    conn = lpm.db()
+    conn.execute(
+        "INSERT INTO installed (name,version,release,arch,provides,symbols,requires,manifest,explicit,install_time)"
+        " VALUES (?,?,?,?,?,?,?,?,?,?)",
+        (name, version, "1", "noarch", "[]", "[]", "[]", "[]", 1, 0),
+    )
+    conn.commit()
+    conn.close()
+
+
+def _names(out):
+    return [line.split()[0] for line in out.splitlines()]
+
+
+def test_list_installed_is_the_default_and_reads_the_db_of_that_root(lpm, lpm_app, tmp_path, capsys):
+    _install(lpm, "zlib", "1.3")
+    _install(lpm, "bash", "5.2")
+    root = tmp_path / "root"
+    with lpm_app.root_state(root):
+        _install(lpm, "busybox", "1.36")
+
+    lpm.main(["list"])
+    default = capsys.readouterr().out
+    lpm.main(["list", "--installed"])
+    assert capsys.readouterr().out == default
+    rows = default.splitlines()[3:5]
+    assert _names("\n".join(rows)) == ["bash", "zlib"]
+    assert "5.2-1" in rows[0]
+
+    lpm.main(["list", "--root", str(root)])
+    out = capsys.readouterr().out
+    assert _names(out.splitlines()[3]) == ["busybox"]
+    assert "zlib" not in out and "bash" not in out
+
+
+def test_list_available_shows_newest_versions(lpm, lpm_app, monkeypatch, capsys):
+    universe = {
+        "zlib": [lpm.PkgMeta(name="zlib", version="1.3"), lpm.PkgMeta(name="zlib", version="1.2")],
+        "bash": [lpm.PkgMeta(name="bash", version="5.2")],
+    }
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: universe)
+
+    lpm.main(["list", "--available"])
+    out = capsys.readouterr().out
+    assert [line.split() for line in out.splitlines()] == [["bash", "5.2"], ["zlib", "1.3"]]
+
+    with pytest.raises(SystemExit):
+        lpm.main(["list", "--installed", "--available"])
+
+
+def test_list_json_output(lpm, lpm_app, monkeypatch, capsys):
+    _install(lpm, "bash", "5.2")
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: {"zlib": [lpm.PkgMeta(name="zlib", version="1.3")]})
+
+    lpm.main(["--format", "json", "list"])
+    assert json.loads(capsys.readouterr().out) == [
+        {"name": "bash", "version": "5.2", "release": "1", "arch": "noarch", "install_time": 0, "explicit": True}
+    ]
+    lpm.main(["--format", "json", "list", "--available"])
+    assert json.loads(capsys.readouterr().out) == [{"name": "zlib", "version": "1.3"}]
@@ -0,0 +1,158 @@
+import os
+import textwrap
+import time
+
+import pytest
+
+from lpm.lpmbuild import HookContext, HookScriptError, HookTimeoutError, run_hook, wrap_install_body
+
+
+BODY = textwrap.dedent(
+    """
+    post_install() {
+        printf 'post_install %s %s\\n' "$1" "$PWD" >> "$LPM_ROOT/hook.log"
+    }
+
+    post_upgrade() {
+        printf 'post_upgrade %s %s\\n' "$1" "$2" >> "$LPM_ROOT/hook.log"
+    }
+
+    post_remove() {
+        printf 'post_remove %s\\n' "$1" >> "$LPM_ROOT/hook.log"
+        echo "removed $LPM_PKG"
+    }
+    """
+)
+
+
+def _script(tmp_path, body=BODY):
+    script = tmp_path / ".lpm-install.sh"
+    script.write_text(wrap_install_body(body))
+    script.chmod(0o755)
+    return script
+
+
+def _log(root):
+    return (root / "hook.log").read_text().splitlines()
+
+
+def test_install_context_invokes_post_install(tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = _script(tmp_path)
+
+    result = run_hook(
+        script, HookContext(action="install", root=root, name="foo", new_version="1.0-1")
+    )
+
+    assert result.returncode == 0
+    assert result.context.function == "post_install"
+    assert _log(root) == [f"post_install 1.0-1 {root}"]
+
+
+def test_upgrade_context_invokes_post_upgrade_with_both_versions(tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = _script(tmp_path)
+
+    run_hook(
+        script,
+        HookContext(
+            action="upgrade",
+            root=root,
+            name="foo",
+            new_version="2.0-3",
+            old_version="1.0-1",
+        ),
+    )
+
+    assert _log(root) == [f"post_install 2.0-3 {root}", "post_upgrade 2.0-3 1.0-1"]
+
+
+def test_remove_context_invokes_post_remove_and_captures_output(tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = _script(tmp_path)
+
+    result = run_hook(
+        script, HookContext(action="remove", root=root, name="foo", old_version="2.0-3")
+    )
+
+    assert _log(root) == ["post_remove 2.0-3"]
+    assert result.output == "removed foo\n"
+
+
+def test_nonzero_exit_raises_with_output(tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = _script(tmp_path, "post_install() {\n    echo 'boom' >&2\n    return 3\n}\n")
+
+    with pytest.raises(HookScriptError) as excinfo:
+        run_hook(script, HookContext(action="install", root=root, name="foo", new_version="1-1"))
+
+    assert excinfo.value.result.returncode == 3
+    assert "post_install for foo failed with exit code 3: boom" in str(excinfo.value)
+
+
+def test_host_environment_is_scrubbed(tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    monkeypatch.setenv("LPM_TEST_SECRET", "hunter2")
+    script = _script(
+        tmp_path,
+        "post_install() {\n    echo \"secret=${LPM_TEST_SECRET:-unset} pkg=$LPM_PKG ver=$LPM_NEW_VERSION\"\n}\n",
+    )
+
+    result = run_hook(script, HookContext(action="install", root=root, name="foo", new_version="1-1"))
+
+    assert result.output == "secret=unset pkg=foo ver=1-1\n"
+
+
+def test_hook_exceeding_timeout_is_killed_with_its_children(tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    script = _script(
+        tmp_path,
+        "post_install() {\n    echo started\n    sleep 30 &\n    echo $! > \"$LPM_ROOT/child.pid\"\n    wait\n}\n",
+    )
+
+    start = time.monotonic()
+    with pytest.raises(HookTimeoutError) as excinfo:
+        run_hook(script, HookContext(action="install", root=root, name="foo", new_version="1-1"), timeout=0.5)
+
+    assert time.monotonic() - start < 10
+    assert isinstance(excinfo.value, HookScriptError)
+    assert "post_install for foo timed out after 0.5s: started" in str(excinfo.value)
+    child = int((root / "child.pid").read_text())
+    for _ in range(50):
+        try:
+            os.kill(child, 0)
+        except ProcessLookupError:
+            break
+        time.sleep(0.1)
+    else:
+        pytest.fail("background child of the hook survived the timeout")
+
+
+def test_install_fails_and_rolls_back_when_post_install_times_out(lpm, make_pkg, tmp_path, monkeypatch, capsys):
+    from lpm import config as lpm_config
+
+    root = tmp_path / "root"
+    root.mkdir()
+    _, blob = make_pkg(
+        "slow",
+        "1",
+        {"usr/bin/slow": "bin\n", ".lpm-install.sh": wrap_install_body("post_install() {\n    sleep 30\n}\n")},
+        modes={".lpm-install.sh": 0o755},
+    )
+    monkeypatch.setattr(lpm_config, "HOOK_TIMEOUT", 0.5)
+
+    with pytest.raises(SystemExit):
+        lpm.installpkg(blob, root=root, dry_run=False, verify=False, force=False, explicit=True)
+
+    assert "rollback install slow: post_install for slow timed out after 0.5s" in capsys.readouterr().err
+    conn = lpm.db()
+    try:
+        assert conn.execute("SELECT 1 FROM installed WHERE name='slow'").fetchone() is None
+    finally:
+        conn.close()
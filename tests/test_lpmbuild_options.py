@@ -0,0 +1,76 @@
+import textwrap
+from types import SimpleNamespace
+
+import pytest
+
+import lpm
+
+
+def _write_recipe(tmp_path):
+    script = tmp_path / "opts.lpmbuild"
+    script.write_text(
+        textwrap.dedent(
+            """
+            NAME=opts
+            VERSION=1.0
+            RELEASE=1
+            ARCH=noarch
+            options=ssl,gui
+            REQUIRES=(zlib "ssl? openssl" "gui? gtk3" "!gui? ncurses")
+
+            prepare() { :; }
+            build() { :; }
+            staging() { :; }
+            """
+        ).strip()
+        + "\n",
+        encoding="utf-8",
+    )
+    return script
+
+
+def test_buildpkg_without_option_exports_env_and_drops_dependency(monkeypatch, tmp_path):
+    script = _write_recipe(tmp_path)
+    captured_envs = {}
+
+    def fake_sandboxed_run(func, cwd, env, script_path, stagedir, buildroot, srcroot, aliases=()):
+        captured_envs[func] = dict(env)
+
+    monkeypatch.setattr(lpm, "sandboxed_run", fake_sandboxed_run)
+    monkeypatch.setattr(lpm, "prompt_install_pkg", lambda *args, **kwargs: None)
+
+    args = SimpleNamespace(
+        script=script,
+        overrides=[],
+        outdir=tmp_path,
+        no_deps=True,
+        install_default=None,
+        python_pip=None,
+        force_rebuild=False,
+        with_options=None,
+        without_options=["gui"],
+    )
+
+    lpm.cmd_buildpkg(args)
+
+    build_env = captured_envs["build"]
+    assert build_env["LPM_OPTION_SSL"] == "1"
+    assert build_env["LPM_OPTION_GUI"] == "0"
+    assert build_env["LPM_OPTIONS"] == "ssl"
+
+    meta, _ = lpm.read_package_meta(tmp_path / "opts-1.0-1.noarch.zst")
+    assert meta.requires == ["zlib", "openssl", "ncurses"]
+
+
+def test_declared_options_default_and_unknown_option():
+    declared = lpm._declared_build_options(["ssl", "-docs"])
+    assert declared == {"ssl": True, "docs": False}
+
+    states = lpm._resolve_build_options(declared, lpm.BuildOptions(enable=["docs"]))
+    assert states == {"ssl": True, "docs": True}
+    assert lpm._filter_conditional_entries(["docs? doxygen", "!ssl? nettle"], states) == [
+        "doxygen"
+    ]
+
+    with pytest.raises(SystemExit):
+        lpm._resolve_build_options(declared, lpm.BuildOptions(disable=["gui"]), package="opts")
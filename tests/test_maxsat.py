@@ -0,0 +1,184 @@
+import itertools
+import os
+import random
+import sys
+
+sys.path.insert(0, os.path.dirname(os.path.dirname(__file__)))
+from src.lpm.maxsat import WeightedCNF, at_most_k, minimize_core_guided, minimize_lexicographic, minimize_linear
+from src.lpm.resolver import CNF, CDCLSolver
+
+
+def _versions_instance():
+    """Three packages with newest-first versions whose newest picks clash."""
+
+    cnf = CNF()
+    a = [cnf.new_var(f"a{i}") for i in range(3)]
+    b = [cnf.new_var(f"b{i}") for i in range(2)]
+    c = [cnf.new_var(f"c{i}") for i in range(2)]
+    for group in (a, b, c):
+        cnf.add(group)
+        for x, y in itertools.combinations(group, 2):
+            cnf.add([-x, -y])
+    cnf.add([-a[0], -b[0]])          # newest a conflicts with newest b
+    cnf.add([-a[0], -c[0]])          # ... and with newest c
+    cnf.add([-b[0], c[1]], [-c[1], a[2], b[0]])
+    older = [-v for group in (a, b, c) for v in group[1:]]
+    return cnf, older
+
+
+def _brute_force_optimum(clauses, nvars, soft):
+    best = None
+    for bits in itertools.product([False, True], repeat=nvars):
+        if all(any(bits[abs(l) - 1] == (l > 0) for l in c) for c in clauses):
+            cost = sum(1 for l in soft if bits[abs(l) - 1] != (l > 0))
+            best = cost if best is None else min(best, cost)
+    return best
+
+
+def test_core_guided_agrees_with_linear_search():
+    cnf, older = _versions_instance()
+    linear = minimize_linear(cnf, older)
+
+    cnf, older = _versions_instance()
+    core = minimize_core_guided(cnf, older)
+
+    assert linear.sat and core.sat
+    assert core.cost == linear.cost == 2
+    assert all(any(core.assign[abs(l)] == (l > 0) for l in c) for c in cnf.clauses if c)
+
+
+def test_strategies_match_brute_force_on_random_instances():
+    for seed in range(25):
+        rng = random.Random(seed)
+        clauses = [[v if rng.random() < 0.5 else -v for v in rng.sample(range(1, 9), 3)] for _ in range(20)]
+        soft = [rng.choice((1, -1)) * v for v in range(1, 9)]
+        expected = _brute_force_optimum(clauses, 8, soft)
+
+        results = []
+        for minimize in (minimize_linear, minimize_core_guided):
+            cnf = CNF()
+            for i in range(8):
+                cnf.new_var(str(i))
+            cnf.add(*clauses)
+            results.append(minimize(cnf, soft, CDCLSolver(cnf)))
+
+        if expected is None:
+            assert not any(r.sat for r in results)
+        else:
+            assert [r.cost for r in results] == [expected, expected], seed
+
+
+def test_at_most_k_bounds_true_literals():
+    cnf = CNF()
+    xs = [cnf.new_var(f"x{i}") for i in range(4)]
+    at_most_k(cnf, xs, 2)
+    assert CDCLSolver(cnf).solve(xs[:2]).sat
+    res = CDCLSolver(cnf).solve(xs[:3])
+    assert not res.sat
+    assert sorted(res.failed_assumptions) == sorted(xs[:3])
+
+
+def test_weighted_maxsat_violates_the_cheaper_soft_clause():
+    wcnf = WeightedCNF()
+    new, old, extra = (wcnf.cnf.new_var(n) for n in ("pkg==2", "pkg==1", "extra"))
+    wcnf.add_clause([new, old])
+    wcnf.add_clause([-new, -old])
+    wcnf.add_clause([-new, extra])
+    wcnf.add_soft_clause([new], 10)       # prefer the newest version
+    wcnf.add_soft_clause([-extra], 4)     # ... over avoiding an extra install
+    wcnf.add_soft_clause([old, -extra], 2)
+
+    best = wcnf.solve_maxsat()
+    assert best.sat
+    assert best.assign[new] and best.assign[extra]
+    assert best.cost == 6
+    assert best.cost == min(
+        wcnf.cost({new: n, old: not n, extra: e}) for n in (False, True) for e in (False, True) if e or not n
+    )
+
+
+def test_weighted_maxsat_does_not_grow_with_the_weights():
+    wcnf = WeightedCNF()
+    x, y = wcnf.cnf.new_var("x"), wcnf.cnf.new_var("y")
+    wcnf.add_clause([-x, -y])
+    wcnf.add_soft_clause([x], 10**9)
+    wcnf.add_soft_clause([y], 10**9 - 1)
+    wcnf.add_soft_clause([x, y], 5)
+    variables = wcnf.cnf.next_var
+
+    best = wcnf.solve_maxsat()
+    assert best.sat and best.assign[x] and not best.assign[y]
+    assert best.cost == 10**9 - 1
+    assert wcnf.cnf.next_var - variables < 10
+
+
+def test_solve_optimizes_newest_versions(monkeypatch):
+    import src.lpm.app as app
+    from src.lpm.app import PkgMeta, Universe, register_universe_candidate
+
+    def plan(mode):
+        monkeypatch.setattr(app._config, "SOLVER_OPTIMIZE", mode)
+        universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+        for pkg in (
+            PkgMeta(name="foo", version="2.0", requires=["lib>=2.0"]),
+            PkgMeta(name="foo", version="1.0", requires=["lib"]),
+            PkgMeta(name="lib", version="2.0", conflicts=["baz"]),
+            PkgMeta(name="lib", version="1.0"),
+            PkgMeta(name="baz", version="3.0"),
+            PkgMeta(name="baz", version="2.0"),
+        ):
+            register_universe_candidate(universe, pkg)
+        return {p.name: p.version for p in app.solve(["foo", "baz"], universe)}
+
+    expected = {"foo": "1.0", "lib": "1.0", "baz": "3.0"}
+    assert plan("linear") == expected
+    assert plan("core") == expected
+
+
+def test_resolution_policies_pick_the_expected_version():
+    from src.lpm.app import PkgMeta, ResolutionPolicy, Universe, register_universe_candidate, solve
+
+    def plan(policy, installed=None):
+        universe = Universe(candidates_by_name={}, providers={}, installed=installed or {}, pins={}, holds=set())
+        for pkg in (
+            PkgMeta(name="app", version="1.0", requires=["lib>=1.0"]),
+            PkgMeta(name="lib", version="1.2"),
+            PkgMeta(name="lib", version="1.1"),
+            PkgMeta(name="lib", version="1.0"),
+        ):
+            register_universe_candidate(universe, pkg)
+        return {p.name: p.version for p in solve(["app"], universe, policy=policy)}
+
+    installed = {"lib": {"version": "1.1", "release": "1", "provides": [], "requires": []}}
+    # Phase bias would keep the installed lib; only the policy moves it.
+    assert plan(ResolutionPolicy.PREFER_NEWEST, installed)["lib"] == "1.2"
+    assert plan(ResolutionPolicy.PREFER_INSTALLED, installed)["lib"] == "1.1"
+    assert plan(ResolutionPolicy.PREFER_INSTALLED)["lib"] == "1.2"
+    assert plan("minimal")["lib"] == "1.0"
+
+
+def test_policies_compare_versions_across_repository_priorities():
+    from src.lpm.app import PkgMeta, ResolutionPolicy, Universe, register_universe_candidate, solve
+
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in (
+        PkgMeta(name="app", version="1.0", requires=["lib"]),
+        # The higher-priority repository ships the older lib, so it sorts first.
+        PkgMeta(name="lib", version="1.0", prio=20),
+        PkgMeta(name="lib", version="1.2", prio=10),
+        PkgMeta(name="lib", version="1.1", prio=10),
+    ):
+        register_universe_candidate(universe, pkg)
+
+    assert {p.name: p.version for p in solve(["app"], universe, policy=ResolutionPolicy.PREFER_NEWEST)}["lib"] == "1.2"
+    assert {p.name: p.version for p in solve(["app"], universe, policy=ResolutionPolicy.MINIMAL)}["lib"] == "1.0"
+
+
+def test_lexicographic_bounds_do_not_stay_in_the_cnf():
+    cnf, older = _versions_instance()
+    solver = CDCLSolver(cnf)
+    best = minimize_lexicographic(cnf, [older], solver)
+    assert best.sat and best.cost == 2
+
+    # A model of cost 3 (older a, b and c) is still admitted afterwards.
+    assert solver.solve_under_assumptions([-lit for lit in older[1:]]).sat
@@ -0,0 +1,109 @@
+import hashlib
+import json
+
+import pytest
+
+import lpm
+from lpm import app as lpm_app
+
+
+@pytest.fixture
+def served(tmp_path, monkeypatch):
+    srv = tmp_path / "srv"
+    srv.mkdir()
+    archive = b"archive-bytes"
+    (srv / "foo-1.0-1.noarch.zst").write_bytes(archive)
+    (srv / "index.json").write_text(
+        json.dumps(
+            {
+                "packages": [
+                    {
+                        "name": "foo",
+                        "version": "1.0",
+                        "release": "1",
+                        "arch": "noarch",
+                        "blob": "https://repo.example.com/foo-1.0-1.noarch.zst",
+                        "sha256": hashlib.sha256(archive).hexdigest(),
+                    }
+                ]
+            }
+        ),
+        encoding="utf-8",
+    )
+    calls = []
+
+    def fake_urlread(url, timeout=10):
+        calls.append(url)
+        path = srv / url.rsplit("/", 1)[-1]
+        if not path.exists():
+            raise RuntimeError(f"Failed to read URL {url}")
+        return path.read_bytes(), url
+
+    cache = tmp_path / "cache"
+    cache.mkdir()
+    monkeypatch.setattr(lpm, "urlread", fake_urlread)
+    monkeypatch.setattr(lpm, "CACHE_DIR", cache)
+    monkeypatch.setattr(lpm_app, "_cache_path_for", lambda url: cache / url.rsplit("/", 1)[-1])
+    return lpm.Repo(name="main", url="https://repo.example.com"), calls
+
+
+def test_offline_resolves_and_fetches_from_cache(served):
+    repo, calls = served
+    (pkg,) = lpm.fetch_repo_index(repo, allow_unsigned=True)
+    lpm.fetch_blob(pkg)
+    calls.clear()
+
+    with lpm.offline_mode():
+        pkgs = lpm.fetch_repo_index(repo, allow_unsigned=True)
+        universe = lpm.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+        for p in pkgs:
+            lpm.register_universe_candidate(universe, p)
+        plan = lpm.solve(["foo"], universe)
+        blob, _ = lpm.fetch_blob(plan[0])
+
+    assert [p.name for p in plan] == ["foo"]
+    assert blob.read_bytes() == b"archive-bytes"
+    assert calls == []
+
+
+def test_offline_missing_artifacts_raise_offline_error(served):
+    repo, calls = served
+
+    with lpm.offline_mode():
+        with pytest.raises(lpm.OfflineError, match="index is not cached"):
+            lpm.fetch_repo_index(repo, allow_unsigned=True)
+
+    (pkg,) = lpm.fetch_repo_index(repo, allow_unsigned=True)
+    calls.clear()
+    with lpm.offline_mode():
+        with pytest.raises(lpm.OfflineError, match="foo-1.0-1.noarch.zst is not cached"):
+            lpm.fetch_blob(pkg)
+    assert calls == []
+
+
+def test_offline_can_be_passed_to_fetch_blob(served):
+    repo, calls = served
+    (pkg,) = lpm.fetch_repo_index(repo, allow_unsigned=True)
+    calls.clear()
+
+    with pytest.raises(lpm.OfflineError, match="foo-1.0-1.noarch.zst is not cached"):
+        lpm.fetch_blob(pkg, offline=True)
+    assert calls == []
+
+
+def test_offline_cached_archive_without_checksum_needs_a_signature(served, monkeypatch):
+    repo, calls = served
+    (pkg,) = lpm.fetch_repo_index(repo, allow_unsigned=True)
+    lpm.fetch_blob(pkg)
+    pkg.sha256 = ""
+    calls.clear()
+
+    # No signature in the cache: the archive cannot be trusted, nor downloaded again.
+    with pytest.raises(lpm.OfflineError, match="is not cached"):
+        lpm.fetch_blob(pkg, offline=True)
+
+    checked = []
+    monkeypatch.setattr(lpm_app, "verify_signature", lambda blob, sig: checked.append(blob))
+    blob, _ = lpm.fetch_blob(pkg, offline=True)
+    assert checked == [blob]
+    assert calls == []
@@ -0,0 +1,53 @@
+import io
+
+import pytest
+
+from lpm import output
+
+
+class _Tty(io.StringIO):
+    def isatty(self):
+        return True
+
+
+@pytest.fixture(autouse=True)
+def _reset_mode(monkeypatch):
+    monkeypatch.setattr(output, "_mode", "auto")
+    monkeypatch.delenv("NO_COLOR", raising=False)
+
+
+def test_always_emits_color_even_when_piped():
+    output.set_color_mode("always")
+    assert output.red("[ERROR]", io.StringIO()) == f"{output.RED}[ERROR]{output.RESET}"
+
+
+def test_never_suppresses_color_on_terminal():
+    output.set_color_mode("never")
+    assert output.green("[OK]", _Tty()) == "[OK]"
+
+
+def test_auto_colors_terminal_but_not_pipe():
+    assert output.cyan("[WARN]", _Tty()) == f"{output.CYAN}[WARN]{output.RESET}"
+    assert output.cyan("[WARN]", io.StringIO()) == "[WARN]"
+
+
+def test_no_color_env_suppresses_color(monkeypatch):
+    monkeypatch.setenv("NO_COLOR", "1")
+    assert not output.color_enabled(_Tty())
+    assert output.purple("msg", _Tty()) == "msg"
+
+
+def test_invalid_mode_rejected():
+    with pytest.raises(ValueError):
+        output.set_color_mode("sometimes")
+
+
+def test_cli_messages_are_plain_when_piped(monkeypatch, capsys):
+    from lpm import app as lpm_app
+
+    lpm_app.warn("careful")
+    lpm_app.print_plan([lpm_app.PkgMeta(name="foo", version="1.0")], {"foo": "2.0"})
+    err = capsys.readouterr().err
+    assert "\033[" not in err
+    assert "[WARN] careful" in err
+    assert "  - foo-1.0 (downgrade from 2.0)" in err
@@ -0,0 +1,53 @@
+import pytest
+
+import src.lpm.app as lpm
+
+
+def _universe(*pkgs):
+    universe = lpm.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in pkgs:
+        lpm.register_universe_candidate(universe, pkg)
+    return universe
+
+
+def test_plan_orders_dependencies_before_dependents():
+    a = lpm.PkgMeta(name="a", version="1", requires=["b"])
+    b = lpm.PkgMeta(name="b", version="1", requires=["c>=1"])
+    c = lpm.PkgMeta(name="c", version="1")
+    universe = _universe(a, b, c)
+
+    assert [p.name for p in lpm.order_plan([a, b, c], universe)] == ["c", "b", "a"]
+    assert [p.name for p in lpm.solve(["a"], universe)] == ["c", "b", "a"]
+
+
+def test_plan_follows_virtual_provides_and_keeps_input_order_within_a_wave():
+    app = lpm.PkgMeta(name="app", version="1", requires=["libssl | libressl", "zlib"])
+    openssl = lpm.PkgMeta(name="openssl", version="3", provides=["libssl"], requires=["openssl"])
+    zlib = lpm.PkgMeta(name="zlib", version="1")
+    universe = _universe(app, openssl, zlib)
+
+    plan = lpm.order_plan([app, zlib, openssl], universe)
+
+    assert [p.name for p in plan] == ["zlib", "openssl", "app"]
+    assert lpm.plan_edges([app, zlib, openssl], universe) == {
+        "app": ["openssl", "zlib"],
+        "zlib": [],
+        "openssl": [],
+    }
+
+
+def test_dependency_cycle_is_reported_with_its_members():
+    a = lpm.PkgMeta(name="a", version="1", requires=["b"])
+    b = lpm.PkgMeta(name="b", version="1", requires=["c"])
+    c = lpm.PkgMeta(name="c", version="1", requires=["a"])
+    d = lpm.PkgMeta(name="d", version="1")
+    e = lpm.PkgMeta(name="e", version="1", requires=["a"])
+    universe = _universe(a, b, c, d, e)
+
+    with pytest.raises(lpm.PlanCycleError) as excinfo:
+        lpm.order_plan([e, d, c, b, a], universe)
+
+    assert excinfo.value.cycle == ["a", "b", "c"]
+    assert str(excinfo.value) == "dependency cycle: a -> b -> c -> a"
+    assert isinstance(excinfo.value, lpm.PlanError)
+    assert isinstance(excinfo.value, lpm.ResolutionError)
@@ -152,3 +152,193 @@ def test_require_root_returns_for_root(monkeypatch, capsys):
         assert capsys.readouterr().err == ""
     finally:
         cleanup()
+
+
+def test_polkit_gate_reexecs_with_original_arguments(monkeypatch, capsys):
+    module, _calls, cleanup = _reload_privileges(
+        monkeypatch,
+        real_ids=(1000, 1000),
+        effective_ids=(1000, 1000),
+    )
+    try:
+        argv = ["/usr/bin/lpm", "--yes", "install", "--no-recommends", "foo"]
+        helpers = {"pkexec": "/usr/bin/pkexec", "sudo": "/usr/bin/sudo"}
+        execs = []
+
+        def make_gate(found):
+            return module.PolkitPrivilegeGate(
+                argv=argv,
+                which=lambda name: helpers[name] if name in found else None,
+                execv=lambda path, args: execs.append((path, args)),
+            )
+
+        make_gate({"pkexec", "sudo"}).escalate("install")
+        make_gate({"sudo"}).escalate("install")
+        assert execs == [
+            ("/usr/bin/pkexec", ["/usr/bin/pkexec", sys.executable, *argv]),
+            ("/usr/bin/sudo", ["/usr/bin/sudo", sys.executable, *argv]),
+        ]
+
+        assert make_gate({"sudo"}).preflight("install") is None
+        assert make_gate(set()).preflight("install") == module.NoEscalationHelper("install", ("pkexec", "sudo"))
+        assert module.DefaultPrivilegeGate().preflight("install") == module.NotRoot("install")
+
+        module.set_privilege_gate(make_gate(set()))
+        with pytest.raises(SystemExit):
+            module.require_root("install")
+        assert "no pkexec or sudo found" in capsys.readouterr().err
+        assert len(execs) == 2
+    finally:
+        cleanup()
+
+
+def test_polkit_gate_does_nothing_for_root(monkeypatch):
+    module, _calls, cleanup = _reload_privileges(
+        monkeypatch,
+        real_ids=(0, 0),
+        effective_ids=(0, 0),
+    )
+    try:
+        gate = module.PolkitPrivilegeGate(argv=["lpm", "remove", "foo"], execv=lambda *a: pytest.fail("re-executed"))
+        gate.escalate("remove")
+        assert isinstance(module.gate_for("none"), module.DefaultPrivilegeGate)
+        assert module.gate_for("sudo").helpers == ("sudo",)
+    finally:
+        cleanup()
+
+
+def test_allowlist_gate_checks_ids_per_command():
+    from lpm.privileges import AllowlistPrivilegeGate, EscalationError
+
+    def gate(euid, egid):
+        return AllowlistPrivilegeGate(uids=[1000], gids=[50], commands={"install"}, ids=lambda: (euid, egid))
+
+    listed = gate(1000, 1000)
+    assert listed.is_allowed("install")
+    assert not listed.is_allowed("remove")
+    listed.escalate("install")
+    with pytest.raises(EscalationError):
+        listed.escalate("remove")
+
+    assert gate(1001, 50).is_allowed("install")
+
+    stranger = gate(1001, 1001)
+    assert not any(stranger.is_allowed(cmd) for cmd in ("install", "remove", "upgrade", "rollback"))
+
+    assert gate(0, 0).is_allowed("remove")
+
+
+def test_require_root_reports_each_denial_reason(monkeypatch, capsys):
+    module, _calls, cleanup = _reload_privileges(
+        monkeypatch,
+        real_ids=(1000, 1000),
+        effective_ids=(1000, 1000),
+    )
+    try:
+        monkeypatch.setattr(sys, "argv", ["lpm", "remove", "foo"])
+        gates = [
+            module.DefaultPrivilegeGate(),
+            module.AllowlistPrivilegeGate(uids=[2000], commands={"remove"}, ids=lambda: (1000, 1000)),
+            module.AllowlistPrivilegeGate(uids=[1000], commands={"install"}, ids=lambda: (1000, 1000)),
+            module.PolkitPrivilegeGate(which=lambda name: None),
+        ]
+        messages = []
+        for gate in gates:
+            module.set_privilege_gate(gate)
+            with pytest.raises(SystemExit):
+                module.require_root("remove")
+            messages.append(capsys.readouterr().err.splitlines()[0])
+
+        assert messages == [
+            "lpm: remove requires root privileges",
+            "lpm: remove requires root privileges: uid 1000 (gid 1000) is not in the allowlist",
+            "lpm: remove is not enabled for allowlisted users and requires root privileges",
+            "lpm: remove requires root privileges (no pkexec or sudo found)",
+        ]
+        with pytest.raises(module.EscalationError) as exc_info:
+            gates[1].escalate("remove")
+        assert exc_info.value.reason == module.NotInAllowlist("remove", 1000, 1000)
+    finally:
+        cleanup()
+
+
+def test_capability_gate_checks_required_caps_not_uid(monkeypatch):
+    module, _calls, cleanup = _reload_privileges(
+        monkeypatch,
+        real_ids=(1000, 1000),
+        effective_ids=(1000, 1000),
+    )
+    try:
+        caps = module.CAPABILITIES
+        dac_fowner = 1 << caps["CAP_DAC_OVERRIDE"] | 1 << caps["CAP_FOWNER"]
+
+        holder = module.CapabilityPrivilegeGate(effective=lambda: dac_fowner)
+        assert holder.is_allowed("remove")
+        assert holder.explain("install") == module.MissingCapabilities("install", ("CAP_CHOWN", "CAP_FSETID"))
+        assert isinstance(holder.explain("repoadd"), module.NotRoot)
+
+        lacking = module.CapabilityPrivilegeGate({"remove": ["CAP_SYS_ADMIN"]}, effective=lambda: dac_fowner)
+        with pytest.raises(module.EscalationError, match="remove requires CAP_SYS_ADMIN"):
+            lacking.escalate("remove")
+    finally:
+        cleanup()
+
+
+def test_configuration_selects_the_allowlist_and_capability_gates():
+    from lpm import app, config
+    from lpm.privileges import AllowlistPrivilegeGate, CapabilityPrivilegeGate
+
+    original_conf = dict(config.CONF)
+    try:
+        config._apply_conf(
+            {
+                "PRIVILEGE_ESCALATION": "allowlist",
+                "PRIVILEGE_ALLOW_USERS": "root, 1234, no-such-user-lpm",
+                "PRIVILEGE_ALLOW_GROUPS": "4321",
+                "PRIVILEGE_ALLOW_COMMANDS": "install upgrade",
+            }
+        )
+        gate = app.configured_gate()
+        assert isinstance(gate, AllowlistPrivilegeGate)
+        assert gate.uids == {0, 1234}
+        assert gate.gids == {4321}
+        assert gate.commands == {"install", "upgrade"}
+
+        config._apply_conf({"PRIVILEGE_ESCALATION": "capabilities"})
+        assert isinstance(app.configured_gate(), CapabilityPrivilegeGate)
+    finally:
+        config._apply_conf(original_conf)
+
+
+def test_explicit_gate_is_not_replaced_by_the_configured_one():
+    from lpm import privileges
+
+    explicit = privileges.AllowlistPrivilegeGate(uids=[1000], commands={"install"}, ids=lambda: (1000, 1000))
+    try:
+        privileges.set_privilege_gate(explicit)
+        privileges.use_configured_gate(privileges.DefaultPrivilegeGate())
+        assert privileges.privilege_gate() is explicit
+        privileges.require_root("install")
+    finally:
+        privileges.set_privilege_gate(None)
+    assert isinstance(privileges.privilege_gate(), privileges.DefaultPrivilegeGate)
+
+
+def test_main_restarts_with_the_argv_it_was_given(lpm, lpm_app, monkeypatch):
+    installed = []
+
+    class Stop(Exception):
+        pass
+
+    def capture(gate):
+        installed.append(gate)
+        raise Stop
+
+    monkeypatch.setattr(lpm_app, "use_configured_gate", capture)
+    monkeypatch.setattr(sys, "argv", ["/usr/bin/host-program", "--unrelated"])
+
+    with pytest.raises(Stop):
+        lpm.main(["install", "--no-recommends", "foo"])
+
+    (gate,) = installed
+    assert gate.command_line() == [sys.executable, "-m", "lpm", "install", "--no-recommends", "foo"]
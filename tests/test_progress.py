@@ -0,0 +1,122 @@
+import io
+import os
+import subprocess
+import sys
+from pathlib import Path
+
+import pytest
+
+from lpm import progress
+
+
+class _Clock:
+    def __init__(self):
+        self.now = 0.0
+
+    def __call__(self):
+        return self.now
+
+
+def test_model_reports_completion_fractions():
+    model = progress.ProgressModel(total=1000)
+    fractions = []
+    for chunk in (100, 250, 150, 500):
+        model.advance(chunk)
+        fractions.append(model.fraction)
+    assert fractions == [0.1, 0.35, 0.5, 1.0]
+
+
+def test_model_clamps_and_rewinds():
+    model = progress.ProgressModel(total=100)
+    model.advance(80)
+    model.advance(-80)
+    assert model.fraction == 0.0
+    model.advance(150)
+    assert model.fraction == 1.0
+    assert progress.ProgressModel().fraction is None
+
+
+def test_batch_total_grows_with_added_items():
+    model = progress.ProgressModel()
+    model.add_total(400)
+    model.add_total(600)
+    model.advance(250)
+    assert model.fraction == pytest.approx(0.25)
+
+
+def test_non_tty_prints_periodic_lines():
+    clock = _Clock()
+    stream = io.StringIO()
+    with progress.track("Fetching", 2048, stream=stream, clock=clock, interval=5.0) as bar:
+        bar(512)
+        clock.now = 6.0
+        bar(512)
+        bar(512)
+        clock.now = 12.0
+        bar(512)
+    lines = stream.getvalue().splitlines()
+    assert lines == [
+        "Fetching:  50% (1.0 KiB of 2.0 KiB)",
+        "Fetching: 100% (2.0 KiB of 2.0 KiB)",
+        "Fetching: 100% (2.0 KiB of 2.0 KiB), done",
+    ]
+    assert bar.fraction == 1.0
+
+
+def test_quiet_is_silent_on_terminal(monkeypatch):
+    class _Tty(io.StringIO):
+        def isatty(self):
+            return True
+
+    stream = _Tty()
+    monkeypatch.setattr(progress, "_quiet", True)
+    with progress.track("Extracting foo", stream=stream) as bar:
+        bar.start(10)
+        bar.inc(10)
+    assert isinstance(bar, progress.NullProgress)
+    assert stream.getvalue() == ""
+
+
+def test_terminal_bar_moves_back_on_rewinds(monkeypatch):
+    monkeypatch.setattr(progress, "_quiet", False)
+    monkeypatch.setattr(progress._output, "is_terminal", lambda stream: True)
+    with progress.track("Fetching", 100, stream=io.StringIO()) as bar:
+        bar.inc(80)
+        bar.inc(-80)
+        bar.inc(30)
+        assert bar._renderer._bar.n == 30
+        bar.inc(-50)
+        assert bar._renderer._bar.n == 0
+
+
+def test_start_sets_total_and_counts_items():
+    clock = _Clock()
+    stream = io.StringIO()
+    bar = progress.track("Installing", unit="pkg", stream=stream, clock=clock, interval=5.0)
+    bar.start(4)
+    bar.inc(1)
+    clock.now = 6.0
+    bar.inc(1)
+    bar.inc(2)
+    bar.finish()
+    bar.finish()
+    assert stream.getvalue().splitlines() == [
+        "Installing:  50% (2 pkg of 4 pkg)",
+        "Installing: 100% (4 pkg of 4 pkg), done",
+    ]
+
+
+@pytest.mark.parametrize("module", ["lpm.progress", "lpm.httpfetch"])
+def test_module_imports_in_a_fresh_interpreter(module, tmp_path):
+    env = dict(os.environ)
+    src_root = Path(__file__).resolve().parent.parent / "src"
+    env["PYTHONPATH"] = os.pathsep.join(filter(None, [str(src_root), env.get("PYTHONPATH", "")]))
+    env["LPM_STATE_DIR"] = str(tmp_path / "state")
+    result = subprocess.run(
+        [sys.executable, "-c", f"import {module}"],
+        env=env,
+        cwd=tmp_path,
+        capture_output=True,
+        text=True,
+    )
+    assert result.returncode == 0, result.stderr
@@ -0,0 +1,44 @@
+from src.lpm.app import PkgMeta, Universe, register_universe_candidate, solve
+
+
+def _universe(*pkgs):
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in pkgs:
+        register_universe_candidate(universe, pkg)
+    return universe
+
+
+def _names(plan):
+    return sorted(p.name for p in plan)
+
+
+def test_recommended_package_is_selected_by_default():
+    universe = _universe(
+        PkgMeta(name="foo", version="1.0", recommends=["bar"]),
+        PkgMeta(name="bar", version="1.0"),
+        PkgMeta(name="unrelated", version="1.0"),
+    )
+
+    assert _names(solve(["foo"], universe)) == ["bar", "foo"]
+    assert _names(solve(["foo"], universe, recommends=False)) == ["foo"]
+
+
+def test_recommended_package_dropped_when_it_conflicts_with_hard_requirement():
+    universe = _universe(
+        PkgMeta(name="foo", version="1.0", recommends=["bar"], requires=["qux"]),
+        PkgMeta(name="bar", version="1.0"),
+        PkgMeta(name="qux", version="1.0", conflicts=["bar"]),
+    )
+
+    assert _names(solve(["foo"], universe)) == ["foo", "qux"]
+
+
+def test_resolver_declines_as_few_recommendations_as_possible():
+    universe = _universe(
+        PkgMeta(name="foo", version="1.0", recommends=["x", "y", "z"]),
+        PkgMeta(name="x", version="1.0", conflicts=["y", "z"]),
+        PkgMeta(name="y", version="1.0"),
+        PkgMeta(name="z", version="1.0"),
+    )
+
+    assert _names(solve(["foo"], universe)) == ["foo", "y", "z"]
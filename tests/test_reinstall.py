@@ -0,0 +1,77 @@
+from types import SimpleNamespace
+
+import pytest
+
+
+def _reinstall(lpm, monkeypatch, root, meta, blob, names=("foo",)):
+    from lpm import app as lpm_app
+
+    for target in (lpm, lpm_app):
+        monkeypatch.setattr(target, "load_universe", lambda: {meta.name: [meta]}, raising=False)
+        monkeypatch.setattr(target, "fetch_all", lambda pkgs: {p.name: (blob, None) for p in pkgs}, raising=False)
+    args = SimpleNamespace(names=list(names), root=str(root), dry_run=False, no_verify=True, force=False)
+    lpm.cmd_reinstall(args)
+
+
+def test_collect_manifest_marks_etc_files_as_config(lpm, tmp_path):
+    staged = tmp_path / "stage"
+    (staged / "etc").mkdir(parents=True)
+    (staged / "usr/bin").mkdir(parents=True)
+    (staged / "etc/foo.conf").write_text("x=1\n")
+    (staged / "usr/bin/foo").write_text("bin\n")
+
+    flags = {e["path"]: e.get("config", False) for e in lpm.collect_manifest(staged)}
+    assert flags == {"/etc/foo.conf": True, "/usr/bin/foo": False}
+
+
+def test_reinstall_restores_payload_and_keeps_edited_config(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    meta, blob = make_pkg(
+        "foo",
+        "1",
+        {"usr/bin/foo": "#!/bin/sh\necho foo\n", "etc/foo.conf": "x=1\n", "etc/foo.d/defaults": "y=2\n"},
+    )
+    lpm.installpkg(blob, root=root, dry_run=False, verify=False, force=False, explicit=True)
+
+    (root / "usr/bin/foo").write_text("corrupted")
+    (root / "etc/foo.conf").write_text("x=42\n")
+    (root / "etc/foo.d/defaults").unlink()
+
+    _reinstall(lpm, monkeypatch, root, meta, blob)
+
+    assert (root / "usr/bin/foo").read_text() == "#!/bin/sh\necho foo\n"
+    assert (root / "etc/foo.conf").read_text() == "x=42\n"
+    assert (root / "etc/foo.d/defaults").read_text() == "y=2\n"
+
+    conn = lpm.db()
+    try:
+        row = conn.execute("SELECT version, explicit FROM installed WHERE name='foo'").fetchone()
+    finally:
+        conn.close()
+    assert tuple(row) == ("1", 1)
+
+
+def test_reinstall_keeps_edited_etc_files_of_a_manifest_without_config_flags(lpm, make_pkg, tmp_path, monkeypatch):
+    root = tmp_path / "root"
+    root.mkdir()
+    meta, blob = make_pkg("foo", "1", {"usr/bin/foo": "bin\n", "etc/foo.conf": "x=1\n"}, config_flags=False)
+    lpm.installpkg(blob, root=root, dry_run=False, verify=False, force=False, explicit=True)
+
+    (root / "usr/bin/foo").write_text("corrupted")
+    (root / "etc/foo.conf").write_text("x=42\n")
+
+    _reinstall(lpm, monkeypatch, root, meta, blob)
+
+    assert (root / "usr/bin/foo").read_text() == "bin\n"
+    assert (root / "etc/foo.conf").read_text() == "x=42\n"
+
+
+def test_reinstall_rejects_packages_that_are_not_installed(lpm, make_pkg, tmp_path, monkeypatch, capsys):
+    root = tmp_path / "root"
+    root.mkdir()
+    meta, blob = make_pkg("foo", "1", {"usr/bin/foo": "bin\n"})
+
+    with pytest.raises(SystemExit):
+        _reinstall(lpm, monkeypatch, root, meta, blob, names=("foo",))
+    assert "Not installed: foo" in capsys.readouterr().err
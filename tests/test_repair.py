@@ -0,0 +1,53 @@
+import json
+from types import SimpleNamespace
+
+
+def _install(lpm, root, name, paths):
+    for path in paths:
+        target = root / path.lstrip("/")
+        target.parent.mkdir(parents=True, exist_ok=True)
+        target.write_text(name)
+    conn = lpm.db()
+    conn.execute(
+        "INSERT INTO installed (name,version,release,arch,provides,symbols,requires,manifest,explicit,install_time)"
+        " VALUES (?,?,?,?,?,?,?,?,?,?)",
+        (name, "1", "1", "noarch", "[]", "[]", "[]", json.dumps([{"path": p} for p in paths]), 1, 0),
+    )
+    conn.commit()
+    conn.close()
+
+
+def _broken(lpm, name):
+    conn = lpm.db()
+    row = conn.execute("SELECT broken FROM installed WHERE name=?", (name,)).fetchone()
+    conn.close()
+    return bool(row[0])
+
+
+def test_repair_flags_package_with_deleted_files(lpm, tmp_path):
+    root = tmp_path / "root"
+    _install(lpm, root, "foo", ["/usr/bin/foo", "/usr/share/foo/data"])
+    _install(lpm, root, "bar", ["/usr/bin/bar"])
+    (root / "usr/share/foo/data").unlink()
+    (root / "usr/bin/stray").write_text("x")
+
+    report = lpm.repair_db(root)
+    assert report.missing == {"foo": ["/usr/share/foo/data"]}
+    assert report.orphans == ["/usr/bin/stray"]
+    assert report.marked == ["foo"]
+    assert not _broken(lpm, "foo")
+
+    lpm.repair_db(root, fix=True)
+    assert _broken(lpm, "foo")
+    assert not _broken(lpm, "bar")
+
+
+def test_repair_reports_clean_database(lpm, tmp_path, capsys):
+    root = tmp_path / "root"
+    _install(lpm, root, "foo", ["/usr/bin/foo"])
+
+    assert lpm.repair_db(root).clean
+
+    lpm.cmd_repair(SimpleNamespace(root=str(root), fix=False))
+    out = capsys.readouterr()
+    assert "no repairs needed" in out.out + out.err
@@ -0,0 +1,89 @@
+import json
+import subprocess
+
+import pytest
+
+import lpm
+
+
+def _keypair(tmp_path, name):
+    priv = tmp_path / f"{name}.key"
+    pub = tmp_path / f"{name}.pem"
+    subprocess.run(
+        ["openssl", "genpkey", "-algorithm", "RSA", "-pkeyopt", "rsa_keygen_bits:2048", "-out", str(priv)],
+        check=True,
+        capture_output=True,
+    )
+    subprocess.run(["openssl", "pkey", "-in", str(priv), "-pubout", "-out", str(pub)], check=True, capture_output=True)
+    return priv, pub
+
+
+@pytest.fixture
+def signed_repo(tmp_path, monkeypatch):
+    priv, pub = _keypair(tmp_path, "repo")
+    trust = tmp_path / "trust"
+    trust.mkdir()
+    (trust / "repo.pem").write_bytes(pub.read_bytes())
+    monkeypatch.setattr(lpm, "TRUST_DIR", trust)
+
+    served = tmp_path / "srv"
+    served.mkdir()
+    index = served / "index.json"
+    index.write_text(
+        json.dumps({"packages": [{"name": "foo", "version": "1.0", "release": "1", "arch": "noarch"}]}),
+        encoding="utf-8",
+    )
+    assert lpm.sign_index(index, priv) == served / "index.json.sig"
+
+    def fake_urlread(url, timeout=10):
+        path = served / url.rsplit("/", 1)[-1]
+        if not path.exists():
+            raise RuntimeError(f"Failed to read URL {url}")
+        return path.read_bytes(), url
+
+    monkeypatch.setattr(lpm, "urlread", fake_urlread)
+    return lpm.Repo(name="main", url="https://repo.example.com"), index
+
+
+def test_signed_index_is_accepted(signed_repo):
+    repo, _ = signed_repo
+    pkgs = lpm.fetch_repo_index(repo, allow_unsigned=False)
+    assert [p.name for p in pkgs] == ["foo"]
+
+
+def test_tampered_index_is_rejected(signed_repo):
+    repo, index = signed_repo
+    index.write_text(index.read_text().replace("1.0", "6.6"), encoding="utf-8")
+
+    with pytest.raises(lpm.RepoIndexError, match="signature verification failed"):
+        lpm.fetch_repo_index(repo, allow_unsigned=False)
+
+
+def test_untrusted_signer_is_rejected(signed_repo, tmp_path):
+    repo, index = signed_repo
+    rogue, _ = _keypair(tmp_path, "rogue")
+    lpm.sign_index(index, rogue)
+
+    with pytest.raises(lpm.RepoIndexError, match="signature verification failed"):
+        lpm.fetch_repo_index(repo, allow_unsigned=False)
+
+
+def test_unsigned_index_requires_explicit_bypass(signed_repo):
+    repo, index = signed_repo
+    (index.parent / "index.json.sig").unlink()
+
+    with pytest.raises(lpm.RepoIndexError, match="not signed"):
+        lpm.fetch_repo_index(repo, allow_unsigned=False)
+
+    pkgs = lpm.fetch_repo_index(repo, allow_unsigned=True)
+    assert [p.name for p in pkgs] == ["foo"]
+
+
+def test_trust_store_without_usable_keys_is_a_repo_index_error(tmp_path, monkeypatch):
+    trust = tmp_path / "trust"
+    trust.mkdir()
+    (trust / "broken.pem").write_text("not a key\n")
+    monkeypatch.setattr(lpm, "TRUST_DIR", trust)
+
+    with pytest.raises(lpm.RepoIndexError, match="no trusted public keys"):
+        lpm.verify_index_signature(lpm.Repo(name="main", url="https://repo.example.com"), b"{}", b"sig")
@@ -0,0 +1,103 @@
+import logging
+import random
+
+import src.lpm.app as lpm
+from src.lpm import config
+
+
+def _packages():
+    P = lpm.PkgMeta
+    return [
+        P(name="webapp", version="2.0", requires=["httpd | nginx", "python>=3", "mta", "libdb"]),
+        P(name="webapp", version="1.0", requires=["httpd", "python>=2"]),
+        P(name="httpd", version="2.4", requires=["libssl", "libpcre"]),
+        P(name="nginx", version="1.25", requires=["libssl", "libpcre"]),
+        P(name="python", version="3.12", requires=["libffi", "zlib", "libdb"]),
+        P(name="python", version="3.11", requires=["libffi", "zlib"]),
+        P(name="postfix", version="3.8", provides=["mta"], requires=["libdb", "libssl"]),
+        P(name="exim", version="4.97", provides=["mta"], requires=["libdb"]),
+        P(name="openssl", version="3.1", provides=["libssl"], requires=["zlib"]),
+        P(name="libressl", version="3.8", provides=["libssl"]),
+        P(name="pcre2", version="10.42", provides=["libpcre"]),
+        P(name="libffi", version="3.4"),
+        P(name="zlib", version="1.3"),
+        P(name="db5", version="5.3", provides=["libdb"]),
+        P(name="lmdb", version="0.9", provides=["libdb"]),
+    ]
+
+
+def _universe(order=None):
+    pkgs = _packages()
+    if order is not None:
+        random.Random(order).shuffle(pkgs)
+    universe = lpm.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in pkgs:
+        lpm.register_universe_candidate(universe, pkg)
+    return universe
+
+
+def _resolve(seed, order=None):
+    universe = _universe(order)
+    plan = lpm.solve(["webapp"], universe, seed=seed)
+    return universe, [lpm.package_id(p) for p in plan]
+
+
+def _holds(expr, chosen, universe):
+    if expr.kind == "atom":
+        return any(lpm.package_id(p) in chosen for p in lpm.providers_for(universe, expr.atom))
+    left, right = _holds(expr.left, chosen, universe), _holds(expr.right, chosen, universe)
+    return left or right if expr.kind == "or" else left and right
+
+
+def _assert_valid(universe, plan_ids):
+    chosen = set(plan_ids)
+    names = [pid.rsplit("-", 2)[0] for pid in plan_ids]
+    assert len(names) == len(set(names))
+    assert "webapp" in names
+    selected = [p for pkgs in universe.candidates_by_name.values() for p in pkgs if lpm.package_id(p) in chosen]
+    edges = lpm.plan_edges(selected, universe)
+    for pkg in selected:
+        for req in pkg.requires:
+            assert _holds(lpm.parse_dep_expr(req), chosen, universe), (pkg.name, req)
+        for dep in edges[pkg.name]:
+            assert names.index(dep) < names.index(pkg.name)
+
+
+def test_same_seed_gives_identical_plans():
+    _, first = _resolve(7)
+    _, second = _resolve(7)
+    assert first == second
+
+    # The plan no longer depends on the order the repositories listed packages in.
+    for order in (1, 2, 3):
+        assert _resolve(7, order)[1] == first
+
+
+def test_different_seeds_give_valid_plans():
+    for seed in (1, 2, 3, 42):
+        universe, plan = _resolve(seed)
+        _assert_valid(universe, plan)
+        assert plan == _resolve(seed, order=seed)[1]
+
+
+def test_seeded_resolve_leaves_the_callers_universe_order_alone():
+    universe = _universe(order=3)
+    names = list(universe.candidates_by_name)
+    providers = {tok: list(pkgs) for tok, pkgs in universe.providers.items()}
+
+    lpm.solve(["webapp"], universe, seed=7)
+
+    assert list(universe.candidates_by_name) == names
+    assert list(universe.providers) == list(providers)
+    assert universe.providers == providers
+
+
+def test_invalid_seed_is_reported(caplog):
+    original_conf = dict(config.CONF)
+    try:
+        with caplog.at_level(logging.WARNING):
+            config._apply_conf({**original_conf, "SOLVER_SEED": "seven"})
+        assert config.SOLVER_SEED is None
+        assert "Invalid SOLVER_SEED 'seven'" in caplog.text
+    finally:
+        config._apply_conf(original_conf)
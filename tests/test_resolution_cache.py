@@ -0,0 +1,64 @@
+import src.lpm.app as app
+from src.lpm.app import PkgMeta, Universe, register_universe_candidate, solve
+from src.lpm.resolvecache import ResolutionCache
+
+
+def _universe(index_version, *pkgs):
+    universe = Universe(
+        candidates_by_name={},
+        providers={},
+        installed={},
+        pins={},
+        holds=set(),
+        index_versions={"main": index_version},
+    )
+    for pkg in pkgs:
+        register_universe_candidate(universe, pkg)
+    return universe
+
+
+def _counting_encoder(monkeypatch):
+    calls = []
+    encode = app.encode_resolution
+
+    def wrapper(*args, **kwargs):
+        calls.append(args[2])
+        return encode(*args, **kwargs)
+
+    monkeypatch.setattr(app, "encode_resolution", wrapper)
+    return calls
+
+
+def _pkgs():
+    return (
+        PkgMeta(name="foo", version="1.0", requires=["bar"], repo="main"),
+        PkgMeta(name="bar", version="1.0", repo="main"),
+    )
+
+
+def test_identical_inputs_hit_the_cache(tmp_path, monkeypatch):
+    calls = _counting_encoder(monkeypatch)
+    cache = ResolutionCache(tmp_path / "resolve")
+
+    first = solve(["foo"], _universe("v1", *_pkgs()), cache=cache)
+    second = solve(["foo"], _universe("v1", *_pkgs()), cache=cache)
+
+    assert [p.name for p in second] == [p.name for p in first] == ["bar", "foo"]
+    assert len(calls) == 1
+    assert cache.hits == 1
+
+
+def test_changed_inputs_miss_the_cache(tmp_path, monkeypatch):
+    calls = _counting_encoder(monkeypatch)
+    cache = ResolutionCache(tmp_path / "resolve")
+
+    solve(["foo"], _universe("v1", *_pkgs()), cache=cache)
+    solve(["foo"], _universe("v2", *_pkgs()), cache=cache)
+    assert len(calls) == 2
+
+    installed = _universe("v2", *_pkgs())
+    installed.installed["bar"] = {"version": "0.9", "release": "1", "arch": "noarch"}
+    solve(["foo"], installed, cache=cache)
+    solve(["foo"], _universe("v2", *_pkgs()), cache=cache, recommends=False)
+    assert len(calls) == 4
+    assert cache.hits == 0
@@ -0,0 +1,44 @@
+import json
+
+from src.lpm.app import PkgMeta, Universe, register_universe_candidate, resolution_trace, solve
+
+
+def _universe(*pkgs):
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in pkgs:
+        register_universe_candidate(universe, pkg)
+    return universe
+
+
+def test_trace_attributes_dependency_to_requiring_package():
+    universe = _universe(
+        PkgMeta(name="A", version="1.0", requires=["B"]),
+        PkgMeta(name="B", version="1.0"),
+    )
+    trace = {}
+
+    plan = solve(["A"], universe, trace=trace)
+
+    assert sorted(p.name for p in plan) == ["A", "B"]
+    json.dumps(trace)
+    b = trace["packages"]["B-1.0-1.noarch"]
+    assert b["selected_because"] == [
+        {"kind": "dependency", "requirement": "B", "package": "A-1.0-1.noarch"}
+    ]
+    a = trace["packages"]["A-1.0-1.noarch"]
+    assert a["selected_because"] == [{"kind": "goal", "requirement": "A"}]
+
+
+def test_trace_explains_rejected_version():
+    universe = _universe(
+        PkgMeta(name="A", version="1.0", requires=["B<2.0"]),
+        PkgMeta(name="B", version="2.0"),
+        PkgMeta(name="B", version="1.0"),
+    )
+    plan = solve(["A"], universe)
+
+    trace = resolution_trace(["A"], universe, plan)
+
+    rejected = trace["packages"]["B-1.0-1.noarch"]["rejected"]
+    assert rejected["B-2.0-1.noarch"]["reason"] == "version"
+    assert "A-1.0-1.noarch requires B<2.0" in rejected["B-2.0-1.noarch"]["detail"]
@@ -1,9 +1,12 @@
 import pytest
 
+from src.lpm import config as lpm_config
 from src.lpm.app import (
+    GoalConflictError,
     PkgMeta,
     ResolutionError,
     Universe,
+    VersionConflictError,
     register_universe_candidate,
     solve,
 )
@@ -99,3 +102,129 @@ def test_unsat_details_include_dependency_cycle():
     msg = str(excinfo.value)
     assert "Unsatisfiable dependency set involving" in msg
     assert "dependency cycle:" in msg
+
+
+def test_dependency_is_pulled_into_the_plan():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["B"]))
+    register_universe_candidate(universe, PkgMeta(name="B", version="1.0"))
+
+    assert [p.name for p in solve(["A"], universe)] == ["B", "A"]
+
+
+def test_conflict_with_installed_package_is_unsatisfiable():
+    installed = {"C": {"version": "1.0", "release": "1", "provides": []}}
+    universe = Universe(candidates_by_name={}, providers={}, installed=installed, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", conflicts=["C"]))
+    register_universe_candidate(universe, PkgMeta(name="C", version="1.0"))
+
+    with pytest.raises(ResolutionError) as excinfo:
+        solve(["A"], universe)
+    assert "A-1.0 conflicts with installed C-1.0" in str(excinfo.value)
+
+    # Upgrading C out of the conflicting range resolves it.
+    universe = Universe(candidates_by_name={}, providers={}, installed=installed, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", conflicts=["C<2"]))
+    register_universe_candidate(universe, PkgMeta(name="C", version="2.0"))
+    register_universe_candidate(universe, PkgMeta(name="C", version="1.0"))
+    assert {(p.name, p.version) for p in solve(["A"], universe)} == {("A", "1.0"), ("C", "2.0")}
+
+
+def test_incompatible_version_requirements_are_reported_as_a_version_conflict():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["lib>=2"]))
+    register_universe_candidate(universe, PkgMeta(name="B", version="1.0", requires=["lib<2", "zlib"]))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="2.1"))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="1.4"))
+    register_universe_candidate(universe, PkgMeta(name="zlib", version="1.3"))
+
+    with pytest.raises(VersionConflictError) as excinfo:
+        solve(["A", "B"], universe)
+
+    err = excinfo.value
+    assert err.package == "lib"
+    assert sorted((who, str(want)) for who, want in err.requested_by) == [("A", ">=2"), ("B", "<2")]
+    assert str(err) == "version conflict on lib: A requires lib>=2, B requires lib<2"
+    assert isinstance(err, ResolutionError)
+
+
+def test_conflict_explanation_falls_back_to_the_failed_solve_once_its_budget_runs_out(monkeypatch):
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["lib>=2"]))
+    register_universe_candidate(universe, PkgMeta(name="B", version="1.0", requires=["lib<2"]))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="2.1"))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="1.4"))
+    monkeypatch.setattr(lpm_config, "UNSAT_CORE_BUDGET", 0)
+
+    with pytest.raises(ResolutionError) as excinfo:
+        solve(["A", "B"], universe)
+
+    assert not isinstance(excinfo.value, VersionConflictError)
+    assert str(excinfo.value).startswith("Unsatisfiable dependency set involving: ")
+
+
+def test_version_conflict_names_the_request_itself():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="app", version="1.0", requires=["lib<2"]))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="2.1"))
+    register_universe_candidate(universe, PkgMeta(name="lib", version="1.4"))
+
+    with pytest.raises(VersionConflictError) as excinfo:
+        solve(["app", "lib>=2"], universe)
+
+    assert "the request requires lib>=2" in str(excinfo.value)
+    assert "app requires lib<2" in str(excinfo.value)
+
+
+def test_batch_request_installs_a_shared_dependency_once():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="curl", version="8.0", requires=["openssl>=3"]))
+    register_universe_candidate(universe, PkgMeta(name="wget", version="1.21", requires=["openssl"]))
+    register_universe_candidate(universe, PkgMeta(name="openssl", version="3.1"))
+
+    plan = [(p.name, p.version) for p in solve(["curl", "wget"], universe)]
+
+    assert plan == [("openssl", "3.1"), ("curl", "8.0"), ("wget", "1.21")]
+
+
+def test_batch_request_names_the_mutually_incompatible_packages():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="zlib", version="1.3"))
+    register_universe_candidate(universe, PkgMeta(name="postfix", version="3.8", conflicts=["mail-server"]))
+    register_universe_candidate(
+        universe, PkgMeta(name="exim", version="4.97", provides=["mail-server"], requires=["zlib"])
+    )
+
+    with pytest.raises(GoalConflictError) as excinfo:
+        solve(["zlib", "postfix", "exim"], universe)
+
+    err = excinfo.value
+    assert err.goals == ["postfix", "exim"]
+    assert str(err).endswith("; requested packages cannot be installed together: postfix, exim")
+    assert isinstance(err, ResolutionError)
+
+
+def test_no_deps_installs_a_package_whose_dependency_is_absent():
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["libmissing>=2", "B"]))
+    register_universe_candidate(universe, PkgMeta(name="B", version="1.0"))
+
+    with pytest.raises(ResolutionError, match="libmissing"):
+        solve(["A"], universe)
+
+    universe = Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["libmissing>=2", "B"]))
+    register_universe_candidate(universe, PkgMeta(name="B", version="1.0"))
+
+    assert [(p.name, p.version) for p in solve(["A"], universe, dependencies=False)] == [("A", "1.0")]
+
+
+def test_no_deps_still_refuses_a_conflict_with_an_installed_package():
+    installed = {"C": {"version": "1.0", "release": "1", "provides": []}}
+    universe = Universe(candidates_by_name={}, providers={}, installed=installed, pins={}, holds=set())
+    register_universe_candidate(universe, PkgMeta(name="A", version="1.0", requires=["B"], conflicts=["C"]))
+    register_universe_candidate(universe, PkgMeta(name="C", version="1.0"))
+
+    with pytest.raises(ResolutionError) as excinfo:
+        solve(["A"], universe, dependencies=False)
+    assert "A-1.0 conflicts with installed C-1.0" in str(excinfo.value)
@@ -0,0 +1,144 @@
+import io
+import socket
+import urllib.error
+from types import SimpleNamespace
+
+import pytest
+
+from lpm.retry import ChecksumError, RetryPolicy, is_retryable
+
+
+class FakeClock:
+    def __init__(self):
+        self.now = 0.0
+        self.sleeps = []
+
+    def sleep(self, seconds):
+        self.sleeps.append(seconds)
+        self.now += seconds
+
+
+def _http_error(code):
+    return urllib.error.HTTPError("https://repo.example.com/x", code, "err", {}, io.BytesIO())
+
+
+def test_retryable_error_retries_up_to_limit():
+    clock = FakeClock()
+    policy = RetryPolicy(max_attempts=4, base_delay=1.0, max_delay=3.0, jitter=0.0)
+    calls = []
+
+    def flaky():
+        calls.append(clock.now)
+        try:
+            raise _http_error(503)
+        except urllib.error.HTTPError as exc:
+            raise RuntimeError("Failed to read URL") from exc
+
+    with pytest.raises(RuntimeError):
+        policy.call(flaky, sleep=clock.sleep)
+
+    assert len(calls) == 4
+    assert clock.sleeps == [1.0, 2.0, 3.0]
+
+
+def test_retry_succeeds_after_transient_timeout():
+    clock = FakeClock()
+    attempts = iter([socket.timeout("timed out"), None])
+
+    def fetch():
+        exc = next(attempts)
+        if exc is not None:
+            raise exc
+        return b"ok"
+
+    policy = RetryPolicy(max_attempts=3, base_delay=2.0, jitter=0.5)
+    assert policy.call(fetch, sleep=clock.sleep, rng=lambda: 1.0) == b"ok"
+    assert clock.sleeps == [3.0]
+
+
+@pytest.mark.parametrize(
+    "exc",
+    [_http_error(404), ChecksumError("checksum mismatch"), FileNotFoundError("blob")],
+)
+def test_fatal_error_fails_immediately(exc):
+    clock = FakeClock()
+    calls = []
+
+    def broken():
+        calls.append(1)
+        raise exc
+
+    assert not is_retryable(exc)
+    with pytest.raises(type(exc)):
+        RetryPolicy(max_attempts=5).call(broken, sleep=clock.sleep)
+
+    assert calls == [1]
+    assert clock.sleeps == []
+
+
+def test_policy_from_config():
+    conf = SimpleNamespace(RETRY_MAX_ATTEMPTS=7, RETRY_BASE_DELAY=0.1, RETRY_MAX_DELAY=4, RETRY_JITTER=2)
+    policy = RetryPolicy.from_config(conf)
+    assert policy == RetryPolicy(max_attempts=7, base_delay=0.1, max_delay=4.0, jitter=1.0)
+
+
+def test_fetch_blob_rejects_checksum_mismatch_without_retrying(monkeypatch, tmp_path):
+    import lpm
+    from lpm import app as lpm_app
+
+    calls = []
+
+    def fake_urlread(url, timeout=10):
+        calls.append(url)
+        return b"corrupt", url
+
+    monkeypatch.setattr(lpm, "urlread", fake_urlread)
+    monkeypatch.setattr(lpm_app, "_cache_path_for", lambda url: tmp_path / "foo.zst")
+    pkg = lpm_app.PkgMeta(name="foo", version="1", blob="https://repo.example.com/foo.zst", sha256="0" * 64)
+
+    with pytest.raises(ChecksumError):
+        lpm_app.fetch_blob(pkg)
+    assert calls == ["https://repo.example.com/foo.zst"]
+
+
+def _flaky_repo(monkeypatch, tmp_path, failures):
+    import lpm
+    from lpm import app as lpm_app
+
+    calls = []
+
+    def fake_urlread(url, timeout=10):
+        calls.append(url)
+        if url.endswith(".sig"):
+            raise _http_error(404)
+        if failures:
+            raise failures.pop(0)
+        return b"archive", url
+
+    monkeypatch.setattr(lpm, "urlread", fake_urlread)
+    monkeypatch.setattr(lpm_app, "_cache_path_for", lambda url: tmp_path / "foo.zst")
+    pkg = lpm_app.PkgMeta(name="foo", version="1", blob="https://repo.example.com/foo.zst")
+    return lpm_app, pkg, calls
+
+
+def test_repo_fetcher_retries_transient_failures_to_success(monkeypatch, tmp_path):
+    clock = FakeClock()
+    reset = ConnectionResetError("connection reset by peer")
+    lpm_app, pkg, calls = _flaky_repo(monkeypatch, tmp_path, [socket.timeout("timed out"), reset])
+    fetcher = lpm_app.RepoFetcher(RetryPolicy(max_attempts=3, base_delay=1.0, jitter=0.0), clock.sleep)
+
+    assert fetcher.fetch(pkg) == (b"archive", None)
+    assert calls == ["https://repo.example.com/foo.zst"] * 3 + ["https://repo.example.com/foo.zst.sig"]
+    assert clock.sleeps == [1.0, 2.0]
+
+
+def test_repo_fetcher_gives_up_on_404_after_one_attempt(monkeypatch, tmp_path):
+    clock = FakeClock()
+    lpm_app, pkg, calls = _flaky_repo(monkeypatch, tmp_path, [_http_error(404)])
+    fetcher = lpm_app.RepoFetcher(RetryPolicy(max_attempts=5, base_delay=1.0), clock.sleep)
+
+    with pytest.raises(Exception) as excinfo:
+        fetcher.fetch(pkg)
+    assert not is_retryable(excinfo.value)
+    assert calls == ["https://repo.example.com/foo.zst"]
+    assert clock.sleeps == []
@@ -0,0 +1,64 @@
+import json
+import os
+from pathlib import Path
+
+import pytest
+
+from lpm.atomic_io import FsError
+from lpm.fs_ops import RootContext
+
+
+def test_root_context_prefixes_package_paths(tmp_path):
+    ctx = RootContext(tmp_path / "root")
+
+    assert ctx.path("/usr/bin/foo") == tmp_path / "root/usr/bin/foo"
+    assert ctx.path("usr/bin/foo") == tmp_path / "root/usr/bin/foo"
+    assert ctx.path("/") == tmp_path / "root"
+    # ``..`` stops at the root, as it would inside a chroot.
+    assert ctx.path("/../../etc/passwd") == tmp_path / "root/etc/passwd"
+    assert ctx.relative(tmp_path / "root/usr/bin/foo") == "/usr/bin/foo"
+    with pytest.raises(FsError):
+        ctx.relative(tmp_path / "elsewhere")
+
+    assert RootContext(Path("/")).path("/usr/bin/foo") == Path("/usr/bin/foo")
+
+
+def test_root_context_resolves_link_targets_inside_the_root(tmp_path):
+    ctx = RootContext(tmp_path)
+
+    assert ctx.link_target("/usr/bin/ld.so", "../lib/ld.so.1") == tmp_path / "usr/lib/ld.so.1"
+    assert ctx.link_target("/usr/lib/libfoo.so", "/usr/lib/libfoo.so.1") == tmp_path / "usr/lib/libfoo.so.1"
+    assert ctx.link_target("/bin", "../../../usr/bin") == tmp_path / "usr/bin"
+
+
+def test_install_verify_and_remove_stay_under_the_root(lpm, make_pkg, tmp_path):
+    root = tmp_path / "root"
+    root.mkdir()
+    _, pkg = make_pkg(
+        "rooted",
+        "1",
+        {"usr/bin/rooted": "#!/bin/sh\n", "usr/lib/librooted.so.1": b"elf", "etc/rooted.conf": "x=1\n"},
+        links={"usr/lib/librooted.so": "/usr/lib/librooted.so.1"},
+    )
+
+    lpm.installpkg(pkg, root=root, dry_run=False, verify=False, force=False, explicit=True)
+    conn = lpm.db()
+    try:
+        manifest = json.loads(conn.execute("SELECT manifest FROM installed WHERE name='rooted'").fetchone()[0])
+    finally:
+        conn.close()
+
+    for entry in manifest:
+        assert os.path.lexists(root / entry["path"].lstrip("/")), entry["path"]
+    assert (root / "usr/bin/rooted").read_text() == "#!/bin/sh\n"
+    # The absolute link target is kept as shipped, not prefixed with the root.
+    link = root / "usr/lib/librooted.so"
+    assert os.readlink(link) == "/usr/lib/librooted.so.1"
+    assert RootContext(root).link_target("/usr/lib/librooted.so", os.readlink(link)).read_bytes() == b"elf"
+    assert lpm.verify_manifest(manifest, root) == []
+
+    lpm.do_remove(["rooted"], root, dry=False)
+
+    for entry in manifest:
+        assert not os.path.lexists(root / entry["path"].lstrip("/")), entry["path"]
+    assert root.is_dir()
@@ -0,0 +1,58 @@
+from __future__ import annotations
+
+import errno
+import os
+import stat
+from pathlib import Path
+
+import pytest
+
+from lpm import atomic_io
+
+
+def _record_fsyncs(monkeypatch):
+    synced = []
+    real_fsync = os.fsync
+
+    def fsync(fd):
+        synced.append(os.fstat(fd).st_mode)
+        return real_fsync(fd)
+
+    monkeypatch.setattr(atomic_io.os, "fsync", fsync)
+    return synced
+
+
+def test_safe_write_syncs_parent_directory_after_rename(tmp_path: Path, monkeypatch):
+    monkeypatch.setattr(atomic_io, "_current_umask", lambda: 0o022)
+    synced = _record_fsyncs(monkeypatch)
+    target = tmp_path / "db" / "state.json"
+
+    assert atomic_io.safe_write(target, "{}", mode=0o640) == target.resolve()
+    assert target.read_text(encoding="utf-8") == "{}"
+    assert stat.S_IMODE(target.stat().st_mode) == 0o640
+    assert stat.S_ISREG(synced[0])
+    assert any(stat.S_ISDIR(mode) for mode in synced[1:])
+
+
+def test_safe_write_surfaces_directory_sync_failure(tmp_path: Path, monkeypatch):
+    real_fsync = os.fsync
+
+    def fsync(fd):
+        if stat.S_ISDIR(os.fstat(fd).st_mode):
+            raise OSError(errno.EIO, "I/O error")
+        return real_fsync(fd)
+
+    monkeypatch.setattr(atomic_io.os, "fsync", fsync)
+    target = tmp_path / "state.json"
+    with pytest.raises(atomic_io.FsError) as excinfo:
+        atomic_io.safe_write(target, "{}")
+    assert excinfo.value.errno == errno.EIO
+    assert target.read_text(encoding="utf-8") == "{}"
+
+    def unsupported(fd):
+        if stat.S_ISDIR(os.fstat(fd).st_mode):
+            raise OSError(errno.EINVAL, "Invalid argument")
+        return real_fsync(fd)
+
+    monkeypatch.setattr(atomic_io.os, "fsync", unsupported)
+    assert atomic_io.safe_write(target, "[]") == target.resolve()
@@ -0,0 +1,53 @@
+import json
+
+import pytest
+
+
+def _universe(lpm):
+    pkgs = [
+        ("foo", "1.2", "The foo tool"),
+        ("libfoo", "1.2", "Shared library for foo"),
+        ("FooBar", "0.9", "Mixed-case foo"),
+        ("libbar", "2.0", "Bar library"),
+        ("zlib", "1.3", "Compression library"),
+    ]
+    return {name: [lpm.PkgMeta(name=name, version=ver, summary=summary)] for name, ver, summary in pkgs}
+
+
+@pytest.fixture
+def search(lpm, lpm_app, monkeypatch):
+    monkeypatch.setattr(lpm_app, "load_universe", lambda: _universe(lpm))
+    return lpm
+
+
+def _names(out):
+    return [line.split()[0] for line in out.splitlines()]
+
+
+def test_search_matches_substrings_ignoring_case(search, capsys):
+    search.main(["search", "foo"])
+    out = capsys.readouterr().out
+    assert _names(out) == ["FooBar", "foo", "libfoo"]
+    assert "Shared library for foo" in out
+
+    search.main(["search", "foo", "--limit", "1"])
+    assert _names(capsys.readouterr().out) == ["FooBar"]
+
+    search.main(["search", "lib*"])
+    assert _names(capsys.readouterr().out) == ["libbar", "libfoo"]
+
+
+def test_search_regex_anchors_to_name(search, capsys):
+    search.main(["search", "--regex", "^lib"])
+    assert _names(capsys.readouterr().out) == ["libbar", "libfoo"]
+
+    with pytest.raises(SystemExit):
+        search.main(["search", "--regex", "("])
+
+
+def test_search_json_output(search, capsys):
+    search.main(["--format", "json", "search", "--regex", "^lib"])
+    assert json.loads(capsys.readouterr().out) == [
+        {"name": "libbar", "version": "2.0", "summary": "Bar library"},
+        {"name": "libfoo", "version": "1.2", "summary": "Shared library for foo"},
+    ]
@@ -6,6 +6,18 @@ sys.path.insert(0, os.path.dirname(os.path.dirname(__file__)))
 from src import CNF, CDCLSolver
 
 
+def _pigeonhole(pigeons, holes):
+    cnf = CNF()
+    x = [[cnf.new_var(f'p{p}h{h}') for h in range(holes)] for p in range(pigeons)]
+    for row in x:
+        cnf.add_clause(row)
+    for h in range(holes):
+        for p in range(pigeons):
+            for q in range(p + 1, pigeons):
+                cnf.add_clause([-x[p][h], -x[q][h]])
+    return cnf
+
+
 def test_conflicting_packages_unsat():
     cnf = CNF()
     a = cnf.new_var('A')
@@ -72,3 +84,700 @@ def test_vsids_unsat_regression():
     solver = CDCLSolver(cnf, decay_map=decay_map)
     res = solver.solve([])
     assert not res.sat
+
+
+def test_unit_chain_solved_by_level_zero_propagation():
+    cnf = CNF()
+    chain = [cnf.new_var(f'P{i}') for i in range(6)]
+    cnf.add_clause([chain[0]])
+    # P0 -> P1 -> ... -> P5, and P5 excludes an extra package
+    for prev, nxt in zip(chain, chain[1:]):
+        cnf.add_clause([-prev, nxt])
+    extra = cnf.new_var('X')
+    cnf.add_clause([-chain[-1], -extra])
+    solver = CDCLSolver(cnf)
+    res = solver.solve([])
+    assert res.sat
+    assert all(res.assign[v] for v in chain)
+    assert not res.assign[extra]
+    assert solver.last_decisions == 0
+
+
+def test_long_implication_chain_propagates_each_literal_once():
+    cnf = CNF()
+    chain = [cnf.new_var(f'P{i}') for i in range(501)]
+    for prev, nxt in zip(chain, chain[1:]):
+        cnf.add_clause([-prev, nxt])
+    cnf.add_clause([chain[0]])
+    solver = CDCLSolver(cnf)
+    res = solver.solve([])
+    assert res.sat
+    assert all(res.assign[v] for v in chain)
+    stats = solver.stats()
+    assert (stats.decisions, stats.conflicts) == (0, 0)
+    assert stats.propagations == len(chain)
+
+
+def test_top_level_conflict_is_unsat_without_decisions():
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    c = cnf.new_var('C')
+    cnf.add_clause([a])
+    cnf.add_clause([-a, b])
+    cnf.add_clause([-b, -a])
+    cnf.add_clause([c, b])
+    solver = CDCLSolver(cnf)
+    res = solver.solve([])
+    assert not res.sat
+    assert solver.last_decisions == 0
+
+
+def test_pure_literal_elimination_composes_with_units():
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    c = cnf.new_var('C')
+    d = cnf.new_var('D')
+    cnf.add_clause([a])
+    cnf.add_clause([-a, b, c])
+    cnf.add_clause([-b, -d])
+    cnf.add_clause([-c, -d])
+    solver = CDCLSolver(cnf, pure_literals=True)
+    res = solver.solve([])
+    assert res.sat
+    # once A is propagated, D occurs only negatively, then B and C only positively
+    assert res.assign[a] and res.assign[b] and res.assign[c]
+    assert not res.assign[d]
+    assert solver.last_decisions == 0
+
+
+def test_pure_literals_are_recomputed_under_each_call_s_assumptions():
+    cnf = CNF()
+    a, b, c = (cnf.new_var(n) for n in 'ABC')
+    cnf.add_clause([a, c])
+    cnf.add_clause([-a, c])
+    cnf.add_clause([a, b])
+    solver = CDCLSolver(cnf, pure_literals=True)
+
+    res = solver.solve([])
+    assert res.sat and res.assign[b] and res.assign[c]
+    # B is pure in the clauses alone, but not once -B is assumed.
+    res = solver.solve_under_assumptions([-b])
+    assert res.sat
+    assert res.assign[a] and not res.assign[b] and res.assign[c]
+
+
+def test_eliminate_pure_literals_fixes_variables_before_solve():
+    cnf = CNF()
+    a, b, c, d = (cnf.new_var(n) for n in 'ABCD')
+    cnf.add_clause([a, b, c])
+    cnf.add_clause([-a, -b, c])
+    cnf.add_clause([-a, b, d])
+    cnf.add_clause([a, -b, -d])
+    solver = CDCLSolver(cnf)
+
+    assert solver.eliminate_pure_literals() == 1
+    assert solver.fixed == {c: True}
+    assert solver.eliminate_pure_literals() == 0
+    res = solver.solve([])
+    assert res.sat
+    assert res.assign[c] is True
+    assert all(any(res.assign[abs(l)] == (l > 0) for l in cl) for cl in cnf.clauses)
+
+
+def test_fixed_pure_literals_give_way_to_assumptions_and_later_clauses():
+    cnf = CNF()
+    a, b, c, d = (cnf.new_var(n) for n in 'ABCD')
+    cnf.add_clause([a, -b])
+    cnf.add_clause([b, c])
+    cnf.add_clause([-c, d])
+    cnf.add_clause([c, -d])
+    solver = CDCLSolver(cnf)
+
+    # Fixing A satisfies (A or -B), after which B turns pure too.
+    assert solver.eliminate_pure_literals() == 2
+    assert solver.fixed == {a: True, b: True}
+    res = solver.solve_under_assumptions([-a])
+    assert res.sat
+    assert not res.assign[a] and not res.assign[b] and res.assign[c] and res.assign[d]
+    cnf.add_clause([-a, -b])
+    res = solver.solve([])
+    assert res.sat
+    assert all(any(res.assign[abs(l)] == (l > 0) for l in cl) for cl in cnf.clauses)
+
+
+@pytest.mark.parametrize("count", [3, 8])
+def test_exactly_one_over_versions_selects_a_single_literal(count):
+    cnf = CNF()
+    versions = [cnf.new_var(f'pkg=={i}') for i in range(count)]
+    solver = CDCLSolver(cnf)
+    solver.add_exactly_one(versions)
+    assert (cnf.next_var - 1 > count) == (count > CDCLSolver.AMO_PAIRWISE_LIMIT)
+
+    res = solver.solve([])
+    assert res.sat
+    assert sum(res.assign[v] for v in versions) == 1
+    for v in versions:
+        res = solver.solve_under_assumptions([v])
+        assert res.sat
+        assert [w for w in versions if res.assign[w]] == [v]
+    assert not solver.solve_under_assumptions([versions[0], versions[-1]]).sat
+    assert not solver.solve_under_assumptions([-v for v in versions]).sat
+
+
+def test_enumerate_returns_each_model_once_and_ignores_auxiliaries():
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    cnf.add_clause([a, b])
+    solver = CDCLSolver(cnf)
+
+    models = solver.enumerate(10)
+    assert sorted((m[a], m[b]) for m in models) == [(False, True), (True, False), (True, True)]
+    # The blocking clauses are gone afterwards: every model is still available.
+    assert sorted((m[a], m[b]) for m in solver.enumerate(10)) == [(False, True), (True, False), (True, True)]
+    assert solver.solve_under_assumptions([a, b]).sat
+
+    cnf = CNF()
+    versions = [cnf.new_var(f'pkg=={i}') for i in range(8)]
+    solver = CDCLSolver(cnf)
+    solver.add_exactly_one(versions)
+    assert solver.aux_vars
+    models = solver.enumerate(3)
+    assert len(models) == 3
+    assert all(set(m) == set(versions) and sum(m.values()) == 1 for m in models)
+    assert len(solver.enumerate(10)) == 8
+
+
+def test_minimal_unsat_core_names_only_the_conflicting_clauses():
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    cnf.add_clause([a, b])
+    cnf.add_clause([b])
+    cnf.add_clause([-b])
+    solver = CDCLSolver(cnf)
+
+    assert solver.minimal_unsat_core() == [1, 2]
+
+    cnf = CNF()
+    a, b, c = cnf.new_var('A'), cnf.new_var('B'), cnf.new_var('C')
+    for clause in ([c], [a, b], [-a, b], [a, -b], [-a, -b]):
+        cnf.add_clause(clause)
+    assert CDCLSolver(cnf).minimal_unsat_core() == [1, 2, 3, 4]
+
+    sat = CNF()
+    sat.add_clause([sat.new_var('A')])
+    assert CDCLSolver(sat).minimal_unsat_core() == []
+
+
+def test_minimal_unsat_core_shrinks_only_the_candidates_within_its_budget():
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    cnf.add_clause([a, b])
+    cnf.add_clause([b])
+    cnf.add_clause([-b])
+    solver = CDCLSolver(cnf)
+
+    assert solver.minimal_unsat_core([0, 2]) == [2]
+    assert solver.minimal_unsat_core(budget=1000) == [1, 2]
+    assert solver.minimal_unsat_core(budget=0) is None
+
+
+def test_stats_report_effort_of_the_last_solve():
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    cnf.add_clause([a, b])
+    solver = CDCLSolver(cnf)
+    assert solver.solve([]).sat
+    stats = solver.stats()
+    assert stats.decisions >= 1
+    assert stats.max_level >= 1
+
+    cnf.add_clause([a])
+    cnf.add_clause([-a, -b])
+    assert solver.solve([]).sat
+    stats = solver.stats()
+    assert stats.decisions == 0
+    assert stats.max_level == 0
+    assert stats.propagations >= 2
+    assert (stats.conflicts, stats.learnt, stats.restarts) == (0, 0, 0)
+
+    solver = CDCLSolver(_pigeonhole(4, 3))
+    assert not solver.solve([]).sat
+    stats = solver.stats()
+    assert stats.conflicts == solver.last_conflicts > 0
+    assert stats.learnt > 0
+
+
+def test_learnt_limit_keeps_database_bounded():
+    cnf = _pigeonhole(7, 6)
+    solver = CDCLSolver(cnf)
+    solver.set_learnt_limit(20)
+    peak = 0
+
+    def watch(event):
+        nonlocal peak
+        peak = max(peak, len(cnf.learnts))
+
+    assert not solver.solve([], on_event=watch).sat
+    assert solver.stats().learnt > 100
+    assert peak < 2 * 20
+    assert len(cnf.learnts) <= 20
+    with pytest.raises(ValueError):
+        solver.set_learnt_limit(0)
+
+
+def test_learnt_limit_never_evicts_binary_clauses():
+    cnf = _pigeonhole(7, 6)
+    solver = CDCLSolver(cnf)
+    solver.set_learnt_limit(5)
+    binary = set()
+
+    def watch(event):
+        binary.update(idx for idx in cnf.learnts if cnf.lengths[idx] == 2)
+
+    assert not solver.solve([], on_event=watch).sat
+    assert binary
+    assert binary <= cnf.learnts
+
+
+def test_learnt_clauses_carry_over_to_a_fresh_solver():
+    first = CDCLSolver(_pigeonhole(5, 4))
+    assert not first.solve([]).sat
+    learnt = first.export_learnt()
+    assert learnt and all(learnt)
+
+    cnf = _pigeonhole(5, 4)
+    problem = len(cnf.lengths)
+    fresh = CDCLSolver(cnf)
+    assert fresh.import_learnt(learnt) == len(learnt)
+    # Imported clauses are redundant: the database reduction may drop them.
+    assert len(cnf.learnts) == len(learnt) and min(cnf.learnts) >= problem
+    assert sorted(map(sorted, fresh.export_learnt())) == sorted(map(sorted, learnt))
+    assert not fresh.solve([]).sat
+    assert fresh.stats().conflicts <= first.stats().conflicts
+
+    sat_cnf = _pigeonhole(4, 4)
+    seeded = CDCLSolver(_pigeonhole(4, 4))
+    assert seeded.solve([]).sat
+    original = list(sat_cnf.clauses)
+    reused = CDCLSolver(sat_cnf)
+    reused.import_learnt(seeded.export_learnt())
+    res = reused.solve([])
+    assert res.sat
+    assert all(any(res.assign[abs(l)] == (l > 0) for l in c) for c in original)
+
+
+def test_import_learnt_rejects_unallocated_variables():
+    from src.lpm.resolver import SolverError
+
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    cnf.add_clause([a, b])
+    cnf.add_clause([-a, b])
+    solver = CDCLSolver(cnf)
+    with pytest.raises(SolverError, match="unallocated variable 3"):
+        solver.import_learnt([[a, -b], [-a, 3]])
+    assert cnf.learnts == set()
+    with pytest.raises(SolverError):
+        solver.import_learnt([[0]])
+    assert solver.import_learnt([[], [a, -a], [b]]) == 1
+    assert solver.export_learnt() == [[b]]
+
+
+def test_import_learnt_drops_clauses_the_new_cnf_does_not_imply():
+    cnf = CNF()
+    a, b, c = (cnf.new_var(n) for n in 'ABC')
+    cnf.add_clause([a, c])
+    cnf.add_clause([b])
+    cnf.add_clause([-a, c])
+    solver = CDCLSolver(cnf)
+    # Learnt against an older CNF that also had (-A or -B); neither follows
+    # from this one, and together with it they are UNSAT.
+    stale = [[-a, -b], [-c]]
+    assert solver.import_learnt(stale) == 0
+    assert cnf.learnts == set()
+    res = solver.solve([])
+    assert res.sat
+    assert res.assign[b] and res.assign[c]
+
+
+def test_unit_propagation_from_original_clauses_learns_nothing():
+    cnf = CNF()
+    a, b, c = cnf.new_var('A'), cnf.new_var('B'), cnf.new_var('C')
+    cnf.add_clause([a])
+    cnf.add_clause([-a, b])
+    cnf.add_clause([-b, c])
+    before = list(cnf.clauses)
+    solver = CDCLSolver(cnf)
+
+    res = solver.solve([])
+    assert res.sat and res.assign[c]
+    assert cnf.learnts == set()
+    assert list(cnf.clauses) == before
+    assert solver.stats().learnt == 0
+
+
+def test_solver_events_report_conflicts_and_verdict():
+    from src.lpm.resolver import SolverEvent
+
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    for clause in ([a, b], [a, -b], [-a, b], [-a, -b]):
+        cnf.add_clause(clause)
+
+    events = []
+    res = CDCLSolver(cnf).solve([], on_event=events.append)
+
+    assert not res.sat
+    kinds = [e.kind for e in events]
+    assert SolverEvent.DECISION in kinds
+    assert SolverEvent.CONFLICT in kinds
+    assert events[-1].kind == SolverEvent.RESULT and events[-1].sat is False
+    assert kinds.count(SolverEvent.RESULT) == 1
+
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    cnf.add_clause([-a, b])
+    events = []
+    assert CDCLSolver(cnf).solve([], on_event=events.append).sat
+    assert events[-1].kind == SolverEvent.RESULT and events[-1].sat is True
+
+
+def test_first_uip_learning_backjumps_without_wiping_lower_levels():
+    from src.lpm.resolver import SolverEvent
+
+    cnf = CNF()
+    a, b, c, d, e = (cnf.new_var(n) for n in "ABCDE")
+    # decisions A@1, B@2, C@3: C -> D, D & A -> E, but D excludes E
+    cnf.add_clause([-c, d])
+    cnf.add_clause([-d, -a, e])
+    cnf.add_clause([-d, -e])
+    events = []
+    res = CDCLSolver(cnf).solve([], on_event=events.append)
+
+    assert res.sat
+    # D is the first UIP at level 3; the learnt clause asserts -D back at A's level
+    assert [cnf.clause(i) for i in cnf.learnts] == [[-d, -a]]
+    kinds = [ev.kind for ev in events]
+    after = [ev for ev in events[kinds.index(SolverEvent.CONFLICT) + 1 :] if ev.kind == SolverEvent.DECISION]
+    assert after[0].level == 2
+    assert a not in [ev.literal for ev in after]
+    assert res.assign[a] and not res.assign[d] and not res.assign[c]
+
+
+def test_add_clause_normalizes_duplicates_and_tautologies():
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+
+    assert cnf.add_clause([a, a, -b])
+    assert cnf.clauses == [[a, -b]]
+    assert not cnf.add_clause([a, -a])
+    assert not cnf.add_clause([-b, a])
+    assert cnf.clauses == [[a, -b]]
+
+    assert cnf.add_clause([])
+    res = CDCLSolver(cnf).solve([])
+    assert not res.sat
+
+
+def test_true_vars_lists_the_model_in_one_based_order():
+    cnf = CNF()
+    a, b, c = cnf.new_var('A'), cnf.new_var('B'), cnf.new_var('C')
+    cnf.add_clause([a])
+    cnf.add_clause([-b])
+    cnf.add_clause([-a, c])
+    res = CDCLSolver(cnf).solve([])
+    assert list(res.true_vars()) == [a, c] == [1, 3]
+    assert [res.assigned_value(v) for v in (a, b, c)] == [True, False, True]
+    assert res.assigned_value(0) is None and res.assigned_value(4) is None
+
+    unsat = CDCLSolver(_pigeonhole(3, 2)).solve([])
+    assert not unsat.sat
+    assert list(unsat.true_vars()) == [] and unsat.assigned_value(1) is None
+
+    hard = CDCLSolver(_pigeonhole(8, 7))
+    hard.set_conflict_budget(1)
+    unknown = hard.solve([])
+    assert unknown.unknown
+    assert list(unknown.true_vars()) == [] and unknown.assigned_value(1) is None
+
+
+def test_empty_cnf_is_trivially_sat():
+    cnf = CNF()
+    solver = CDCLSolver(cnf)
+    res = solver.solve([])
+    assert res.sat and res.assign == {} and res.unsat_core is None
+    assert solver.stats().decisions == 0
+    assert solver.solve_under_assumptions([]).sat
+    assert solver.minimal_unsat_core() == []
+
+    # Enumerating zero variables yields the empty model once and keeps the CNF satisfiable.
+    assert solver.enumerate(3) == [{}]
+    assert solver.enumerate(3) == [{}]
+    assert solver.solve([]).sat
+
+    cnf = CNF()
+    a, b = cnf.new_var('A'), cnf.new_var('B')
+    res = CDCLSolver(cnf).solve([])
+    assert res.sat and set(res.assign) == {a, b}
+
+
+def test_empty_clause_is_immediately_unsat():
+    cnf = CNF.from_dimacs("p cnf 0 1\n0\n")
+    solver = CDCLSolver(cnf)
+    events = []
+    res = solver.solve([], on_event=events.append)
+    assert not res.sat and not res.unknown
+    assert res.unsat_core == [] and res.assign == {}
+    assert solver.stats().decisions == 0
+    assert solver.minimal_unsat_core() == [0]
+    assert solver.conflict_explanation().nodes == ()
+
+    cnf = CNF()
+    a = cnf.new_var('A')
+    cnf.add_clause([a])
+    cnf.add_clause([])
+    assert not CDCLSolver(cnf).solve([a]).sat
+
+
+def test_zero_literal_is_rejected():
+    from src.lpm.resolver import SolverError, literal
+
+    with pytest.raises(SolverError):
+        literal(0)
+    assert literal(-3) == -3
+
+    cnf = CNF()
+    a = cnf.new_var('A')
+    with pytest.raises(SolverError, match="literal 0"):
+        cnf.add_clause([a, 0])
+    with pytest.raises(SolverError, match="unallocated"):
+        cnf.add_clause([a, -7])
+    assert cnf.clauses == []
+
+
+def test_assumption_against_unit_clause_is_reported():
+    cnf = CNF()
+    a = cnf.new_var('A')
+    b = cnf.new_var('B')
+    cnf.add([-a])
+    cnf.add([-b, a])
+    solver = CDCLSolver(cnf)
+
+    res = solver.solve([b])
+    assert not res.sat
+    assert res.failed_assumptions == [b]
+    assert solver.solve([]).sat
+
+
+def test_solve_under_assumptions_reuses_solver_for_contradictory_queries():
+    from src.lpm.resolver import SolverError
+
+    cnf = CNF()
+    x1, x2, y, z = (cnf.new_var(n) for n in ('X=1', 'X=2', 'Y', 'Z'))
+    cnf.add_clause([-x1, -x2])
+    cnf.add_clause([x1, x2])
+    cnf.add_clause([-y, x1])
+    solver = CDCLSolver(cnf)
+
+    res = solver.solve_under_assumptions([x2, z])
+    assert res.sat and res.assign[x2] and not res.assign[x1] and not res.assign[y]
+
+    res = solver.solve_under_assumptions([-x2, z, y])
+    assert res.sat and res.assign[x1] and res.assign[y]
+
+    res = solver.solve_under_assumptions([z, y, x2])
+    assert not res.sat
+    assert sorted(res.failed_assumptions) == sorted([y, x2])
+    assert sorted(res.unsat_core) == sorted([-y, -x2])
+
+    assert solver.solve_under_assumptions([x2]).sat
+    with pytest.raises(SolverError):
+        solver.solve_under_assumptions([0])
+
+
+def _rup(clauses, lemma):
+    """True when unit propagation on *clauses* plus the negated *lemma* conflicts."""
+    assign = {-l for l in lemma}
+    changed = True
+    while changed:
+        changed = False
+        for clause in clauses:
+            if any(l in assign for l in clause):
+                continue
+            open_lits = [l for l in clause if -l not in assign]
+            if not open_lits:
+                return True
+            if len(open_lits) == 1:
+                assign.add(open_lits[0])
+                changed = True
+    return False
+
+
+def test_drat_proof_derives_empty_clause_for_unsat():
+    cnf = CNF()
+    a, b, c = cnf.new_var('A'), cnf.new_var('B'), cnf.new_var('C')
+    original = [[a, b, c], [a, b, -c], [a, -b, c], [a, -b, -c], [-a, b], [-a, -b]]
+    for clause in original:
+        cnf.add_clause(clause)
+    solver = CDCLSolver(cnf)
+    solver.enable_proof()
+
+    assert not solver.solve([]).sat
+    proof = solver.take_proof()
+    assert proof[-1] == "0"
+    assert solver.take_proof() == []
+
+    clauses = [list(c) for c in original]
+    for line in proof:
+        lits = [int(tok) for tok in line.split() if tok != "d"]
+        assert lits[-1] == 0
+        if line.startswith("d "):
+            clauses.remove(lits[:-1])
+        else:
+            assert _rup(clauses, lits[:-1]), line
+            clauses.append(lits[:-1])
+
+    assert CDCLSolver(cnf).take_proof() == []
+
+
+def test_conflict_budget_returns_unknown_on_hard_instance():
+    # 9 pigeons into 8 holes takes far more than 10 conflicts.
+    solver = CDCLSolver(_pigeonhole(9, 8))
+    solver.set_conflict_budget(10)
+
+    res = solver.solve([])
+    assert res.unknown
+    assert not res.sat
+    assert solver.last_conflicts == 11
+
+    with pytest.raises(ValueError):
+        solver.set_conflict_budget(-1)
+    assert not CDCLSolver(CNF()).solve([]).unknown
+
+
+def test_clause_arena_preserves_clauses_and_results():
+    import itertools
+    import random
+
+    rng = random.Random(7)
+    for _ in range(20):
+        cnf = CNF()
+        vars = [cnf.new_var(f"X{i}") for i in range(8)]
+        clauses = [
+            [v if rng.random() < 0.5 else -v for v in rng.sample(vars, rng.randint(1, 3))]
+            for _ in range(rng.randint(10, 30))
+        ]
+        stored = [clause for clause in clauses if cnf.add_clause(clause)]
+        assert cnf.clauses == stored
+        assert cnf.clause(len(stored) - 1) == stored[-1]
+
+        def holds(assign):
+            return all(any(assign[abs(l)] == (l > 0) for l in c) for c in clauses)
+
+        expected = any(
+            holds(dict(zip(vars, bits))) for bits in itertools.product([False, True], repeat=len(vars))
+        )
+        res = CDCLSolver(cnf).solve([])
+        assert res.sat == expected
+        if res.sat:
+            assert holds(res.assign)
+        assert [c for c in cnf.clauses][: len(stored)] == stored
+
+
+def test_trail_backjump_keeps_levels_up_to_the_target():
+    from src.lpm.resolver import Trail
+
+    trail = Trail()
+    trail.push(1)
+    trail.push(-2)
+    for level_lits in ([3, 4], [-5], [6, -7, 8]):
+        trail.new_level()
+        for lit in level_lits:
+            trail.push(lit)
+    assert trail.level == 3
+    assert trail.level_lits(0) == [1, -2]
+    assert trail.level_lits(3) == [6, -7, 8]
+
+    assert trail.backjump(1) == [8, -7, 6, -5]
+
+    assert trail.level == 1
+    assert list(trail) == [1, -2, 3, 4]
+    assert trail.level_lits(0) == [1, -2]
+    assert trail.level_lits(1) == [3, 4]
+    assert trail.backjump(1) == []
+    assert list(trail) == [1, -2, 3, 4]
+
+
+def test_solver_backjumps_only_undo_higher_levels(monkeypatch):
+    import src.lpm.resolver as solver_module
+
+    jumps = []
+
+    class RecordingTrail(solver_module.Trail):
+        def backjump(self, level):
+            before = list(self.lits)
+            kept = sum((self.level_lits(k) for k in range(level + 1)), [])
+            undone = super().backjump(level)
+            jumps.append((before, kept, list(self.lits), undone))
+            return undone
+
+    monkeypatch.setattr(solver_module, "Trail", RecordingTrail)
+    solver = CDCLSolver(_pigeonhole(5, 4))
+    solver.restart_base = 0
+
+    assert not solver.solve([]).sat
+    assert jumps
+    for before, kept, after, undone in jumps:
+        assert after == kept == before[: len(after)]
+        assert undone == list(reversed(before[len(after):]))
+
+
+def test_conflict_explanation_traces_the_falsified_clause():
+    import json
+
+    from src.lpm.resolver import ConflictGraph, ConflictNode, SolverError
+
+    cnf = CNF()
+    a, b, c, d = (cnf.new_var(n) for n in 'ABCD')
+    cnf.add_clause([a])
+    cnf.add_clause([-a, b])
+    cnf.add_clause([-a, c])
+    cnf.add_clause([-b, -c])
+    cnf.add_clause([d, b])
+    solver = CDCLSolver(cnf)
+
+    assert not solver.solve([]).sat
+    graph = solver.conflict_explanation()
+
+    assert graph.conflict == (-b, -c)
+    assert graph.variables() == {a, b, c}
+    assert graph.nodes == (ConflictNode(a, 0, 0), ConflictNode(b, 0, 1), ConflictNode(c, 0, 2))
+    assert set(graph.edges) == {(a, b), (a, c)}
+    assert ConflictGraph.from_dict(json.loads(json.dumps(graph.to_dict()))) == graph
+
+    sat = CNF()
+    sat.new_var('X')
+    solver = CDCLSolver(sat)
+    assert solver.solve([]).sat
+    with pytest.raises(SolverError):
+        solver.conflict_explanation()
+
+
+def test_conflict_explanation_for_a_failed_assumption():
+    cnf = CNF()
+    x, y, z = (cnf.new_var(n) for n in 'XYZ')
+    cnf.add_clause([-x, y])
+    cnf.add_clause([-y, z])
+    solver = CDCLSolver(cnf)
+
+    assert not solver.solve([x, -z]).sat
+    graph = solver.conflict_explanation()
+
+    assert graph.conflict == (-z,)
+    # The assumption x is a decision (no reason) that implied y and then z.
+    assert [(n.literal, n.reason) for n in graph.nodes] == [(x, None), (y, 0), (z, 1)]
+    assert set(graph.edges) == {(x, y), (y, z)}
@@ -0,0 +1,103 @@
+import shutil
+import sqlite3
+from types import SimpleNamespace
+
+import pytest
+
+
+@pytest.fixture
+def lpm(lpm, tmp_path, monkeypatch):
+    monkeypatch.setattr(lpm, "HOOK_DIR", tmp_path / "no-hooks")
+    monkeypatch.setattr(lpm, "LIBLPM_HOOK_DIRS", ())
+    return lpm
+
+
+def _installed_rows(tmp_path):
+    conn = sqlite3.connect(tmp_path / "state" / "state.db")
+    try:
+        return dict(conn.execute("SELECT name, version FROM installed"))
+    finally:
+        conn.close()
+
+
+def test_rollback_transaction_restores_pre_install_state(lpm, make_pkg, tmp_path, capsys):
+    root = tmp_path / "root"
+    root.mkdir()
+
+    lpm.apply_plan([make_pkg("alpha", "1.0", {"usr/share/alpha/data": "v1\n"})], root, verify=False)
+    lpm.apply_plan(
+        [
+            make_pkg("alpha", "2.0", {"usr/share/alpha/data": "v2\n"}),
+            make_pkg("beta", "1.0", {"usr/lib/beta/libbeta.txt": "beta\n"}),
+        ],
+        root,
+        verify=False,
+    )
+    assert (root / "usr/share/alpha/data").read_text() == "v2\n"
+    capsys.readouterr()
+
+    lpm.cmd_history(SimpleNamespace(transactions=True))
+    lines = capsys.readouterr().out.splitlines()
+    assert lines[0].split()[0] == "2" and lines[0].endswith("committed   alpha, beta")
+    assert lines[1].split()[0] == "1" and lines[1].endswith("committed   alpha")
+
+    with pytest.raises(SystemExit):
+        lpm.cmd_rollback(SimpleNamespace(transaction=1, snapshot_id=None))
+    assert "roll back transaction(s) 2 first" in capsys.readouterr().err
+
+    lpm.cmd_rollback(SimpleNamespace(transaction=2, snapshot_id=None))
+    assert (root / "usr/share/alpha/data").read_text() == "v1\n"
+    assert not (root / "usr/lib/beta").exists()
+    assert _installed_rows(tmp_path) == {"alpha": "1.0"}
+
+    capsys.readouterr()
+    lpm.cmd_history(SimpleNamespace(transactions=True))
+    assert capsys.readouterr().out.splitlines()[0].endswith("rolled back alpha, beta")
+    with pytest.raises(SystemExit):
+        lpm.cmd_rollback(SimpleNamespace(transaction=2, snapshot_id=None))
+
+    lpm.cmd_rollback(SimpleNamespace(transaction=1, snapshot_id=None))
+    assert not (root / "usr").exists()
+    assert _installed_rows(tmp_path) == {}
+
+
+def test_only_the_newest_transactions_keep_backups(lpm, make_pkg, tmp_path, monkeypatch, capsys):
+    from lpm import app as lpm_app
+
+    monkeypatch.setattr(lpm_app._config, "MAX_TRANSACTIONS", 1)
+    root = tmp_path / "root"
+    root.mkdir()
+    lpm.apply_plan([make_pkg("alpha", "1.0", {"usr/bin/alpha": "a\n"})], root, verify=False)
+    lpm.apply_plan([make_pkg("beta", "1.0", {"usr/bin/beta": "b\n"})], root, verify=False)
+
+    assert len(list((tmp_path / "state" / "transactions").iterdir())) == 1
+    capsys.readouterr()
+    lpm.cmd_history(SimpleNamespace(transactions=True))
+    assert capsys.readouterr().out.splitlines()[1].endswith("alpha (no backup)")
+    lpm.cmd_rollback(SimpleNamespace(transaction=2, snapshot_id=None))
+    with pytest.raises(SystemExit):
+        lpm.cmd_rollback(SimpleNamespace(transaction=1, snapshot_id=None))
+    assert "has no backup left" in capsys.readouterr().err
+
+
+def test_failed_file_restore_leaves_the_database_and_backups_for_a_retry(lpm, make_pkg, tmp_path, monkeypatch, capsys):
+    root = tmp_path / "root"
+    root.mkdir()
+    lpm.apply_plan([make_pkg("alpha", "1.0", {"usr/share/alpha/data": "v1\n"})], root, verify=False)
+    lpm.apply_plan([make_pkg("alpha", "2.0", {"usr/share/alpha/data": "v2\n"})], root, verify=False)
+
+    real_move = shutil.move
+
+    def failing_move(src, dst):
+        raise OSError(5, "Input/output error")
+
+    monkeypatch.setattr(shutil, "move", failing_move)
+    with pytest.raises(SystemExit):
+        lpm.cmd_rollback(SimpleNamespace(transaction=2, snapshot_id=None))
+    assert "cannot restore" in capsys.readouterr().err
+    assert _installed_rows(tmp_path) == {"alpha": "2.0"}
+
+    monkeypatch.setattr(shutil, "move", real_move)
+    lpm.cmd_rollback(SimpleNamespace(transaction=2, snapshot_id=None))
+    assert (root / "usr/share/alpha/data").read_text() == "v1\n"
+    assert _installed_rows(tmp_path) == {"alpha": "1.0"}
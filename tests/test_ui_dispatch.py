@@ -0,0 +1,135 @@
+from __future__ import annotations
+
+import io
+import subprocess
+import sys
+import time
+
+from lpm.privileges import NotRoot
+from lpm.ui_launcher import run_text_loop
+from src.ui.backend import DispatchStatus, LPMBackend
+
+
+class _FakeGate:
+    def __init__(self, allow: bool) -> None:
+        self.allow = allow
+        self.checked: list[str] = []
+
+    def preflight(self, command):
+        self.checked.append(command)
+        return None if self.allow else NotRoot(command)
+
+
+def _backend(allow, monkeypatch, returncode=0):
+    gate = _FakeGate(allow)
+    backend = LPMBackend(gate=gate)
+    calls = []
+
+    def run_cli(args, *, root=None):
+        calls.append(list(args))
+        return subprocess.CompletedProcess(args, returncode, stdout="[OK] Installed foo\n", stderr="")
+
+    monkeypatch.setattr(backend, "run_cli", run_cli)
+    return backend, gate, calls
+
+
+def test_install_action_dispatches_through_the_cli(monkeypatch):
+    backend, gate, calls = _backend(True, monkeypatch)
+
+    result = backend.dispatch(["install", "foo"])
+
+    assert result.status is DispatchStatus.SUCCESS and result.ok
+    assert result.command == "install"
+    assert result.message == "[OK] Installed foo"
+    assert gate.checked == ["install"]
+    assert calls == [["install", "foo"]]
+
+    backend, _, _ = _backend(True, monkeypatch, returncode=2)
+    assert backend.dispatch(["install", "foo"]).status is DispatchStatus.FAILED
+
+
+def test_install_action_is_blocked_when_the_gate_denies(monkeypatch):
+    backend, gate, calls = _backend(False, monkeypatch)
+
+    result = backend.dispatch(["install", "foo"])
+    assert result.status is DispatchStatus.BLOCKED
+    assert result.message == "install requires root privileges"
+    assert calls == []
+
+    # Read-only and dry-run commands never consult the gate.
+    assert backend.dispatch(["install", "--dry-run", "foo"]).ok
+    assert backend.dispatch(["search", "foo"]).ok
+    assert gate.checked == ["install"]
+
+    invalid = backend.dispatch(["frobnicate"])
+    assert invalid.status is DispatchStatus.INVALID
+    assert "invalid choice" in invalid.message
+
+
+def test_text_loop_reads_actions_until_quit(monkeypatch):
+    backend, _, calls = _backend(False, monkeypatch)
+    out = io.StringIO()
+
+    failures = run_text_loop(backend, io.StringIO("search foo\n\ninstall foo\nquit\ninstall bar\n"), out)
+
+    assert failures == 1
+    assert calls == [["search", "foo"]]
+    assert "[success] search: [OK] Installed foo" in out.getvalue()
+    assert "[blocked] install: install requires root privileges" in out.getvalue()
+
+
+class _ScriptBackend(LPMBackend):
+    """Runs a Python snippet in place of the real CLI."""
+
+    def __init__(self, script, **kwargs):
+        super().__init__(gate=_FakeGate(True), **kwargs)
+        self.script = script
+        self.commands = []
+
+    def _cli_command(self, args):
+        self.commands.append(list(args))
+        return [sys.executable, "-c", self.script]
+
+
+_SLOW = """
+import signal, sys, time
+def abort(signum, frame):
+    print("rolled back")
+    sys.exit(1)
+signal.signal(signal.SIGTERM, abort)
+time.sleep(30)
+"""
+
+
+def test_command_running_past_the_timeout_is_blocked():
+    backend = _ScriptBackend(_SLOW, timeout=0.5)
+
+    started = time.monotonic()
+    result = backend.dispatch(["install", "foo"])
+
+    assert time.monotonic() - started < 10
+    assert result.status is DispatchStatus.BLOCKED
+    assert result.message == "timed out"
+    # SIGTERM lets the CLI roll back instead of being killed outright.
+    assert "rolled back" in result.output
+    assert backend.commands == [["install", "foo", "--timeout", "0.5"]]
+
+
+def test_command_finishing_within_the_timeout_succeeds():
+    backend = _ScriptBackend("print('[OK] Installed foo')")
+
+    result = backend.dispatch(["search", "foo"], timeout=10)
+
+    assert result.status is DispatchStatus.SUCCESS
+    assert result.message == "[OK] Installed foo"
+    assert backend.commands == [["search", "foo"]]
+
+
+def test_only_the_deadline_exit_status_counts_as_timed_out():
+    deadline = _ScriptBackend("import sys; print('[ERROR] aborted: operation timed out'); sys.exit(124)")
+    failing = _ScriptBackend("import sys; print('[ERROR] mirror says: deadline exceeded'); sys.exit(2)")
+
+    assert deadline.dispatch(["install", "foo"], timeout=10).status is DispatchStatus.BLOCKED
+    result = failing.dispatch(["install", "foo"], timeout=10)
+    assert result.status is DispatchStatus.FAILED
+    assert result.returncode == 2
@@ -0,0 +1,63 @@
+from types import SimpleNamespace
+
+import pytest
+
+
+class _NotRoot(Exception):
+    pass
+
+
+def _setup(lpm_app, monkeypatch):
+    def require_root(cmd):
+        raise _NotRoot(cmd)
+
+    monkeypatch.setattr(lpm_app, "require_root", require_root)
+
+
+def test_update_refreshes_indexes_without_root(lpm, lpm_app, monkeypatch, capsys):
+    _setup(lpm_app, monkeypatch)
+    repos = [SimpleNamespace(name="core", priority=1), SimpleNamespace(name="extra", priority=2)]
+    fetched = []
+
+    def fetch(repo):
+        fetched.append(repo.name)
+        if repo.name == "extra":
+            raise OSError("unreachable")
+        return [lpm.PkgMeta(name="zlib", version="1.3")]
+
+    monkeypatch.setattr(lpm_app, "list_repos", lambda: repos)
+    monkeypatch.setattr(lpm_app, "fetch_repo_index", fetch)
+
+    lpm.main(["update"])
+    assert fetched == ["core", "extra"]
+    captured = capsys.readouterr()
+    assert "core: 1 packages" in captured.err
+    assert "unreachable" in captured.err
+
+    monkeypatch.setattr(lpm_app, "list_repos", lambda: repos[1:])
+    with pytest.raises(SystemExit):
+        lpm.main(["update"])
+
+
+def test_upgrade_requires_root(lpm, lpm_app, monkeypatch):
+    _setup(lpm_app, monkeypatch)
+    monkeypatch.setattr(lpm_app, "cmd_upgrade", lambda a: pytest.fail("ran without root"))
+    with pytest.raises(_NotRoot):
+        lpm.main(["upgrade"])
+
+
+def test_upgrade_without_names_targets_all_installed(lpm, lpm_app, monkeypatch):
+    _setup(lpm_app, monkeypatch)
+    installed = {"bash": {"version": "5.2"}, "zlib": {"version": "1.3"}}
+    seen = {}
+
+    def solve(goals, u, **kwargs):
+        seen["goals"] = goals
+        return []
+
+    monkeypatch.setattr(lpm_app, "require_root", lambda cmd: None)
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: SimpleNamespace(installed=installed))
+    monkeypatch.setattr(lpm_app, "solve", solve)
+
+    lpm.main(["upgrade", "--dry-run"])
+    assert seen["goals"] == ["bash ~= 5.2", "zlib ~= 1.3"]
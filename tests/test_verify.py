@@ -0,0 +1,66 @@
+import subprocess
+
+import pytest
+
+from src.lpm.verify import ED25519, Keyring, PublicKey, VerifyError, sign_command, verify_detached
+
+
+def _keypair(tmp_path, name, algorithm="ed25519"):
+    priv = tmp_path / f"{name}.key"
+    pub = tmp_path / "keys" / f"{name}.pem"
+    pub.parent.mkdir(exist_ok=True)
+    subprocess.run(["openssl", "genpkey", "-algorithm", algorithm, "-out", str(priv)], check=True, capture_output=True)
+    subprocess.run(["openssl", "pkey", "-in", str(priv), "-pubout", "-out", str(pub)], check=True, capture_output=True)
+    return priv, pub
+
+
+def _sign(tmp_path, priv, data):
+    blob = tmp_path / "blob"
+    sig = tmp_path / "blob.sig"
+    blob.write_bytes(data)
+    subprocess.run(sign_command(priv, blob, sig), check=True, capture_output=True)
+    return sig.read_bytes()
+
+
+def test_ed25519_signature_verifies_and_tampering_fails(tmp_path):
+    priv, pub = _keypair(tmp_path, "repo")
+    key = PublicKey.load(pub)
+    assert key.algorithm == ED25519
+    data = b"package archive bytes"
+    signature = _sign(tmp_path, priv, data)
+    assert len(signature) == 64
+
+    verify_detached(data, signature, key)
+    with pytest.raises(VerifyError, match="does not match"):
+        verify_detached(data + b"!", signature, key)
+    with pytest.raises(VerifyError, match="malformed"):
+        verify_detached(data, signature[:-1], key)
+
+
+def test_keyring_rejects_signatures_from_unknown_keys(tmp_path):
+    trusted_priv, _ = _keypair(tmp_path, "trusted")
+    rsa_priv, _ = _keypair(tmp_path, "legacy", "RSA")
+    outsider_priv, outsider_pub = _keypair(tmp_path, "outsider")
+    outsider_pub.unlink()
+    keyring = Keyring.load(tmp_path / "keys")
+    assert sorted(k.path.name for k in keyring) == ["legacy.pem", "trusted.pem"]
+
+    data = b"index.json"
+    assert keyring.verify(data, _sign(tmp_path, trusted_priv, data)).path.name == "trusted.pem"
+    assert keyring.verify(data, _sign(tmp_path, rsa_priv, data)).path.name == "legacy.pem"
+    with pytest.raises(VerifyError, match="not from a trusted key"):
+        keyring.verify(data, _sign(tmp_path, outsider_priv, data))
+    with pytest.raises(VerifyError, match="no trusted public keys"):
+        Keyring.load(tmp_path / "missing").verify(data, b"")
+
+
+def test_keyring_skips_key_files_it_cannot_load(tmp_path):
+    priv, _ = _keypair(tmp_path, "trusted")
+    (tmp_path / "keys" / "broken.pem").write_text("not a key\n")
+    (tmp_path / "keys" / "private.pem").write_bytes(priv.read_bytes())
+
+    keyring = Keyring.load(tmp_path / "keys")
+
+    assert [k.path.name for k in keyring] == ["trusted.pem"]
+    data = b"index.json"
+    assert keyring.verify(data, _sign(tmp_path, priv, data)).path.name == "trusted.pem"
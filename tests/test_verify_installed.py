@@ -0,0 +1,160 @@
+import json
+import os
+from types import SimpleNamespace
+
+import pytest
+
+
+def _install(lpm, root, name, files, links=()):
+    """Write *files* (and *links*) under *root* and record them as installed *name*."""
+
+    stage = root.parent / f"stage-{name}"
+    for rel, text in files.items():
+        for base in (stage, root):
+            target = base / rel
+            target.parent.mkdir(parents=True, exist_ok=True)
+            target.write_text(text)
+    for rel, dest in links:
+        for base in (stage, root):
+            target = base / rel
+            target.parent.mkdir(parents=True, exist_ok=True)
+            os.symlink(dest, target)
+    manifest = lpm.collect_manifest(stage)
+    conn = lpm.db()
+    conn.execute(
+        "INSERT INTO installed(name,version,release,arch,provides,symbols,requires,manifest,explicit,install_time)"
+        " VALUES(?,?,?,?,?,?,?,?,?,?)",
+        (name, "1.0", "1", "noarch", "[]", "[]", "[]", json.dumps(manifest), 1, 0),
+    )
+    conn.commit()
+    conn.close()
+    return manifest
+
+
+def _run_verify(lpm, root, names=()):
+    args = SimpleNamespace(root=str(root), names=list(names))
+    try:
+        lpm.cmd_verify(args)
+    except SystemExit as exc:
+        return exc.code
+    return 0
+
+
+def test_verify_manifest_reports_modified_and_missing_files(lpm, tmp_path):
+    root = tmp_path / "root"
+    manifest = _install(
+        lpm,
+        root,
+        "foo",
+        {"usr/bin/foo": "#!/bin/sh\n", "usr/share/foo/data": "original", "etc/foo.conf": "x=1\n"},
+    )
+
+    assert lpm.verify_manifest(manifest, root) == []
+
+    (root / "usr/share/foo/data").write_text("tampered")
+    (root / "etc/foo.conf").unlink()
+
+    problems = {p["path"]: p["problem"] for p in lpm.verify_manifest(manifest, root)}
+    assert problems == {"/usr/share/foo/data": "hash", "/etc/foo.conf": "missing"}
+
+
+def test_verify_manifest_reports_changed_symlink_targets(lpm, tmp_path):
+    root = tmp_path / "root"
+    manifest = _install(
+        lpm,
+        root,
+        "libfoo",
+        {"usr/lib/libfoo.so.1": "elf"},
+        links=[("usr/lib/libfoo.so", "libfoo.so.1"), ("usr/lib/dangling", "gone")],
+    )
+
+    # A dangling link is still exactly what the manifest recorded.
+    assert lpm.verify_manifest(manifest, root) == []
+
+    link = root / "usr/lib/libfoo.so"
+    link.unlink()
+    os.symlink("/tmp/evil", link)
+    (root / "usr/lib/dangling").unlink()
+    (root / "usr/lib/dangling").write_text("not a link")
+
+    problems = {p["path"]: p for p in lpm.verify_manifest(manifest, root)}
+    assert set(problems) == {"/usr/lib/libfoo.so", "/usr/lib/dangling"}
+    assert problems["/usr/lib/libfoo.so"]["problem"] == "link"
+    assert "/tmp/evil" in problems["/usr/lib/libfoo.so"]["detail"]
+    assert problems["/usr/lib/dangling"]["problem"] == "link"
+
+
+def test_cmd_verify_distinguishes_clean_and_dirty_packages(lpm, tmp_path, capsys):
+    root = tmp_path / "root"
+    _install(lpm, root, "good", {"usr/bin/good": "ok"})
+    _install(lpm, root, "bad", {"usr/bin/bad": "ok", "usr/share/bad/readme": "text"})
+
+    assert _run_verify(lpm, root) == 0
+    assert "All files validated successfully" in capsys.readouterr().err
+
+    (root / "usr/bin/bad").write_text("modified!")
+    (root / "usr/share/bad/readme").unlink()
+
+    assert _run_verify(lpm, root) == 1
+    captured = capsys.readouterr()
+    assert "[SIZE MISMATCH] bad: /usr/bin/bad" in captured.out
+    assert "[MISSING] bad: /usr/share/bad/readme" in captured.out
+    assert "[DIRTY] bad" in captured.out
+    assert "[CLEAN] good" in captured.out
+    assert "1 of 2 packages" in captured.err
+
+    assert _run_verify(lpm, root, ["good"]) == 0
+    out = capsys.readouterr().out
+    assert "[CLEAN] good" in out
+    assert "bad" not in out
+
+
+def test_cmd_verify_rejects_unknown_packages(lpm, tmp_path, capsys):
+    root = tmp_path / "root"
+    _install(lpm, root, "good", {"usr/bin/good": "ok"})
+
+    assert _run_verify(lpm, root, ["good", "nope"]) != 0
+    assert "Not installed: nope" in capsys.readouterr().err
+
+
+def _large_fixture(lpm, root):
+    files = {f"usr/share/big/{i:03d}.txt": f"file {i}\n" * (i % 7 + 1) for i in range(120)}
+    manifest = _install(
+        lpm,
+        root,
+        "big",
+        files,
+        links=[("usr/share/big/latest", "119.txt"), ("usr/share/big/first", "000.txt")],
+    )
+    (root / "usr/share/big/007.txt").write_text("file 7\n" * 2)
+    (root / "usr/share/big/042.txt").write_text("tampered\n")
+    (root / "usr/share/big/099.txt").unlink()
+    (root / "usr/share/big/latest").unlink()
+    os.symlink("042.txt", root / "usr/share/big/latest")
+    return manifest
+
+
+def test_parallel_verification_matches_sequential(lpm, tmp_path):
+    root = tmp_path / "root"
+    manifest = _large_fixture(lpm, root)
+
+    sequential = lpm.verify_manifest(manifest, root)
+
+    assert [p["path"] for p in sequential] == [
+        "/usr/share/big/007.txt",
+        "/usr/share/big/042.txt",
+        "/usr/share/big/099.txt",
+        "/usr/share/big/latest",
+    ]
+    for threads in (2, 4, 16):
+        assert lpm.verify_parallel(manifest, root, threads) == sequential
+
+
+def test_parallel_verification_with_one_thread(lpm, tmp_path):
+    root = tmp_path / "root"
+    manifest = _large_fixture(lpm, root)
+
+    assert lpm.verify_parallel(manifest, root, 1) == lpm.verify_manifest(manifest, root)
+    assert lpm.verify_parallel([], root, 1) == []
+    with pytest.raises(ValueError):
+        lpm.verify_parallel(manifest, root, 0)
@@ -0,0 +1,57 @@
+import pytest
+
+from lpm.version import Constraint, Version, compare
+
+
+@pytest.mark.parametrize(
+    "older, newer",
+    [
+        ("1.2", "1.10"),
+        ("1.2.0", "1.2.1"),
+        ("1.0a", "1.0.1"),
+        ("1.0-alpha", "1.0-alpha.1"),
+        ("1.0-alpha.1", "1.0-alpha.beta"),
+        ("1.0-beta.2", "1.0-beta.11"),
+        ("1.0-rc.1", "1.0"),
+        ("1.0~beta", "1.0"),
+        ("1.0", "1.0-1"),
+        ("1.0-1", "1.0-2"),
+        ("9.9", "1:0.1"),
+    ],
+)
+def test_version_ordering(older, newer):
+    assert Version.parse(older) < Version.parse(newer)
+    assert compare(newer, older) == 1
+
+
+def test_version_equality_ignores_padding_and_build_metadata():
+    assert Version.parse("1.2") == Version.parse("1.2.0")
+    assert Version.parse("1.2.0+git123") == Version.parse("1.2.0")
+    assert Version.parse("0:1.2") == Version.parse("1.2")
+    assert compare("2.0", "2") == 0
+    assert sorted(["1.0", "1.0-rc1", "0.9", "1.0-1"], key=Version.parse) == ["0.9", "1.0-rc1", "1.0", "1.0-1"]
+
+
+def test_constraint_operators_and_intersections():
+    assert Constraint.parse(">=1.2.0").matches("1.2")
+    assert not Constraint.parse(">=1.2.0").matches("1.1.9")
+    assert Constraint.parse("<2.0").matches("1.99")
+    assert not Constraint.parse("<2.0").matches("2.0")
+    assert Constraint.parse("=1.4.1").matches("1.4.1")
+    assert not Constraint.parse("=1.4.1").matches("1.4.2")
+    assert Constraint.parse("1.4.1").matches("1.4.1")
+    assert Constraint.parse("!=1.3").matches("1.4")
+
+    window = Constraint.parse(">=1.2") & Constraint.parse("<2.0")
+    assert window == Constraint.parse(">=1.2,<2.0")
+    assert [v for v in ("1.1", "1.2", "1.9", "2.0") if window.matches(v)] == ["1.2", "1.9"]
+
+    assert Constraint.parse("3.3.*").matches("3.9")
+    assert not Constraint.parse("3.3.*").matches("4.0")
+    assert Constraint.parse("").matches("anything")
+
+
+def test_prerelease_precedence_in_constraints():
+    assert not Constraint.parse(">=2.0").matches("2.0-rc1")
+    assert Constraint.parse("<2.0").matches("2.0-rc1")
+    assert Constraint.parse(">2.0").matches("2.0-1")
@@ -0,0 +1,29 @@
+import pytest
+
+
+@pytest.fixture
+def why(lpm, lpm_app, monkeypatch):
+    universe = lpm_app.Universe(candidates_by_name={}, providers={}, installed={}, pins={}, holds=set())
+    for pkg in (
+        lpm.PkgMeta(name="A", version="1.0", requires=["B"]),
+        lpm.PkgMeta(name="B", version="1.0", requires=["C"]),
+        lpm.PkgMeta(name="C", version="1.0"),
+        lpm.PkgMeta(name="D", version="1.0"),
+    ):
+        lpm_app.register_universe_candidate(universe, pkg)
+    monkeypatch.setattr(lpm_app, "build_universe", lambda: universe)
+    return lpm
+
+
+def test_why_walks_back_to_the_requested_package(why, capsys):
+    why.main(["why", "C", "--goal", "A"])
+    assert capsys.readouterr().out == "C-1.0-1.noarch <- B-1.0-1.noarch <- A-1.0-1.noarch (requested as A)\n"
+
+    why.main(["why", "A", "--goal", "A"])
+    assert capsys.readouterr().out == "A-1.0-1.noarch (requested as A)\n"
+
+
+def test_why_reports_packages_outside_the_plan(why, capsys):
+    with pytest.raises(SystemExit):
+        why.main(["why", "D", "--goal", "A"])
+    assert "D is not part of the plan for A" in capsys.readouterr().err